@@ -0,0 +1,190 @@
+//! Client hot paths against the in-process mock server.
+//!
+//! Run with `cargo bench --features testing`. Everything runs in memory, so
+//! the numbers measure the client's own work: batching, body building,
+//! parsing and copying.
+
+use bytes::Bytes;
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use durable_streams::testing::MockServer;
+use durable_streams::{DurableStream, LiveMode, Offset, SseParser};
+use serde::Serialize;
+use std::time::Duration;
+use tokio::runtime::Runtime;
+
+const RECORDS: usize = 1_000;
+
+#[derive(Serialize)]
+struct Event {
+    id: u64,
+    kind: &'static str,
+    user: String,
+    amount: f64,
+    tags: Vec<&'static str>,
+}
+
+fn event(id: u64) -> Event {
+    Event {
+        id,
+        kind: "order.created",
+        user: format!("user-{}", id % 97),
+        amount: id as f64 * 1.25,
+        tags: vec!["web", "eu-west"],
+    }
+}
+
+fn runtime() -> Runtime {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+}
+
+/// A stream on a fresh mock server, not yet created.
+fn stream(content_type: &str) -> (MockServer, DurableStream) {
+    let server = MockServer::new();
+    let stream = server
+        .client()
+        .stream_builder("/bench")
+        .content_type(content_type)
+        .build();
+    (server, stream)
+}
+
+fn producer_batching(c: &mut Criterion) {
+    let rt = runtime();
+    let mut group = c.benchmark_group("producer");
+    group.throughput(Throughput::Elements(RECORDS as u64));
+
+    for size in [64usize, 1024, 16 * 1024] {
+        let record = Bytes::from(vec![b'x'; size]);
+        group.bench_with_input(BenchmarkId::new("append", size), &record, |b, record| {
+            b.to_async(&rt).iter_batched(
+                || stream("application/octet-stream"),
+                |(_server, stream)| async move {
+                    stream.create().await.unwrap();
+                    let producer = stream
+                        .producer("bench")
+                        .linger(Duration::from_millis(1))
+                        .build();
+                    for _ in 0..RECORDS {
+                        producer.append(record.clone());
+                    }
+                    producer.close().await.unwrap();
+                },
+                BatchSize::PerIteration,
+            );
+        });
+    }
+    group.finish();
+}
+
+fn json_encode(c: &mut Criterion) {
+    let rt = runtime();
+    let events: Vec<Event> = (0..RECORDS as u64).map(event).collect();
+    let mut group = c.benchmark_group("json");
+    group.throughput(Throughput::Elements(RECORDS as u64));
+
+    group.bench_function("append_json", |b| {
+        b.to_async(&rt).iter_batched(
+            || stream("application/json"),
+            |(_server, stream)| {
+                let events = &events;
+                async move {
+                    stream.create().await.unwrap();
+                    let producer = stream
+                        .producer("bench")
+                        .linger(Duration::from_millis(1))
+                        .build();
+                    for event in events {
+                        producer.append_json(event);
+                    }
+                    producer.close().await.unwrap();
+                }
+            },
+            BatchSize::PerIteration,
+        );
+    });
+    group.finish();
+}
+
+fn chunk_iteration(c: &mut Criterion) {
+    let rt = runtime();
+    let mut group = c.benchmark_group("read");
+
+    for (name, content_type) in [
+        ("bytes", "application/octet-stream"),
+        ("json", "application/json"),
+    ] {
+        let (server, stream) = stream(content_type);
+        rt.block_on(async {
+            stream.create().await.unwrap();
+            let producer = stream.producer("fill").build();
+            for id in 0..RECORDS as u64 {
+                producer.append_json(&event(id));
+            }
+            producer.close().await.unwrap();
+        });
+        let total = server.contents("/bench").unwrap().len();
+        group.throughput(Throughput::Bytes(total as u64));
+
+        for live in [LiveMode::Off, LiveMode::Sse] {
+            let id = BenchmarkId::new(name, format!("{live:?}"));
+            group.bench_function(id, |b| {
+                b.to_async(&rt).iter(|| async {
+                    let mut chunks = stream
+                        .read()
+                        .offset(Offset::Beginning)
+                        .live(live.clone())
+                        .build()
+                        .unwrap();
+                    while let Some(chunk) = chunks.next_chunk().await.unwrap() {
+                        criterion::black_box(&chunk.data);
+                        if chunks.is_up_to_date() {
+                            break;
+                        }
+                    }
+                });
+            });
+        }
+    }
+    group.finish();
+}
+
+fn sse_parsing(c: &mut Criterion) {
+    let mut body = Vec::new();
+    for id in 0..RECORDS as u64 {
+        let data = serde_json::to_string(&event(id)).unwrap();
+        body.extend_from_slice(format!("event: data\nid: {id}\ndata: {data}\n\n").as_bytes());
+    }
+    let mut group = c.benchmark_group("sse");
+    group.throughput(Throughput::Bytes(body.len() as u64));
+
+    // Whole body at once, then in network-sized pieces that split lines
+    for piece in [body.len(), 1400, 64] {
+        group.bench_with_input(BenchmarkId::new("parse", piece), &piece, |b, &piece| {
+            b.iter(|| {
+                let mut parser = SseParser::new();
+                let mut events = 0;
+                for bytes in body.chunks(piece) {
+                    parser.push(bytes);
+                    while let Some(event) = parser.next_event() {
+                        criterion::black_box(&event);
+                        events += 1;
+                    }
+                }
+                assert_eq!(events, RECORDS);
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    producer_batching,
+    json_encode,
+    chunk_iteration,
+    sse_parsing
+);
+criterion_main!(benches);
@@ -0,0 +1,242 @@
+//! Event-sourced aggregates with optimistic concurrency.
+
+use crate::client::Client;
+use crate::error::StreamError;
+use crate::stream::{AppendOptions, CreateOptions, DurableStream};
+use crate::types::{LiveMode, Offset};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::marker::PhantomData;
+
+const JSON: &str = "application/json";
+
+/// State rebuilt from its own stream of events.
+///
+/// # Example
+///
+/// ```ignore
+/// #[derive(Default)]
+/// struct Account {
+///     balance: i64,
+/// }
+///
+/// impl Aggregate for Account {
+///     type Event = AccountEvent;
+///     type Command = AccountCommand;
+///
+///     fn apply(&mut self, event: &AccountEvent) {
+///         match event {
+///             AccountEvent::Deposited(n) => self.balance += n,
+///             AccountEvent::Withdrawn(n) => self.balance -= n,
+///         }
+///     }
+///
+///     fn handle(&self, command: AccountCommand) -> Vec<AccountEvent> {
+///         match command {
+///             AccountCommand::Withdraw(n) if n > self.balance => vec![],
+///             AccountCommand::Withdraw(n) => vec![AccountEvent::Withdrawn(n)],
+///             AccountCommand::Deposit(n) => vec![AccountEvent::Deposited(n)],
+///         }
+///     }
+/// }
+/// ```
+pub trait Aggregate: Default + Send {
+    /// What happened; stored as JSON.
+    type Event: Serialize + DeserializeOwned + Send;
+    /// What is asked of the aggregate. Cloned when a command is retried
+    /// after a conflict.
+    type Command: Clone + Send;
+
+    /// Fold one event into the state. Must not fail: the event happened.
+    fn apply(&mut self, event: &Self::Event);
+
+    /// Decide which events a command causes, given the current state. An
+    /// empty list rejects the command or makes it a no-op.
+    fn handle(&self, command: Self::Command) -> Vec<Self::Event>;
+}
+
+/// An aggregate's state together with the position it was rebuilt up to.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Versioned<A> {
+    /// The aggregate's ID.
+    pub id: String,
+    /// State after all events so far.
+    pub state: A,
+    /// Number of events applied.
+    pub version: u64,
+    /// Offset just past the last event applied.
+    pub offset: Offset,
+}
+
+/// Loads and saves [`Aggregate`]s, each in its own JSON stream under a
+/// base URL.
+///
+/// Commits are conditional on the version they were decided at: events
+/// are appended in one request carrying a `Stream-Seq` derived from that
+/// version, so if another writer committed in the meantime the server
+/// rejects the append with [`StreamError::SeqConflict`] rather than
+/// interleaving decisions made on stale state. This needs a server that
+/// scopes `Stream-Seq` per stream rather than per writer identity, and
+/// every writer of an aggregate's stream must go through a repository.
+///
+/// # Example
+///
+/// ```ignore
+/// let accounts = Repository::<Account>::new(&client, "/v1/stream/accounts");
+/// let account = accounts.execute("acc-42", AccountCommand::Deposit(100)).await?;
+/// println!("balance {} at version {}", account.state.balance, account.version);
+/// ```
+pub struct Repository<A> {
+    client: Client,
+    base_url: String,
+    max_retries: u32,
+    _aggregate: PhantomData<fn() -> A>,
+}
+
+impl<A: Aggregate> Repository<A> {
+    /// Repository for aggregates stored at `{base_url}/{id}`.
+    pub fn new(client: &Client, base_url: impl Into<String>) -> Self {
+        Self {
+            client: client.clone(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            max_retries: 3,
+            _aggregate: PhantomData,
+        }
+    }
+
+    /// How many times [`execute`](Self::execute) reloads and decides again
+    /// after a conflict (default 3).
+    #[must_use]
+    pub fn max_retries(mut self, retries: u32) -> Self {
+        self.max_retries = retries;
+        self
+    }
+
+    /// The stream holding aggregate `id`'s events.
+    pub fn stream(&self, id: &str) -> DurableStream {
+        self.client
+            .stream_builder(&format!("{}/{}", self.base_url, id))
+            .content_type(JSON)
+            .build()
+    }
+
+    /// Rebuild aggregate `id` by replaying its stream. An aggregate without
+    /// a stream yet starts from `A::default()` at version 0.
+    pub async fn load(&self, id: &str) -> Result<Versioned<A>, StreamError> {
+        let mut loaded = Versioned {
+            id: id.to_string(),
+            state: A::default(),
+            version: 0,
+            offset: Offset::Beginning,
+        };
+        self.refresh(&mut loaded).await?;
+        Ok(loaded)
+    }
+
+    /// Apply the events committed since `loaded` was last brought up to
+    /// date.
+    pub async fn refresh(&self, loaded: &mut Versioned<A>) -> Result<(), StreamError> {
+        let mut reader = self
+            .stream(&loaded.id)
+            .read()
+            .offset(loaded.offset.clone())
+            .live(LiveMode::Off)
+            .typed::<A::Event>()
+            .build()?;
+        loop {
+            match reader.next_chunk().await {
+                Ok(Some(chunk)) => {
+                    for event in &chunk.items {
+                        loaded.state.apply(event);
+                        loaded.version += 1;
+                    }
+                    loaded.offset = chunk.next_offset;
+                }
+                Ok(None) => return Ok(()),
+                Err(StreamError::NotFound { .. }) if loaded.version == 0 => return Ok(()),
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Append `events` to `loaded`'s stream if nobody else has since it
+    /// was loaded, and apply them to it.
+    ///
+    /// Fails with [`StreamError::SeqConflict`] if another writer got there
+    /// first; [`refresh`](Self::refresh) and decide again.
+    pub async fn commit(
+        &self,
+        loaded: &mut Versioned<A>,
+        events: Vec<A::Event>,
+    ) -> Result<(), StreamError> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        let stream = self.stream(&loaded.id);
+        if loaded.version == 0 {
+            stream
+                .create_with(CreateOptions::new().content_type(JSON))
+                .await?;
+        }
+
+        let body = serde_json::to_vec(&events)?;
+        let options = AppendOptions::new().seq(version_seq(loaded.version + 1));
+        stream.append_with(body, options).await?;
+
+        // Catch up through the server, so the offset lands after our events
+        // and the state matches what every other reader sees
+        self.refresh(loaded).await
+    }
+
+    /// Load aggregate `id`, run `command` against it and commit the
+    /// resulting events, deciding again on fresh state if another writer
+    /// commits first.
+    ///
+    /// Fails with [`StreamError::SeqConflict`] if conflicts persist past
+    /// [`max_retries`](Self::max_retries).
+    pub async fn execute(
+        &self,
+        id: &str,
+        command: A::Command,
+    ) -> Result<Versioned<A>, StreamError> {
+        let mut loaded = self.load(id).await?;
+        let mut attempt = 0;
+        loop {
+            let events = loaded.state.handle(command.clone());
+            match self.commit(&mut loaded, events).await {
+                Err(StreamError::SeqConflict) if attempt < self.max_retries => {
+                    attempt += 1;
+                    self.refresh(&mut loaded).await?;
+                }
+                result => return result.map(|()| loaded),
+            }
+        }
+    }
+}
+
+impl<A> Clone for Repository<A> {
+    fn clone(&self) -> Self {
+        Self {
+            client: self.client.clone(),
+            base_url: self.base_url.clone(),
+            max_retries: self.max_retries,
+            _aggregate: PhantomData,
+        }
+    }
+}
+
+impl<A> std::fmt::Debug for Repository<A> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Repository")
+            .field("base_url", &self.base_url)
+            .field("max_retries", &self.max_retries)
+            .finish_non_exhaustive()
+    }
+}
+
+/// `Stream-Seq` for the event at `version`, padded so that byte order
+/// matches numeric order.
+fn version_seq(version: u64) -> String {
+    format!("{version:020}")
+}
@@ -0,0 +1,57 @@
+//! Asynchronous authentication providers.
+
+use async_trait::async_trait;
+use reqwest::header::HeaderMap;
+
+#[cfg(feature = "auth-oauth2")]
+mod oauth2;
+
+#[cfg(feature = "auth-oauth2")]
+pub use oauth2::OAuth2Provider;
+
+/// Supplies authentication headers for every request.
+///
+/// Unlike [`ClientBuilder::header_provider`](crate::ClientBuilder::header_provider),
+/// providers are async, so they can fetch or refresh tokens without
+/// blocking. When a request comes back `401 Unauthorized`, the client calls
+/// [`on_unauthorized`](Self::on_unauthorized); if that reports a successful
+/// refresh, the request is retried once with fresh headers.
+///
+/// # Example
+///
+/// ```ignore
+/// struct Bearer(tokio::sync::RwLock<String>);
+///
+/// #[async_trait::async_trait]
+/// impl AuthProvider for Bearer {
+///     async fn headers(&self) -> HeaderMap {
+///         let mut headers = HeaderMap::new();
+///         let value = format!("Bearer {}", self.0.read().await);
+///         headers.insert("authorization", value.parse().unwrap());
+///         headers
+///     }
+///
+///     async fn on_unauthorized(&self) -> bool {
+///         match fetch_new_token().await {
+///             Ok(token) => {
+///                 *self.0.write().await = token;
+///                 true
+///             }
+///             Err(_) => false,
+///         }
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait AuthProvider: Send + Sync {
+    /// Headers to add to the next request.
+    async fn headers(&self) -> HeaderMap;
+
+    /// Called when the server rejects a request with 401.
+    ///
+    /// Return `true` if credentials were refreshed and the request should be
+    /// retried. The default never retries.
+    async fn on_unauthorized(&self) -> bool {
+        false
+    }
+}
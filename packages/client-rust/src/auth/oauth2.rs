@@ -0,0 +1,189 @@
+//! OAuth2 client-credentials provider.
+
+use super::AuthProvider;
+use crate::rt::Instant;
+use async_trait::async_trait;
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+use serde::Deserialize;
+use std::time::Duration;
+
+/// [`AuthProvider`] implementing the OAuth2 client-credentials grant
+/// (RFC 6749 section 4.4).
+///
+/// Tokens are cached and refreshed shortly before they expire (see
+/// [`refresh_margin`](Self::refresh_margin)). Concurrent requests share a
+/// single token fetch. If the server still answers 401, the cached token is
+/// discarded and a new one is fetched before the request is retried.
+///
+/// # Example
+///
+/// ```ignore
+/// let client = Client::builder()
+///     .auth_provider(OAuth2Provider::client_credentials(
+///         "https://auth.example.com/oauth/token",
+///         "my-client-id",
+///         "my-client-secret",
+///         ["streams:read", "streams:write"],
+///     ))
+///     .build()?;
+/// ```
+pub struct OAuth2Provider {
+    http: reqwest::Client,
+    token_url: String,
+    client_id: String,
+    client_secret: String,
+    scopes: Vec<String>,
+    audience: Option<String>,
+    credentials_in_body: bool,
+    refresh_margin: Duration,
+    token: tokio::sync::Mutex<Option<CachedToken>>,
+}
+
+struct CachedToken {
+    header: HeaderValue,
+    expires_at: Option<Instant>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    token_type: Option<String>,
+    #[serde(default)]
+    expires_in: Option<u64>,
+}
+
+impl std::fmt::Debug for OAuth2Provider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OAuth2Provider")
+            .field("token_url", &self.token_url)
+            .field("client_id", &self.client_id)
+            .field("scopes", &self.scopes)
+            .field("audience", &self.audience)
+            .finish_non_exhaustive()
+    }
+}
+
+impl OAuth2Provider {
+    /// Authenticate with the client-credentials grant against `token_url`.
+    pub fn client_credentials<S>(
+        token_url: impl Into<String>,
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+        scopes: impl IntoIterator<Item = S>,
+    ) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            http: reqwest::Client::new(),
+            token_url: token_url.into(),
+            client_id: client_id.into(),
+            client_secret: client_secret.into(),
+            scopes: scopes.into_iter().map(Into::into).collect(),
+            audience: None,
+            credentials_in_body: false,
+            refresh_margin: Duration::from_secs(30),
+            token: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    /// Refresh tokens this long before they expire (default 30s).
+    pub fn refresh_margin(mut self, margin: Duration) -> Self {
+        self.refresh_margin = margin;
+        self
+    }
+
+    /// Request a token for `audience` (used by Auth0 and similar providers).
+    pub fn audience(mut self, audience: impl Into<String>) -> Self {
+        self.audience = Some(audience.into());
+        self
+    }
+
+    /// Send the client ID and secret as form fields instead of HTTP Basic
+    /// auth, for servers that only support `client_secret_post`.
+    pub fn credentials_in_body(mut self, enabled: bool) -> Self {
+        self.credentials_in_body = enabled;
+        self
+    }
+
+    /// Use `client` for token requests.
+    pub fn http_client(mut self, client: reqwest::Client) -> Self {
+        self.http = client;
+        self
+    }
+
+    /// Return the cached `Authorization` value, fetching a new token if
+    /// there is none or it is about to expire.
+    async fn authorization(&self, force_refresh: bool) -> Option<HeaderValue> {
+        let mut cached = self.token.lock().await;
+        if let Some(token) = cached.as_ref() {
+            let fresh = token
+                .expires_at
+                .is_none_or(|at| Instant::now() + self.refresh_margin < at);
+            if fresh && !force_refresh {
+                return Some(token.header.clone());
+            }
+        }
+
+        *cached = self.fetch().await;
+        cached.as_ref().map(|token| token.header.clone())
+    }
+
+    async fn fetch(&self) -> Option<CachedToken> {
+        let scope = self.scopes.join(" ");
+        let mut form = vec![("grant_type", "client_credentials")];
+        if !scope.is_empty() {
+            form.push(("scope", &scope));
+        }
+        if let Some(audience) = &self.audience {
+            form.push(("audience", audience));
+        }
+
+        let mut req = self.http.post(&self.token_url);
+        if self.credentials_in_body {
+            form.push(("client_id", &self.client_id));
+            form.push(("client_secret", &self.client_secret));
+        } else {
+            req = req.basic_auth(&self.client_id, Some(&self.client_secret));
+        }
+
+        let requested_at = Instant::now();
+        let resp = req.form(&form).send().await.ok()?;
+        if !resp.status().is_success() {
+            return None;
+        }
+        let body = resp.bytes().await.ok()?;
+        let token: TokenResponse = serde_json::from_slice(&body).ok()?;
+
+        // Token types are case-insensitive; normalize the common one
+        let token_type = match token.token_type.as_deref() {
+            None => "Bearer",
+            Some(t) if t.eq_ignore_ascii_case("bearer") => "Bearer",
+            Some(t) => t,
+        };
+        let header = HeaderValue::from_str(&format!("{} {}", token_type, token.access_token)).ok()?;
+
+        Some(CachedToken {
+            header,
+            expires_at: token
+                .expires_in
+                .map(|secs| requested_at + Duration::from_secs(secs)),
+        })
+    }
+}
+
+#[async_trait]
+impl AuthProvider for OAuth2Provider {
+    async fn headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Some(value) = self.authorization(false).await {
+            headers.insert(AUTHORIZATION, value);
+        }
+        headers
+    }
+
+    async fn on_unauthorized(&self) -> bool {
+        self.authorization(true).await.is_some()
+    }
+}
@@ -87,7 +87,7 @@ struct BenchmarkOperation {
 }
 
 // Result types sent back to test runner
-#[derive(Debug, Serialize)]
+#[derive(Debug, Default, Serialize)]
 #[serde(rename_all = "camelCase")]
 struct Result {
     #[serde(rename = "type")]
@@ -286,15 +286,11 @@ async fn handle_create(state: &Arc<Mutex<Option<AppState>>>, cmd: Command) -> Re
     let app_state = guard.as_mut().unwrap();
 
     let path = cmd.path.unwrap_or_default();
-    let mut stream = app_state.client.stream(&path);
-
-    if let Some(ct) = app_state.stream_content_types.get(&path) {
-        stream.set_content_type(ct.clone());
-    }
-
+    let mut builder = app_state.client.stream_builder(&path);
     if let Some(ct) = app_state.stream_content_types.get(&path) {
-        stream.set_content_type(ct.clone());
+        builder = builder.content_type(ct.clone());
     }
+    let stream = builder.build();
 
     let content_type = cmd.content_type.unwrap_or_else(|| "application/octet-stream".to_string());
 
@@ -357,7 +353,7 @@ async fn handle_connect(state: &Arc<Mutex<Option<AppState>>>, cmd: Command) -> R
     let app_state = guard.as_mut().unwrap();
 
     let path = cmd.path.unwrap_or_default();
-    let mut stream = app_state.client.stream(&path);
+    let stream = app_state.client.stream(&path);
 
     match stream.head().await {
         Ok(meta) => {
@@ -382,12 +378,12 @@ async fn handle_append(state: &Arc<Mutex<Option<AppState>>>, cmd: Command) -> Re
     let app_state = guard.as_mut().unwrap();
 
     let path = cmd.path.unwrap_or_default();
-    let mut stream = app_state.client.stream(&path);
-
     // Set content type from cache
+    let mut builder = app_state.client.stream_builder(&path);
     if let Some(ct) = app_state.stream_content_types.get(&path) {
-        stream.set_content_type(ct.clone());
+        builder = builder.content_type(ct.clone());
     }
+    let stream = builder.build();
 
     // Resolve dynamic headers/params
     let headers_sent = resolve_dynamic_headers(&app_state.dynamic_headers);
@@ -449,7 +445,7 @@ async fn handle_read(state: &Arc<Mutex<Option<AppState>>>, cmd: Command) -> Resu
     let app_state = guard.as_mut().unwrap();
 
     let path = cmd.path.unwrap_or_default();
-    let mut stream = app_state.client.stream(&path);
+    let stream = app_state.client.stream(&path);
 
     // Check if this is a JSON stream from cached content type
     let is_json_stream = app_state
@@ -622,7 +618,7 @@ async fn handle_head(state: &Arc<Mutex<Option<AppState>>>, cmd: Command) -> Resu
     let app_state = guard.as_ref().unwrap();
 
     let path = cmd.path.unwrap_or_default();
-    let mut stream = app_state.client.stream(&path);
+    let stream = app_state.client.stream(&path);
 
     match stream.head().await {
         Ok(meta) => Result {
@@ -643,7 +639,7 @@ async fn handle_close(state: &Arc<Mutex<Option<AppState>>>, cmd: Command) -> Res
     let app_state = guard.as_ref().unwrap();
 
     let path = cmd.path.unwrap_or_default();
-    let mut stream = app_state.client.stream(&path);
+    let mut builder = app_state.client.stream_builder(&path);
 
     // Get data
     let data: Option<Bytes> = if cmd.binary.unwrap_or(false) {
@@ -665,13 +661,13 @@ async fn handle_close(state: &Arc<Mutex<Option<AppState>>>, cmd: Command) -> Res
         .content_type
         .or_else(|| app_state.stream_content_types.get(&path).cloned());
     if let Some(ct) = content_type {
-        stream.set_content_type(ct.clone());
+        builder = builder.content_type(ct.clone());
         if has_data {
             options = options.content_type(ct);
         }
     }
 
-    match stream.close_with(options).await {
+    match builder.build().close_with(options).await {
         Ok(result) => Result {
             result_type: "close".to_string(),
             success: true,
@@ -1054,13 +1050,14 @@ async fn handle_benchmark(state: &Arc<Mutex<Option<AppState>>>, cmd: Command) ->
 }
 
 async fn benchmark_append(app_state: &AppState, op: &BenchmarkOperation) -> (i64, Option<BenchmarkMetrics>) {
-    let path = op.path.as_ref().map(|s| s.as_str()).unwrap_or("");
+    let path = op.path.as_deref().unwrap_or("");
     let size = op.size.unwrap_or(100);
 
-    let mut stream = app_state.client.stream(path);
+    let mut builder = app_state.client.stream_builder(path);
     if let Some(ct) = app_state.stream_content_types.get(path) {
-        stream.set_content_type(ct.clone());
+        builder = builder.content_type(ct.clone());
     }
+    let stream = builder.build();
 
     let data: Vec<u8> = (0..size).map(|i| (i % 256) as u8).collect();
 
@@ -1070,7 +1067,7 @@ async fn benchmark_append(app_state: &AppState, op: &BenchmarkOperation) -> (i64
 }
 
 async fn benchmark_read(app_state: &AppState, op: &BenchmarkOperation) -> (i64, Option<BenchmarkMetrics>) {
-    let path = op.path.as_ref().map(|s| s.as_str()).unwrap_or("");
+    let path = op.path.as_deref().unwrap_or("");
     let stream = app_state.client.stream(path);
 
     let mut builder = stream.read();
@@ -1086,16 +1083,17 @@ async fn benchmark_read(app_state: &AppState, op: &BenchmarkOperation) -> (i64,
 }
 
 async fn benchmark_roundtrip(app_state: &AppState, op: &BenchmarkOperation) -> (i64, Option<BenchmarkMetrics>) {
-    let path = op.path.as_ref().map(|s| s.as_str()).unwrap_or("");
+    let path = op.path.as_deref().unwrap_or("");
     let size = op.size.unwrap_or(100);
-    let live = op.live.as_ref().map(|s| s.as_str()).unwrap_or("long-poll");
+    let live = op.live.as_deref().unwrap_or("long-poll");
 
-    let mut stream = app_state.client.stream(path);
+    let mut builder = app_state.client.stream_builder(path);
     if let Some(ct) = &op.content_type {
-        stream.set_content_type(ct.clone());
+        builder = builder.content_type(ct.clone());
     } else if let Some(ct) = app_state.stream_content_types.get(path) {
-        stream.set_content_type(ct.clone());
+        builder = builder.content_type(ct.clone());
     }
+    let stream = builder.build();
 
     let data: Vec<u8> = (0..size).map(|i| (i % 256) as u8).collect();
 
@@ -1129,8 +1127,8 @@ async fn benchmark_roundtrip(app_state: &AppState, op: &BenchmarkOperation) -> (
 }
 
 async fn benchmark_create(app_state: &AppState, op: &BenchmarkOperation) -> (i64, Option<BenchmarkMetrics>) {
-    let path = op.path.as_ref().map(|s| s.as_str()).unwrap_or("");
-    let content_type = op.content_type.as_ref().map(|s| s.as_str()).unwrap_or("application/octet-stream");
+    let path = op.path.as_deref().unwrap_or("");
+    let content_type = op.content_type.as_deref().unwrap_or("application/octet-stream");
 
     let stream = app_state.client.stream(path);
 
@@ -1140,7 +1138,7 @@ async fn benchmark_create(app_state: &AppState, op: &BenchmarkOperation) -> (i64
 }
 
 async fn benchmark_throughput_append(app_state: &AppState, op: &BenchmarkOperation) -> (i64, Option<BenchmarkMetrics>) {
-    let path = op.path.as_ref().map(|s| s.as_str()).unwrap_or("");
+    let path = op.path.as_deref().unwrap_or("");
     let count = op.count.unwrap_or(1000);
     let size = op.size.unwrap_or(100);
 
@@ -1185,9 +1183,12 @@ async fn benchmark_throughput_append(app_state: &AppState, op: &BenchmarkOperati
 }
 
 async fn benchmark_throughput_read(app_state: &AppState, op: &BenchmarkOperation) -> (i64, Option<BenchmarkMetrics>) {
-    let path = op.path.as_ref().map(|s| s.as_str()).unwrap_or("");
-    let mut stream = app_state.client.stream(path);
-    stream.set_content_type("application/json");
+    let path = op.path.as_deref().unwrap_or("");
+    let stream = app_state
+        .client
+        .stream_builder(path)
+        .content_type("application/json")
+        .build();
 
     let start = Instant::now();
 
@@ -1316,6 +1317,13 @@ fn producer_error_result(cmd_type: &str, err: durable_streams::ProducerError) ->
         durable_streams::ProducerError::SequenceGap { .. } => ("SEQUENCE_GAP", Some(409)),
         durable_streams::ProducerError::Stream { .. } => ("STREAM_ERROR", None),
         durable_streams::ProducerError::MixedAppendTypes => ("MIXED_APPEND_TYPES", None),
+        durable_streams::ProducerError::StateStore { .. } => ("STATE_STORE_ERROR", None),
+        durable_streams::ProducerError::Validation(_) => ("VALIDATION_ERROR", None),
+        // Report the first failed batch, which carries the protocol error
+        durable_streams::ProducerError::Flush { failures } => match failures.first() {
+            Some(first) => return producer_error_result(cmd_type, first.error.clone()),
+            None => ("STREAM_ERROR", None),
+        },
     };
 
     Result {
@@ -1328,33 +1336,3 @@ fn producer_error_result(cmd_type: &str, err: durable_streams::ProducerError) ->
         ..Default::default()
     }
 }
-
-impl Default for Result {
-    fn default() -> Self {
-        Self {
-            result_type: String::new(),
-            success: false,
-            client_name: None,
-            client_version: None,
-            features: None,
-            status: None,
-            offset: None,
-            content_type: None,
-            chunks: None,
-            up_to_date: None,
-            stream_closed: None,
-            final_offset: None,
-            cursor: None,
-            headers: None,
-            command_type: None,
-            error_code: None,
-            message: None,
-            duplicate: None,
-            iteration_id: None,
-            duration_ns: None,
-            metrics: None,
-            headers_sent: None,
-            params_sent: None,
-        }
-    }
-}
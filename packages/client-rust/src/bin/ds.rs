@@ -0,0 +1,394 @@
+//! `ds`: command-line tool for Durable Streams.
+//!
+//! Run `ds --help` for usage.
+
+use durable_streams::{
+    Client, CreateOptions, DurableStream, InMemoryOffsetStore, LiveMode, Offset, Replicator,
+    StreamError,
+};
+use std::io::{self, BufRead, Read, Write};
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::runtime::Runtime;
+
+const JSON: &str = "application/json";
+
+const USAGE: &str = "\
+Usage: ds [OPTIONS] <COMMAND> <URL> [ARGS]
+
+Commands:
+  create <url>           Create a stream
+  append <url> [data]    Append data, or stdin if no data is given
+  head <url>             Show stream metadata
+  delete <url>           Delete a stream
+  read <url>             Print the stream from --offset to its current end
+  tail <url>             Print new data as it arrives (from --offset, default now)
+  copy <src> <dst>       Copy a stream into another, creating it if needed
+  export <url> <file>    Save the stream to a portable export file
+  import <file> <url>    Create a stream from an export file
+
+Options:
+  -H, --header <NAME: VALUE>  Send a header with every request (repeatable)
+      --token <TOKEN>         Send `Authorization: Bearer <TOKEN>` [env: DS_TOKEN]
+      --base-url <URL>        Resolve relative stream URLs against this URL [env: DS_BASE_URL]
+      --offset <OFFSET>       Offset to read from: an offset token, -1 (beginning) or now
+      --live <MODE>           How to follow the stream: sse, long-poll or off
+                              (read and copy default to off, tail to sse)
+      --json                  create/append: use application/json
+                              head: print metadata as JSON
+                              read/tail: print one JSON message per line
+      --content-type <TYPE>   create/append: content type (append defaults to the stream's)
+      --ttl <SECONDS>         create: expire the stream after this long
+      --lines                 append: append each stdin line as its own message
+  -h, --help                  Print this help
+";
+
+/// Parsed command line.
+#[derive(Default)]
+struct Args {
+    positional: Vec<String>,
+    headers: Vec<(String, String)>,
+    token: Option<String>,
+    base_url: Option<String>,
+    offset: Option<Offset>,
+    live: Option<LiveMode>,
+    json: bool,
+    content_type: Option<String>,
+    ttl: Option<Duration>,
+    lines: bool,
+}
+
+/// Error for an invalid command line, printed with the usage hint.
+struct UsageError(String);
+
+fn parse_args(raw: impl IntoIterator<Item = String>) -> Result<Option<Args>, UsageError> {
+    let mut args = Args::default();
+    let mut raw = raw.into_iter();
+    while let Some(arg) = raw.next() {
+        if arg == "--" {
+            args.positional.extend(raw.by_ref());
+            break;
+        }
+        if arg == "-" || !arg.starts_with('-') {
+            args.positional.push(arg);
+            continue;
+        }
+
+        let (name, inline) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => {
+                (name.to_string(), Some(value.to_string()))
+            }
+            _ => (arg, None),
+        };
+        let mut value = |name: &str| {
+            inline
+                .clone()
+                .or_else(|| raw.next())
+                .ok_or_else(|| UsageError(format!("{name} needs a value")))
+        };
+        match name.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-H" | "--header" => {
+                let header = value(&name)?;
+                let (key, val) = header
+                    .split_once(':')
+                    .ok_or_else(|| UsageError(format!("header {header:?} is not NAME: VALUE")))?;
+                args.headers
+                    .push((key.trim().to_string(), val.trim().to_string()));
+            }
+            "--token" => args.token = Some(value(&name)?),
+            "--base-url" => args.base_url = Some(value(&name)?),
+            "--offset" => args.offset = Some(Offset::parse(&value(&name)?)),
+            "--live" => {
+                args.live = Some(match value(&name)?.as_str() {
+                    "sse" => LiveMode::Sse,
+                    "long-poll" => LiveMode::LongPoll,
+                    "off" => LiveMode::Off,
+                    other => {
+                        return Err(UsageError(format!(
+                            "unknown live mode {other:?} (expected sse, long-poll or off)"
+                        )))
+                    }
+                });
+            }
+            "--json" => args.json = true,
+            "--content-type" => args.content_type = Some(value(&name)?),
+            "--ttl" => {
+                let secs = value(&name)?;
+                let secs = secs.parse().map_err(|_| {
+                    UsageError(format!("--ttl {secs:?} is not a number of seconds"))
+                })?;
+                args.ttl = Some(Duration::from_secs(secs));
+            }
+            "--lines" => args.lines = true,
+            _ => return Err(UsageError(format!("unknown option {name}"))),
+        }
+    }
+    Ok(Some(args))
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(args)) if !args.positional.is_empty() => args,
+        Ok(_) => {
+            print!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(UsageError(message)) => {
+            eprintln!("ds: {message}\n\nRun `ds --help` for usage.");
+            return ExitCode::from(2);
+        }
+    };
+
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(Failure::Usage(message)) => {
+            eprintln!("ds: {message}\n\nRun `ds --help` for usage.");
+            ExitCode::from(2)
+        }
+        Err(Failure::Stream(e)) => {
+            eprintln!("ds: {e}");
+            ExitCode::FAILURE
+        }
+        // The reader went away, e.g. `ds tail ... | head`
+        Err(Failure::Io(e)) if e.kind() == io::ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(Failure::Io(e)) => {
+            eprintln!("ds: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+enum Failure {
+    Usage(String),
+    Stream(StreamError),
+    Io(io::Error),
+}
+
+impl From<StreamError> for Failure {
+    fn from(e: StreamError) -> Self {
+        Failure::Stream(e)
+    }
+}
+
+impl From<io::Error> for Failure {
+    fn from(e: io::Error) -> Self {
+        Failure::Io(e)
+    }
+}
+
+fn run(args: Args) -> Result<(), Failure> {
+    let client = client(&args)?;
+    let rt = Runtime::new()?;
+
+    let command = args.positional[0].as_str();
+    let urls = &args.positional[1..];
+    let arity = match command {
+        "create" | "head" | "delete" | "read" | "tail" => 1..=1,
+        "append" => 1..=2,
+        "copy" | "export" | "import" => 2..=2,
+        _ => return Err(Failure::Usage(format!("unknown command `{command}`"))),
+    };
+    if !arity.contains(&urls.len()) {
+        return Err(Failure::Usage(format!(
+            "wrong number of arguments for `{command}`"
+        )));
+    }
+    if command == "import" {
+        let summary = rt.block_on(client.stream(&urls[1]).import_from(&urls[0]))?;
+        writeln!(io::stdout(), "{}", summary.end_offset)?;
+        return Ok(());
+    }
+    let stream = client.stream(&urls[0]);
+
+    match command {
+        "create" => {
+            let mut options = CreateOptions::new();
+            if let Some(ct) = content_type(&args) {
+                options = options.content_type(ct);
+            }
+            if let Some(ttl) = args.ttl {
+                options = options.ttl(ttl);
+            }
+            rt.block_on(stream.create_with(options))?;
+        }
+        "append" => {
+            let ct = match content_type(&args) {
+                Some(ct) => ct,
+                None => rt
+                    .block_on(stream.head())?
+                    .content_type
+                    .unwrap_or_else(|| "application/octet-stream".to_string()),
+            };
+            let stream = client.stream_builder(&urls[0]).content_type(ct).build();
+            append(&rt, &stream, &args, urls.get(1))?;
+        }
+        "head" => head(&rt, &stream, args.json)?,
+        "delete" => rt.block_on(stream.delete())?,
+        "read" => {
+            let offset = args.offset.clone().unwrap_or(Offset::Beginning);
+            let live = args.live.clone().unwrap_or(LiveMode::Off);
+            rt.block_on(print_stream(&stream, offset, live, args.json))?;
+        }
+        "tail" => {
+            let offset = args.offset.clone().unwrap_or(Offset::Now);
+            let live = args.live.clone().unwrap_or(LiveMode::Sse);
+            rt.block_on(print_stream(&stream, offset, live, args.json))?;
+        }
+        "copy" => {
+            let target = client.stream(&urls[1]);
+            let live = args.live.clone().unwrap_or(LiveMode::Off);
+            let offset = rt.block_on(
+                Replicator::new(stream, target, Arc::new(InMemoryOffsetStore::new()))
+                    .live(live)
+                    .run(std::future::pending()),
+            )?;
+            writeln!(io::stdout(), "{offset}")?;
+        }
+        "export" => {
+            let summary = rt.block_on(stream.export_to(&urls[1]))?;
+            writeln!(io::stdout(), "{}", summary.end_offset)?;
+        }
+        _ => unreachable!("unknown commands are rejected above"),
+    }
+    Ok(())
+}
+
+fn client(args: &Args) -> Result<Client, Failure> {
+    let mut builder = Client::builder();
+    if let Some(url) = args
+        .base_url
+        .clone()
+        .or_else(|| std::env::var("DS_BASE_URL").ok())
+    {
+        builder = builder.base_url(url);
+    }
+    if let Some(token) = args
+        .token
+        .clone()
+        .or_else(|| std::env::var("DS_TOKEN").ok())
+    {
+        builder = builder
+            .try_default_header("authorization", &format!("Bearer {token}"))
+            .map_err(|e| Failure::Usage(e.to_string()))?;
+    }
+    for (name, value) in &args.headers {
+        builder = builder
+            .try_default_header(name, value)
+            .map_err(|e| Failure::Usage(e.to_string()))?;
+    }
+    Ok(builder.build().map_err(StreamError::from)?)
+}
+
+fn content_type(args: &Args) -> Option<String> {
+    match (&args.content_type, args.json) {
+        (Some(ct), _) => Some(ct.clone()),
+        (None, true) => Some(JSON.to_string()),
+        (None, false) => None,
+    }
+}
+
+/// Append `data`, or stdin if none was given, printing the new tail offset.
+fn append(
+    rt: &Runtime,
+    stream: &DurableStream,
+    args: &Args,
+    data: Option<&String>,
+) -> Result<(), Failure> {
+    let mut offset = None;
+    match data {
+        Some(data) if data != "-" => {
+            offset = Some(rt.block_on(stream.append(data.clone()))?.next_offset);
+        }
+        _ if args.lines => {
+            for line in io::stdin().lock().lines() {
+                let line = line?;
+                if line.is_empty() {
+                    continue;
+                }
+                offset = Some(rt.block_on(stream.append(line))?.next_offset);
+            }
+        }
+        _ => {
+            let mut data = Vec::new();
+            io::stdin().read_to_end(&mut data)?;
+            if !data.is_empty() {
+                offset = Some(rt.block_on(stream.append(data))?.next_offset);
+            }
+        }
+    }
+    if let Some(offset) = offset {
+        writeln!(io::stdout(), "{offset}")?;
+    }
+    Ok(())
+}
+
+fn head(rt: &Runtime, stream: &DurableStream, json: bool) -> Result<(), Failure> {
+    let meta = rt.block_on(stream.metadata())?;
+    if json {
+        let mut value = serde_json::json!({
+            "next_offset": meta.next_offset,
+            "content_type": meta.content_type,
+            "ttl_seconds": meta.ttl.map(|ttl| ttl.as_secs()),
+            "expires_at": meta.expires_at,
+            "etag": meta.etag,
+            "closed": meta.stream_closed,
+        });
+        if !meta.other.is_empty() {
+            value["headers"] = serde_json::json!(meta.other);
+        }
+        writeln!(io::stdout(), "{value}")?;
+        return Ok(());
+    }
+
+    writeln!(io::stdout(), "next-offset: {}", meta.next_offset)?;
+    if let Some(ct) = &meta.content_type {
+        writeln!(io::stdout(), "content-type: {ct}")?;
+    }
+    if let Some(ttl) = meta.ttl {
+        writeln!(io::stdout(), "ttl: {}s", ttl.as_secs())?;
+    }
+    if let Some(expires_at) = &meta.expires_at {
+        writeln!(io::stdout(), "expires-at: {expires_at}")?;
+    }
+    if let Some(etag) = &meta.etag {
+        writeln!(io::stdout(), "etag: {etag}")?;
+    }
+    writeln!(io::stdout(), "closed: {}", meta.stream_closed)?;
+    for (name, value) in &meta.other {
+        writeln!(io::stdout(), "{name}: {value}")?;
+    }
+    Ok(())
+}
+
+/// Write the stream to stdout as it is read.
+async fn print_stream(
+    stream: &DurableStream,
+    offset: Offset,
+    live: LiveMode,
+    json: bool,
+) -> Result<(), Failure> {
+    let mut stdout = io::stdout().lock();
+    if json {
+        let mut reader = stream
+            .read()
+            .offset(offset)
+            .live(live)
+            .typed::<serde_json::Value>()
+            .build()?;
+        while let Some(chunk) = reader.next_chunk().await? {
+            for item in chunk.items {
+                writeln!(stdout, "{item}")?;
+            }
+            stdout.flush()?;
+        }
+    } else {
+        let mut reader = stream.read().offset(offset).live(live).build()?;
+        while let Some(chunk) = reader.next_chunk().await? {
+            stdout.write_all(&chunk.data)?;
+            stdout.flush()?;
+        }
+    }
+    Ok(())
+}
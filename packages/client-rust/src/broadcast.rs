@@ -0,0 +1,126 @@
+//! Fan one reader out to many in-process consumers.
+
+use crate::error::StreamError;
+use crate::iterator::{Chunk, ChunkIterator};
+use crate::retry::sleep_before_retry;
+use crate::rt;
+use crate::types::Offset;
+use tokio::sync::{broadcast, oneshot};
+
+impl ChunkIterator {
+    /// Move this reader to a background task that sends every chunk to
+    /// any number of in-process subscribers.
+    ///
+    /// One network read then feeds e.g. many websocket sessions or caches.
+    /// Each receiver buffers up to `capacity` chunks; one that falls
+    /// further behind gets [`RecvError::Lagged`](broadcast::error::RecvError::Lagged)
+    /// and skips ahead. Receivers see the stream's end as
+    /// [`RecvError::Closed`](broadcast::error::RecvError::Closed); use
+    /// [`ChunkBroadcast::join`] for how it ended.
+    ///
+    /// Retryable errors are retried with the client's backoff. The reader
+    /// stops when the stream ends, on any other error, or when the
+    /// [`ChunkBroadcast`] is dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let (feed, mut rx) = stream.read().live(LiveMode::Sse).build()?.into_broadcast(256);
+    ///
+    /// for socket in sockets {
+    ///     let mut rx = feed.subscribe();
+    ///     tokio::spawn(async move {
+    ///         while let Ok(chunk) = rx.recv().await {
+    ///             socket.send(chunk.data).await?;
+    ///         }
+    ///         Ok::<_, Error>(())
+    ///     });
+    /// }
+    /// ```
+    pub fn into_broadcast(self, capacity: usize) -> (ChunkBroadcast, broadcast::Receiver<Chunk>) {
+        let (tx, rx) = broadcast::channel(capacity);
+        let (stop_tx, stop_rx) = oneshot::channel();
+        let (result_tx, result_rx) = oneshot::channel();
+        let handle = ChunkBroadcast {
+            tx: tx.downgrade(),
+            _stop: stop_tx,
+            result: result_rx,
+        };
+        rt::spawn(fan_out(self, tx, stop_rx, result_tx));
+        (handle, rx)
+    }
+}
+
+/// Handle to a reader shared by broadcast, from
+/// [`ChunkIterator::into_broadcast`]. Dropping it stops the reader.
+#[derive(Debug)]
+pub struct ChunkBroadcast {
+    /// Weak, so receivers see the channel close when the reader ends.
+    tx: broadcast::WeakSender<Chunk>,
+    /// Dropped with the handle, which stops the reader.
+    _stop: oneshot::Sender<()>,
+    result: oneshot::Receiver<Result<Offset, StreamError>>,
+}
+
+impl ChunkBroadcast {
+    /// A new receiver, getting every chunk read from now on. If the reader
+    /// has ended, the receiver is already closed.
+    pub fn subscribe(&self) -> broadcast::Receiver<Chunk> {
+        match self.tx.upgrade() {
+            Some(tx) => tx.subscribe(),
+            None => broadcast::channel(1).1,
+        }
+    }
+
+    /// Number of receivers currently subscribed.
+    pub fn receiver_count(&self) -> usize {
+        self.tx.upgrade().map_or(0, |tx| tx.receiver_count())
+    }
+
+    /// Wait for the reader to end. Returns the offset after the last chunk
+    /// read, or the error that stopped it.
+    pub async fn join(mut self) -> Result<Offset, StreamError> {
+        (&mut self.result)
+            .await
+            .unwrap_or(Err(StreamError::IteratorClosed))
+    }
+}
+
+async fn fan_out(
+    mut iter: ChunkIterator,
+    tx: broadcast::Sender<Chunk>,
+    mut stop: oneshot::Receiver<()>,
+    result: oneshot::Sender<Result<Offset, StreamError>>,
+) {
+    let mut failures = 0;
+    let outcome = loop {
+        let next = tokio::select! {
+            _ = &mut stop => break Ok(iter.offset().clone()),
+            next = iter.next_chunk() => next,
+        };
+        match next {
+            Ok(Some(chunk)) => {
+                failures = 0;
+                // No receivers right now is fine; later subscribers pick up
+                // from the next chunk
+                let _ = tx.send(chunk);
+            }
+            Ok(None) => break Ok(iter.offset().clone()),
+            Err(err) if err.is_retryable() => {
+                failures += 1;
+                let stream = iter.stream();
+                tokio::select! {
+                    _ = &mut stop => break Ok(iter.offset().clone()),
+                    _ = sleep_before_retry(&stream.client, &stream.url, failures, &err) => {}
+                }
+            }
+            Err(err) => break Err(err),
+        }
+    };
+    iter.close().await;
+    let _ = result.send(outcome);
+}
@@ -0,0 +1,74 @@
+//! CRC32C content digests (RFC 9530 `Content-Digest`).
+
+use crate::error::StreamError;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+
+pub(crate) const HEADER_CONTENT_DIGEST: &str = "content-digest";
+
+fn format(crc: u32) -> String {
+    format!("crc32c=:{}:", BASE64.encode(crc.to_be_bytes()))
+}
+
+/// `Content-Digest` value for `data`.
+pub(crate) fn content_digest(data: &[u8]) -> String {
+    format(crc32c::crc32c(data))
+}
+
+/// The CRC32C in a `Content-Digest` header, if it carries one.
+///
+/// Other algorithms are ignored; a malformed `crc32c` member is an error.
+pub(crate) fn expected(header: Option<&str>) -> Result<Option<u32>, StreamError> {
+    let Some(header) = header else {
+        return Ok(None);
+    };
+    for member in header.split(',') {
+        let Some((alg, value)) = member.split_once('=') else {
+            continue;
+        };
+        if !alg.trim().eq_ignore_ascii_case("crc32c") {
+            continue;
+        }
+        let value = value.split(';').next().unwrap_or_default().trim();
+        let bytes = value
+            .strip_prefix(':')
+            .and_then(|v| v.strip_suffix(':'))
+            .and_then(|v| BASE64.decode(v).ok())
+            .and_then(|b| <[u8; 4]>::try_from(b).ok())
+            .ok_or_else(|| StreamError::ParseError(format!("invalid crc32c digest: {value}")))?;
+        return Ok(Some(u32::from_be_bytes(bytes)));
+    }
+    Ok(None)
+}
+
+/// Running CRC32C of a body read in pieces.
+#[derive(Debug)]
+pub(crate) struct Digest {
+    expected: u32,
+    actual: u32,
+}
+
+impl Digest {
+    pub(crate) fn new(expected: u32) -> Self {
+        Self {
+            expected,
+            actual: 0,
+        }
+    }
+
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        self.actual = crc32c::crc32c_append(self.actual, data);
+    }
+
+    /// Check the whole body has been seen and matches.
+    pub(crate) fn finish(&self) -> Result<(), StreamError> {
+        if self.actual == self.expected {
+            Ok(())
+        } else {
+            Err(StreamError::ChecksumMismatch {
+                expected: format(self.expected),
+                actual: format(self.actual),
+            })
+        }
+    }
+}
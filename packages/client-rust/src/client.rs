@@ -1,8 +1,32 @@
 //! HTTP client and configuration.
 
-use crate::error::InvalidHeaderError;
-use crate::stream::DurableStream;
-use reqwest::header::HeaderMap;
+use crate::auth::AuthProvider;
+#[cfg(feature = "checksum")]
+use crate::checksum;
+use crate::error::{InvalidHeaderError, StreamError};
+use crate::failover::Endpoints;
+#[cfg(all(feature = "http2", not(target_arch = "wasm32")))]
+use crate::http2::Http2Config;
+use crate::metrics::Metrics;
+use crate::middleware::{Middleware, Operation, RequestParts};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::net::{IpPreference, NetConfig};
+use crate::long_poll::LongPollScheduler;
+use crate::rate_limit::{RateLimiter, TokenBucket};
+use crate::redact;
+use crate::redirect::RedirectPolicy;
+use crate::request_id;
+use crate::rt;
+use crate::retry::{RetryConfig, RetryEvent, RetryHook};
+use crate::stream::{AppendOptions, CreateOptions, DurableStream, ReadOptions, StreamBuilder};
+use crate::transport::HttpTransport;
+#[cfg(all(any(feature = "rustls", feature = "native-tls"), not(target_arch = "wasm32")))]
+use crate::tls::TlsConfig;
+#[cfg(feature = "checksum")]
+use reqwest::header::HeaderValue;
+use reqwest::header::{HeaderMap, HeaderName};
+#[cfg(not(target_arch = "wasm32"))]
+use std::net::IpAddr;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -12,10 +36,39 @@ use std::time::Duration;
 /// It manages connection pooling.
 #[derive(Clone)]
 pub struct Client {
+    /// Builds requests; they are sent through `transport`.
     pub(crate) inner: reqwest::Client,
+    pub(crate) transport: Arc<dyn HttpTransport>,
     pub(crate) base_url: Option<String>,
     pub(crate) default_headers: HeaderMap,
     pub(crate) header_provider: Option<Arc<dyn Fn() -> HeaderMap + Send + Sync>>,
+    pub(crate) retry: RetryConfig,
+    pub(crate) on_retry: Option<RetryHook>,
+    pub(crate) middleware: Vec<Arc<dyn Middleware>>,
+    pub(crate) auth: Option<Arc<dyn AuthProvider>>,
+    pub(crate) metrics: Arc<Metrics>,
+    pub(crate) rate_limiter: Arc<RateLimiter>,
+    /// Per-host long-poll slots; `None` leaves long-polls unscheduled.
+    pub(crate) long_polls: Option<Arc<LongPollScheduler>>,
+    /// Redirects followed by the client; `None` leaves them to reqwest.
+    pub(crate) redirects: Option<Arc<RedirectPolicy>>,
+    pub(crate) endpoints: Option<Arc<Endpoints>>,
+    /// Prefix for generated request IDs; `None` disables them.
+    pub(crate) request_id_prefix: Option<Arc<str>>,
+    /// Headers whose values are flagged sensitive before sending.
+    pub(crate) redacted_headers: Arc<[HeaderName]>,
+    /// Whether bodies are checksummed on appends and verified on reads.
+    #[cfg(feature = "checksum")]
+    pub(crate) checksums: bool,
+    pub(crate) defaults: Arc<Defaults>,
+}
+
+/// Options applied to every call unless the call overrides them.
+#[derive(Debug, Default)]
+pub(crate) struct Defaults {
+    pub(crate) create: CreateOptions,
+    pub(crate) append: AppendOptions,
+    pub(crate) read: ReadOptions,
 }
 
 impl std::fmt::Debug for Client {
@@ -24,6 +77,11 @@ impl std::fmt::Debug for Client {
             .field("base_url", &self.base_url)
             .field("default_headers", &self.default_headers)
             .field("has_header_provider", &self.header_provider.is_some())
+            .field("retry", &self.retry)
+            .field("has_retry_hook", &self.on_retry.is_some())
+            .field("middleware", &self.middleware.len())
+            .field("has_auth_provider", &self.auth.is_some())
+            .field("redacted_headers", &self.redacted_headers)
             .finish()
     }
 }
@@ -69,6 +127,58 @@ impl Client {
         }
     }
 
+    /// Like [`stream`](Self::stream), but check the resolved URL up front.
+    ///
+    /// Fails with [`StreamError::InvalidUrl`] if it is not an absolute
+    /// `http` or `https` URL, e.g. a path given without a
+    /// [`base_url`](ClientBuilder::base_url), or if it has a fragment, which
+    /// usually means a stream name with an unescaped `#`. The URL is
+    /// normalized, percent-encoding characters such as spaces.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let stream = client.try_stream(&format!("/v1/stream/{name}"))?;
+    /// ```
+    pub fn try_stream(&self, url: &str) -> Result<DurableStream, StreamError> {
+        let mut stream = self.stream(url);
+        let invalid = |message: &str| StreamError::InvalidUrl {
+            url: stream.url.clone(),
+            message: message.to_string(),
+        };
+        let parsed = reqwest::Url::parse(&stream.url).map_err(|e| invalid(&e.to_string()))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(invalid("scheme must be http or https"));
+        }
+        if parsed.fragment().is_some() {
+            return Err(invalid("fragments are not sent to the server; escape `#` as %23"));
+        }
+        stream.url = parsed.into();
+        Ok(stream)
+    }
+
+    /// Configure a stream handle before using it: its content type, and
+    /// headers and retries that apply to it alone.
+    ///
+    /// The url is resolved as in [`stream`](Self::stream).
+    ///
+    /// # Example
+    /// ```ignore
+    /// let events = client
+    ///     .stream_builder("/v1/stream/events")
+    ///     .content_type("application/json")
+    ///     .header("x-tenant", "acme")
+    ///     .retry(RetryConfig { max_retries: 10, ..Default::default() })
+    ///     .build();
+    /// ```
+    pub fn stream_builder(&self, url: &str) -> StreamBuilder {
+        StreamBuilder::new(self.stream(url))
+    }
+
+    /// Metrics recorded by this client and its clones.
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
     /// Get headers for a request, including dynamic headers if configured.
     pub(crate) fn get_headers(&self) -> HeaderMap {
         let mut headers = self.default_headers.clone();
@@ -79,6 +189,147 @@ impl Client {
         }
         headers
     }
+
+    /// Send a request, applying auth headers and the middleware chain.
+    ///
+    /// On 401, the auth provider (if any) gets one chance to refresh
+    /// credentials, after which the request is retried once.
+    pub(crate) async fn send(
+        &self,
+        operation: Operation,
+        req: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, reqwest::Error> {
+        self.send_request(operation, req.build()?).await
+    }
+
+    /// Like [`send`](Self::send), for a request that is already built.
+    pub(crate) async fn send_request(
+        &self,
+        operation: Operation,
+        request: reqwest::Request,
+    ) -> Result<reqwest::Response, reqwest::Error> {
+        let Some(auth) = &self.auth else {
+            return self.execute(operation, request).await;
+        };
+
+        // All request bodies are buffered, so cloning only fails for
+        // exotic cases - which then simply aren't retried
+        let retry = request.try_clone();
+        let resp = self.execute(operation, request).await?;
+        if resp.status().as_u16() == 401 {
+            if let Some(retry) = retry {
+                if auth.on_unauthorized().await {
+                    return self.execute(operation, retry).await;
+                }
+            }
+        }
+        Ok(resp)
+    }
+
+    async fn execute(
+        &self,
+        operation: Operation,
+        mut request: reqwest::Request,
+    ) -> Result<reqwest::Response, reqwest::Error> {
+        if !self.rate_limiter.is_unlimited() {
+            self.rate_limiter.acquire(operation).await;
+        }
+
+        if let Some(prefix) = &self.request_id_prefix {
+            request_id::stamp(&mut request, prefix);
+        }
+
+        if let Some(auth) = &self.auth {
+            let mut headers = auth.headers().await;
+            redact::mark_all(&mut headers);
+            for (key, value) in headers.iter() {
+                request.headers_mut().insert(key.clone(), value.clone());
+            }
+        }
+        redact::mark(request.headers_mut(), &self.redacted_headers);
+
+        if !self.middleware.is_empty() {
+            let mut parts = RequestParts::from_request(operation, &request);
+            for middleware in &self.middleware {
+                parts = middleware.handle(parts).await;
+            }
+            parts.apply(&mut request);
+            redact::mark(request.headers_mut(), &self.redacted_headers);
+        }
+
+        // Computed last, so the digest covers any body middleware rewrote
+        #[cfg(feature = "checksum")]
+        if self.checksums && matches!(operation, Operation::Create | Operation::Append | Operation::ProducerAppend) {
+            if let Some(body) = request.body().and_then(|b| b.as_bytes()) {
+                let digest = checksum::content_digest(body);
+                if let Ok(value) = HeaderValue::from_str(&digest) {
+                    request.headers_mut().insert(checksum::HEADER_CONTENT_DIGEST, value);
+                }
+            }
+        }
+        let request_id = request.headers().get(request_id::HEADER_REQUEST_ID).cloned();
+
+        let body_bytes = request
+            .body()
+            .and_then(|b| b.as_bytes())
+            .map_or(0, <[u8]>::len);
+        self.metrics.request_started(body_bytes);
+        let started = rt::Instant::now();
+        let result = match &self.endpoints {
+            Some(endpoints) => self.execute_with_failover(endpoints, request).await,
+            None => self.send_following(request).await,
+        };
+        let failed = result.as_ref().map_or(true, |r| r.status().as_u16() >= 400);
+        self.metrics
+            .request_finished(operation, started.elapsed(), failed);
+        result.map(|mut resp| {
+            // Servers that don't echo the ID still get it reported
+            if let Some(id) = request_id {
+                resp.headers_mut()
+                    .entry(request_id::HEADER_REQUEST_ID)
+                    .or_insert(id);
+            }
+            resp
+        })
+    }
+}
+
+impl Client {
+    /// Send `request` to the best available endpoint, moving on to the next
+    /// one if the connection fails.
+    async fn execute_with_failover(
+        &self,
+        endpoints: &Endpoints,
+        mut request: reqwest::Request,
+    ) -> Result<reqwest::Response, reqwest::Error> {
+        let mut tried = Vec::new();
+        loop {
+            let index = endpoints.pick(&tried);
+            tried.push(index);
+            let next = if tried.len() < endpoints.len() {
+                request.try_clone()
+            } else {
+                None
+            };
+            endpoints.route(request.url_mut(), index);
+
+            let result = self.send_following(request).await;
+            match &result {
+                Err(e) if e.is_connect() => {
+                    endpoints.failed(index);
+                    if let Some(next) = next {
+                        request = next;
+                        continue;
+                    }
+                }
+                // Left to the retry policy, which will land elsewhere
+                Ok(resp) if matches!(resp.status().as_u16(), 502..=504) => endpoints.failed(index),
+                Ok(_) => endpoints.succeeded(index),
+                Err(_) => {}
+            }
+            return result;
+        }
+    }
 }
 
 impl Default for Client {
@@ -93,7 +344,36 @@ pub struct ClientBuilder {
     base_url: Option<String>,
     default_headers: HeaderMap,
     timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    pool_max_idle_per_host: usize,
+    pool_idle_timeout: Option<Duration>,
+    tcp_keepalive: Option<Duration>,
+    tcp_nodelay: bool,
+    #[cfg(not(target_arch = "wasm32"))]
+    net: NetConfig,
+    #[cfg(unix)]
+    unix_socket: Option<std::path::PathBuf>,
+    #[cfg(all(feature = "http2", not(target_arch = "wasm32")))]
+    http2: Http2Config,
+    #[cfg(all(any(feature = "rustls", feature = "native-tls"), not(target_arch = "wasm32")))]
+    tls: TlsConfig,
     header_provider: Option<Arc<dyn Fn() -> HeaderMap + Send + Sync>>,
+    retry: RetryConfig,
+    on_retry: Option<RetryHook>,
+    middleware: Vec<Arc<dyn Middleware>>,
+    auth: Option<Arc<dyn AuthProvider>>,
+    http_client: Option<reqwest::Client>,
+    transport: Option<Arc<dyn HttpTransport>>,
+    rate_limiter: RateLimiter,
+    long_poll_limit: Option<usize>,
+    long_poll_stagger: Duration,
+    redirects: Option<RedirectPolicy>,
+    endpoints: Vec<String>,
+    request_id_prefix: Option<String>,
+    redacted_headers: Vec<HeaderName>,
+    #[cfg(feature = "checksum")]
+    checksums: bool,
+    defaults: Defaults,
 }
 
 impl ClientBuilder {
@@ -103,7 +383,36 @@ impl ClientBuilder {
             base_url: None,
             default_headers: HeaderMap::new(),
             timeout: None,
+            connect_timeout: None,
+            pool_max_idle_per_host: 10,
+            pool_idle_timeout: Some(Duration::from_secs(90)),
+            tcp_keepalive: None,
+            tcp_nodelay: true,
+            #[cfg(not(target_arch = "wasm32"))]
+            net: NetConfig::default(),
+            #[cfg(unix)]
+            unix_socket: None,
+            #[cfg(all(feature = "http2", not(target_arch = "wasm32")))]
+            http2: Http2Config::default(),
+            #[cfg(all(any(feature = "rustls", feature = "native-tls"), not(target_arch = "wasm32")))]
+            tls: TlsConfig::default(),
             header_provider: None,
+            retry: RetryConfig::default(),
+            on_retry: None,
+            middleware: Vec::new(),
+            auth: None,
+            http_client: None,
+            transport: None,
+            rate_limiter: RateLimiter::default(),
+            long_poll_limit: None,
+            long_poll_stagger: Duration::ZERO,
+            redirects: None,
+            endpoints: Vec::new(),
+            request_id_prefix: Some(String::new()),
+            redacted_headers: redact::default_names(),
+            #[cfg(feature = "checksum")]
+            checksums: false,
+            defaults: Defaults::default(),
         }
     }
 
@@ -138,8 +447,13 @@ impl ClientBuilder {
     ) -> std::result::Result<Self, InvalidHeaderError> {
         let name = reqwest::header::HeaderName::from_bytes(key.as_bytes())
             .map_err(|_| InvalidHeaderError::InvalidName(key.to_string()))?;
-        let val = reqwest::header::HeaderValue::from_str(value)
-            .map_err(|_| InvalidHeaderError::InvalidValue(value.to_string()))?;
+        let val = reqwest::header::HeaderValue::from_str(value).map_err(|_| {
+            if self.redacted_headers.contains(&name) {
+                InvalidHeaderError::InvalidValue("[redacted]".to_string())
+            } else {
+                InvalidHeaderError::InvalidValue(value.to_string())
+            }
+        })?;
         self.default_headers.insert(name, val);
         Ok(self)
     }
@@ -150,12 +464,258 @@ impl ClientBuilder {
         self
     }
 
-    /// Set the request timeout.
-    pub fn timeout(mut self, timeout: Duration) -> Self {
+    /// Set the request timeout; the same as
+    /// [`request_timeout`](Self::request_timeout).
+    pub fn timeout(self, timeout: Duration) -> Self {
+        self.request_timeout(timeout)
+    }
+
+    /// Default time limit for each request, from sending it to reading the
+    /// whole response body.
+    ///
+    /// Individual calls can override it: see [`CreateOptions::timeout`],
+    /// [`AppendOptions::timeout`], [`HeadOptions::timeout`],
+    /// [`ProducerBuilder::request_timeout`] and
+    /// [`ReadBuilder::request_timeout`]. Long-poll reads use
+    /// [`ReadBuilder::timeout`] instead. Ignored on wasm, where the host's
+    /// fetch implementation applies its own limits.
+    ///
+    /// [`CreateOptions::timeout`]: crate::CreateOptions::timeout
+    /// [`AppendOptions::timeout`]: crate::AppendOptions::timeout
+    /// [`HeadOptions::timeout`]: crate::HeadOptions::timeout
+    /// [`ProducerBuilder::request_timeout`]: crate::ProducerBuilder::request_timeout
+    /// [`ReadBuilder::request_timeout`]: crate::ReadBuilder::request_timeout
+    /// [`ReadBuilder::timeout`]: crate::ReadBuilder::timeout
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
         self.timeout = Some(timeout);
         self
     }
 
+    /// Time limit for establishing a connection, including TLS.
+    ///
+    /// Unlike [`request_timeout`](Self::request_timeout), this does not
+    /// bound slow responses, so it can stay short even when large catch-up
+    /// reads need a long request timeout. Ignored on wasm.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Maximum idle connections kept per host (default 10).
+    ///
+    /// High-fan-out services reading many streams from one host may want
+    /// more; `0` disables pooling. Ignored on wasm.
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = max;
+        self
+    }
+
+    /// How long an idle pooled connection is kept (default 90 seconds);
+    /// `None` keeps it until the server closes it. Ignored on wasm.
+    pub fn pool_idle_timeout(mut self, timeout: impl Into<Option<Duration>>) -> Self {
+        self.pool_idle_timeout = timeout.into();
+        self
+    }
+
+    /// Enable TCP keepalive probes at this interval (default off), so
+    /// long-lived live reads notice dead peers and NAT timeouts are avoided.
+    /// Ignored on wasm.
+    pub fn tcp_keepalive(mut self, interval: impl Into<Option<Duration>>) -> Self {
+        self.tcp_keepalive = interval.into();
+        self
+    }
+
+    /// Set `TCP_NODELAY` on connections (default `true`). Ignored on wasm.
+    pub fn tcp_nodelay(mut self, enabled: bool) -> Self {
+        self.tcp_nodelay = enabled;
+        self
+    }
+
+    /// Which IP family to connect over when a host resolves to both
+    /// (default: the system resolver's order).
+    ///
+    /// The preferred family is tried first, with the other as a fallback
+    /// after a short delay; the `*Only` variants drop the other family
+    /// entirely, e.g. on hosts whose IPv6 route is broken.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn ip_preference(mut self, preference: IpPreference) -> Self {
+        self.net.ip_preference = preference;
+        self
+    }
+
+    /// Bind outgoing connections to `addr`, choosing the interface on a
+    /// multi-homed host.
+    ///
+    /// Only hosts reachable over `addr`'s family can be connected to;
+    /// pair an IPv4 address with [`IpPreference::Ipv4Only`] when servers
+    /// also have IPv6 addresses.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn local_address(mut self, addr: IpAddr) -> Self {
+        self.net.local_address = Some(addr);
+        self
+    }
+
+    /// Resolve `host` to `addrs` instead of asking DNS, as in an
+    /// `/etc/hosts` entry.
+    ///
+    /// Use it for split-horizon DNS, where the name in stream URLs must
+    /// reach internal addresses the resolver does not return. The port
+    /// still comes from the URL. Calling it again for the same host
+    /// replaces its entry.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let client = Client::builder()
+    ///     .base_url("https://streams.example.com")
+    ///     .resolve("streams.example.com", ["10.0.4.21".parse()?, "10.0.4.22".parse()?])
+    ///     .local_address("10.0.4.7".parse()?)
+    ///     .ip_preference(IpPreference::Ipv4Only)
+    ///     .build()?;
+    /// ```
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn resolve<I>(mut self, host: impl Into<String>, addrs: I) -> Self
+    where
+        I: IntoIterator<Item = IpAddr>,
+    {
+        let host = host.into().to_ascii_lowercase();
+        self.net.hosts.insert(host, addrs.into_iter().collect());
+        self
+    }
+
+    /// Follow redirects under `policy` instead of reqwest's default (up to
+    /// 10 hops, credentials dropped across origins).
+    ///
+    /// Deployments that answer with signed redirects to region-local
+    /// endpoints can allow those while dropping credentials the target
+    /// should not see; others can refuse redirects outright. A redirect
+    /// that is not followed fails with [`StreamError::Redirect`]. It applies
+    /// to any [`transport`](Self::transport), but a client passed to
+    /// [`with_http_client`](Self::with_http_client) follows redirects by its
+    /// own policy first.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let client = Client::builder()
+    ///     .redirect_policy(
+    ///         RedirectPolicy::new()
+    ///             .max_redirects(3)
+    ///             .strip_auth(StripAuth::CrossOrigin),
+    ///     )
+    ///     .build()?;
+    /// ```
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn redirect_policy(mut self, policy: RedirectPolicy) -> Self {
+        self.redirects = Some(policy);
+        self
+    }
+
+    /// Send every request over the Unix domain socket at `path` instead of
+    /// TCP.
+    ///
+    /// Stream URLs keep their usual form; their host is only used for the
+    /// `Host` header, so `http://localhost/...` is typical. Proxy and other
+    /// TCP settings do not apply.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let client = Client::builder()
+    ///     .unix_socket("/run/durable-streams.sock")
+    ///     .base_url("http://localhost")
+    ///     .build()?;
+    /// let stream = client.stream("/v1/stream/events");
+    /// ```
+    #[cfg(unix)]
+    pub fn unix_socket(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.unix_socket = Some(path.into());
+        self
+    }
+
+    /// Speak HTTP/2 from the first byte, without negotiation.
+    ///
+    /// This is the only way to use HTTP/2 over plain `http://` (h2c), as in
+    /// many service meshes, and makes `https://` connections HTTP/2-only.
+    /// Without it, HTTP/2 is still used over TLS when the server offers it.
+    /// Either way, requests to one host share a connection, so a producer's
+    /// in-flight batches are multiplexed rather than opening a connection
+    /// each.
+    ///
+    /// The number of concurrent HTTP/2 streams per connection cannot be
+    /// configured: reqwest does not expose the setting, so the limit the
+    /// server advertises in its `SETTINGS` frame applies. Cap a producer's
+    /// concurrency with
+    /// [`ProducerBuilder::max_in_flight`](crate::ProducerBuilder::max_in_flight)
+    /// instead.
+    #[cfg(all(feature = "http2", not(target_arch = "wasm32")))]
+    pub fn http2_prior_knowledge(mut self) -> Self {
+        self.http2.prior_knowledge = true;
+        self
+    }
+
+    /// Send HTTP/2 pings every `interval` and drop the connection if one
+    /// goes unanswered for `timeout`.
+    ///
+    /// Pings are only sent while requests are open unless
+    /// [`http2_keep_alive_while_idle`](Self::http2_keep_alive_while_idle)
+    /// is set.
+    #[cfg(all(feature = "http2", not(target_arch = "wasm32")))]
+    pub fn http2_keep_alive(mut self, interval: Duration, timeout: Duration) -> Self {
+        self.http2.keep_alive = Some((interval, timeout));
+        self
+    }
+
+    /// Keep pinging connections with no open requests (default `false`).
+    #[cfg(all(feature = "http2", not(target_arch = "wasm32")))]
+    pub fn http2_keep_alive_while_idle(mut self, enabled: bool) -> Self {
+        self.http2.keep_alive_while_idle = enabled;
+        self
+    }
+
+    /// Size HTTP/2 flow-control windows from measured bandwidth and latency
+    /// (default `false`), which helps large catch-up reads on
+    /// high-latency links.
+    #[cfg(all(feature = "http2", not(target_arch = "wasm32")))]
+    pub fn http2_adaptive_window(mut self, enabled: bool) -> Self {
+        self.http2.adaptive_window = enabled;
+        self
+    }
+
+    /// Trust the certificates in `pem` in addition to the built-in roots.
+    ///
+    /// `pem` may hold several certificates, e.g. a private CA bundle. It is
+    /// parsed by [`build`](Self::build), which fails if it is malformed.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let client = Client::builder()
+    ///     .add_root_certificate_pem(std::fs::read("internal-ca.pem")?)
+    ///     .build()?;
+    /// ```
+    #[cfg(all(any(feature = "rustls", feature = "native-tls"), not(target_arch = "wasm32")))]
+    pub fn add_root_certificate_pem(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.tls.root_certificates.push(pem.into());
+        self
+    }
+
+    /// Whether to trust the TLS backend's built-in root certificates
+    /// (default `true`). Disable to trust only roots added with
+    /// [`add_root_certificate_pem`](Self::add_root_certificate_pem).
+    #[cfg(all(any(feature = "rustls", feature = "native-tls"), not(target_arch = "wasm32")))]
+    pub fn tls_built_in_root_certs(mut self, enabled: bool) -> Self {
+        self.tls.built_in_roots = enabled;
+        self
+    }
+
+    /// Present a client certificate (mutual TLS).
+    ///
+    /// `cert_pem` is the certificate chain and `key_pem` its private key.
+    /// With the `native-tls` feature the key must be PKCS#8; with `rustls`
+    /// PKCS#1, PKCS#8 and SEC1 keys are accepted.
+    #[cfg(all(any(feature = "rustls", feature = "native-tls"), not(target_arch = "wasm32")))]
+    pub fn client_identity_pem(mut self, cert_pem: impl Into<Vec<u8>>, key_pem: impl Into<Vec<u8>>) -> Self {
+        self.tls.identity = Some((cert_pem.into(), key_pem.into()));
+        self
+    }
+
     /// Set a dynamic header provider (called per-request).
     pub fn header_provider<F>(mut self, provider: F) -> Self
     where
@@ -165,30 +725,386 @@ impl ClientBuilder {
         self
     }
 
+    /// Set the retry/backoff configuration.
+    pub fn retry_config(mut self, config: RetryConfig) -> Self {
+        self.retry = config;
+        self
+    }
+
+    /// Set a hook called before every retry.
+    ///
+    /// The hook receives a [`RetryEvent`] describing the attempt, the
+    /// scheduled and jittered delays, and the cause - useful for logging
+    /// where time went on slow requests. It runs on the request's task, so
+    /// keep it cheap.
+    pub fn on_retry<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&RetryEvent) + Send + Sync + 'static,
+    {
+        self.on_retry = Some(Arc::new(hook));
+        self
+    }
+
+    /// Set an async authentication provider.
+    ///
+    /// Its headers are added to every request (after default and dynamic
+    /// headers, before middleware). On 401 the provider may refresh its
+    /// credentials, and the request is retried once.
+    pub fn auth_provider(mut self, provider: impl AuthProvider + 'static) -> Self {
+        self.auth = Some(Arc::new(provider));
+        self
+    }
+
+    /// Add middleware run on every outgoing request.
+    ///
+    /// Middleware runs in registration order after default and dynamic
+    /// headers have been applied, and sees every operation: create, append,
+    /// read, head, delete, close, and producer requests. Use it for request
+    /// signing, request IDs, audit logging, or tenant headers.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let client = Client::builder()
+    ///     .middleware(|mut parts: RequestParts| async move {
+    ///         let id = uuid::Uuid::new_v4().to_string();
+    ///         parts.headers.insert("x-request-id", id.parse().unwrap());
+    ///         parts
+    ///     })
+    ///     .build()?;
+    /// ```
+    pub fn middleware(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    /// Fail over between equivalent deployments, in priority order.
+    ///
+    /// The first endpoint becomes the [`base_url`](Self::base_url), and
+    /// requests to it are routed to the first endpoint that is not backing
+    /// off. An endpoint backs off (from 1 second, doubling up to a minute)
+    /// when a connection to it fails or it answers 502, 503 or 504; after a
+    /// connection failure the request is resent to the next endpoint at
+    /// once, while error responses are retried under the client's
+    /// [`RetryConfig`]. When its backoff expires, the next request probes
+    /// the endpoint again, so traffic returns to the primary once it
+    /// recovers. Middleware sees the primary's URL.
+    ///
+    /// Offsets must be interchangeable between the endpoints, as with
+    /// replicas of one deployment.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let client = Client::builder()
+    ///     .endpoints(["https://streams.us-east.example.com", "https://streams.us-west.example.com"])
+    ///     .build()?;
+    /// let stream = client.stream("/v1/stream/events");
+    /// ```
+    pub fn endpoints<I, S>(mut self, endpoints: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.endpoints = endpoints.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Limit this client to `requests_per_sec` reads and the same number
+    /// of writes, each allowing bursts of up to `burst` requests.
+    ///
+    /// Reads (GET and HEAD) and writes (everything else, including
+    /// producer batches) draw from separate token buckets, shared by all
+    /// clones of the client. A request over the limit waits for a token
+    /// rather than failing, and retries take tokens too, so a tight
+    /// consumer loop or retry storm is slowed down instead of reaching the
+    /// server.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `requests_per_sec` is not a positive, finite number.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let client = Client::builder()
+    ///     .rate_limit(50.0, 10)
+    ///     .write_rate_limit(200.0, 50)   // producers may write faster
+    ///     .build()?;
+    /// ```
+    pub fn rate_limit(self, requests_per_sec: f64, burst: u32) -> Self {
+        self.read_rate_limit(requests_per_sec, burst)
+            .write_rate_limit(requests_per_sec, burst)
+    }
+
+    /// Limit reads only; see [`rate_limit`](Self::rate_limit).
+    pub fn read_rate_limit(mut self, requests_per_sec: f64, burst: u32) -> Self {
+        self.rate_limiter.read = Some(bucket(requests_per_sec, burst));
+        self
+    }
+
+    /// Limit writes only; see [`rate_limit`](Self::rate_limit).
+    pub fn write_rate_limit(mut self, requests_per_sec: f64, burst: u32) -> Self {
+        self.rate_limiter.write = Some(bucket(requests_per_sec, burst));
+        self
+    }
+
+    /// Allow at most `max` long-polls to the same host at once.
+    ///
+    /// Every long-polling reader holds a connection while it waits, so
+    /// hundreds of readers on one host can exhaust the connection pool or
+    /// the server's connection limit. With a cap, readers over it queue and
+    /// are let through in the order they asked, shared by all clones of the
+    /// client. Catch-up reads and SSE are not affected. Unlimited by
+    /// default.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let client = Client::builder()
+    ///     .max_long_polls_per_host(64)
+    ///     .long_poll_stagger(Duration::from_millis(50))
+    ///     .build()?;
+    /// ```
+    pub fn max_long_polls_per_host(mut self, max: usize) -> Self {
+        self.long_poll_limit = Some(max);
+        self
+    }
+
+    /// Delay each long-poll by a random time of up to `stagger`.
+    ///
+    /// An append wakes every reader polling the stream at once; staggering
+    /// spreads their next polls out instead of reconnecting them together.
+    /// Zero (no delay) by default.
+    pub fn long_poll_stagger(mut self, stagger: Duration) -> Self {
+        self.long_poll_stagger = stagger;
+        self
+    }
+
+    /// Prefix generated request IDs with `prefix`, e.g. the service name.
+    ///
+    /// Every request carries an `x-request-id` header: the one set by the
+    /// caller or middleware, or else a random ID generated per attempt.
+    /// Responses and errors report the server's request ID, falling back to
+    /// the one sent.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let client = Client::builder()
+    ///     .request_id_prefix("billing-")
+    ///     .build()?;
+    /// ```
+    pub fn request_id_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.request_id_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Enable or disable generated request IDs (enabled by default).
+    pub fn request_ids(mut self, enabled: bool) -> Self {
+        self.request_id_prefix = match (enabled, self.request_id_prefix.take()) {
+            (true, prefix) => Some(prefix.unwrap_or_default()),
+            (false, _) => None,
+        };
+        self
+    }
+
+    /// Treat header `name` as sensitive, in addition to `authorization`,
+    /// `proxy-authorization`, `cookie`, `set-cookie` and `x-api-key`.
+    ///
+    /// Values of sensitive headers, and of every header set by the
+    /// [`auth_provider`](Self::auth_provider), print as `Sensitive` in the
+    /// `Debug` output of the client and of the [`RequestParts`] middleware
+    /// sees, and are left out of header errors. Invalid names are ignored.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let client = Client::builder()
+    ///     .default_header("x-tenant-token", &token)
+    ///     .redact_header("x-tenant-token")
+    ///     .build()?;
+    /// ```
+    pub fn redact_header(mut self, name: &str) -> Self {
+        if let Ok(name) = HeaderName::from_bytes(name.as_bytes()) {
+            if !self.redacted_headers.contains(&name) {
+                self.redacted_headers.push(name);
+            }
+        }
+        self
+    }
+
+    /// Send a CRC32C `Content-Digest` header with every append and check
+    /// the `Content-Digest` of every read response that carries one.
+    ///
+    /// A response whose body does not match fails with
+    /// [`StreamError::ChecksumMismatch`](crate::StreamError::ChecksumMismatch)
+    /// before its data is returned, and the read can simply be retried.
+    /// Streaming appends, SSE events and bodies read in pieces with
+    /// [`incremental`](crate::ReadBuilder::incremental) are the exceptions:
+    /// streaming appends are sent without a digest, SSE responses carry
+    /// none, and a body read in pieces is only checked once its last piece
+    /// has arrived.
+    #[cfg(feature = "checksum")]
+    pub fn checksums(mut self, enabled: bool) -> Self {
+        self.checksums = enabled;
+        self
+    }
+
+    /// Options for every [`create_with`](DurableStream::create_with) (and
+    /// [`create`](DurableStream::create)) call made through this client.
+    ///
+    /// Options set on a call take precedence; headers are merged, with a
+    /// call's header replacing a default of the same name.
+    pub fn default_create_options(mut self, options: CreateOptions) -> Self {
+        self.defaults.create = options;
+        self
+    }
+
+    /// Options for every [`append_with`](DurableStream::append_with),
+    /// [`append`](DurableStream::append) and
+    /// [`append_stream`](DurableStream::append_stream) call made through
+    /// this client. Producers are configured separately.
+    ///
+    /// Options set on a call take precedence; headers are merged, with a
+    /// call's header replacing a default of the same name.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let client = Client::builder()
+    ///     .default_append_options(AppendOptions::new().timeout(Duration::from_secs(5)).header("x-origin", "billing"))
+    ///     .default_read_options(ReadOptions::new().live(LiveMode::Sse))
+    ///     .build()?;
+    /// ```
+    pub fn default_append_options(mut self, options: AppendOptions) -> Self {
+        self.defaults.append = options;
+        self
+    }
+
+    /// Settings every [`ReadBuilder`](crate::ReadBuilder) made through this
+    /// client starts from. Calls on the builder take precedence; headers are
+    /// merged, with a builder header replacing a default of the same name.
+    pub fn default_read_options(mut self, options: ReadOptions) -> Self {
+        self.defaults.read = options;
+        self
+    }
+
+    /// Use an existing `reqwest::Client`, e.g. to share its connection pool
+    /// with other libraries.
+    ///
+    /// The client is used as is, so this builder's timeout, pool, TLS,
+    /// Unix socket and HTTP/2 settings are ignored; configure them on the
+    /// `reqwest::Client` instead. Per-request timeouts still apply.
+    pub fn with_http_client(mut self, client: reqwest::Client) -> Self {
+        self.http_client = Some(client);
+        self
+    }
+
+    /// Send requests through `transport` instead of the HTTP client.
+    ///
+    /// See [`HttpTransport`].
+    pub fn transport(mut self, transport: impl HttpTransport + 'static) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
     /// Build the client.
     ///
     /// Returns an error if the underlying HTTP client fails to build
-    /// (e.g., due to TLS configuration issues).
+    /// (e.g., due to TLS configuration issues or a malformed certificate).
     pub fn build(self) -> Result<Client, reqwest::Error> {
-        let mut builder = reqwest::Client::builder()
-            .pool_max_idle_per_host(10)
-            .pool_idle_timeout(Duration::from_secs(90));
+        // Connection pooling and client-wide timeouts are managed by the
+        // host's fetch implementation on wasm
+        #[cfg(not(target_arch = "wasm32"))]
+        let builder = {
+            let mut builder = reqwest::Client::builder()
+                .pool_max_idle_per_host(self.pool_max_idle_per_host)
+                .pool_idle_timeout(self.pool_idle_timeout)
+                .tcp_keepalive(self.tcp_keepalive)
+                .tcp_nodelay(self.tcp_nodelay);
 
-        if let Some(timeout) = self.timeout {
-            builder = builder.timeout(timeout);
-        }
+            if let Some(timeout) = self.timeout {
+                builder = builder.timeout(timeout);
+            }
+            if let Some(timeout) = self.connect_timeout {
+                builder = builder.connect_timeout(timeout);
+            }
+            builder = self.net.apply(builder);
+            if self.redirects.is_some() {
+                builder = builder.redirect(reqwest::redirect::Policy::none());
+            }
+            #[cfg(unix)]
+            if let Some(path) = self.unix_socket {
+                builder = builder.unix_socket(path);
+            }
+            #[cfg(feature = "http2")]
+            {
+                builder = self.http2.apply(builder);
+            }
+            #[cfg(any(feature = "rustls", feature = "native-tls"))]
+            {
+                builder = self.tls.apply(builder)?;
+            }
+            builder
+        };
+        #[cfg(target_arch = "wasm32")]
+        let builder = {
+            let _ = (self.timeout, self.connect_timeout, self.pool_max_idle_per_host);
+            let _ = (self.pool_idle_timeout, self.tcp_keepalive, self.tcp_nodelay);
+            reqwest::Client::builder()
+        };
+
+        let mut default_headers = self.default_headers;
+        redact::mark(&mut default_headers, &self.redacted_headers);
 
-        let inner = builder.build()?;
+        let inner = match self.http_client {
+            Some(client) => client,
+            None => builder.build()?,
+        };
+        let transport = match self.transport {
+            Some(transport) => transport,
+            None => Arc::new(inner.clone()),
+        };
+
+        let endpoints = (!self.endpoints.is_empty()).then(|| Arc::new(Endpoints::new(self.endpoints)));
+        let base_url = match &endpoints {
+            Some(endpoints) => Some(endpoints.primary().to_string()),
+            None => self.base_url,
+        };
+
+        let long_polls = (self.long_poll_limit.is_some() || !self.long_poll_stagger.is_zero())
+            .then(|| {
+                let scheduler = LongPollScheduler::new(self.long_poll_limit, self.long_poll_stagger);
+                Arc::new(scheduler)
+            });
 
         Ok(Client {
             inner,
-            base_url: self.base_url,
-            default_headers: self.default_headers,
+            transport,
+            base_url,
+            default_headers,
             header_provider: self.header_provider,
+            retry: self.retry,
+            on_retry: self.on_retry,
+            middleware: self.middleware,
+            auth: self.auth,
+            metrics: Arc::default(),
+            rate_limiter: Arc::new(self.rate_limiter),
+            long_polls,
+            redirects: self.redirects.map(Arc::new),
+            endpoints,
+            request_id_prefix: self.request_id_prefix.map(Arc::from),
+            redacted_headers: self.redacted_headers.into(),
+            #[cfg(feature = "checksum")]
+            checksums: self.checksums,
+            defaults: Arc::new(self.defaults),
         })
     }
 }
 
+fn bucket(requests_per_sec: f64, burst: u32) -> TokenBucket {
+    assert!(
+        requests_per_sec.is_finite() && requests_per_sec > 0.0,
+        "rate limit must be a positive, finite number of requests per second"
+    );
+    TokenBucket::new(requests_per_sec, burst)
+}
+
 impl Default for ClientBuilder {
     fn default() -> Self {
         Self::new()
@@ -0,0 +1,101 @@
+//! MessagePack and CBOR encoding for binary streams.
+//!
+//! Binary streams store appends back to back, and both formats are
+//! self-delimiting, so a chunk is decoded as a sequence of values.
+
+use crate::error::{LimitKind, StreamError};
+use crate::typed::DecodeLimits;
+use bytes::Bytes;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Content type for MessagePack streams.
+#[cfg(feature = "msgpack")]
+pub const CONTENT_TYPE_MSGPACK: &str = "application/msgpack";
+
+/// Content type for CBOR streams.
+#[cfg(feature = "cbor")]
+pub const CONTENT_TYPE_CBOR: &str = "application/cbor";
+
+/// Encode `value` as MessagePack, with struct fields as named map keys.
+#[cfg(feature = "msgpack")]
+pub(crate) fn to_msgpack<T: Serialize>(value: &T) -> Result<Bytes, StreamError> {
+    rmp_serde::to_vec_named(value)
+        .map(Bytes::from)
+        .map_err(|e| StreamError::ParseError(format!("msgpack encode error: {}", e)))
+}
+
+/// Encode `value` as CBOR.
+#[cfg(feature = "cbor")]
+pub(crate) fn to_cbor<T: Serialize>(value: &T) -> Result<Bytes, StreamError> {
+    let mut out = Vec::new();
+    ciborium::into_writer(value, &mut out)
+        .map_err(|e| StreamError::ParseError(format!("cbor encode error: {}", e)))?;
+    Ok(out.into())
+}
+
+/// Decode a chunk of concatenated MessagePack values.
+#[cfg(feature = "msgpack")]
+pub(crate) fn decode_msgpack<T: DeserializeOwned>(
+    data: &[u8],
+    limits: &DecodeLimits,
+) -> Result<Vec<T>, StreamError> {
+    let mut de = rmp_serde::Deserializer::new(std::io::Cursor::new(data));
+    de.set_max_depth(limits.max_depth);
+
+    let mut items = Vec::new();
+    let mut start = 0;
+    while start < data.len() {
+        let item = T::deserialize(&mut de)
+            .map_err(|e| StreamError::ParseError(format!("msgpack decode error: {}", e)))?;
+        let end = de.position() as usize;
+        push_item(&mut items, item, end - start, limits)?;
+        start = end;
+    }
+    Ok(items)
+}
+
+/// Decode a chunk of concatenated CBOR values (an RFC 8742 sequence).
+#[cfg(feature = "cbor")]
+pub(crate) fn decode_cbor<T: DeserializeOwned>(
+    data: &[u8],
+    limits: &DecodeLimits,
+) -> Result<Vec<T>, StreamError> {
+    let mut rest = data;
+    let mut items = Vec::new();
+    while !rest.is_empty() {
+        let before = rest.len();
+        let item = ciborium::de::from_reader_with_recursion_limit(&mut rest, limits.max_depth)
+            .map_err(|e| StreamError::ParseError(format!("cbor decode error: {}", e)))?;
+        push_item(&mut items, item, before - rest.len(), limits)?;
+    }
+    Ok(items)
+}
+
+/// Enforce item size and count limits.
+///
+/// Unlike JSON, these are checked as each value is decoded; a value can
+/// never be larger than the chunk it came from.
+fn push_item<T>(
+    items: &mut Vec<T>,
+    item: T,
+    len: usize,
+    limits: &DecodeLimits,
+) -> Result<(), StreamError> {
+    if len > limits.max_item_bytes {
+        return Err(StreamError::PayloadTooLarge {
+            kind: LimitKind::ItemBytes,
+            limit: limits.max_item_bytes,
+            actual: len,
+        });
+    }
+    if items.len() == limits.max_items_per_chunk {
+        return Err(StreamError::PayloadTooLarge {
+            kind: LimitKind::ItemsPerChunk,
+            limit: limits.max_items_per_chunk,
+            actual: items.len() + 1,
+        });
+    }
+    items.push(item);
+    Ok(())
+}
@@ -0,0 +1,48 @@
+//! Response body decompression.
+
+use crate::error::StreamError;
+use bytes::Bytes;
+use std::io::Read;
+
+/// `Accept-Encoding` value sent when compression is requested.
+pub(crate) const ACCEPT_ENCODING: &str = "gzip, zstd";
+
+/// Decode `data` according to its `Content-Encoding`.
+///
+/// `identity` (or no encoding) passes through unchanged.
+pub(crate) fn decompress(encoding: Option<&str>, data: Bytes) -> Result<Bytes, StreamError> {
+    let encoding = match encoding.map(str::trim) {
+        None | Some("") => return Ok(data),
+        Some(e) => e.to_ascii_lowercase(),
+    };
+
+    let mut out = Vec::new();
+    let result = match encoding.as_str() {
+        "identity" => return Ok(data),
+        "gzip" | "x-gzip" => flate2::read::MultiGzDecoder::new(&data[..])
+            .read_to_end(&mut out)
+            .map(|_| ()),
+        "zstd" => decode_zstd(&data, &mut out),
+        other => {
+            return Err(StreamError::ParseError(format!(
+                "unsupported Content-Encoding: {}",
+                other
+            )))
+        }
+    };
+
+    result.map_err(|e| {
+        StreamError::ParseError(format!("failed to decode {} response: {}", encoding, e))
+    })?;
+    Ok(out.into())
+}
+
+/// Decode every zstd frame in `data`.
+fn decode_zstd(mut data: &[u8], out: &mut Vec<u8>) -> std::io::Result<()> {
+    while !data.is_empty() {
+        let mut decoder = ruzstd::decoding::StreamingDecoder::new(&mut data)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        decoder.read_to_end(out)?;
+    }
+    Ok(())
+}
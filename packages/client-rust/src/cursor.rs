@@ -0,0 +1,74 @@
+//! Sharing CDN cursors between readers.
+
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Latest `Stream-Cursor` per stream, shared by the readers given it.
+///
+/// A CDN can only collapse live reads that have the same URL, cursor
+/// included. Each reader otherwise keeps the cursor from its own last
+/// response, so readers at the same offset drift apart and every poll
+/// misses the cache. Readers sharing a coordinator send the newest cursor
+/// any of them has seen, so their polls converge on one URL per offset.
+///
+/// Cursors that are decimal numbers, as the reference server sends, are
+/// compared numerically; otherwise the most recently seen cursor wins.
+/// Clones share the same state.
+///
+/// # Example
+/// ```ignore
+/// let cursors = CursorCoordinator::new();
+/// for _ in 0..100 {
+///     let reader = stream
+///         .read()
+///         .offset(Offset::Now)
+///         .live(LiveMode::LongPoll)
+///         .cursor_coordinator(&cursors)
+///         .build()?;
+///     tokio::spawn(consume(reader));
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CursorCoordinator {
+    cursors: Arc<Mutex<HashMap<String, String>>>,
+}
+
+impl CursorCoordinator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The newest cursor seen for the stream at `url`.
+    pub fn get(&self, url: &str) -> Option<String> {
+        self.cursors.lock().get(url).cloned()
+    }
+
+    /// Record `own`, a reader's current cursor, and return the newest
+    /// cursor for `url` to send instead.
+    pub(crate) fn sync(&self, url: &str, own: Option<&str>) -> Option<String> {
+        let mut cursors = self.cursors.lock();
+        match (cursors.get_mut(url), own) {
+            (Some(shared), Some(own)) => {
+                if is_newer(own, shared) {
+                    *shared = own.to_string();
+                }
+                Some(shared.clone())
+            }
+            (Some(shared), None) => Some(shared.clone()),
+            (None, Some(own)) => {
+                cursors.insert(url.to_string(), own.to_string());
+                Some(own.to_string())
+            }
+            (None, None) => None,
+        }
+    }
+}
+
+/// Whether `candidate` should replace `current`.
+fn is_newer(candidate: &str, current: &str) -> bool {
+    match (candidate.parse::<u64>(), current.parse::<u64>()) {
+        (Ok(candidate), Ok(current)) => candidate > current,
+        _ => candidate != current,
+    }
+}
@@ -0,0 +1,122 @@
+//! Dead-letter streams for records that could not be delivered.
+
+use crate::error::StreamError;
+use crate::stream::DurableStream;
+use crate::types::Offset;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
+
+const JSON: &str = "application/json";
+
+const BASE64_ENCODING: &str = "base64";
+
+/// A record that a [`Producer`](crate::Producer) could not write or a
+/// [`SinkRunner`](crate::SinkRunner) could not apply, as stored in a
+/// dead-letter stream.
+///
+/// Dead-letter streams are JSON streams of these, so they can be read with
+/// `typed::<DeadLetter>()` and replayed with [`payload_bytes`](Self::payload_bytes).
+///
+/// # Example
+/// ```ignore
+/// let mut reader = client.stream("/v1/stream/orders-dlq").read().typed::<DeadLetter>().build()?;
+/// while let Some(chunk) = reader.next_chunk().await? {
+///     for letter in chunk.items {
+///         eprintln!("{} failed: {}", letter.source, letter.error);
+///         orders.append(letter.payload_bytes()?).await?;
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct DeadLetter {
+    /// URL of the stream the record was written to or read from.
+    pub source: String,
+    /// Producer that failed to write the record.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub producer_id: Option<String>,
+    /// Offset the record was read from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offset: Option<Offset>,
+    /// Why delivery failed.
+    pub error: String,
+    /// Attempts made before giving up, when known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attempts: Option<u32>,
+    /// When delivery was given up on, in milliseconds since the Unix epoch.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "crate::record::millis"
+    )]
+    pub failed_at: Option<SystemTime>,
+    /// Content type of the source stream.
+    pub content_type: String,
+    /// The record: its text, or base64 if `encoding` is `"base64"`.
+    pub payload: String,
+    /// `"base64"` for payloads that are not UTF-8.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+}
+
+impl DeadLetter {
+    pub(crate) fn new(
+        source: &str,
+        content_type: &str,
+        data: &[u8],
+        error: impl std::fmt::Display,
+    ) -> Self {
+        let (payload, encoding) = match std::str::from_utf8(data) {
+            Ok(text) => (text.to_string(), None),
+            Err(_) => (BASE64.encode(data), Some(BASE64_ENCODING.to_string())),
+        };
+        Self {
+            source: source.to_string(),
+            producer_id: None,
+            offset: None,
+            error: error.to_string(),
+            attempts: None,
+            failed_at: Some(SystemTime::now()),
+            content_type: content_type.to_string(),
+            payload,
+            encoding,
+        }
+    }
+
+    /// The record's original bytes.
+    pub fn payload_bytes(&self) -> Result<Vec<u8>, StreamError> {
+        match self.encoding.as_deref() {
+            None => Ok(self.payload.clone().into_bytes()),
+            Some(BASE64_ENCODING) => BASE64
+                .decode(&self.payload)
+                .map_err(|e| StreamError::ParseError(format!("invalid dead letter payload: {e}"))),
+            Some(other) => Err(StreamError::ParseError(format!(
+                "unknown dead letter encoding: {other}"
+            ))),
+        }
+    }
+}
+
+/// Appends [`DeadLetter`]s to a JSON stream.
+#[derive(Clone, Debug)]
+pub(crate) struct DeadLetterQueue {
+    stream: DurableStream,
+}
+
+impl DeadLetterQueue {
+    pub(crate) fn new(mut stream: DurableStream) -> Self {
+        stream.content_type = Some(JSON.to_string());
+        Self { stream }
+    }
+
+    /// Append `letters` in one request.
+    pub(crate) async fn send(&self, letters: &[DeadLetter]) -> Result<(), StreamError> {
+        if letters.is_empty() {
+            return Ok(());
+        }
+        self.stream.append(serde_json::to_vec(letters)?).await?;
+        Ok(())
+    }
+}
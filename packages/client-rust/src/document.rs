@@ -0,0 +1,421 @@
+//! Follow a JSON document through a stream of patches.
+
+use crate::error::{PatchError, StreamError};
+use crate::materializer::{Materialized, Materializer, Reducer};
+use crate::producer::Producer;
+use crate::snapshot::Snapshot;
+use crate::stream::{AppendResponse, DurableStream};
+use crate::types::{LiveMode, Offset};
+use serde_json::{Map, Value};
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::watch;
+
+const JSON: &str = "application/json";
+
+/// How the records of a document stream change the document.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PatchFormat {
+    /// [RFC 6902](https://www.rfc-editor.org/rfc/rfc6902) JSON Patch: each
+    /// record is one operation, or an array of operations applied all or
+    /// nothing.
+    #[default]
+    JsonPatch,
+    /// [RFC 7386](https://www.rfc-editor.org/rfc/rfc7386) JSON Merge Patch:
+    /// each record is merged into the document.
+    MergePatch,
+}
+
+impl PatchFormat {
+    /// Apply `patch` to `doc`. On failure `doc` is left unchanged.
+    pub fn apply(self, doc: &mut Value, patch: &Value) -> Result<(), PatchError> {
+        match self {
+            PatchFormat::JsonPatch => json_patch(doc, patch),
+            PatchFormat::MergePatch => {
+                merge_patch(doc, patch);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Keeps a JSON document current by applying the patches in a stream, for
+/// shared state such as a collaborative form or a config document.
+///
+/// Built on [`Materializer`]: [`run`](Self::run) replays the stream and
+/// follows it, while clones read the [`current`](Self::current) document
+/// or [`subscribe`](Self::subscribe) to changes. A patch that does not
+/// apply (a failed `test`, a path that does not exist) is skipped and
+/// counted in [`rejected`](Self::rejected), so every follower ends up with
+/// the same document.
+///
+/// # Example
+///
+/// ```ignore
+/// let doc = DocumentFollower::new(client.stream("/v1/stream/form-7"), PatchFormat::JsonPatch)
+///     .initial(json!({ "fields": {} }));
+/// tokio::spawn({
+///     let doc = doc.clone();
+///     async move { doc.run(std::future::pending()).await }
+/// });
+///
+/// doc.emit(json!([{ "op": "add", "path": "/fields/name", "value": "Ada" }])).await?;
+/// doc.wait_up_to_date().await;
+/// println!("{}", doc.current());
+/// ```
+pub struct DocumentFollower {
+    stream: DurableStream,
+    format: PatchFormat,
+    live: LiveMode,
+    rejected: Arc<AtomicU64>,
+    materializer: Materializer<Value, PatchReducer>,
+}
+
+impl DocumentFollower {
+    /// Follow the document patched by `stream`, starting from `null`.
+    pub fn new(mut stream: DurableStream, format: PatchFormat) -> Self {
+        stream.content_type.get_or_insert_with(|| JSON.to_string());
+        let rejected = Arc::new(AtomicU64::new(0));
+        let reducer = PatchReducer {
+            format,
+            initial: Value::Null,
+            rejected: rejected.clone(),
+        };
+        Self {
+            materializer: Materializer::new(stream.clone(), reducer).live(LiveMode::Sse),
+            stream,
+            format,
+            live: LiveMode::Sse,
+            rejected,
+        }
+    }
+
+    /// Document the first patch applies to (default `null`). Set it
+    /// before [`resume_from`](Self::resume_from), which it replaces.
+    #[must_use]
+    pub fn initial(mut self, doc: Value) -> Self {
+        let reducer = PatchReducer {
+            format: self.format,
+            initial: doc,
+            rejected: self.rejected.clone(),
+        };
+        self.materializer = Materializer::new(self.stream.clone(), reducer).live(self.live.clone());
+        self
+    }
+
+    /// How to follow the stream once caught up (default
+    /// [`LiveMode::Sse`]).
+    #[must_use]
+    pub fn live(mut self, mode: LiveMode) -> Self {
+        self.live = mode.clone();
+        self.materializer = self.materializer.live(mode);
+        self
+    }
+
+    /// Start from `snapshot` and apply only the patches after its offset.
+    #[must_use]
+    pub fn resume_from(mut self, snapshot: Snapshot<Value>) -> Self {
+        self.materializer = self.materializer.resume_from(snapshot);
+        self
+    }
+
+    /// The latest document.
+    pub fn current(&self) -> Arc<Value> {
+        self.materializer.current()
+    }
+
+    /// The latest document with the offset it was patched up to.
+    pub fn snapshot(&self) -> Materialized<Value> {
+        self.materializer.snapshot()
+    }
+
+    /// Offset the document has been patched up to.
+    pub fn offset(&self) -> Offset {
+        self.materializer.snapshot().offset
+    }
+
+    /// Receive every new version of the document.
+    pub fn subscribe(&self) -> watch::Receiver<Materialized<Value>> {
+        self.materializer.subscribe()
+    }
+
+    /// Wait until the document has caught up with the stream tail.
+    ///
+    /// Only completes while [`run`](Self::run) is running.
+    pub async fn wait_up_to_date(&self) {
+        self.materializer.wait_up_to_date().await;
+    }
+
+    /// Number of patches skipped because they did not apply.
+    pub fn rejected(&self) -> u64 {
+        self.rejected.load(Ordering::Relaxed)
+    }
+
+    /// The underlying materializer, e.g. for a
+    /// [`SnapshotWriter`](crate::SnapshotWriter).
+    pub fn materializer(&self) -> &Materializer<Value, PatchReducer> {
+        &self.materializer
+    }
+
+    /// Replay the stream and follow it until `shutdown` resolves.
+    pub async fn run<S>(&self, shutdown: S) -> Result<Offset, StreamError>
+    where
+        S: Future<Output = ()>,
+    {
+        self.materializer.run(shutdown).await
+    }
+
+    /// Append `patch` to the stream as one record.
+    ///
+    /// The document changes once the patch is read back, so concurrent
+    /// writers' patches apply in the same order everywhere.
+    pub async fn emit(&self, patch: Value) -> Result<AppendResponse, StreamError> {
+        // Wrapped so that an array of operations stays one record
+        let body = serde_json::to_vec(&[patch])?;
+        self.stream.append(body).await
+    }
+
+    /// Queue `patch` on `producer` as one record, for batched or
+    /// exactly-once writes. The producer must write to this stream.
+    pub fn emit_with(&self, producer: &Producer, patch: &Value) {
+        producer.append_json(patch);
+    }
+}
+
+impl Clone for DocumentFollower {
+    fn clone(&self) -> Self {
+        Self {
+            stream: self.stream.clone(),
+            format: self.format,
+            live: self.live.clone(),
+            rejected: self.rejected.clone(),
+            materializer: self.materializer.clone(),
+        }
+    }
+}
+
+impl std::fmt::Debug for DocumentFollower {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DocumentFollower")
+            .field("stream", &self.stream.url())
+            .field("format", &self.format)
+            .field("live", &self.live)
+            .field("rejected", &self.rejected())
+            .finish_non_exhaustive()
+    }
+}
+
+/// [`Reducer`] applying patch records to a document.
+#[derive(Debug)]
+pub struct PatchReducer {
+    format: PatchFormat,
+    initial: Value,
+    rejected: Arc<AtomicU64>,
+}
+
+impl Reducer<Value> for PatchReducer {
+    type State = Value;
+
+    fn initial(&self) -> Value {
+        self.initial.clone()
+    }
+
+    fn reduce(&self, doc: &mut Value, patch: Value) {
+        if self.format.apply(doc, &patch).is_err() {
+            self.rejected.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Apply an RFC 6902 patch: one operation or an array of them.
+fn json_patch(doc: &mut Value, patch: &Value) -> Result<(), PatchError> {
+    match patch {
+        Value::Array(ops) => {
+            // All or nothing: work on a copy unless one op cannot half-apply
+            if let [op] = ops.as_slice() {
+                if op.get("op").and_then(Value::as_str) != Some("move") {
+                    return apply_op(doc, op);
+                }
+            }
+            let mut next = doc.clone();
+            for op in ops {
+                apply_op(&mut next, op)?;
+            }
+            *doc = next;
+            Ok(())
+        }
+        Value::Object(_) if patch.get("op").and_then(Value::as_str) == Some("move") => {
+            let mut next = doc.clone();
+            apply_op(&mut next, patch)?;
+            *doc = next;
+            Ok(())
+        }
+        Value::Object(_) => apply_op(doc, patch),
+        _ => Err(PatchError::new(
+            "a JSON Patch is an operation or an array of them",
+        )),
+    }
+}
+
+fn apply_op(doc: &mut Value, op: &Value) -> Result<(), PatchError> {
+    let member = |name: &str| {
+        op.get(name)
+            .ok_or_else(|| PatchError::new(format!("operation is missing \"{name}\"")))
+    };
+    let pointer = |name: &str| {
+        member(name)?
+            .as_str()
+            .ok_or_else(|| PatchError::new(format!("\"{name}\" is not a string")))
+            .and_then(parse_pointer)
+    };
+
+    let name = member("op")?.as_str().unwrap_or_default();
+    let path = pointer("path")?;
+    match name {
+        "add" => add(doc, &path, member("value")?.clone()),
+        "remove" => remove(doc, &path).map(drop),
+        "replace" => {
+            let target = get_mut(doc, &path)?;
+            *target = member("value")?.clone();
+            Ok(())
+        }
+        "move" => {
+            let from = pointer("from")?;
+            if path.len() > from.len() && path.starts_with(&from) {
+                return Err(PatchError::new("cannot move a value into itself"));
+            }
+            let value = remove(doc, &from)?;
+            add(doc, &path, value)
+        }
+        "copy" => {
+            let value = get_mut(doc, &pointer("from")?)?.clone();
+            add(doc, &path, value)
+        }
+        "test" => {
+            if *get_mut(doc, &path)? == *member("value")? {
+                Ok(())
+            } else {
+                Err(PatchError::new(format!(
+                    "test failed at {}",
+                    join_pointer(&path)
+                )))
+            }
+        }
+        other => Err(PatchError::new(format!("unknown operation \"{other}\""))),
+    }
+}
+
+/// Split an RFC 6901 JSON Pointer into unescaped reference tokens.
+fn parse_pointer(pointer: &str) -> Result<Vec<String>, PatchError> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+    let Some(rest) = pointer.strip_prefix('/') else {
+        return Err(PatchError::new(format!(
+            "invalid JSON Pointer \"{pointer}\""
+        )));
+    };
+    Ok(rest
+        .split('/')
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+        .collect())
+}
+
+fn join_pointer(tokens: &[String]) -> String {
+    tokens
+        .iter()
+        .map(|token| format!("/{}", token.replace('~', "~0").replace('/', "~1")))
+        .collect()
+}
+
+/// Array index for `token`, which may equal `len` when `append` is set.
+fn index(token: &str, len: usize, append: bool) -> Result<usize, PatchError> {
+    if append && token == "-" {
+        return Ok(len);
+    }
+    let valid = !token.is_empty()
+        && token.bytes().all(|b| b.is_ascii_digit())
+        && (token == "0" || !token.starts_with('0'));
+    match token.parse::<usize>() {
+        Ok(i) if valid && (i < len || (append && i == len)) => Ok(i),
+        _ => Err(PatchError::new(format!(
+            "invalid array index \"{token}\""
+        ))),
+    }
+}
+
+fn get_mut<'a>(doc: &'a mut Value, path: &[String]) -> Result<&'a mut Value, PatchError> {
+    let mut current = doc;
+    for token in path {
+        current = match current {
+            Value::Object(map) => map.get_mut(token),
+            Value::Array(items) => {
+                let i = index(token, items.len(), false)?;
+                items.get_mut(i)
+            }
+            _ => None,
+        }
+        .ok_or_else(|| PatchError::new(format!("no value at {}", join_pointer(path))))?;
+    }
+    Ok(current)
+}
+
+fn add(doc: &mut Value, path: &[String], value: Value) -> Result<(), PatchError> {
+    let Some((last, parent)) = path.split_last() else {
+        *doc = value;
+        return Ok(());
+    };
+    match get_mut(doc, parent)? {
+        Value::Object(map) => {
+            map.insert(last.clone(), value);
+        }
+        Value::Array(items) => {
+            let i = index(last, items.len(), true)?;
+            items.insert(i, value);
+        }
+        _ => {
+            return Err(PatchError::new(format!(
+                "{} is not a container",
+                join_pointer(parent)
+            )))
+        }
+    }
+    Ok(())
+}
+
+fn remove(doc: &mut Value, path: &[String]) -> Result<Value, PatchError> {
+    let Some((last, parent)) = path.split_last() else {
+        return Err(PatchError::new("cannot remove the whole document"));
+    };
+    let removed = match get_mut(doc, parent)? {
+        Value::Object(map) => map.remove(last),
+        Value::Array(items) => {
+            let i = index(last, items.len(), false)?;
+            Some(items.remove(i))
+        }
+        _ => None,
+    };
+    removed.ok_or_else(|| PatchError::new(format!("no value at {}", join_pointer(path))))
+}
+
+/// Apply an RFC 7386 merge patch.
+fn merge_patch(doc: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *doc = patch.clone();
+        return;
+    };
+    if !doc.is_object() {
+        *doc = Value::Object(Map::new());
+    }
+    if let Value::Object(target) = doc {
+        for (key, value) in patch {
+            if value.is_null() {
+                target.remove(key);
+            } else {
+                merge_patch(target.entry(key.clone()).or_insert(Value::Null), value);
+            }
+        }
+    }
+}
@@ -0,0 +1,594 @@
+//! Client-side encryption of record payloads.
+
+use crate::error::{EncryptionError, ProducerError, StreamError};
+use crate::producer::Producer;
+use crate::stream::{AppendResponse, CreateOptions, DurableStream};
+use crate::typed::{TypedChunk, TypedIterator, TypedReadBuilder};
+use crate::types::{LiveMode, Offset};
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use parking_lot::{Mutex, RwLock};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+const JSON: &str = "application/json";
+
+/// Algorithm named in every envelope.
+const ALGORITHM: &str = "A256GCM";
+
+/// Unwrapped data keys kept by [`EnvelopeKeys`] before the cache is reset.
+const MAX_CACHED_KEYS: usize = 1024;
+
+/// A 256-bit AES key and the ID envelopes refer to it by.
+#[derive(Clone)]
+pub struct DataKey {
+    id: String,
+    key: [u8; 32],
+    wrapped: Option<Vec<u8>>,
+}
+
+impl DataKey {
+    /// Key `key` known as `id`.
+    pub fn new(id: impl Into<String>, key: [u8; 32]) -> Self {
+        Self {
+            id: id.into(),
+            key,
+            wrapped: None,
+        }
+    }
+
+    /// Attach the key encrypted under a key-encryption key, to be carried
+    /// in every envelope so readers can unwrap it.
+    #[must_use]
+    pub fn with_wrapped(mut self, wrapped: Vec<u8>) -> Self {
+        self.wrapped = Some(wrapped);
+        self
+    }
+
+    /// The key's ID.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn cipher(&self) -> Result<LessSafeKey, EncryptionError> {
+        UnboundKey::new(&AES_256_GCM, &self.key)
+            .map(LessSafeKey::new)
+            .map_err(|_| EncryptionError::new("invalid AES-256 key"))
+    }
+}
+
+impl std::fmt::Debug for DataKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DataKey")
+            .field("id", &self.id)
+            .field("key", &"[redacted]")
+            .field("wrapped", &self.wrapped.is_some())
+            .finish()
+    }
+}
+
+/// Source of the keys an [`Encryptor`] uses.
+#[async_trait]
+pub trait KeyProvider: Send + Sync {
+    /// Key to encrypt new records with. Called for every record, so it
+    /// should not block.
+    fn current_key(&self) -> Result<DataKey, EncryptionError>;
+
+    /// Key a record was encrypted with, from the key ID and wrapped key
+    /// (if any) in its envelope.
+    async fn key(&self, id: &str, wrapped: Option<&[u8]>) -> Result<DataKey, EncryptionError>;
+}
+
+/// Shares a provider, e.g. to [`rotate`](EnvelopeKeys::rotate) keys an
+/// [`Encryptor`] is using.
+#[async_trait]
+impl<P: KeyProvider + ?Sized> KeyProvider for Arc<P> {
+    fn current_key(&self) -> Result<DataKey, EncryptionError> {
+        (**self).current_key()
+    }
+
+    async fn key(&self, id: &str, wrapped: Option<&[u8]>) -> Result<DataKey, EncryptionError> {
+        (**self).key(id, wrapped).await
+    }
+}
+
+/// Keys held in memory: one to encrypt with, and any number of older ones
+/// still accepted for decryption.
+///
+/// # Example
+/// ```ignore
+/// let keys = StaticKeys::new("2024-06", load_key("2024-06")?)
+///     .with_key("2024-01", load_key("2024-01")?);
+/// ```
+#[derive(Debug)]
+pub struct StaticKeys {
+    current: DataKey,
+    keys: HashMap<String, DataKey>,
+}
+
+impl StaticKeys {
+    /// Encrypt with `key`, known as `id`.
+    pub fn new(id: impl Into<String>, key: [u8; 32]) -> Self {
+        let current = DataKey::new(id, key);
+        let keys = HashMap::from([(current.id.clone(), current.clone())]);
+        Self { current, keys }
+    }
+
+    /// Also decrypt records encrypted with `key`, e.g. before a rotation.
+    #[must_use]
+    pub fn with_key(mut self, id: impl Into<String>, key: [u8; 32]) -> Self {
+        let key = DataKey::new(id, key);
+        self.keys.insert(key.id.clone(), key);
+        self
+    }
+}
+
+#[async_trait]
+impl KeyProvider for StaticKeys {
+    fn current_key(&self) -> Result<DataKey, EncryptionError> {
+        Ok(self.current.clone())
+    }
+
+    async fn key(&self, id: &str, _wrapped: Option<&[u8]>) -> Result<DataKey, EncryptionError> {
+        self.keys
+            .get(id)
+            .cloned()
+            .ok_or_else(|| EncryptionError::new(format!("unknown key {id}")))
+    }
+}
+
+/// Encrypts and decrypts data keys under a key-encryption key that never
+/// leaves a key management service.
+#[async_trait]
+pub trait KeyWrapper: Send + Sync {
+    /// Encrypt data key `key`.
+    async fn wrap(&self, key: &[u8]) -> Result<Vec<u8>, EncryptionError>;
+
+    /// Decrypt a data key returned by [`wrap`](Self::wrap).
+    async fn unwrap(&self, wrapped: &[u8]) -> Result<Vec<u8>, EncryptionError>;
+}
+
+/// Envelope encryption: records are encrypted with random data keys, and
+/// each envelope carries its data key wrapped by a [`KeyWrapper`] (e.g. a
+/// cloud KMS).
+///
+/// The key service is called once per data key, not per record: when
+/// created, on [`rotate`](Self::rotate), and the first time a reader meets
+/// each data key. Revoking the key-encryption key makes every record
+/// unreadable.
+///
+/// # Example
+/// ```ignore
+/// let keys = Arc::new(EnvelopeKeys::new(KmsWrapper::new(kms_client, key_arn)).await?);
+/// let encryptor = Encryptor::new(keys.clone());
+/// // later
+/// keys.rotate().await?;
+/// ```
+pub struct EnvelopeKeys<W> {
+    wrapper: W,
+    rng: SystemRandom,
+    current: RwLock<DataKey>,
+    unwrapped: Mutex<HashMap<String, DataKey>>,
+}
+
+impl<W: KeyWrapper> EnvelopeKeys<W> {
+    /// Generate and wrap a first data key.
+    pub async fn new(wrapper: W) -> Result<Self, EncryptionError> {
+        let rng = SystemRandom::new();
+        let current = generate(&wrapper, &rng).await?;
+        Ok(Self {
+            wrapper,
+            rng,
+            current: RwLock::new(current),
+            unwrapped: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Switch to a new data key, e.g. daily or every so many records.
+    pub async fn rotate(&self) -> Result<(), EncryptionError> {
+        let key = generate(&self.wrapper, &self.rng).await?;
+        // Records under the old key stay readable without the key service
+        let old = std::mem::replace(&mut *self.current.write(), key);
+        self.unwrapped.lock().insert(old.id.clone(), old);
+        Ok(())
+    }
+}
+
+async fn generate<W: KeyWrapper>(
+    wrapper: &W,
+    rng: &SystemRandom,
+) -> Result<DataKey, EncryptionError> {
+    let mut key = [0u8; 32];
+    let mut id = [0u8; 8];
+    rng.fill(&mut key)
+        .and_then(|()| rng.fill(&mut id))
+        .map_err(|_| EncryptionError::new("no secure randomness available"))?;
+    let wrapped = wrapper.wrap(&key).await?;
+    let id: String = id.iter().map(|b| format!("{b:02x}")).collect();
+    Ok(DataKey::new(id, key).with_wrapped(wrapped))
+}
+
+#[async_trait]
+impl<W: KeyWrapper> KeyProvider for EnvelopeKeys<W> {
+    fn current_key(&self) -> Result<DataKey, EncryptionError> {
+        Ok(self.current.read().clone())
+    }
+
+    async fn key(&self, id: &str, wrapped: Option<&[u8]>) -> Result<DataKey, EncryptionError> {
+        if let Some(key) = self.unwrapped.lock().get(id) {
+            return Ok(key.clone());
+        }
+        let current = self.current.read().clone();
+        if current.id == id {
+            return Ok(current);
+        }
+
+        let wrapped = wrapped.ok_or_else(|| {
+            EncryptionError::new(format!("envelope for key {id} has no wrapped key"))
+        })?;
+        let key: [u8; 32] = self
+            .wrapper
+            .unwrap(wrapped)
+            .await?
+            .try_into()
+            .map_err(|_| EncryptionError::new(format!("key {id} did not unwrap to 32 bytes")))?;
+        let key = DataKey::new(id, key).with_wrapped(wrapped.to_vec());
+
+        let mut unwrapped = self.unwrapped.lock();
+        if unwrapped.len() >= MAX_CACHED_KEYS {
+            unwrapped.clear();
+        }
+        unwrapped.insert(id.to_string(), key.clone());
+        Ok(key)
+    }
+}
+
+impl<W> std::fmt::Debug for EnvelopeKeys<W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EnvelopeKeys")
+            .field("current", &self.current.read().id)
+            .field("cached", &self.unwrapped.lock().len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// An encrypted record as stored in the stream.
+///
+/// Serialized as `{"alg": "A256GCM", "kid": ..., "iv": ..., "ct": ...}`,
+/// plus `"wk"` (the wrapped data key) under envelope encryption. Binary
+/// fields are base64. The key ID is authenticated along with the
+/// ciphertext.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Envelope {
+    alg: String,
+    kid: String,
+    iv: String,
+    ct: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    wk: Option<String>,
+}
+
+impl Envelope {
+    /// ID of the key the record was encrypted with.
+    pub fn key_id(&self) -> &str {
+        &self.kid
+    }
+}
+
+/// Encrypts records with AES-256-GCM before they leave the process and
+/// decrypts them after reading, with keys from a [`KeyProvider`].
+///
+/// Each record gets a random 96-bit nonce. Only payloads are encrypted:
+/// stream URLs, offsets, timing and record sizes remain visible to the
+/// server.
+#[derive(Clone)]
+pub struct Encryptor {
+    keys: Arc<dyn KeyProvider>,
+    rng: SystemRandom,
+}
+
+impl Encryptor {
+    /// Encrypt with keys from `keys`.
+    pub fn new(keys: impl KeyProvider + 'static) -> Self {
+        Self {
+            keys: Arc::new(keys),
+            rng: SystemRandom::new(),
+        }
+    }
+
+    /// Encrypt `plaintext` with the current key.
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Envelope, EncryptionError> {
+        let key = self.keys.current_key()?;
+        let mut iv = [0u8; NONCE_LEN];
+        self.rng
+            .fill(&mut iv)
+            .map_err(|_| EncryptionError::new("no secure randomness available"))?;
+
+        let mut data = plaintext.to_vec();
+        key.cipher()?
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(iv),
+                Aad::from(key.id.as_bytes()),
+                &mut data,
+            )
+            .map_err(|_| EncryptionError::new("encryption failed"))?;
+        Ok(Envelope {
+            alg: ALGORITHM.to_string(),
+            kid: key.id,
+            iv: BASE64.encode(iv),
+            ct: BASE64.encode(data),
+            wk: key.wrapped.map(|wrapped| BASE64.encode(wrapped)),
+        })
+    }
+
+    /// Decrypt `envelope`, failing if it was tampered with or its key is
+    /// unknown.
+    pub async fn decrypt(&self, envelope: &Envelope) -> Result<Vec<u8>, EncryptionError> {
+        if envelope.alg != ALGORITHM {
+            return Err(EncryptionError::new(format!(
+                "unsupported algorithm {}",
+                envelope.alg
+            )));
+        }
+        let decode = |field: &str, value: &str| {
+            BASE64
+                .decode(value)
+                .map_err(|_| EncryptionError::new(format!("envelope field {field} is not base64")))
+        };
+        let iv: [u8; NONCE_LEN] = decode("iv", &envelope.iv)?
+            .try_into()
+            .map_err(|_| EncryptionError::new("envelope nonce has the wrong length"))?;
+        let wrapped = envelope
+            .wk
+            .as_deref()
+            .map(|wk| decode("wk", wk))
+            .transpose()?;
+        let mut data = decode("ct", &envelope.ct)?;
+
+        let key = self.keys.key(&envelope.kid, wrapped.as_deref()).await?;
+        let plaintext = key
+            .cipher()?
+            .open_in_place(
+                Nonce::assume_unique_for_key(iv),
+                Aad::from(envelope.kid.as_bytes()),
+                &mut data,
+            )
+            .map_err(|_| {
+                EncryptionError::new(format!(
+                    "record under key {} failed authentication",
+                    envelope.kid
+                ))
+            })?
+            .len();
+        data.truncate(plaintext);
+        Ok(data)
+    }
+
+    /// Serialize `item` as JSON and encrypt it.
+    pub fn encrypt_json<T: Serialize>(&self, item: &T) -> Result<Envelope, StreamError> {
+        Ok(self.encrypt(&serde_json::to_vec(item)?)?)
+    }
+
+    /// Decrypt `envelope` and parse the plaintext as JSON.
+    pub async fn decrypt_json<T: DeserializeOwned>(
+        &self,
+        envelope: &Envelope,
+    ) -> Result<T, StreamError> {
+        Ok(serde_json::from_slice(&self.decrypt(envelope).await?)?)
+    }
+}
+
+impl std::fmt::Debug for Encryptor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Encryptor").finish_non_exhaustive()
+    }
+}
+
+impl DurableStream {
+    /// View this stream as a JSON stream of `T`, encrypted with
+    /// `encryptor`: every item is stored as an [`Envelope`].
+    ///
+    /// # Example
+    /// ```ignore
+    /// let encryptor = Encryptor::new(StaticKeys::new("k1", key));
+    /// let patients = client.stream("/v1/stream/patients").encrypted::<Patient>(&encryptor);
+    /// patients.create().await?;
+    /// patients.append(&patient).await?;
+    ///
+    /// let mut reader = patients.read().build()?;
+    /// while let Some(chunk) = reader.next_chunk().await? {
+    ///     for patient in chunk.items { /* ... */ }
+    /// }
+    /// ```
+    pub fn encrypted<T: Serialize + DeserializeOwned>(
+        &self,
+        encryptor: &Encryptor,
+    ) -> EncryptedStream<T> {
+        let mut stream = self.clone();
+        stream.content_type = Some(JSON.to_string());
+        EncryptedStream {
+            stream,
+            encryptor: encryptor.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// A JSON stream whose items are `T`, encrypted client-side.
+///
+/// Created by [`DurableStream::encrypted`].
+#[derive(Debug)]
+pub struct EncryptedStream<T> {
+    stream: DurableStream,
+    encryptor: Encryptor,
+    _marker: PhantomData<fn(T) -> T>,
+}
+
+impl<T> Clone for EncryptedStream<T> {
+    fn clone(&self) -> Self {
+        Self {
+            stream: self.stream.clone(),
+            encryptor: self.encryptor.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Serialize + DeserializeOwned> EncryptedStream<T> {
+    /// The underlying stream handle.
+    pub fn inner(&self) -> &DurableStream {
+        &self.stream
+    }
+
+    /// Create the stream as `application/json`.
+    pub async fn create(&self) -> Result<(), StreamError> {
+        self.stream
+            .create_with(CreateOptions::new().content_type(JSON))
+            .await
+    }
+
+    /// Encrypt and append one item.
+    pub async fn append(&self, item: &T) -> Result<AppendResponse, StreamError> {
+        let envelope = self.encryptor.encrypt_json(item)?;
+        self.stream.append(serde_json::to_vec(&envelope)?).await
+    }
+
+    /// Start a decrypting read (defaults to the beginning of the stream).
+    pub fn read(&self) -> EncryptedReadBuilder<T> {
+        EncryptedReadBuilder {
+            inner: self.stream.read().typed::<Envelope>(),
+            encryptor: self.encryptor.clone(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Build a producer with default settings that encrypts `T`.
+    pub fn producer(&self, producer_id: impl Into<String>) -> EncryptedProducer<T> {
+        EncryptedProducer::new(
+            self.stream.producer(producer_id).content_type(JSON).build(),
+            &self.encryptor,
+        )
+    }
+}
+
+/// Builder for an [`EncryptedIterator`].
+#[must_use = "builders do nothing unless you call .build()"]
+pub struct EncryptedReadBuilder<T> {
+    inner: TypedReadBuilder<Envelope>,
+    encryptor: Encryptor,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> EncryptedReadBuilder<T> {
+    /// Offset to start reading from.
+    pub fn offset(mut self, offset: impl Into<Offset>) -> Self {
+        self.inner = self.inner.offset(offset);
+        self
+    }
+
+    /// Live tailing mode.
+    pub fn live(mut self, mode: LiveMode) -> Self {
+        self.inner = self.inner.live(mode);
+        self
+    }
+
+    /// Build the iterator.
+    pub fn build(self) -> Result<EncryptedIterator<T>, StreamError> {
+        Ok(EncryptedIterator {
+            inner: self.inner.build()?,
+            encryptor: self.encryptor,
+            _marker: PhantomData,
+        })
+    }
+}
+
+/// Reads an encrypted stream and decrypts its items.
+pub struct EncryptedIterator<T> {
+    inner: TypedIterator<Envelope>,
+    encryptor: Encryptor,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> EncryptedIterator<T> {
+    /// Next chunk of decrypted items.
+    ///
+    /// Fails with [`StreamError::Encryption`] if an item cannot be
+    /// decrypted.
+    pub async fn next_chunk(&mut self) -> Result<Option<TypedChunk<T>>, StreamError> {
+        let Some(chunk) = self.inner.next_chunk().await? else {
+            return Ok(None);
+        };
+        let mut items = Vec::with_capacity(chunk.items.len());
+        for envelope in &chunk.items {
+            items.push(self.encryptor.decrypt_json(envelope).await?);
+        }
+        Ok(Some(TypedChunk {
+            items,
+            next_offset: chunk.next_offset,
+            up_to_date: chunk.up_to_date,
+            cursor: chunk.cursor,
+        }))
+    }
+
+    /// Offset the next chunk will be read from.
+    pub fn offset(&self) -> &Offset {
+        self.inner.offset()
+    }
+}
+
+/// A [`Producer`] that encrypts `T` before appending it.
+pub struct EncryptedProducer<T> {
+    producer: Producer,
+    encryptor: Encryptor,
+    _marker: PhantomData<fn(T)>,
+}
+
+impl<T> Clone for EncryptedProducer<T> {
+    fn clone(&self) -> Self {
+        Self {
+            producer: self.producer.clone(),
+            encryptor: self.encryptor.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Serialize> EncryptedProducer<T> {
+    /// Wrap a producer; it should target a JSON stream.
+    pub fn new(producer: Producer, encryptor: &Encryptor) -> Self {
+        Self {
+            producer,
+            encryptor: encryptor.clone(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// The underlying producer.
+    pub fn inner(&self) -> &Producer {
+        &self.producer
+    }
+
+    /// Encrypt and queue one item (fire-and-forget); see
+    /// [`Producer::append_json`]. Fails only if encryption does.
+    pub fn append(&self, item: &T) -> Result<(), StreamError> {
+        let envelope = self.encryptor.encrypt_json(item)?;
+        self.producer.append_json(&envelope);
+        Ok(())
+    }
+
+    /// Wait for all queued items; see [`Producer::flush`].
+    pub async fn flush(&self) -> Result<(), ProducerError> {
+        self.producer.flush().await
+    }
+
+    /// Flush and close; see [`Producer::close`].
+    pub async fn close(&self) -> Result<(), ProducerError> {
+        self.producer.close().await
+    }
+}
@@ -13,13 +13,20 @@ pub enum InvalidHeaderError {
 }
 
 /// Main error type for stream operations.
+///
+/// Variants that come from an HTTP error response carry the server's
+/// [`ErrorDetails`], if the response body had any; see
+/// [`details`](Self::details).
 #[derive(Debug, Error)]
 pub enum StreamError {
     #[error("stream not found: {url}")]
-    NotFound { url: String },
+    NotFound {
+        url: String,
+        details: Option<Box<ErrorDetails>>,
+    },
 
     #[error("stream already exists with different configuration")]
-    Conflict,
+    Conflict { details: Option<Box<ErrorDetails>> },
 
     #[error("sequence conflict")]
     SeqConflict,
@@ -28,26 +35,42 @@ pub enum StreamError {
     StreamClosed,
 
     #[error("offset gone (retention/compaction): {offset}")]
-    OffsetGone { offset: String },
+    OffsetGone {
+        offset: String,
+        details: Option<Box<ErrorDetails>>,
+    },
 
     #[error("unauthorized")]
-    Unauthorized,
+    Unauthorized { details: Option<Box<ErrorDetails>> },
 
     #[error("forbidden")]
-    Forbidden,
+    Forbidden { details: Option<Box<ErrorDetails>> },
 
     #[error("rate limited")]
-    RateLimited { retry_after: Option<Duration> },
+    RateLimited {
+        retry_after: Option<Duration>,
+        details: Option<Box<ErrorDetails>>,
+    },
 
     #[error("invalid request: {message}")]
-    BadRequest { message: String },
+    BadRequest {
+        message: String,
+        details: Option<Box<ErrorDetails>>,
+    },
 
     #[error("server error: {status} - {message}")]
-    ServerError { status: u16, message: String },
+    ServerError {
+        status: u16,
+        message: String,
+        details: Option<Box<ErrorDetails>>,
+    },
 
     #[error("network error: {0}")]
     Network(#[source] reqwest::Error),
 
+    #[error("invalid url {url}: {message}")]
+    InvalidUrl { url: String, message: String },
+
     #[error("timeout")]
     Timeout,
 
@@ -62,6 +85,227 @@ pub enum StreamError {
 
     #[error("iterator closed")]
     IteratorClosed,
+
+    #[error("checkpoint store error: {0}")]
+    Store(#[from] StoreError),
+
+    #[error("handler error: {0}")]
+    Handler(#[source] crate::handler::HandlerError),
+
+    #[error("payload too large: {kind} is {actual}, limit is {limit}")]
+    PayloadTooLarge {
+        kind: LimitKind,
+        limit: usize,
+        actual: usize,
+    },
+
+    #[error(transparent)]
+    Validation(#[from] ValidationError),
+
+    /// Another process took over a role this one held, e.g. a consumer
+    /// group member that restarted under the same ID.
+    #[error("fenced: {message}")]
+    Fenced { message: String },
+
+    /// A [`Lease`](crate::Lease) is held by another owner.
+    #[error("lease held by {owner} for another {expires_in:?}")]
+    LeaseHeld { owner: String, expires_in: Duration },
+
+    #[error(transparent)]
+    Encryption(#[from] EncryptionError),
+
+    /// A signed record failed verification.
+    #[error(transparent)]
+    Signature(#[from] SignatureError),
+
+    #[error("producer error: {0}")]
+    Producer(#[source] Box<ProducerError>),
+
+    /// A response body did not match its `Content-Digest`, e.g. because a
+    /// proxy corrupted it in transit.
+    #[error("checksum mismatch: expected {expected}, got {actual}")]
+    ChecksumMismatch { expected: String, actual: String },
+
+    /// The stream was reset while being read, e.g. deleted and recreated,
+    /// so offsets from before no longer refer to the same data.
+    #[error("stream reset")]
+    StreamReset,
+
+    /// A redirect the client's [`RedirectPolicy`](crate::RedirectPolicy)
+    /// did not follow.
+    #[error("redirect not followed ({status})")]
+    Redirect { status: u16, location: Option<String> },
+}
+
+/// The body of a server error response.
+///
+/// Parsed from `{"error": {"code": ..., "message": ...}}`, the same fields at
+/// the top level, or a short plain-text body (as `message`).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ErrorDetails {
+    /// Machine-readable error code, e.g. `"FENCED"`.
+    pub code: Option<String>,
+    /// Human-readable explanation.
+    pub message: Option<String>,
+    /// Request ID for correlating with server logs: from the body, else the
+    /// response's `x-request-id` header.
+    pub request_id: Option<String>,
+    /// Whether the server says retrying may succeed.
+    pub retryable: Option<bool>,
+}
+
+impl ErrorDetails {
+    /// Bodies larger than this are not parsed.
+    const MAX_BODY: usize = 64 * 1024;
+    /// Plain-text bodies longer than this are not used as a message.
+    const MAX_TEXT: usize = 1024;
+
+    /// Read and parse the body of error response `resp`.
+    pub(crate) async fn read(resp: reqwest::Response) -> Option<Box<Self>> {
+        let header_id = crate::request_id::from_headers(resp.headers());
+        let mut details = match resp.bytes().await {
+            Ok(body) => Self::parse(&body),
+            Err(_) => None,
+        };
+        if let Some(id) = header_id {
+            let details = details.get_or_insert_with(Self::default);
+            details.request_id.get_or_insert(id);
+        }
+        details.map(Box::new)
+    }
+
+    /// Details known from the headers of error response `resp` alone.
+    fn from_headers(resp: &reqwest::Response) -> Option<Box<Self>> {
+        let request_id = crate::request_id::from_headers(resp.headers())?;
+        Some(Box::new(Self {
+            request_id: Some(request_id),
+            ..Self::default()
+        }))
+    }
+
+    /// Parse an error response body; `None` if it says nothing useful.
+    pub(crate) fn parse(body: &[u8]) -> Option<Self> {
+        if body.is_empty() || body.len() > Self::MAX_BODY {
+            return None;
+        }
+        #[cfg(feature = "json")]
+        if let Ok(serde_json::Value::Object(mut object)) = serde_json::from_slice(body) {
+            let fields = match object.remove("error") {
+                Some(serde_json::Value::Object(error)) => error,
+                Some(serde_json::Value::String(message)) => {
+                    object.insert("message".to_string(), message.into());
+                    object
+                }
+                _ => object,
+            };
+            let text = |keys: &[&str]| {
+                keys.iter()
+                    .find_map(|key| fields.get(*key).and_then(|v| v.as_str()))
+                    .map(str::to_string)
+            };
+            let details = Self {
+                code: text(&["code"]),
+                message: text(&["message"]),
+                request_id: text(&["requestId", "request_id"]),
+                retryable: fields.get("retryable").and_then(|v| v.as_bool()),
+            };
+            return (details != Self::default()).then_some(details);
+        }
+        let text = std::str::from_utf8(body).ok()?.trim();
+        if text.is_empty() || text.len() > Self::MAX_TEXT || text.starts_with(['{', '[', '<']) {
+            return None;
+        }
+        Some(Self {
+            message: Some(text.to_string()),
+            ..Self::default()
+        })
+    }
+}
+
+/// A record rejected by a [`SchemaValidator`](crate::SchemaValidator).
+#[derive(Debug, Clone, Error)]
+#[error("validation failed: {message}")]
+pub struct ValidationError {
+    /// Why the record was rejected.
+    pub message: String,
+}
+
+impl ValidationError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+/// A patch that could not be applied to a document.
+#[derive(Debug, Clone, Error)]
+#[error("patch failed: {message}")]
+pub struct PatchError {
+    /// Why the patch did not apply.
+    pub message: String,
+}
+
+impl PatchError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+/// A record that could not be encrypted or decrypted.
+#[derive(Debug, Clone, Error)]
+#[error("encryption failed: {message}")]
+pub struct EncryptionError {
+    /// What went wrong; never includes key material.
+    pub message: String,
+}
+
+impl EncryptionError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+/// A signature that could not be made or did not verify.
+#[derive(Debug, Clone, Error)]
+#[error("signature check failed: {message}")]
+pub struct SignatureError {
+    /// What went wrong; never includes key material.
+    pub message: String,
+}
+
+impl SignatureError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+/// Which decode limit a payload exceeded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LimitKind {
+    /// Encoded size of a single item.
+    ItemBytes,
+    /// Number of items in one chunk.
+    ItemsPerChunk,
+    /// Nesting depth of an item.
+    Depth,
+}
+
+impl std::fmt::Display for LimitKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            LimitKind::ItemBytes => "item size",
+            LimitKind::ItemsPerChunk => "items per chunk",
+            LimitKind::Depth => "nesting depth",
+        })
+    }
 }
 
 impl StreamError {
@@ -70,35 +314,141 @@ impl StreamError {
         match status {
             400 => StreamError::BadRequest {
                 message: "Bad request".to_string(),
+                details: None,
             },
-            401 => StreamError::Unauthorized,
-            403 => StreamError::Forbidden,
+            401 => StreamError::Unauthorized { details: None },
+            403 => StreamError::Forbidden { details: None },
             404 => StreamError::NotFound {
                 url: url.to_string(),
+                details: None,
             },
-            409 => StreamError::Conflict,
+            409 => StreamError::Conflict { details: None },
             410 => StreamError::OffsetGone {
                 offset: String::new(),
+                details: None,
+            },
+            429 => StreamError::RateLimited {
+                retry_after: None,
+                details: None,
+            },
+            300..=399 => StreamError::Redirect {
+                status,
+                location: None,
             },
-            429 => StreamError::RateLimited { retry_after: None },
             _ if status >= 500 => StreamError::ServerError {
                 status,
                 message: format!("Server error {}", status),
+                details: None,
             },
             _ => StreamError::ServerError {
                 status,
                 message: format!("Unexpected status {}", status),
+                details: None,
+            },
+        }
+    }
+
+    /// Create error from an HTTP response, preserving header-borne details
+    /// such as `Retry-After` on 429.
+    pub(crate) fn from_response(resp: &reqwest::Response, url: &str) -> Self {
+        let err = match Self::from_status(resp.status().as_u16(), url) {
+            StreamError::RateLimited { .. } => StreamError::RateLimited {
+                retry_after: crate::retry::retry_after_from_headers(resp.headers()),
+                details: None,
             },
+            StreamError::Redirect { status, .. } => StreamError::Redirect {
+                status,
+                location: resp
+                    .headers()
+                    .get(reqwest::header::LOCATION)
+                    .and_then(|v| v.to_str().ok())
+                    .map(str::to_string),
+            },
+            other => other,
+        };
+        match ErrorDetails::from_headers(resp) {
+            Some(details) => err.with_details(details),
+            None => err,
+        }
+    }
+
+    /// Create error from an HTTP response, including details from its body.
+    pub(crate) async fn from_error_response(resp: reqwest::Response, url: &str) -> Self {
+        let err = Self::from_response(&resp, url);
+        match ErrorDetails::read(resp).await {
+            Some(details) => err.with_details(details),
+            None => err,
+        }
+    }
+
+    /// Attach the server's error details; a server message replaces the
+    /// generic one.
+    fn with_details(mut self, new: Box<ErrorDetails>) -> Self {
+        match &mut self {
+            StreamError::BadRequest { message, details }
+            | StreamError::ServerError { message, details, .. } => {
+                if let Some(server_message) = &new.message {
+                    *message = server_message.clone();
+                }
+                *details = Some(new);
+            }
+            StreamError::NotFound { details, .. }
+            | StreamError::Conflict { details }
+            | StreamError::OffsetGone { details, .. }
+            | StreamError::Unauthorized { details }
+            | StreamError::Forbidden { details }
+            | StreamError::RateLimited { details, .. } => *details = Some(new),
+            _ => {}
+        }
+        self
+    }
+
+    /// The server's error response details, if any.
+    pub fn details(&self) -> Option<&ErrorDetails> {
+        match self {
+            StreamError::NotFound { details, .. }
+            | StreamError::Conflict { details }
+            | StreamError::OffsetGone { details, .. }
+            | StreamError::Unauthorized { details }
+            | StreamError::Forbidden { details }
+            | StreamError::RateLimited { details, .. }
+            | StreamError::BadRequest { details, .. }
+            | StreamError::ServerError { details, .. } => details.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// The server's machine-readable error code, e.g. `"FENCED"`.
+    pub fn code(&self) -> Option<&str> {
+        self.details()?.code.as_deref()
+    }
+
+    /// The server's ID for the failed request.
+    pub fn request_id(&self) -> Option<&str> {
+        self.details()?.request_id.as_deref()
+    }
+
+    /// Server-requested delay before retrying, if any.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            StreamError::RateLimited { retry_after, .. } => *retry_after,
+            _ => None,
         }
     }
 
     /// Whether this error is retryable
+    ///
+    /// The server's `retryable` flag, when present, takes precedence.
     pub fn is_retryable(&self) -> bool {
+        if let Some(retryable) = self.details().and_then(|d| d.retryable) {
+            return retryable;
+        }
         match self {
             StreamError::RateLimited { .. } => true,
             StreamError::ServerError { status, .. } => *status >= 500,
             StreamError::Network(_) => true,
             StreamError::Timeout => true,
+            StreamError::ChecksumMismatch { .. } => true,
             _ => false,
         }
     }
@@ -107,9 +457,9 @@ impl StreamError {
     pub fn status_code(&self) -> Option<u16> {
         match self {
             StreamError::NotFound { .. } => Some(404),
-            StreamError::Conflict => Some(409),
-            StreamError::Unauthorized => Some(401),
-            StreamError::Forbidden => Some(403),
+            StreamError::Conflict { .. } => Some(409),
+            StreamError::Unauthorized { .. } => Some(401),
+            StreamError::Forbidden { .. } => Some(403),
             StreamError::RateLimited { .. } => Some(429),
             StreamError::BadRequest { .. } => Some(400),
             StreamError::ServerError { status, .. } => Some(*status),
@@ -124,14 +474,18 @@ impl StreamError {
     pub fn to_error_code(&self) -> &'static str {
         match self {
             StreamError::NotFound { .. } => "NOT_FOUND",
-            StreamError::Conflict => "CONFLICT",
+            StreamError::Conflict { .. } => "CONFLICT",
             StreamError::SeqConflict => "SEQUENCE_CONFLICT",
             StreamError::StreamClosed => "STREAM_CLOSED",
             StreamError::OffsetGone { .. } => "INVALID_OFFSET",
             StreamError::BadRequest { .. } => "INVALID_OFFSET",
-            StreamError::Unauthorized => "UNAUTHORIZED",
-            StreamError::Forbidden => "FORBIDDEN",
+            StreamError::Unauthorized { .. } => "UNAUTHORIZED",
+            StreamError::Forbidden { .. } => "FORBIDDEN",
             StreamError::ParseError(_) => "PARSE_ERROR",
+            StreamError::Validation(_) => "VALIDATION_ERROR",
+            StreamError::ChecksumMismatch { .. } => "CHECKSUM_MISMATCH",
+            StreamError::StreamReset => "STREAM_RESET",
+            StreamError::Redirect { .. } => "REDIRECT",
             _ => "UNEXPECTED_STATUS",
         }
     }
@@ -154,6 +508,19 @@ impl From<serde_json::Error> for StreamError {
     }
 }
 
+/// Errors from checkpoint and producer state stores.
+#[derive(Debug, Error)]
+pub enum StoreError {
+    #[error("store io error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("store backend error: {0}")]
+    Backend(String),
+
+    #[error("corrupt store data: {0}")]
+    Corrupt(String),
+}
+
 /// Producer-specific errors.
 #[derive(Debug, Clone, Error)]
 pub enum ProducerError {
@@ -174,6 +541,52 @@ pub enum ProducerError {
 
     #[error("mixed append types in JSON mode")]
     MixedAppendTypes,
+
+    #[error("state store error: {message}")]
+    StateStore { message: String },
+
+    #[error("{} batch(es) failed since the last flush", .failures.len())]
+    Flush { failures: Vec<BatchFailure> },
+
+    #[error(transparent)]
+    Validation(#[from] ValidationError),
+}
+
+/// Invalid producer configuration, reported by
+/// [`ProducerBuilder::try_build`](crate::ProducerBuilder::try_build).
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ProducerConfigError {
+    #[error("producer id must not be empty")]
+    EmptyProducerId,
+
+    #[error("producer id is not a valid header value: {0:?}")]
+    InvalidProducerId(String),
+
+    #[error("content type is not a valid header value: {0:?}")]
+    InvalidContentType(String),
+
+    #[error("max_batch_bytes must be positive")]
+    ZeroMaxBatchBytes,
+
+    #[error("max_in_flight must be positive")]
+    ZeroMaxInFlight,
+}
+
+/// A producer batch that could not be written, reported by
+/// [`Producer::flush`](crate::Producer::flush).
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct BatchFailure {
+    /// Producer sequence number of the batch.
+    pub seq: u64,
+    /// Why the batch failed.
+    pub error: ProducerError,
+}
+
+impl std::fmt::Display for BatchFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "batch {}: {}", self.seq, self.error)
+    }
 }
 
 impl From<reqwest::Error> for ProducerError {
@@ -184,10 +597,19 @@ impl From<reqwest::Error> for ProducerError {
     }
 }
 
+impl From<StoreError> for ProducerError {
+    fn from(err: StoreError) -> Self {
+        ProducerError::StateStore {
+            message: err.to_string(),
+        }
+    }
+}
+
 impl From<StreamError> for ProducerError {
     fn from(err: StreamError) -> Self {
         match err {
             StreamError::StreamClosed => ProducerError::StreamClosed,
+            StreamError::Validation(e) => ProducerError::Validation(e),
             other => ProducerError::Stream {
                 message: other.to_string(),
             },
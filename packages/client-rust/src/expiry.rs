@@ -0,0 +1,129 @@
+//! Stream expiry: when a stream expires and keeping it alive.
+
+use crate::error::StreamError;
+use crate::rt;
+use crate::stream::{DurableStream, HeadResponse};
+use crate::types::Offset;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+impl HeadResponse {
+    /// The stream's absolute expiry (`Stream-Expires-At`), if it has one
+    /// and it is a valid RFC 3339 timestamp.
+    pub fn expires_at_time(&self) -> Option<SystemTime> {
+        parse_rfc3339(self.expires_at.as_deref()?)
+    }
+
+    /// How long until the stream expires, if it expires at all.
+    ///
+    /// For an absolute expiry this is the time left until it, zero once
+    /// passed. For a sliding [`ttl`](Self::ttl) it is the whole window: the
+    /// server does not report when the countdown was last reset, so the
+    /// stream may expire sooner. If it has both, the earlier applies.
+    pub fn time_to_expiry(&self) -> Option<Duration> {
+        let absolute = self.expires_at_time().map(|at| {
+            let at = at.duration_since(UNIX_EPOCH).unwrap_or_default();
+            at.saturating_sub(rt::unix_now())
+        });
+        match (absolute, self.ttl) {
+            (Some(a), Some(ttl)) => Some(a.min(ttl)),
+            (a, ttl) => a.or(ttl),
+        }
+    }
+}
+
+impl DurableStream {
+    /// Reset the stream's sliding TTL without reading or writing data.
+    ///
+    /// Every read or write resets the countdown, but `HEAD` does not, so
+    /// this reads from the tail, which returns no data. Call it
+    /// periodically to keep a session-scoped stream alive while it is
+    /// idle. The protocol fixes a stream's TTL and expiry when it is
+    /// created; an absolute expiry cannot be moved.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let head = session.head().await?;
+    /// if head.time_to_expiry().is_some_and(|left| left < Duration::from_secs(60)) {
+    ///     session.touch().await?;
+    /// }
+    /// ```
+    pub async fn touch(&self) -> Result<(), StreamError> {
+        let mut reader = self.read().offset(Offset::Now).build()?;
+        reader.next_chunk().await?;
+        Ok(())
+    }
+}
+
+/// Parse an RFC 3339 timestamp such as `2025-06-01T12:00:00Z` or
+/// `2025-06-01T14:00:00.5+02:00`. Times before the Unix epoch are `None`.
+pub(crate) fn parse_rfc3339(s: &str) -> Option<SystemTime> {
+    let b = s.trim().as_bytes();
+    if b.len() < 20 || b[4] != b'-' || b[7] != b'-' || b[13] != b':' || b[16] != b':' {
+        return None;
+    }
+    if !matches!(b[10], b'T' | b't' | b' ') {
+        return None;
+    }
+    let num = |range: std::ops::Range<usize>| -> Option<i64> {
+        let digits = b.get(range)?;
+        if !digits.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        std::str::from_utf8(digits).ok()?.parse().ok()
+    };
+    let (year, month, day) = (num(0..4)?, num(5..7)?, num(8..10)?);
+    let (hour, minute, second) = (num(11..13)?, num(14..16)?, num(17..19)?);
+    if !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+
+    let mut rest = &b[19..];
+    let mut nanos = 0u32;
+    if let Some(frac) = rest.strip_prefix(b".") {
+        let len = frac.iter().take_while(|c| c.is_ascii_digit()).count();
+        if len == 0 {
+            return None;
+        }
+        for (i, digit) in frac[..len.min(9)].iter().enumerate() {
+            nanos += u32::from(digit - b'0') * 10u32.pow(8 - i as u32);
+        }
+        rest = &frac[len..];
+    }
+    let offset = match rest {
+        [b'Z' | b'z'] => 0,
+        [sign @ (b'+' | b'-'), h1, h2, b':', m1, m2] => {
+            let field = |a: u8, b: u8| -> Option<i64> {
+                (a.is_ascii_digit() && b.is_ascii_digit())
+                    .then(|| i64::from((a - b'0') * 10 + (b - b'0')))
+            };
+            let minutes = field(*h1, *h2)? * 60 + field(*m1, *m2)?;
+            if *sign == b'-' {
+                -minutes * 60
+            } else {
+                minutes * 60
+            }
+        }
+        _ => return None,
+    };
+
+    let days = days_from_civil(year, month, day);
+    let secs = days * 86_400 + hour * 3_600 + minute * 60 + second - offset;
+    let secs = u64::try_from(secs).ok()?;
+    Some(UNIX_EPOCH + Duration::new(secs, nanos))
+}
+
+/// Days from 1970-01-01 to the given proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
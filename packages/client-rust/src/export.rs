@@ -0,0 +1,333 @@
+//! Portable stream export files.
+
+use crate::error::{StoreError, StreamError};
+use crate::rt;
+use crate::stream::{AppendOptions, CreateOptions, DurableStream};
+use crate::types::{LiveMode, Offset};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const FORMAT: &str = "durable-streams-export";
+const VERSION: u32 = 1;
+/// Lines read from an export file per blocking call.
+const READ_BATCH: usize = 64;
+
+/// First line of an export file.
+#[derive(Serialize, Deserialize)]
+struct Header {
+    format: String,
+    version: u32,
+    source: String,
+    content_type: Option<String>,
+    ttl_seconds: Option<u64>,
+    expires_at: Option<String>,
+    closed: bool,
+    exported_at_ms: u64,
+}
+
+/// One chunk of stream data.
+#[derive(Serialize, Deserialize)]
+struct Record {
+    offset: Offset,
+    next_offset: Offset,
+    read_at_ms: u64,
+    /// Base64 of the chunk's bytes.
+    data: String,
+}
+
+/// Last line of an export file, so truncated files are detected.
+#[derive(Serialize, Deserialize)]
+struct Trailer {
+    end: Offset,
+    chunks: u64,
+    bytes: u64,
+}
+
+/// A line of an export file after the header.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Line {
+    Record(Record),
+    Trailer(Trailer),
+}
+
+/// What an export or import moved, from [`DurableStream::export_to`] or
+/// [`DurableStream::import_from`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ExportSummary {
+    /// Content type of the exported stream.
+    pub content_type: Option<String>,
+    /// Number of chunks in the file.
+    pub chunks: u64,
+    /// Total data bytes in the file.
+    pub bytes: u64,
+    /// Source stream offset the export ends at.
+    pub end_offset: Offset,
+    /// Whether the source stream was closed.
+    pub closed: bool,
+}
+
+impl DurableStream {
+    /// Write the whole stream to a portable export file at `path`.
+    ///
+    /// The file is newline-delimited JSON: a header with the source URL,
+    /// content type, TTL, closed state and export time, then one line per
+    /// chunk with its start and end offsets, read time and base64 data,
+    /// then a trailer with the chunk and byte counts. The file is written
+    /// to a temporary sibling and renamed into place when complete.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let summary = stream.export_to("orders.dsx").await?;
+    /// println!("exported {} bytes up to {}", summary.bytes, summary.end_offset);
+    /// ```
+    pub async fn export_to(&self, path: impl AsRef<Path>) -> Result<ExportSummary, StreamError> {
+        let meta = self.metadata().await?;
+        let header = Header {
+            format: FORMAT.to_string(),
+            version: VERSION,
+            source: self.url().to_string(),
+            content_type: meta.content_type.clone(),
+            ttl_seconds: meta.ttl.map(|ttl| ttl.as_secs()),
+            expires_at: meta.expires_at,
+            closed: meta.stream_closed,
+            exported_at_ms: unix_ms(),
+        };
+
+        let path = path.as_ref().to_path_buf();
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+        let mut out = {
+            let (tmp, line) = (tmp.clone(), to_line(&header)?);
+            blocking(move || {
+                let mut out = BufWriter::new(File::create(&tmp)?);
+                out.write_all(&line)?;
+                Ok(out)
+            })
+            .await?
+        };
+
+        let mut summary = ExportSummary {
+            content_type: meta.content_type,
+            chunks: 0,
+            bytes: 0,
+            end_offset: Offset::Beginning,
+            closed: meta.stream_closed,
+        };
+        let mut reader = self
+            .read()
+            .offset(Offset::Beginning)
+            .live(LiveMode::Off)
+            .build()?;
+        let mut start = Offset::Beginning;
+        while let Some(chunk) = reader.next_chunk().await? {
+            if !chunk.data.is_empty() {
+                let line = to_line(&Record {
+                    offset: start,
+                    next_offset: chunk.next_offset.clone(),
+                    read_at_ms: unix_ms(),
+                    data: base64::engine::general_purpose::STANDARD.encode(&chunk.data),
+                })?;
+                out = blocking(move || {
+                    out.write_all(&line)?;
+                    Ok(out)
+                })
+                .await?;
+                summary.chunks += 1;
+                summary.bytes += chunk.data.len() as u64;
+            }
+            start = chunk.next_offset;
+        }
+        summary.end_offset = start;
+
+        let line = to_line(&Trailer {
+            end: summary.end_offset.clone(),
+            chunks: summary.chunks,
+            bytes: summary.bytes,
+        })?;
+        blocking(move || {
+            out.write_all(&line)?;
+            let file = out.into_inner().map_err(|e| e.into_error())?;
+            file.sync_all()?;
+            fs::rename(&tmp, &path)?;
+            Ok(())
+        })
+        .await?;
+        Ok(summary)
+    }
+
+    /// Recreate a stream from an export file written by
+    /// [`export_to`](Self::export_to).
+    ///
+    /// The stream is created with the exported content type and TTL (an
+    /// absolute expiry time is not carried over), then each chunk is
+    /// appended and the stream is closed if the source was. The file is
+    /// checked to be complete before anything is written.
+    ///
+    /// Chunks are appended with their source offsets as `Stream-Seq`, so
+    /// an import that failed part-way can be run again and resumes where
+    /// it stopped. Import into a new stream, not one written by others.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let staging = client.stream("https://staging.example.com/streams/orders");
+    /// staging.import_from("orders.dsx").await?;
+    /// ```
+    pub async fn import_from(&self, path: impl AsRef<Path>) -> Result<ExportSummary, StreamError> {
+        let path = path.as_ref().to_path_buf();
+        let (header, summary) = {
+            let path = path.clone();
+            blocking(move || verify(&path)).await?
+        };
+
+        let mut options = CreateOptions::new();
+        if let Some(ct) = &header.content_type {
+            options = options.content_type(ct.clone());
+        }
+        if let Some(ttl) = header.ttl_seconds {
+            options = options.ttl(Duration::from_secs(ttl));
+        }
+        self.create_with(options).await?;
+        let mut target = self.clone();
+        if header.content_type.is_some() {
+            target.content_type = header.content_type;
+        }
+
+        let mut lines = {
+            let path = path.clone();
+            blocking(move || {
+                let mut lines = BufReader::new(File::open(&path)?).lines();
+                // Skip the header, checked by `verify`
+                lines.next().transpose()?;
+                Ok(lines)
+            })
+            .await?
+        };
+        loop {
+            let (rest, batch) = blocking(move || {
+                let batch = lines
+                    .by_ref()
+                    .take(READ_BATCH)
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok((lines, batch))
+            })
+            .await?;
+            lines = rest;
+            if batch.is_empty() {
+                break;
+            }
+            for line in batch {
+                let Line::Record(record) = parse_line(&path, &line)? else {
+                    continue;
+                };
+                let data = decode_data(&path, &record)?;
+                let options = AppendOptions::new().seq(record.next_offset.as_str());
+                match target.append_with(data, options).await {
+                    // Already imported by an earlier run
+                    Ok(_) | Err(StreamError::SeqConflict) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+
+        if summary.closed {
+            target.close().await?;
+        }
+        Ok(summary)
+    }
+}
+
+/// Check that the export file at `path` is complete and well-formed.
+fn verify(path: &Path) -> Result<(Header, ExportSummary), StoreError> {
+    let corrupt = |msg: &str| StoreError::Corrupt(format!("{}: {msg}", path.display()));
+    let mut lines = BufReader::new(File::open(path)?).lines();
+
+    let first = lines
+        .next()
+        .transpose()?
+        .ok_or_else(|| corrupt("empty file"))?;
+    let header: Header =
+        serde_json::from_str(&first).map_err(|_| corrupt("not a durable streams export"))?;
+    if header.format != FORMAT {
+        return Err(corrupt("not a durable streams export"));
+    }
+    if header.version != VERSION {
+        return Err(corrupt(&format!(
+            "unsupported export version {}",
+            header.version
+        )));
+    }
+
+    let (mut chunks, mut bytes) = (0, 0);
+    for line in lines.by_ref() {
+        match parse_line(path, &line?)? {
+            Line::Record(record) => {
+                chunks += 1;
+                bytes += decode_data(path, &record)?.len() as u64;
+            }
+            Line::Trailer(trailer) => {
+                if trailer.chunks != chunks || trailer.bytes != bytes {
+                    return Err(corrupt("chunk count does not match trailer"));
+                }
+                if lines.next().is_some() {
+                    return Err(corrupt("data after trailer"));
+                }
+                let summary = ExportSummary {
+                    content_type: header.content_type.clone(),
+                    chunks,
+                    bytes,
+                    end_offset: trailer.end,
+                    closed: header.closed,
+                };
+                return Ok((header, summary));
+            }
+        }
+    }
+    Err(corrupt("truncated (no trailer)"))
+}
+
+fn parse_line(path: &Path, line: &str) -> Result<Line, StoreError> {
+    serde_json::from_str(line)
+        .map_err(|e| StoreError::Corrupt(format!("{}: bad line: {e}", path.display())))
+}
+
+fn decode_data(path: &Path, record: &Record) -> Result<Vec<u8>, StoreError> {
+    base64::engine::general_purpose::STANDARD
+        .decode(&record.data)
+        .map_err(|e| {
+            StoreError::Corrupt(format!(
+                "{}: bad data at {}: {e}",
+                path.display(),
+                record.offset
+            ))
+        })
+}
+
+fn to_line<T: Serialize>(value: &T) -> Result<Vec<u8>, StreamError> {
+    let mut line = serde_json::to_vec(value)?;
+    line.push(b'\n');
+    Ok(line)
+}
+
+fn unix_ms() -> u64 {
+    rt::unix_now().as_millis() as u64
+}
+
+/// Run blocking file I/O off the async runtime.
+async fn blocking<T, F>(f: F) -> Result<T, StoreError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, StoreError> + Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| StoreError::Backend(e.to_string()))?
+}
@@ -0,0 +1,97 @@
+//! Failover between equivalent server endpoints.
+//!
+//! Requests are addressed to the primary endpoint and routed to the first
+//! endpoint, in priority order, that is not backing off. Endpoint health
+//! comes from the requests themselves: a connection failure or a 502, 503
+//! or 504 backs the endpoint off, and once the backoff expires the next
+//! request probes it again.
+
+use crate::rt::Instant;
+use parking_lot::Mutex;
+use reqwest::Url;
+use std::time::Duration;
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+pub(crate) struct Endpoints {
+    list: Vec<Endpoint>,
+}
+
+struct Endpoint {
+    /// Base URL without a trailing slash.
+    base: String,
+    health: Mutex<Health>,
+}
+
+#[derive(Default)]
+struct Health {
+    consecutive_failures: u32,
+    retry_at: Option<Instant>,
+}
+
+impl Endpoints {
+    pub(crate) fn new(bases: Vec<String>) -> Self {
+        Self {
+            list: bases
+                .into_iter()
+                .map(|base| Endpoint {
+                    base: base.trim_end_matches('/').to_string(),
+                    health: Mutex::default(),
+                })
+                .collect(),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub(crate) fn primary(&self) -> &str {
+        &self.list[0].base
+    }
+
+    /// Endpoint to use next, skipping those already `tried`.
+    ///
+    /// Prefers the first healthy endpoint; if all are backing off, the one
+    /// that recovers soonest.
+    pub(crate) fn pick(&self, tried: &[usize]) -> usize {
+        let now = Instant::now();
+        let candidates = || (0..self.list.len()).filter(|i| !tried.contains(i));
+        candidates()
+            .find(|&i| self.list[i].health.lock().retry_at.is_none_or(|at| at <= now))
+            .or_else(|| candidates().min_by_key(|&i| self.list[i].health.lock().retry_at))
+            .unwrap_or(0)
+    }
+
+    /// Point `url` at endpoint `index` if it addresses the primary.
+    pub(crate) fn route(&self, url: &mut Url, index: usize) {
+        if index == 0 {
+            return;
+        }
+        let Some(rest) = url.as_str().strip_prefix(self.primary()) else {
+            return;
+        };
+        if !(rest.is_empty() || rest.starts_with(['/', '?'])) {
+            return;
+        }
+        if let Ok(routed) = Url::parse(&format!("{}{}", self.list[index].base, rest)) {
+            *url = routed;
+        }
+    }
+
+    pub(crate) fn succeeded(&self, index: usize) {
+        *self.list[index].health.lock() = Health::default();
+    }
+
+    /// Back endpoint `index` off, doubling the delay on each consecutive
+    /// failure.
+    pub(crate) fn failed(&self, index: usize) {
+        let mut health = self.list[index].health.lock();
+        let backoff = INITIAL_BACKOFF
+            .saturating_mul(1 << health.consecutive_failures.min(6))
+            .min(MAX_BACKOFF);
+        health.consecutive_failures += 1;
+        health.retry_at = Some(Instant::now() + backoff);
+    }
+}
@@ -0,0 +1,156 @@
+//! Record framing for chunked reads.
+
+use crate::iterator::Chunk;
+use crate::types::Offset;
+use bytes::{Bytes, BytesMut};
+use std::collections::VecDeque;
+
+/// How a [`ChunkIterator`](crate::ChunkIterator) splits response bodies.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Framing {
+    /// One chunk per response body or SSE event (default).
+    #[default]
+    None,
+    /// Newline-delimited JSON: one chunk per line, with partial lines
+    /// buffered until the rest arrives.
+    NdJson,
+}
+
+/// Splits chunks into newline-delimited records.
+pub(crate) struct LineFramer {
+    /// Bytes of a line whose newline has not arrived yet.
+    partial: BytesMut,
+    /// Offset of the response the partial line began in.
+    partial_start: Offset,
+    /// Records split out but not yet returned.
+    ready: VecDeque<Chunk>,
+    /// Metadata of the last chunk pushed, for the final unterminated line.
+    last: Option<Chunk>,
+    /// Resumption offset of the last record returned.
+    position: Offset,
+}
+
+impl LineFramer {
+    pub(crate) fn new(position: Offset) -> Self {
+        Self {
+            partial: BytesMut::new(),
+            partial_start: position.clone(),
+            ready: VecDeque::new(),
+            last: None,
+            position,
+        }
+    }
+
+    /// Offset to resume from without skipping any record not yet returned.
+    pub(crate) fn position(&self) -> &Offset {
+        &self.position
+    }
+
+    /// Whether no records or partial lines are buffered.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.ready.is_empty() && self.partial.is_empty()
+    }
+
+    /// Drop buffered data and restart at `offset`.
+    pub(crate) fn reset(&mut self, offset: Offset) {
+        *self = Self::new(offset);
+    }
+
+    /// Next complete record, if any.
+    pub(crate) fn pop(&mut self) -> Option<Chunk> {
+        let record = self.ready.pop_front()?;
+        self.position = record.next_offset.clone();
+        Some(record)
+    }
+
+    /// Split `chunk`, which was requested at offset `start`.
+    ///
+    /// A record's `next_offset` skips past it only when it ends exactly at
+    /// a response boundary; otherwise it points at the response the record
+    /// began in, so resuming from it re-delivers earlier records of that
+    /// response (at-least-once). Chunks without data pass through so that
+    /// long-poll timeouts and up-to-date signals stay visible.
+    pub(crate) fn push(&mut self, chunk: Chunk, start: Offset) {
+        if chunk.data.is_empty() {
+            let mut chunk = chunk;
+            if !self.partial.is_empty() {
+                chunk.next_offset = self.partial_start.clone();
+                chunk.up_to_date = false;
+            }
+            self.ready.push_back(chunk);
+            return;
+        }
+
+        let mut data = chunk.data.clone();
+        let mut resume = if self.partial.is_empty() {
+            start.clone()
+        } else {
+            self.partial_start.clone()
+        };
+        let first_new = self.ready.len();
+
+        while let Some(pos) = data.iter().position(|&b| b == b'\n') {
+            let line = data.split_to(pos + 1);
+            let line = if self.partial.is_empty() {
+                line
+            } else {
+                self.partial.extend_from_slice(&line);
+                self.partial.split().freeze()
+            };
+            let at_end = data.is_empty();
+
+            if let Some(line) = trim_line(line) {
+                self.ready.push_back(Chunk {
+                    data: line,
+                    next_offset: if at_end {
+                        chunk.next_offset.clone()
+                    } else {
+                        resume.clone()
+                    },
+                    up_to_date: false,
+                    cursor: chunk.cursor.clone(),
+                    status_code: chunk.status_code,
+                    lag: if at_end { chunk.lag.clone() } else { None },
+                    request_id: chunk.request_id.clone(),
+                    event_id: chunk.event_id.clone(),
+                    reset: false,
+                });
+            }
+            resume = start.clone();
+        }
+
+        if !data.is_empty() {
+            if self.partial.is_empty() {
+                self.partial_start = start;
+            }
+            self.partial.extend_from_slice(&data);
+        } else if self.ready.len() > first_new {
+            // The response ended on a record boundary
+            if let Some(last) = self.ready.back_mut() {
+                last.up_to_date = chunk.up_to_date;
+            }
+        }
+        self.last = Some(chunk);
+    }
+
+    /// Flush a final line that never got its newline, once the read ends.
+    pub(crate) fn finish(&mut self) -> Option<Chunk> {
+        if let Some(record) = self.pop() {
+            return Some(record);
+        }
+        let last = self.last.take()?;
+        let line = trim_line(self.partial.split().freeze())?;
+        let record = Chunk { data: line, ..last };
+        self.position = record.next_offset.clone();
+        Some(record)
+    }
+}
+
+/// Strip the line terminator; blank lines are not records.
+fn trim_line(mut line: Bytes) -> Option<Bytes> {
+    while matches!(line.last(), Some(b'\n' | b'\r')) {
+        line.truncate(line.len() - 1);
+    }
+    (!line.is_empty()).then_some(line)
+}
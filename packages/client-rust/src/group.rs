@@ -0,0 +1,605 @@
+//! Consumer groups: partitions shared between processes.
+
+use crate::client::Client;
+use crate::error::{ProducerError, StreamError};
+use crate::iterator::{Chunk, ChunkIterator};
+use crate::lease::{append_acked, producer_error};
+use crate::partitioned::{next_of, PartitionedChunk, PartitionedStream};
+use crate::producer::{AppendReceipt, Producer};
+use crate::rt::{self, unix_now};
+use crate::stream::{CreateOptions, DurableStream};
+use crate::typed::TypedIterator;
+use crate::types::{LiveMode, Offset};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+
+const JSON: &str = "application/json";
+
+type RebalanceCallback = Arc<dyn Fn(&[usize]) + Send + Sync>;
+
+impl Client {
+    /// Configure membership of the consumer group coordinated through the
+    /// lease stream at `lease_url`, as member `member_id`.
+    ///
+    /// Member IDs must be unique and stable across restarts of a process,
+    /// e.g. a hostname or pod name.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let mut group = client
+    ///     .consumer_group("/v1/stream/orders.group", hostname)
+    ///     .partitions(&orders)
+    ///     .on_revoked(|partitions| println!("lost {partitions:?}"))
+    ///     .join()
+    ///     .await?;
+    ///
+    /// while let Some(item) = group.next_chunk().await? {
+    ///     process(item.partition, &item.chunk.data).await;
+    ///     group.commit(&item).await?;
+    /// }
+    /// ```
+    pub fn consumer_group(
+        &self,
+        lease_url: &str,
+        member_id: impl Into<String>,
+    ) -> ConsumerGroupBuilder {
+        ConsumerGroupBuilder {
+            lease: self.stream(lease_url),
+            member: member_id.into(),
+            partitions: Vec::new(),
+            session_timeout: Duration::from_secs(10),
+            heartbeat_interval: Duration::from_secs(3),
+            live: LiveMode::LongPoll,
+            on_assigned: None,
+            on_revoked: None,
+        }
+    }
+}
+
+/// Builder for a [`ConsumerGroup`] member.
+#[must_use = "builders do nothing unless you call .join()"]
+pub struct ConsumerGroupBuilder {
+    lease: DurableStream,
+    member: String,
+    partitions: Vec<DurableStream>,
+    session_timeout: Duration,
+    heartbeat_interval: Duration,
+    live: LiveMode,
+    on_assigned: Option<RebalanceCallback>,
+    on_revoked: Option<RebalanceCallback>,
+}
+
+impl ConsumerGroupBuilder {
+    /// Share the partitions of `stream`.
+    pub fn partitions(mut self, stream: &PartitionedStream) -> Self {
+        self.partitions = stream.partitions().to_vec();
+        self
+    }
+
+    /// Share `streams`, numbered in this order. Every member must list the
+    /// same streams in the same order.
+    pub fn streams(mut self, streams: Vec<DurableStream>) -> Self {
+        self.partitions = streams;
+        self
+    }
+
+    /// How long after its last heartbeat a member is considered gone and
+    /// its partitions are reassigned (default 10s).
+    pub fn session_timeout(mut self, timeout: Duration) -> Self {
+        self.session_timeout = timeout;
+        self
+    }
+
+    /// How often to send heartbeats (default 3s); keep it well below the
+    /// session timeout.
+    pub fn heartbeat_interval(mut self, interval: Duration) -> Self {
+        self.heartbeat_interval = interval;
+        self
+    }
+
+    /// Live mode for reading partitions (default long-poll).
+    pub fn live(mut self, mode: LiveMode) -> Self {
+        self.live = mode;
+        self
+    }
+
+    /// Called with the partition numbers this member starts reading,
+    /// before their first chunk is returned.
+    pub fn on_assigned<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&[usize]) + Send + Sync + 'static,
+    {
+        self.on_assigned = Some(Arc::new(callback));
+        self
+    }
+
+    /// Called with the partition numbers this member stops reading, before
+    /// they are handed to another member. Chunks returned but not
+    /// [`commit`](ConsumerGroup::commit)ted are read again by the new
+    /// owner.
+    pub fn on_revoked<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&[usize]) + Send + Sync + 'static,
+    {
+        self.on_revoked = Some(Arc::new(callback));
+        self
+    }
+
+    /// Create the lease stream if needed, catch up with it, and join the
+    /// group.
+    ///
+    /// Fails with [`StreamError::Fenced`] if another process joined under
+    /// the same member ID at the same moment.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no partitions were set.
+    pub async fn join(self) -> Result<ConsumerGroup, StreamError> {
+        assert!(
+            !self.partitions.is_empty(),
+            "a consumer group needs partitions to share"
+        );
+        self.lease
+            .create_with(CreateOptions::new().content_type(JSON))
+            .await?;
+
+        let mut lease = self
+            .lease
+            .read()
+            .live(LiveMode::LongPoll)
+            .typed::<LeaseRecord>()
+            .build()?;
+        let mut state = GroupState::new(self.partitions.len(), self.session_timeout);
+        while let Some(chunk) = lease.next_chunk().await? {
+            for record in &chunk.items {
+                state.apply(record);
+            }
+            if chunk.up_to_date {
+                break;
+            }
+        }
+
+        // A higher epoch than any earlier run of this member fences them
+        let epoch = state.epochs.get(&self.member).map_or(0, |epoch| epoch + 1);
+        let producer = self
+            .lease
+            .producer(self.member.clone())
+            .epoch(epoch)
+            .content_type(JSON)
+            .build();
+
+        let mut group = ConsumerGroup {
+            member: self.member,
+            epoch,
+            partitions: self.partitions,
+            lease,
+            producer,
+            state,
+            readers: BTreeMap::new(),
+            exhausted: BTreeSet::new(),
+            releasing: BTreeSet::new(),
+            next_heartbeat: rt::Instant::now() + self.heartbeat_interval,
+            next: 0,
+            heartbeat_interval: self.heartbeat_interval,
+            live: self.live,
+            on_assigned: self.on_assigned,
+            on_revoked: self.on_revoked,
+        };
+        let receipt = group.send(group.record(Kind::Heartbeat)).await?;
+        if receipt.duplicate {
+            return Err(StreamError::Fenced {
+                message: format!("member {} joined concurrently elsewhere", group.member),
+            });
+        }
+        Ok(group)
+    }
+}
+
+impl std::fmt::Debug for ConsumerGroupBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConsumerGroupBuilder")
+            .field("lease", &self.lease.url())
+            .field("member", &self.member)
+            .field("partitions", &self.partitions.len())
+            .field("session_timeout", &self.session_timeout)
+            .field("heartbeat_interval", &self.heartbeat_interval)
+            .field("live", &self.live)
+            .finish_non_exhaustive()
+    }
+}
+
+/// One record of the lease stream.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct LeaseRecord {
+    #[serde(rename = "type")]
+    kind: Kind,
+    member: String,
+    epoch: u64,
+    /// Unix milliseconds when written.
+    at: u64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    partitions: Vec<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    partition: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    offset: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Kind {
+    /// The member is alive; the first one joins it to the group.
+    Heartbeat,
+    /// The member left the group.
+    Leave,
+    /// The member stopped reading `partitions`.
+    Release,
+    /// The member processed `partition` up to `offset`.
+    Commit,
+    /// Written by a newer version; ignored.
+    #[serde(other)]
+    Unknown,
+}
+
+/// Group state as it follows from the lease stream. Every member replays
+/// the same records, so they all agree on it.
+struct GroupState {
+    session_timeout_ms: u64,
+    /// Members by ID, with the time of their last heartbeat.
+    members: BTreeMap<String, u64>,
+    /// Latest time seen in the stream.
+    clock: u64,
+    /// Member reading each partition.
+    owners: Vec<Option<String>>,
+    /// Offset each partition has been processed up to.
+    committed: Vec<Option<Offset>>,
+    /// Highest epoch seen for each member ID.
+    epochs: HashMap<String, u64>,
+}
+
+impl GroupState {
+    fn new(partitions: usize, session_timeout: Duration) -> Self {
+        Self {
+            session_timeout_ms: session_timeout.as_millis() as u64,
+            members: BTreeMap::new(),
+            clock: 0,
+            owners: vec![None; partitions],
+            committed: vec![None; partitions],
+            epochs: HashMap::new(),
+        }
+    }
+
+    fn apply(&mut self, record: &LeaseRecord) {
+        // The server fences stale epochs; this keeps replays consistent
+        // with that
+        let epoch = self.epochs.entry(record.member.clone()).or_insert(0);
+        if record.epoch < *epoch {
+            return;
+        }
+        *epoch = record.epoch;
+        self.clock = self.clock.max(record.at);
+
+        let owns = |owner: &Option<String>| owner.as_deref() == Some(record.member.as_str());
+        match record.kind {
+            Kind::Heartbeat => {
+                self.members.insert(record.member.clone(), record.at);
+            }
+            Kind::Leave => {
+                self.members.remove(&record.member);
+            }
+            Kind::Release => {
+                for &partition in &record.partitions {
+                    if let Some(owner) = self.owners.get_mut(partition).filter(|o| owns(o)) {
+                        *owner = None;
+                    }
+                }
+            }
+            Kind::Commit => {
+                if let (Some(partition), Some(offset)) = (record.partition, &record.offset) {
+                    if self.owners.get(partition).is_some_and(owns) {
+                        self.committed[partition] = Some(Offset::parse(offset));
+                    }
+                }
+            }
+            Kind::Unknown => {}
+        }
+
+        let expired_before = self.clock.saturating_sub(self.session_timeout_ms);
+        self.members.retain(|_, last| *last >= expired_before);
+
+        // Partitions of departed members are free; free partitions go to
+        // their assignee
+        let assignment = self.assignment();
+        for (owner, assignee) in self.owners.iter_mut().zip(assignment) {
+            if owner
+                .as_ref()
+                .is_some_and(|o| !self.members.contains_key(o))
+            {
+                *owner = None;
+            }
+            if owner.is_none() {
+                *owner = assignee;
+            }
+        }
+    }
+
+    /// Member each partition should go to: round-robin over the members in
+    /// ID order.
+    fn assignment(&self) -> Vec<Option<String>> {
+        let members: Vec<&String> = self.members.keys().collect();
+        (0..self.owners.len())
+            .map(|partition| {
+                (!members.is_empty()).then(|| members[partition % members.len()].clone())
+            })
+            .collect()
+    }
+}
+
+/// A member of a consumer group: processes that share the partitions of a
+/// topic, each partition read by one member at a time.
+///
+/// Members coordinate through a JSON lease stream: they append heartbeats,
+/// partition releases and committed offsets, and all replay it to agree on
+/// who reads what. Partitions are spread round-robin over the members in ID
+/// order. When a member joins, leaves or misses heartbeats for the session
+/// timeout, partitions move: the old owner stops reading and releases them
+/// (or is presumed gone), and only then does the new owner start, from the
+/// last committed offset. Delivery is at least once: chunks not committed
+/// before a partition moves are read again.
+///
+/// Each member writes with an idempotent producer whose epoch is above any
+/// earlier run of the same member ID, so a stale process that is still
+/// running is fenced: its next write fails and it gets
+/// [`StreamError::Fenced`].
+///
+/// Expiry compares heartbeat times written by different machines, so
+/// clocks should agree to well within the session timeout. The lease
+/// stream grows with every heartbeat.
+pub struct ConsumerGroup {
+    member: String,
+    epoch: u64,
+    partitions: Vec<DurableStream>,
+    lease: TypedIterator<LeaseRecord>,
+    producer: Producer,
+    state: GroupState,
+    /// Readers of the partitions this member owns.
+    readers: BTreeMap<usize, ChunkIterator>,
+    /// Owned partitions whose stream ended.
+    exhausted: BTreeSet<usize>,
+    /// Partitions released but still owned until the release is read back.
+    releasing: BTreeSet<usize>,
+    next_heartbeat: rt::Instant,
+    /// Partition to poll first.
+    next: usize,
+    heartbeat_interval: Duration,
+    live: LiveMode,
+    on_assigned: Option<RebalanceCallback>,
+    on_revoked: Option<RebalanceCallback>,
+}
+
+/// What woke up [`ConsumerGroup::next_chunk`].
+enum Step<T> {
+    Lease(Result<Option<T>, StreamError>),
+    Heartbeat,
+    Chunk(usize, Result<Option<Chunk>, StreamError>),
+}
+
+impl ConsumerGroup {
+    /// This member's ID.
+    pub fn member_id(&self) -> &str {
+        &self.member
+    }
+
+    /// Partitions this member is reading.
+    pub fn assignment(&self) -> Vec<usize> {
+        self.readers
+            .keys()
+            .chain(&self.exhausted)
+            .copied()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// Members of the group, by ID.
+    pub fn members(&self) -> Vec<String> {
+        self.state.members.keys().cloned().collect()
+    }
+
+    /// Offset `partition` has been committed up to by any member.
+    pub fn committed(&self, partition: usize) -> Option<&Offset> {
+        self.state.committed.get(partition)?.as_ref()
+    }
+
+    /// Next chunk from any partition assigned to this member, sending
+    /// heartbeats and following rebalances while waiting.
+    ///
+    /// Fails with [`StreamError::Fenced`] once a newer run of this member
+    /// has joined; the member then stops.
+    pub async fn next_chunk(&mut self) -> Result<Option<PartitionedChunk>, StreamError> {
+        loop {
+            let wait = self
+                .next_heartbeat
+                .saturating_duration_since(rt::Instant::now());
+            let step = {
+                let readers = self.readers.iter_mut().map(|(i, iter)| (*i, iter));
+                tokio::select! {
+                    biased;
+                    chunk = self.lease.next_chunk() => Step::Lease(chunk),
+                    _ = rt::sleep(wait) => Step::Heartbeat,
+                    Some((partition, chunk)) = next_of(readers, self.next) => {
+                        Step::Chunk(partition, chunk)
+                    }
+                }
+            };
+
+            match step {
+                Step::Lease(chunk) => {
+                    let Some(chunk) = chunk? else {
+                        return Err(StreamError::Fenced {
+                            message: "the lease stream was closed".to_string(),
+                        });
+                    };
+                    for record in &chunk.items {
+                        self.state.apply(record);
+                    }
+                    self.rebalance().await?;
+                }
+                Step::Heartbeat => {
+                    self.next_heartbeat = rt::Instant::now() + self.heartbeat_interval;
+                    self.send(self.record(Kind::Heartbeat)).await?;
+                }
+                Step::Chunk(partition, chunk) => {
+                    self.next = partition + 1;
+                    match chunk? {
+                        Some(chunk) => return Ok(Some(PartitionedChunk { partition, chunk })),
+                        None => {
+                            self.readers.remove(&partition);
+                            self.exhausted.insert(partition);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Record that `item`'s partition has been processed up to the end of
+    /// its chunk, so a member taking the partition over resumes after it.
+    ///
+    /// Fails with [`StreamError::Fenced`] if the partition has moved to
+    /// another member in the meantime or a newer run of this member has
+    /// joined.
+    pub async fn commit(&mut self, item: &PartitionedChunk) -> Result<(), StreamError> {
+        self.commit_offset(item.partition, &item.chunk.next_offset)
+            .await
+    }
+
+    /// Record that `partition` has been processed up to `offset`.
+    pub async fn commit_offset(
+        &mut self,
+        partition: usize,
+        offset: &Offset,
+    ) -> Result<(), StreamError> {
+        if !self.readers.contains_key(&partition) && !self.exhausted.contains(&partition) {
+            return Err(StreamError::Fenced {
+                message: format!("partition {partition} is not assigned to {}", self.member),
+            });
+        }
+        let mut record = self.record(Kind::Commit);
+        record.partition = Some(partition);
+        record.offset = Some(offset.to_string());
+        self.send(record).await?;
+        Ok(())
+    }
+
+    /// Stop reading and leave the group, so the remaining members take
+    /// over the partitions at once instead of after the session timeout.
+    pub async fn leave(mut self) -> Result<(), StreamError> {
+        let owned = self.assignment();
+        self.stop_reading(&owned).await;
+        self.send(self.record(Kind::Leave)).await?;
+        self.lease.close().await;
+        self.producer.close().await.map_err(producer_error)
+    }
+
+    /// Start and stop readers to follow the group state.
+    async fn rebalance(&mut self) -> Result<(), StreamError> {
+        let assignment = self.state.assignment();
+        let me = Some(self.member.as_str());
+        let owned = |p: usize| self.state.owners[p].as_deref() == me;
+        let assigned = |p: usize| assignment[p].as_deref() == me;
+
+        let revoked: Vec<usize> = self
+            .assignment()
+            .into_iter()
+            .filter(|&p| !owned(p) || !assigned(p))
+            .collect();
+        let release: Vec<usize> = (0..self.partitions.len())
+            .filter(|&p| owned(p) && !assigned(p) && !self.releasing.contains(&p))
+            .collect();
+        let started: Vec<usize> = (0..self.partitions.len())
+            .filter(|&p| owned(p) && assigned(p) && !self.releasing.contains(&p))
+            .filter(|p| !self.readers.contains_key(p) && !self.exhausted.contains(p))
+            .collect();
+
+        self.stop_reading(&revoked).await;
+        if !release.is_empty() {
+            self.releasing.extend(&release);
+            let mut record = self.record(Kind::Release);
+            record.partitions = release;
+            self.send(record).await?;
+        }
+        let me = Some(self.member.as_str());
+        let owners = &self.state.owners;
+        self.releasing.retain(|&p| owners[p].as_deref() == me);
+
+        for &partition in &started {
+            let offset = self.state.committed[partition]
+                .clone()
+                .unwrap_or(Offset::Beginning);
+            let reader = self.partitions[partition]
+                .read()
+                .offset(offset)
+                .live(self.live.clone())
+                .build()?;
+            self.readers.insert(partition, reader);
+        }
+        if !started.is_empty() {
+            if let Some(callback) = &self.on_assigned {
+                callback(&started);
+            }
+        }
+        Ok(())
+    }
+
+    async fn stop_reading(&mut self, partitions: &[usize]) {
+        if partitions.is_empty() {
+            return;
+        }
+        if let Some(callback) = &self.on_revoked {
+            callback(partitions);
+        }
+        for partition in partitions {
+            self.exhausted.remove(partition);
+            if let Some(mut reader) = self.readers.remove(partition) {
+                reader.close().await;
+            }
+        }
+    }
+
+    fn record(&self, kind: Kind) -> LeaseRecord {
+        LeaseRecord {
+            kind,
+            member: self.member.clone(),
+            epoch: self.epoch,
+            at: unix_now().as_millis() as u64,
+            partitions: Vec::new(),
+            partition: None,
+            offset: None,
+        }
+    }
+
+    /// Append `record` to the lease stream and wait for it to be written.
+    async fn send(&mut self, record: LeaseRecord) -> Result<AppendReceipt, StreamError> {
+        let result = append_acked(&self.producer, &record).await;
+        if let Err(ProducerError::StaleEpoch { .. }) = &result {
+            // Superseded: stop reading before reporting it
+            let owned = self.assignment();
+            self.stop_reading(&owned).await;
+        }
+        result.map_err(producer_error)
+    }
+}
+
+impl std::fmt::Debug for ConsumerGroup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConsumerGroup")
+            .field("member", &self.member)
+            .field("epoch", &self.epoch)
+            .field("partitions", &self.partitions.len())
+            .field("assignment", &self.assignment())
+            .finish_non_exhaustive()
+    }
+}
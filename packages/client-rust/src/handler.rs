@@ -0,0 +1,151 @@
+//! Typed subscription handlers with lifecycle callbacks.
+
+use crate::error::StreamError;
+use crate::iterator::{Chunk, ReadBuilder};
+use crate::retry::sleep_before_retry;
+use crate::types::Offset;
+use async_trait::async_trait;
+use std::future::Future;
+
+/// Error type returned by handler callbacks.
+pub type HandlerError = Box<dyn std::error::Error + Send + Sync>;
+
+/// What a runner should do after [`StreamHandler::on_error`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorAction {
+    /// Keep going: skip the failed item (handler errors) or back off and
+    /// reconnect from the last good offset (stream errors).
+    Continue,
+    /// Stop the subscription and return the error.
+    Stop,
+}
+
+/// Application logic for consuming a stream.
+///
+/// Runners call the methods in this order:
+///
+/// 1. [`on_item`](Self::on_item) for every chunk carrying data
+/// 2. [`on_catch_up_complete`](Self::on_catch_up_complete) once, the first
+///    time the reader reaches the tail
+/// 3. [`on_reset`](Self::on_reset) if the reader's position was invalidated
+///    and consumption restarts from an earlier offset
+/// 4. [`on_shutdown`](Self::on_shutdown) exactly once when the runner exits,
+///    whatever the reason
+///
+/// [`on_error`](Self::on_error) may be called at any point and decides
+/// whether the runner continues.
+///
+/// # Example
+///
+/// ```ignore
+/// struct Printer;
+///
+/// #[async_trait::async_trait]
+/// impl StreamHandler for Printer {
+///     async fn on_item(&mut self, chunk: &Chunk) -> Result<(), HandlerError> {
+///         println!("{} bytes", chunk.data.len());
+///         Ok(())
+///     }
+/// }
+///
+/// stream.read().live(LiveMode::Sse)
+///     .run_handler(&mut Printer, tokio::signal::ctrl_c().map(|_| ()))
+///     .await?;
+/// ```
+#[async_trait]
+pub trait StreamHandler: Send {
+    /// Process one chunk of data.
+    async fn on_item(&mut self, chunk: &Chunk) -> Result<(), HandlerError>;
+
+    /// Called once when the reader first catches up with the stream tail.
+    async fn on_catch_up_complete(&mut self, _offset: &Offset) {}
+
+    /// Called when reading or handling fails.
+    ///
+    /// Handler failures arrive as [`StreamError::Handler`]. The default stops
+    /// the subscription.
+    async fn on_error(&mut self, _err: &StreamError) -> ErrorAction {
+        ErrorAction::Stop
+    }
+
+    /// Called when consumption restarts from `offset` because the previous
+    /// position is no longer valid. Discard any state derived from earlier
+    /// items.
+    async fn on_reset(&mut self, _offset: &Offset) {}
+
+    /// Called once when the runner exits, with the last processed offset.
+    async fn on_shutdown(&mut self, _offset: &Offset) {}
+}
+
+impl ReadBuilder {
+    /// Drive `handler` until the stream ends, the handler stops, or
+    /// `shutdown` resolves.
+    ///
+    /// On 410 Gone (offset fell out of retention) the handler is reset and
+    /// reading restarts from the beginning of the stream. Returns the offset
+    /// after the last item the handler accepted; if a checkpoint store is
+    /// configured, that offset is committed before returning.
+    pub async fn run_handler<H, S>(
+        self,
+        handler: &mut H,
+        shutdown: S,
+    ) -> Result<Offset, StreamError>
+    where
+        H: StreamHandler + ?Sized,
+        S: Future<Output = ()>,
+    {
+        let mut iter = self.build()?;
+        tokio::pin!(shutdown);
+
+        let mut position = iter.offset().clone();
+        let mut caught_up = false;
+        let mut failures = 0;
+
+        let result = loop {
+            let next = tokio::select! {
+                _ = &mut shutdown => break Ok(()),
+                next = iter.next_chunk() => next,
+            };
+
+            match next {
+                Ok(Some(chunk)) => {
+                    failures = 0;
+                    if !chunk.data.is_empty() {
+                        if let Err(e) = handler.on_item(&chunk).await {
+                            let err = StreamError::Handler(e);
+                            if handler.on_error(&err).await == ErrorAction::Stop {
+                                break Err(err);
+                            }
+                        }
+                    }
+                    position = chunk.next_offset.clone();
+                    if chunk.up_to_date && !caught_up {
+                        caught_up = true;
+                        handler.on_catch_up_complete(&chunk.next_offset).await;
+                    }
+                }
+                Ok(None) => break Ok(()),
+                Err(StreamError::OffsetGone { .. }) => {
+                    iter.reset_to(Offset::Beginning);
+                    position = Offset::Beginning;
+                    caught_up = false;
+                    handler.on_reset(&position).await;
+                }
+                Err(err) => {
+                    if handler.on_error(&err).await == ErrorAction::Stop {
+                        break Err(err);
+                    }
+                    failures += 1;
+                    let stream = iter.stream();
+                    sleep_before_retry(&stream.client, &stream.url, failures, &err).await;
+                }
+            }
+        };
+
+        handler.on_shutdown(&position).await;
+        result?;
+        // Everything up to `position` was accepted, so it is safe to persist
+        iter.commit().await?;
+        Ok(position)
+    }
+}
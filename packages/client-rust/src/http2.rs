@@ -0,0 +1,29 @@
+//! HTTP/2 settings collected by `ClientBuilder`.
+
+use std::time::Duration;
+
+/// HTTP/2 connection settings for a client.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Http2Config {
+    pub(crate) prior_knowledge: bool,
+    /// Ping interval and how long to wait for the reply.
+    pub(crate) keep_alive: Option<(Duration, Duration)>,
+    pub(crate) keep_alive_while_idle: bool,
+    pub(crate) adaptive_window: bool,
+}
+
+impl Http2Config {
+    /// Apply these settings to `builder`.
+    pub(crate) fn apply(self, mut builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+        if self.prior_knowledge {
+            builder = builder.http2_prior_knowledge();
+        }
+        if let Some((interval, timeout)) = self.keep_alive {
+            builder = builder
+                .http2_keep_alive_interval(interval)
+                .http2_keep_alive_timeout(timeout)
+                .http2_keep_alive_while_idle(self.keep_alive_while_idle);
+        }
+        builder.http2_adaptive_window(self.adaptive_window)
+    }
+}
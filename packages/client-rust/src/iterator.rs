@@ -1,11 +1,31 @@
 //! Stream consumption with ChunkIterator.
 
-use crate::error::StreamError;
-use crate::stream::{DurableStream, HEADER_STREAM_CURSOR, HEADER_STREAM_OFFSET, HEADER_STREAM_UP_TO_DATE};
-use crate::types::{LiveMode, Offset};
+#[cfg(feature = "checksum")]
+use crate::checksum::{self, Digest};
+#[cfg(feature = "compression")]
+use crate::compression;
+use crate::cursor::CursorCoordinator;
+use crate::error::{ErrorDetails, StreamError};
+use crate::framing::{Framing, LineFramer};
+use crate::lag::Lag;
+use crate::middleware::Operation;
+use crate::request_id;
+use crate::sse::SseReader;
+use crate::retry::send_with_rate_limit_retry;
+use crate::stream::{
+    merge_headers, DurableStream, HEADER_ETAG, HEADER_IF_NONE_MATCH, HEADER_STREAM_CURSOR,
+    HEADER_STREAM_OFFSET, HEADER_STREAM_UP_TO_DATE,
+};
+use crate::types::{LiveMode, Offset, Restart};
+use crate::store::OffsetStore;
 use base64::Engine;
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
+#[cfg(feature = "compression")]
+use reqwest::header::{ACCEPT_ENCODING, CONTENT_ENCODING};
+use std::sync::Arc;
+use crate::rt::{self, sleep, BodyReader, Instant};
 use std::time::Duration;
+use tokio::sync::oneshot;
 
 /// A chunk of data from the stream.
 ///
@@ -15,7 +35,7 @@ use std::time::Duration;
 ///
 /// | Mode | What `data` contains |
 /// |------|---------------------|
-/// | **Catch-up** | One HTTP response body |
+/// | **Catch-up** | One HTTP response body, or a piece of one with [`ReadBuilder::incremental`] |
 /// | **Long-poll** | One HTTP response body (data that arrived during poll) |
 /// | **SSE** | One SSE data event payload |
 ///
@@ -45,6 +65,62 @@ pub struct Chunk {
     /// - `Some(304)`: Not modified
     /// - `None`: SSE connection closed, reconnect will happen on next iteration
     pub status_code: Option<u16>,
+    /// Distance from the stream tail after this chunk, on chunks where it
+    /// was measured; see [`ReadBuilder::track_lag`].
+    pub lag: Option<Lag>,
+    /// Request ID of the response this chunk came from: the server's, else
+    /// the `x-request-id` the client sent.
+    pub request_id: Option<String>,
+    /// SSE last event ID when this chunk was read: the most recent `id:`
+    /// field the server sent. `None` outside SSE or when the server sends
+    /// no IDs.
+    pub event_id: Option<String>,
+    /// Whether this is the first chunk after the reader restarted, because
+    /// the stream was reset (see [`ReadBuilder::on_stream_reset`]) or its
+    /// offset was gone (see [`ReadBuilder::on_offset_gone`]). State built
+    /// from earlier chunks may no longer match the stream.
+    pub reset: bool,
+}
+
+impl Chunk {
+    /// This chunk's metadata, without the data.
+    pub fn meta(&self) -> ChunkMeta {
+        ChunkMeta {
+            next_offset: self.next_offset.clone(),
+            up_to_date: self.up_to_date,
+            cursor: self.cursor.clone(),
+            status_code: self.status_code,
+            lag: self.lag.clone(),
+            request_id: self.request_id.clone(),
+            event_id: self.event_id.clone(),
+            reset: self.reset,
+        }
+    }
+}
+
+/// The fields of a [`Chunk`] other than its data, e.g. to persist how far
+/// a read got. Serializable with the `json` feature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ChunkMeta {
+    /// See [`Chunk::next_offset`].
+    pub next_offset: Offset,
+    /// See [`Chunk::up_to_date`].
+    pub up_to_date: bool,
+    /// See [`Chunk::cursor`].
+    pub cursor: Option<String>,
+    /// See [`Chunk::status_code`].
+    pub status_code: Option<u16>,
+    /// See [`Chunk::lag`].
+    pub lag: Option<Lag>,
+    /// See [`Chunk::request_id`].
+    pub request_id: Option<String>,
+    /// See [`Chunk::event_id`].
+    pub event_id: Option<String>,
+    /// See [`Chunk::reset`].
+    #[cfg_attr(feature = "json", serde(default))]
+    pub reset: bool,
 }
 
 /// Builder for configuring stream reads.
@@ -56,18 +132,82 @@ pub struct ReadBuilder {
     live: LiveMode,
     timeout: Duration,
     headers: Vec<(String, String)>,
+    query: Vec<(String, String)>,
     cursor: Option<String>,
+    cursors: Option<CursorCoordinator>,
+    checkpoint: Option<CheckpointConfig>,
+    if_none_match: Option<String>,
+    framing: Framing,
+    until: Option<Offset>,
+    max_bytes: Option<usize>,
+    lag_interval: Option<Duration>,
+    stall_timeout: Option<Duration>,
+    request_timeout: Option<Duration>,
+    incremental: Option<usize>,
+    restarts: RestartPolicy,
+    #[cfg(feature = "compression")]
+    accept_compressed: bool,
+}
+
+/// Called with the offset a reader left before it restarts.
+type RestartCallback = Arc<dyn Fn(&Offset) + Send + Sync>;
+
+/// What a reader does on 410 Gone and on a stream reset.
+#[derive(Clone, Default)]
+struct RestartPolicy {
+    offset_gone: Restart,
+    stream_reset: Restart,
+    on_restart: Option<RestartCallback>,
+}
+
+impl std::fmt::Debug for RestartPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RestartPolicy")
+            .field("offset_gone", &self.offset_gone)
+            .field("stream_reset", &self.stream_reset)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Automatic checkpointing configuration.
+#[derive(Clone)]
+struct CheckpointConfig {
+    store: Arc<dyn OffsetStore>,
+    interval: Duration,
+}
+
+impl std::fmt::Debug for CheckpointConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CheckpointConfig")
+            .field("interval", &self.interval)
+            .finish_non_exhaustive()
+    }
 }
 
 impl ReadBuilder {
     pub(crate) fn new(stream: DurableStream) -> Self {
+        let defaults = &stream.client.defaults.read;
         Self {
+            live: defaults.live.clone().unwrap_or(LiveMode::Off),
+            timeout: defaults.timeout.unwrap_or(Duration::from_secs(30)),
+            request_timeout: defaults.request_timeout,
             stream,
             offset: Offset::Beginning,
-            live: LiveMode::Off,
-            timeout: Duration::from_secs(30),
             headers: Vec::new(),
+            query: Vec::new(),
             cursor: None,
+            cursors: None,
+            checkpoint: None,
+            if_none_match: None,
+            framing: Framing::None,
+            until: None,
+            max_bytes: None,
+            lag_interval: None,
+            stall_timeout: None,
+            incremental: None,
+            restarts: RestartPolicy::default(),
+            #[cfg(feature = "compression")]
+            accept_compressed: false,
         }
     }
 
@@ -103,84 +243,910 @@ impl ReadBuilder {
         self
     }
 
+    /// Add a query parameter to every read request, e.g. for a server's
+    /// protocol extension. Keys and values are percent-encoded.
+    ///
+    /// The protocol's own `offset`, `live` and `cursor` parameters are
+    /// managed by the reader; parameters with those names are ignored.
+    pub fn query_param(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        let key = key.into();
+        if !matches!(key.as_str(), "offset" | "live" | "cursor") {
+            self.query.push((key, value.into()));
+        }
+        self
+    }
+
     /// Set initial cursor for CDN collapsing.
     pub fn cursor(mut self, cursor: impl Into<String>) -> Self {
         self.cursor = Some(cursor.into());
         self
     }
 
+    /// Share CDN cursors with other readers given the same coordinator, so
+    /// their live reads of this stream can be collapsed into cache hits.
+    pub fn cursor_coordinator(mut self, cursors: &CursorCoordinator) -> Self {
+        self.cursors = Some(cursors.clone());
+        self
+    }
+
+    /// Send `If-None-Match` with the first request, so re-reading from the
+    /// same offset gets a cheap `304 Not Modified` if nothing changed.
+    ///
+    /// Pair it with the offset the ETag was issued for, taken from
+    /// [`ChunkIterator::etag`] on an earlier read. A 304 chunk has empty
+    /// `data`; the caller already holds that response.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let mut reader = stream.read().offset(start.clone()).build()?;
+    /// let chunk = reader.next_chunk().await?;
+    /// let etag = reader.etag().map(str::to_string);
+    ///
+    /// // Later: re-poll the same range
+    /// let mut reader = stream.read().offset(start).if_none_match(etag.unwrap()).build()?;
+    /// ```
+    pub fn if_none_match(mut self, etag: impl Into<String>) -> Self {
+        self.if_none_match = Some(etag.into());
+        self
+    }
+
+    /// Resume from and automatically commit progress to an offset store.
+    ///
+    /// On the first `next_chunk()` call the iterator loads the stored offset
+    /// for this stream (keyed by URL); if one exists it replaces the offset
+    /// set via [`offset`](Self::offset), which then only applies on first run.
+    ///
+    /// Progress is committed at most once per `interval`, when `next_chunk()`
+    /// is called again - i.e. after the caller has finished with the previous
+    /// chunk - giving at-least-once processing. A zero interval commits on
+    /// every call. Use [`ChunkIterator::commit`] to force a commit, e.g. on
+    /// shutdown.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let store = Arc::new(FileOffsetStore::new("offsets.json"));
+    /// let mut reader = stream.read()
+    ///     .checkpoint(store, Duration::from_secs(5))
+    ///     .live(LiveMode::LongPoll)
+    ///     .build()?;
+    /// ```
+    pub fn checkpoint(mut self, store: Arc<dyn OffsetStore>, interval: Duration) -> Self {
+        self.checkpoint = Some(CheckpointConfig { store, interval });
+        self
+    }
+
+    /// Choose what happens when the read offset has fallen out of the
+    /// stream's retention (410 Gone), e.g. because a long-lived consumer's
+    /// checkpoint is older than the data the server keeps.
+    ///
+    /// With [`Restart::FromEarliest`] or [`Restart::FromNow`] the reader
+    /// moves there and carries on; the data in between is lost to it, so
+    /// state built from earlier chunks may need rebuilding (see
+    /// [`on_restart`](Self::on_restart)). Defaults to [`Restart::Fail`],
+    /// which returns [`StreamError::OffsetGone`].
+    ///
+    /// # Example
+    /// ```ignore
+    /// let mut reader = stream.read()
+    ///     .checkpoint(store, Duration::from_secs(5))
+    ///     .on_offset_gone(Restart::FromEarliest)
+    ///     .on_restart(|gone| tracing::warn!(%gone, "checkpoint expired, replaying"))
+    ///     .live(LiveMode::LongPoll)
+    ///     .build()?;
+    /// ```
+    pub fn on_offset_gone(mut self, restart: Restart) -> Self {
+        self.restarts.offset_gone = restart;
+        self
+    }
+
+    /// Choose what happens when the stream is reset while being read: it
+    /// was deleted and recreated, e.g. by a schema migration, so offsets
+    /// from before no longer refer to the same data.
+    ///
+    /// A reset is detected from what the protocol defines: the server
+    /// reports a tail behind the offset being read, or a read gets 404 Not
+    /// Found and a HEAD then finds the stream again. Defaults to
+    /// [`Restart::Fail`], which returns [`StreamError::StreamReset`]; with
+    /// [`Restart::FromEarliest`] the reader replays the new stream from its
+    /// start. Either way the first chunk after a restart has
+    /// [`Chunk::reset`] set.
+    ///
+    /// Detection is best effort: the protocol gives a recreated stream no
+    /// identity of its own. One recreated between two requests and already
+    /// written past the reader's offset looks like a continuation, and its
+    /// data is read from that offset.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let mut reader = stream.read()
+    ///     .live(LiveMode::LongPoll)
+    ///     .on_stream_reset(Restart::FromEarliest)
+    ///     .build()?;
+    /// while let Some(chunk) = reader.next_chunk().await? {
+    ///     if chunk.reset {
+    ///         view.clear();
+    ///     }
+    ///     view.apply(&chunk.data);
+    /// }
+    /// ```
+    pub fn on_stream_reset(mut self, restart: Restart) -> Self {
+        self.restarts.stream_reset = restart;
+        self
+    }
+
+    /// Called with the offset the reader left each time it restarts under
+    /// [`on_offset_gone`](Self::on_offset_gone) or
+    /// [`on_stream_reset`](Self::on_stream_reset), before the first chunk
+    /// from the new position is fetched.
+    pub fn on_restart<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&Offset) + Send + Sync + 'static,
+    {
+        self.restarts.on_restart = Some(Arc::new(callback));
+        self
+    }
+
+    /// Stop once the read position reaches `offset`.
+    ///
+    /// Together with [`offset`](Self::offset) this replays a historical
+    /// range, e.g. between two checkpoints, and works in any live mode.
+    /// Responses are not split, so the last chunk may extend past `offset`
+    /// if the server returned more in one response; compare offsets when an
+    /// exact cut matters.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let mut reader = stream.read()
+    ///     .offset(checkpoint_a)
+    ///     .until(checkpoint_b)
+    ///     .build()?;
+    /// ```
+    pub fn until(mut self, offset: impl Into<Offset>) -> Self {
+        self.until = Some(offset.into());
+        self
+    }
+
+    /// Stop after `bytes` of chunk data have been returned.
+    ///
+    /// The iterator ends after the chunk that reaches the limit, so the
+    /// total can exceed it by at most one response; resume from
+    /// [`ChunkIterator::offset`] to continue.
+    pub fn max_bytes(mut self, bytes: usize) -> Self {
+        self.max_bytes = Some(bytes);
+        self
+    }
+
+    /// Attach a [`Lag`] measurement to chunks, at most once per `interval`.
+    ///
+    /// Up-to-date chunks always report zero lag without a request; while
+    /// behind, the iterator issues a HEAD for the tail when `interval` has
+    /// elapsed and leaves [`Chunk::lag`] as `None` in between. Use
+    /// [`ChunkIterator::lag`] to measure on demand instead.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let mut reader = stream.read().track_lag(Duration::from_secs(10)).build()?;
+    /// while let Some(chunk) = reader.next_chunk().await? {
+    ///     if let Some(bytes) = chunk.lag.and_then(|lag| lag.bytes) {
+    ///         gauge.set(bytes);
+    ///     }
+    /// }
+    /// ```
+    pub fn track_lag(mut self, interval: Duration) -> Self {
+        self.lag_interval = Some(interval);
+        self
+    }
+
+    /// Reconnect an SSE connection that receives nothing for `timeout`.
+    ///
+    /// Any bytes count as activity, including heartbeat comments, so
+    /// `timeout` should exceed the server's heartbeat interval. A stalled
+    /// connection, such as a half-open TCP connection, is dropped and the
+    /// iterator returns a chunk with `status_code: None`, the same as when
+    /// the server closes the connection; the next call reconnects from the
+    /// current offset. Long-poll requests are bounded by
+    /// [`timeout`](Self::timeout) instead.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let mut reader = stream.read()
+    ///     .live(LiveMode::Sse)
+    ///     .stall_timeout(Duration::from_secs(45))
+    ///     .build()?;
+    /// ```
+    pub fn stall_timeout(mut self, timeout: Duration) -> Self {
+        self.stall_timeout = Some(timeout);
+        self
+    }
+
+    /// Time limit for each catch-up request, overriding the client's
+    /// [`request_timeout`](crate::ClientBuilder::request_timeout).
+    ///
+    /// Raise it for large historical reads without raising it for every
+    /// other request. Long-poll requests use [`timeout`](Self::timeout),
+    /// and SSE connections are not bounded (see
+    /// [`stall_timeout`](Self::stall_timeout)).
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Return catch-up and long-poll responses in pieces of at most
+    /// `max_buffered` bytes as they arrive, instead of reading each whole
+    /// body into memory first.
+    ///
+    /// Use this for reads whose responses can run to hundreds of
+    /// megabytes. Only the last piece of a response moves
+    /// [`Chunk::next_offset`] past it; earlier pieces carry the offset the
+    /// response started at, with `up_to_date` false, so resuming from any
+    /// piece re-reads the rest of its response. If the connection fails
+    /// mid-response, the next call re-requests it from the start, repeating
+    /// the pieces already returned. Compressed responses (see
+    /// `accept_compressed`) are still read whole.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_buffered` is zero.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let mut reader = stream.read()
+    ///     .offset(Offset::Beginning)
+    ///     .incremental(1024 * 1024)
+    ///     .build()?;
+    /// while let Some(piece) = reader.next_chunk().await? {
+    ///     file.write_all(&piece.data).await?;
+    /// }
+    /// ```
+    pub fn incremental(mut self, max_buffered: usize) -> Self {
+        assert!(max_buffered > 0, "max_buffered must be non-zero");
+        self.incremental = Some(max_buffered);
+        self
+    }
+
+    /// Split response bodies into records.
+    ///
+    /// With [`Framing::NdJson`], each [`Chunk`] holds one line (without its
+    /// newline), and lines split across responses are reassembled. A
+    /// record's `next_offset` moves past it only if it ends a response;
+    /// otherwise it is the offset of the response it started in, so
+    /// resuming from any record's offset never skips data but may repeat
+    /// records from that response. Chunks without data (long-poll timeouts,
+    /// up-to-date signals) are passed through.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let mut reader = stream.read().framing(Framing::NdJson).build()?;
+    /// while let Some(record) = reader.next_chunk().await? {
+    ///     if !record.data.is_empty() {
+    ///         let event: Event = serde_json::from_slice(&record.data)?;
+    ///     }
+    /// }
+    /// ```
+    pub fn framing(mut self, framing: Framing) -> Self {
+        self.framing = framing;
+        self
+    }
+
+    /// Ask the server to compress catch-up and long-poll responses.
+    ///
+    /// Sends `Accept-Encoding: gzip, zstd` and decompresses bodies before
+    /// they are returned as [`Chunk::data`]. SSE connections are never
+    /// compressed, since buffering in the encoder would delay events.
+    #[cfg(feature = "compression")]
+    pub fn accept_compressed(mut self) -> Self {
+        self.accept_compressed = true;
+        self
+    }
+
     /// Build the ChunkIterator.
     ///
     /// No network request is made until `next_chunk()` is called.
     pub fn build(self) -> Result<ChunkIterator, StreamError> {
-        Ok(ChunkIterator {
+        let checkpoint = self.checkpoint.map(|config| Checkpointer {
+            key: self.stream.url.clone(),
+            config,
+            restored: false,
+            committed: None,
+            last_commit: Instant::now(),
+        });
+        let validator = self.if_none_match.map(|etag| (self.offset.clone(), etag));
+        let framer = match self.framing {
+            Framing::None => None,
+            Framing::NdJson => Some(LineFramer::new(self.offset.clone())),
+        };
+
+        let headers = merge_headers(&self.stream.client.defaults.read.headers, self.headers);
+        Ok(ChunkIterator::new(Reader {
             stream: self.stream,
             offset: self.offset,
             live: self.live,
             timeout: self.timeout,
-            headers: self.headers,
+            headers,
+            query: self.query,
             cursor: self.cursor,
+            cursors: self.cursors,
             encoding: None,
             up_to_date: false,
+            lagging: false,
             closed: false,
             done: false,
             sse_state: None,
-        })
+            last_event_id: None,
+            sse_retry: None,
+            sse_reconnect: false,
+            checkpoint,
+            validator,
+            framer,
+            until: self.until,
+            max_bytes: self.max_bytes,
+            bytes_read: 0,
+            lag_interval: self.lag_interval,
+            last_lag_check: None,
+            stall_timeout: self.stall_timeout,
+            request_timeout: self.request_timeout,
+            incremental: self.incremental,
+            body: None,
+            restarts: self.restarts,
+            restarted: false,
+            request_id: None,
+            #[cfg(feature = "compression")]
+            accept_compressed: self.accept_compressed,
+        }))
     }
 }
 
 /// Iterator for reading chunks from a stream.
+///
+/// Each fetch runs in a background task that owns the read state, so
+/// [`next_chunk`](Self::next_chunk) is cancellation safe: if its future is
+/// dropped, e.g. by losing a `tokio::select!`, the fetch keeps its place and
+/// the next call returns its result. No chunk is lost or read twice.
+///
+/// # Example
+///
+/// ```ignore
+/// loop {
+///     tokio::select! {
+///         chunk = reader.next_chunk() => match chunk? {
+///             Some(chunk) => process(&chunk.data),
+///             None => break,
+///         },
+///         _ = shutdown.recv() => {
+///             reader.close().await;
+///             break;
+///         }
+///     }
+/// }
+/// ```
 pub struct ChunkIterator {
+    /// Read state, handed to the background task while a fetch runs.
+    reader: Option<Box<Reader>>,
+    fetch: Option<Fetch>,
+    /// Outcome of a finished fetch not yet returned to the caller.
+    ready: Option<Result<Option<Chunk>, StreamError>>,
+    /// Position after the last chunk returned.
+    view: View,
+    /// Offset to move to once the running fetch has stopped.
+    reset: Option<Offset>,
+    stream: DurableStream,
+    closed: bool,
+}
+
+/// A fetch running in the background.
+struct Fetch {
+    /// Dropped to stop the fetch early.
+    stop: Option<oneshot::Sender<()>>,
+    /// The read state, and the fetch's outcome unless it was stopped.
+    done: oneshot::Receiver<FetchOutcome>,
+}
+
+type FetchOutcome = (Box<Reader>, Option<Result<Option<Chunk>, StreamError>>);
+
+/// What the accessors report: the read state as of the last chunk
+/// returned, which a running fetch may already have moved past.
+struct View {
+    offset: Offset,
+    up_to_date: bool,
+    cursor: Option<String>,
+    etag: Option<String>,
+}
+
+impl View {
+    fn of(reader: &Reader) -> Self {
+        Self {
+            offset: reader.offset().clone(),
+            up_to_date: reader.up_to_date,
+            cursor: reader.cursor.clone(),
+            etag: reader.etag().map(str::to_string),
+        }
+    }
+}
+
+/// Read state and the requests that move it forward.
+struct Reader {
     stream: DurableStream,
     offset: Offset,
     live: LiveMode,
     timeout: Duration,
     headers: Vec<(String, String)>,
+    /// Extra query parameters for read requests.
+    query: Vec<(String, String)>,
     cursor: Option<String>,
+    /// Cursors shared with other readers, for CDN collapsing.
+    cursors: Option<CursorCoordinator>,
     encoding: Option<String>,
     up_to_date: bool,
+    lagging: bool,
     closed: bool,
     done: bool,
-    sse_state: Option<SseState>,
+    sse_state: Option<SseReader>,
+    /// Last event ID of the previous SSE connection, sent as
+    /// `Last-Event-ID` when reconnecting.
+    last_event_id: Option<String>,
+    /// Reconnection delay from the server's last `retry:` field.
+    sse_retry: Option<Duration>,
+    /// Whether an SSE connection dropped, so the next one waits `sse_retry`.
+    sse_reconnect: bool,
+    checkpoint: Option<Checkpointer>,
+    /// ETag of the last full response and the offset it was requested at.
+    validator: Option<(Offset, String)>,
+    /// Record splitter, when framing is enabled.
+    framer: Option<LineFramer>,
+    /// Offset at which the read ends.
+    until: Option<Offset>,
+    /// Chunk bytes after which the read ends.
+    max_bytes: Option<usize>,
+    bytes_read: usize,
+    /// Minimum time between lag measurements attached to chunks.
+    lag_interval: Option<Duration>,
+    last_lag_check: Option<Instant>,
+    /// Longest an SSE connection may go without sending anything.
+    stall_timeout: Option<Duration>,
+    /// Time limit for catch-up requests.
+    request_timeout: Option<Duration>,
+    /// Largest piece of a response body to return, when reading
+    /// incrementally.
+    incremental: Option<usize>,
+    /// Response body being returned in pieces.
+    body: Option<PartialBody>,
+    restarts: RestartPolicy,
+    /// Whether the next chunk is the first since a restart.
+    restarted: bool,
+    /// Request ID of the latest response.
+    request_id: Option<String>,
+    #[cfg(feature = "compression")]
+    accept_compressed: bool,
+}
+
+impl Drop for Reader {
+    fn drop(&mut self) {
+        self.set_lagging(false);
+    }
 }
 
-struct SseState {
-    response: reqwest::Response,
-    buffer: String,           // Accumulated bytes from network
-    pending_data: Vec<String>, // Accumulated data lines for current event
-    current_event_type: Option<String>,
+/// A response body read incrementally, and where its response ends.
+struct PartialBody {
+    reader: BodyReader,
+    /// Bytes read but not yet returned.
+    buffer: BytesMut,
+    /// Whether the whole body has been read.
+    ended: bool,
+    next_offset: Offset,
+    cursor: Option<String>,
+    up_to_date: bool,
+    /// CRC32C of the bytes read so far, when the response carried one.
+    #[cfg(feature = "checksum")]
+    digest: Option<Digest>,
+}
+
+/// Tracks checkpoint progress for an iterator.
+struct Checkpointer {
+    config: CheckpointConfig,
+    key: String,
+    restored: bool,
+    committed: Option<Offset>,
+    last_commit: Instant,
+}
+
+impl Checkpointer {
+    /// Replace `offset` with the stored checkpoint, if any.
+    async fn restore(&mut self, offset: &mut Offset) -> Result<(), StreamError> {
+        if let Some(saved) = self.config.store.load(&self.key).await? {
+            *offset = saved.clone();
+            self.committed = Some(saved);
+        }
+        self.restored = true;
+        Ok(())
+    }
+
+    /// Commit `offset` if it changed and the interval elapsed (or `force`).
+    async fn commit(&mut self, offset: &Offset, force: bool) -> Result<(), StreamError> {
+        // "now" is a sentinel, not a position - never persist it
+        if offset.is_now() || self.committed.as_ref() == Some(offset) {
+            return Ok(());
+        }
+        if !force && self.last_commit.elapsed() < self.config.interval {
+            return Ok(());
+        }
+        self.config.store.commit(&self.key, offset).await?;
+        self.committed = Some(offset.clone());
+        self.last_commit = Instant::now();
+        Ok(())
+    }
 }
 
 impl ChunkIterator {
+    fn new(reader: Reader) -> Self {
+        Self {
+            view: View::of(&reader),
+            stream: reader.stream.clone(),
+            reader: Some(Box::new(reader)),
+            fetch: None,
+            ready: None,
+            reset: None,
+            closed: false,
+        }
+    }
+
     /// Get the current offset.
+    ///
+    /// With framing enabled, this is the resumption offset of the last
+    /// record returned.
     pub fn offset(&self) -> &Offset {
-        &self.offset
+        &self.view.offset
     }
 
     /// Check if we've caught up to the stream tail.
     pub fn is_up_to_date(&self) -> bool {
-        self.up_to_date
+        self.view.up_to_date
     }
 
     /// Get the current cursor.
     pub fn cursor(&self) -> Option<&str> {
-        self.cursor.as_deref()
+        self.view.cursor.as_deref()
+    }
+
+    /// ETag of the last catch-up or long-poll response with data.
+    ///
+    /// It validates a repeat read from the offset that response was
+    /// requested at; see [`ReadBuilder::if_none_match`].
+    pub fn etag(&self) -> Option<&str> {
+        self.view.etag.as_deref()
+    }
+
+    /// Commit the current offset to the checkpoint store immediately.
+    ///
+    /// No-op if no store was configured via [`ReadBuilder::checkpoint`].
+    /// Waits for a fetch left running by a cancelled
+    /// [`next_chunk`](Self::next_chunk), keeping its result for the next
+    /// call.
+    pub async fn commit(&mut self) -> Result<(), StreamError> {
+        self.settle().await;
+        match &mut self.reader {
+            Some(reader) => reader.commit(&self.view.offset).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Move the read position to `offset`, dropping any open connection and
+    /// cursor. A running fetch is stopped and its result discarded.
+    pub(crate) fn reset_to(&mut self, offset: Offset) {
+        self.ready = None;
+        self.view = View {
+            offset: offset.clone(),
+            up_to_date: false,
+            cursor: None,
+            etag: self.view.etag.take(),
+        };
+        match (&mut self.reader, &mut self.fetch) {
+            (Some(reader), _) => reader.reset_to(offset),
+            (None, Some(fetch)) => {
+                fetch.stop = None;
+                self.reset = Some(offset);
+            }
+            (None, None) => {}
+        }
+    }
+
+    /// Get the stream being read.
+    pub(crate) fn stream(&self) -> &DurableStream {
+        &self.stream
     }
 
     /// Close the iterator and release resources.
-    pub fn close(&mut self) {
+    ///
+    /// A request in flight is aborted; this returns once it has been
+    /// dropped.
+    pub async fn close(&mut self) {
         self.closed = true;
-        self.sse_state = None;
+        self.ready = None;
+        if let Some(fetch) = &mut self.fetch {
+            fetch.stop = None;
+        }
+        self.settle().await;
+        if let Some(reader) = &mut self.reader {
+            reader.close();
+        }
     }
 
     /// Fetch the next chunk.
+    ///
+    /// Cancellation safe: a fetch interrupted by dropping the returned
+    /// future continues in the background, and the next call returns its
+    /// result.
     pub async fn next_chunk(&mut self) -> Result<Option<Chunk>, StreamError> {
         if self.closed {
             return Err(StreamError::IteratorClosed);
         }
+        if self.ready.is_none() {
+            if self.fetch.is_none() {
+                self.start_fetch();
+            }
+            self.settle().await;
+        }
+        let result = self.ready.take().unwrap_or(Err(StreamError::IteratorClosed));
+        if let Some(reader) = &self.reader {
+            self.view = View::of(reader);
+        }
+        result
+    }
 
-        if self.done {
-            return Ok(None);
+    /// Measure how far this reader is behind the stream tail.
+    ///
+    /// Issues a HEAD request for the current tail and compares it with
+    /// [`offset`](Self::offset).
+    pub async fn lag(&self) -> Result<Lag, StreamError> {
+        let head = self.stream.head().await?;
+        Ok(Lag::between(self.offset().clone(), head.next_offset))
+    }
+
+    /// Hand the read state to a background task fetching the next chunk.
+    fn start_fetch(&mut self) {
+        let Some(mut reader) = self.reader.take() else {
+            return;
+        };
+        let (stop_tx, mut stop) = oneshot::channel::<()>();
+        let (done_tx, done) = oneshot::channel();
+        rt::spawn(async move {
+            let result = tokio::select! {
+                result = reader.next_chunk() => Some(result),
+                _ = &mut stop => None,
+            };
+            let _ = done_tx.send((reader, result));
+        });
+        self.fetch = Some(Fetch {
+            stop: Some(stop_tx),
+            done,
+        });
+    }
+
+    /// Wait for the running fetch, if any, to hand back the read state.
+    ///
+    /// Cancellation safe: the fetch stays in place until it finishes.
+    async fn settle(&mut self) {
+        let Some(fetch) = &mut self.fetch else {
+            return;
+        };
+        let outcome = (&mut fetch.done).await;
+        self.fetch = None;
+        // An error means the task was dropped with its runtime, taking the
+        // read state with it; the iterator then reports itself closed
+        if let Ok((mut reader, result)) = outcome {
+            match self.reset.take() {
+                Some(offset) => reader.reset_to(offset),
+                None => self.ready = result,
+            }
+            self.reader = Some(reader);
+        }
+    }
+}
+
+impl Reader {
+    /// Resumption offset of the last chunk or record read.
+    fn offset(&self) -> &Offset {
+        match &self.framer {
+            Some(framer) => framer.position(),
+            None => &self.offset,
+        }
+    }
+
+    fn etag(&self) -> Option<&str> {
+        self.validator.as_ref().map(|(_, etag)| etag.as_str())
+    }
+
+    /// Commit `offset` to the checkpoint store immediately.
+    async fn commit(&mut self, offset: &Offset) -> Result<(), StreamError> {
+        match &mut self.checkpoint {
+            Some(cp) => cp.commit(offset, true).await,
+            None => Ok(()),
+        }
+    }
+
+    fn reset_to(&mut self, offset: Offset) {
+        self.offset = offset;
+        self.cursor = None;
+        self.up_to_date = false;
+        self.done = false;
+        self.sse_state = None;
+        self.last_event_id = None;
+        self.sse_reconnect = false;
+        self.body = None;
+        if let Some(framer) = &mut self.framer {
+            framer.reset(self.offset.clone());
+        }
+    }
+
+    fn close(&mut self) {
+        self.closed = true;
+        self.sse_state = None;
+        self.body = None;
+        self.set_lagging(false);
+    }
+
+    async fn next_chunk(&mut self) -> Result<Option<Chunk>, StreamError> {
+        if self.closed {
+            return Err(StreamError::IteratorClosed);
+        }
+
+        // Restore from or commit to the checkpoint store. Reaching this point
+        // means the caller is done with the previously returned chunk.
+        if let Some(cp) = &mut self.checkpoint {
+            if !cp.restored {
+                cp.restore(&mut self.offset).await?;
+                if let Some(framer) = &mut self.framer {
+                    framer.reset(self.offset.clone());
+                }
+            } else {
+                let offset = match &self.framer {
+                    Some(framer) => framer.position(),
+                    None => &self.offset,
+                };
+                let done = self.done && self.framer.as_ref().is_none_or(|f| f.is_empty());
+                cp.commit(offset, done).await?;
+            }
+        }
+
+        let mut chunk = self.read_next().await?;
+        // Offer other readers the cursor we just got
+        self.sync_cursor();
+        if let Some(chunk) = &mut chunk {
+            chunk.reset = std::mem::take(&mut self.restarted);
+        }
+        Ok(chunk)
+    }
+
+    /// Next chunk or record, restarting as configured on 410 Gone or a
+    /// stream reset.
+    async fn read_next(&mut self) -> Result<Option<Chunk>, StreamError> {
+        loop {
+            if let Some(record) = self.framer.as_mut().and_then(LineFramer::pop) {
+                return Ok(Some(record));
+            }
+            if !self.done && self.bound_reached() {
+                self.done = true;
+                self.sse_state = None;
+                self.body = None;
+                self.set_lagging(false);
+            }
+            if self.done {
+                return Ok(self.framer.as_mut().and_then(LineFramer::finish));
+            }
+
+            let start = self.offset.clone();
+            let mut result = self.fetch_next().await;
+            if matches!(result, Err(StreamError::NotFound { .. })) {
+                result = self.check_recreated(result).await;
+            }
+            let mut chunk = match result {
+                Err(StreamError::OffsetGone { .. })
+                    if self.restart(self.restarts.offset_gone, &start) =>
+                {
+                    continue
+                }
+                Err(StreamError::StreamReset)
+                    if self.restart(self.restarts.stream_reset, &start) =>
+                {
+                    continue
+                }
+                chunk => chunk?,
+            };
+            if let Some(chunk) = &mut chunk {
+                self.stream.client.metrics.bytes_received(chunk.data.len());
+                self.set_lagging(!chunk.up_to_date);
+                self.bytes_read += chunk.data.len();
+                chunk.lag = self.sample_lag(chunk.up_to_date).await;
+            }
+            match (&mut self.framer, chunk) {
+                (Some(framer), Some(chunk)) => framer.push(chunk, start),
+                (Some(framer), None) => return Ok(framer.finish()),
+                (None, chunk) => return Ok(chunk),
+            }
+        }
+    }
+
+    /// Move to the restart position for `restart`, leaving `from`.
+    ///
+    /// Returns false under [`Restart::Fail`], or if the reader was already
+    /// at the restart position, so the error is returned instead of
+    /// looping.
+    fn restart(&mut self, restart: Restart, from: &Offset) -> bool {
+        let Some(offset) = restart.offset() else {
+            return false;
+        };
+        if *from == offset {
+            return false;
+        }
+        if let Some(callback) = &self.restarts.on_restart {
+            callback(from);
+        }
+        self.reset_to(offset);
+        self.restarted = true;
+        true
+    }
+
+    /// Fail with [`StreamError::StreamReset`] if the response shows the
+    /// stream was reset: its tail `next_offset` is behind the offset
+    /// requested.
+    fn check_reset(&self, next_offset: Option<&Offset>) -> Result<(), StreamError> {
+        let behind = matches!(self.offset, Offset::At(_))
+            && next_offset.is_some_and(|next| *next < self.offset);
+        if behind {
+            return Err(StreamError::StreamReset);
+        }
+        Ok(())
+    }
+
+    /// Turn a 404 Not Found into [`StreamError::StreamReset`] if the stream
+    /// exists again, i.e. it was deleted and recreated while being read.
+    ///
+    /// Only checked once the reader has a position in the stream; a reader
+    /// of a stream that never existed gets the 404.
+    async fn check_recreated(
+        &mut self,
+        result: Result<Option<Chunk>, StreamError>,
+    ) -> Result<Option<Chunk>, StreamError> {
+        if !matches!(self.offset, Offset::At(_)) || self.stream.head().await.is_err() {
+            return result;
+        }
+        Err(StreamError::StreamReset)
+    }
+
+    /// Lag to attach to a freshly fetched chunk, if tracking is due.
+    ///
+    /// Up-to-date chunks need no request. A failed HEAD only leaves the
+    /// chunk without a measurement; it never fails the read.
+    async fn sample_lag(&mut self, up_to_date: bool) -> Option<Lag> {
+        let interval = self.lag_interval?;
+        if up_to_date {
+            self.last_lag_check = Some(Instant::now());
+            return Some(Lag::caught_up(self.offset.clone()));
+        }
+        if self
+            .last_lag_check
+            .is_some_and(|checked| checked.elapsed() < interval)
+        {
+            return None;
+        }
+        self.last_lag_check = Some(Instant::now());
+        let head = self.stream.head().await.ok()?;
+        Some(Lag::between(self.offset.clone(), head.next_offset))
+    }
+
+    /// Whether the `until` offset or `max_bytes` budget has been reached.
+    fn bound_reached(&self) -> bool {
+        let past_until = self
+            .until
+            .as_ref()
+            .is_some_and(|until| self.offset.partial_cmp(until).is_some_and(|o| o.is_ge()));
+        let over_budget = self.max_bytes.is_some_and(|max| self.bytes_read >= max);
+        past_until || over_budget
+    }
+
+    async fn fetch_next(&mut self) -> Result<Option<Chunk>, StreamError> {
+        // Finish a response being read in pieces
+        if self.body.is_some() {
+            return self.next_body_piece().await;
         }
 
         // If we have an active SSE connection, use it
@@ -196,46 +1162,90 @@ impl ChunkIterator {
         }
     }
 
+    /// Swap in the newest cursor from the shared coordinator, if any,
+    /// after offering it ours.
+    fn sync_cursor(&mut self) {
+        if let Some(cursors) = &self.cursors {
+            self.cursor = cursors.sync(&self.stream.url, self.cursor.as_deref());
+        }
+    }
+
+    /// Keep the client's lagging-readers gauge in sync with this iterator.
+    fn set_lagging(&mut self, lagging: bool) {
+        if self.lagging != lagging {
+            self.lagging = lagging;
+            self.stream.client.metrics.reader_lagging(lagging);
+        }
+    }
+
     async fn next_http(&mut self, live_param: Option<&str>) -> Result<Option<Chunk>, StreamError> {
+        self.sync_cursor();
         let url = self
             .stream
-            .build_read_url(&self.offset, live_param, self.cursor.as_deref());
+            .build_read_url(&self.offset, live_param, self.cursor.as_deref(), &self.query);
 
-        let mut req = self.stream.client.inner.get(&url);
+        let build = || {
+            let mut req = self.stream.client.inner.get(&url);
 
-        // Add headers
-        let client_headers = self.stream.client.get_headers();
-        for (key, value) in client_headers.iter() {
-            req = req.header(key.clone(), value.clone());
-        }
-        for (key, value) in &self.headers {
-            req = req.header(key.as_str(), value.as_str());
-        }
+            // Add headers
+            let client_headers = self.stream.client.get_headers();
+            for (key, value) in client_headers.iter() {
+                req = req.header(key.clone(), value.clone());
+            }
+            for (key, value) in &self.headers {
+                req = req.header(key.as_str(), value.as_str());
+            }
 
-        // Set timeout for long-poll
-        if live_param == Some("long-poll") {
-            req = req.timeout(self.timeout);
-        }
+            // Set timeout for long-poll
+            if live_param == Some("long-poll") {
+                req = req.timeout(self.timeout);
+            } else if let Some(timeout) = self.request_timeout {
+                req = req.timeout(timeout);
+            }
+
+            #[cfg(feature = "compression")]
+            if self.accept_compressed {
+                req = req.header(ACCEPT_ENCODING, compression::ACCEPT_ENCODING);
+            }
 
-        let resp = match req.send().await {
+            // Revalidate when re-requesting the offset we hold an ETag for
+            if let Some((offset, etag)) = &self.validator {
+                if *offset == self.offset {
+                    req = req.header(HEADER_IF_NONE_MATCH, etag.as_str());
+                }
+            }
+
+            req
+        };
+
+        let client = &self.stream.client;
+        // Wait for a slot on the host; held until the response is handled
+        let _slot = match (&client.long_polls, live_param) {
+            (Some(scheduler), Some("long-poll")) => Some(scheduler.acquire(&url).await),
+            _ => None,
+        };
+        let sent = send_with_rate_limit_retry(client, Operation::Read, &self.stream.url, build).await;
+        let resp = match sent {
             Ok(r) => r,
-            Err(e) if e.is_timeout() => {
-                // Timeout in long-poll means up-to-date
+            Err(e) if e.is_timeout() && live_param == Some("long-poll") => {
+                // Timeout in long-poll means up-to-date; a catch-up timeout
+                // is an error, since data may remain
                 self.up_to_date = true;
-                if self.live == LiveMode::Off {
-                    self.done = true;
-                    return Ok(None);
-                }
                 return Ok(Some(Chunk {
                     data: Bytes::new(),
                     next_offset: self.offset.clone(),
                     up_to_date: true,
                     cursor: self.cursor.clone(),
                     status_code: Some(204),
+                    lag: None,
+                    request_id: None,
+                    event_id: None,
+                    reset: false,
                 }));
             }
             Err(e) => return Err(e.into()),
         };
+        self.request_id = request_id::from_headers(resp.headers());
 
         let status = resp.status().as_u16();
 
@@ -261,9 +1271,73 @@ impl ChunkIterator {
                     .and_then(|v| v.to_str().ok())
                     == Some("true");
 
+                let etag = resp
+                    .headers()
+                    .get(HEADER_ETAG)
+                    .and_then(|v| v.to_str().ok())
+                    .map(|s| s.to_string());
+
+                self.check_reset(Some(&next_offset))?;
+
+                #[cfg(feature = "compression")]
+                let content_encoding = resp
+                    .headers()
+                    .get(CONTENT_ENCODING)
+                    .and_then(|v| v.to_str().ok())
+                    .map(|s| s.to_string());
+
+                // Covers the body as sent, before any decompression
+                #[cfg(feature = "checksum")]
+                let digest = if self.stream.client.checksums {
+                    let header = resp.headers().get(checksum::HEADER_CONTENT_DIGEST);
+                    checksum::expected(header.and_then(|v| v.to_str().ok()))?.map(Digest::new)
+                } else {
+                    None
+                };
+
+                if let Some(max) = self.incremental {
+                    #[cfg(feature = "compression")]
+                    let encoded = self.accept_compressed
+                        && content_encoding.as_deref().map(str::trim).is_some_and(|e| {
+                            !e.is_empty() && !e.eq_ignore_ascii_case("identity")
+                        });
+                    #[cfg(not(feature = "compression"))]
+                    let encoded = false;
+                    if !encoded {
+                        if let Some(etag) = etag {
+                            self.validator = Some((self.offset.clone(), etag));
+                        }
+                        self.body = Some(PartialBody {
+                            reader: BodyReader::new(resp),
+                            buffer: BytesMut::with_capacity(max),
+                            ended: false,
+                            next_offset,
+                            cursor,
+                            up_to_date,
+                            #[cfg(feature = "checksum")]
+                            digest,
+                        });
+                        return self.next_body_piece().await;
+                    }
+                }
+
                 let data = resp.bytes().await?;
+                #[cfg(feature = "checksum")]
+                if let Some(mut digest) = digest {
+                    digest.update(&data);
+                    digest.finish()?;
+                }
+                #[cfg(feature = "compression")]
+                let data = if self.accept_compressed {
+                    compression::decompress(content_encoding.as_deref(), data)?
+                } else {
+                    data
+                };
 
                 // Update state
+                if let Some(etag) = etag {
+                    self.validator = Some((self.offset.clone(), etag));
+                }
                 self.offset = next_offset.clone();
                 self.cursor = cursor.clone();
                 self.up_to_date = up_to_date;
@@ -278,6 +1352,10 @@ impl ChunkIterator {
                     up_to_date,
                     cursor,
                     status_code: Some(200),
+                    lag: None,
+                    request_id: self.request_id.clone(),
+                    event_id: None,
+                    reset: false,
                 }))
             }
             204 => {
@@ -294,6 +1372,7 @@ impl ChunkIterator {
                     .and_then(|v| v.to_str().ok())
                     .map(|s| s.to_string());
 
+                self.check_reset(next_offset.as_ref())?;
                 if let Some(offset) = next_offset {
                     self.offset = offset;
                 }
@@ -313,10 +1392,15 @@ impl ChunkIterator {
                     up_to_date: true,
                     cursor: self.cursor.clone(),
                     status_code: Some(204),
+                    lag: None,
+                    request_id: self.request_id.clone(),
+                    event_id: None,
+                    reset: false,
                 }))
             }
             304 => {
-                // Not modified - just advance cursor
+                // Not modified - the caller already holds this response, so
+                // skip past it if the server says where it ends
                 if let Some(cursor) = resp
                     .headers()
                     .get(HEADER_STREAM_CURSOR)
@@ -324,6 +1408,18 @@ impl ChunkIterator {
                 {
                     self.cursor = Some(cursor.to_string());
                 }
+                if let Some(offset) = resp
+                    .headers()
+                    .get(HEADER_STREAM_OFFSET)
+                    .and_then(|v| v.to_str().ok())
+                {
+                    self.offset = Offset::parse(offset);
+                }
+
+                // Nothing new to catch up on; re-polling would only repeat the 304
+                if self.live == LiveMode::Off {
+                    self.done = true;
+                }
 
                 Ok(Some(Chunk {
                     data: Bytes::new(),
@@ -331,41 +1427,134 @@ impl ChunkIterator {
                     up_to_date: self.up_to_date,
                     cursor: self.cursor.clone(),
                     status_code: Some(304),
+                    lag: None,
+                    request_id: self.request_id.clone(),
+                    event_id: None,
+                    reset: false,
                 }))
             }
             404 => Err(StreamError::NotFound {
                 url: self.stream.url.clone(),
+                details: ErrorDetails::read(resp).await,
             }),
             410 => Err(StreamError::OffsetGone {
                 offset: self.offset.to_string(),
+                details: ErrorDetails::read(resp).await,
             }),
-            _ => Err(StreamError::from_status(status, &self.stream.url)),
+            _ => Err(StreamError::from_error_response(resp, &self.stream.url).await),
+        }
+    }
+
+    /// Return the next piece of an incrementally read response, moving
+    /// the offset past the response with its last piece.
+    async fn next_body_piece(&mut self) -> Result<Option<Chunk>, StreamError> {
+        let max = self.incremental.unwrap_or(usize::MAX);
+        let Some(body) = &mut self.body else {
+            return Ok(None);
+        };
+        while !body.ended && body.buffer.len() < max {
+            match body.reader.chunk().await {
+                Ok(Some(bytes)) => {
+                    #[cfg(feature = "checksum")]
+                    if let Some(digest) = &mut body.digest {
+                        digest.update(&bytes);
+                    }
+                    body.buffer.extend_from_slice(&bytes);
+                }
+                Ok(None) => {
+                    body.ended = true;
+                    // The last piece is held back until the whole body checks out
+                    #[cfg(feature = "checksum")]
+                    if let Some(Err(e)) = body.digest.as_ref().map(Digest::finish) {
+                        self.body = None;
+                        return Err(e);
+                    }
+                }
+                Err(e) => {
+                    // Re-request the response from its start on the next call
+                    self.body = None;
+                    return Err(e.into());
+                }
+            }
+        }
+
+        if !body.ended || body.buffer.len() > max {
+            let data = body.buffer.split_to(max.min(body.buffer.len())).freeze();
+            return Ok(Some(Chunk {
+                data,
+                next_offset: self.offset.clone(),
+                up_to_date: false,
+                cursor: self.cursor.clone(),
+                status_code: Some(200),
+                lag: None,
+                request_id: self.request_id.clone(),
+                event_id: None,
+                reset: false,
+            }));
+        }
+
+        let Some(body) = self.body.take() else {
+            return Ok(None);
+        };
+        self.offset = body.next_offset.clone();
+        self.cursor = body.cursor.clone();
+        self.up_to_date = body.up_to_date;
+        if body.up_to_date && self.live == LiveMode::Off {
+            self.done = true;
         }
+        Ok(Some(Chunk {
+            data: body.buffer.freeze(),
+            next_offset: body.next_offset,
+            up_to_date: body.up_to_date,
+            cursor: body.cursor,
+            status_code: Some(200),
+            lag: None,
+            request_id: self.request_id.clone(),
+            event_id: None,
+            reset: false,
+        }))
     }
 
     async fn establish_sse_and_read(&mut self) -> Result<Option<Chunk>, StreamError> {
+        // Wait as long as the server asked before reconnecting
+        if std::mem::take(&mut self.sse_reconnect) {
+            if let Some(delay) = self.sse_retry {
+                sleep(delay).await;
+            }
+        }
+
         // Establish SSE connection
+        self.sync_cursor();
         let url = self
             .stream
-            .build_read_url(&self.offset, Some("sse"), self.cursor.as_deref());
-
-        let mut req = self
-            .stream
-            .client
-            .inner
-            .get(&url)
-            .header("Accept", "text/event-stream");
+            .build_read_url(&self.offset, Some("sse"), self.cursor.as_deref(), &self.query);
+
+        let build = || {
+            let mut req = self
+                .stream
+                .client
+                .inner
+                .get(&url)
+                .header("Accept", "text/event-stream");
+
+            // Add headers
+            let client_headers = self.stream.client.get_headers();
+            for (key, value) in client_headers.iter() {
+                req = req.header(key.clone(), value.clone());
+            }
+            for (key, value) in &self.headers {
+                req = req.header(key.as_str(), value.as_str());
+            }
+            if let Some(id) = &self.last_event_id {
+                req = req.header("Last-Event-ID", id.as_str());
+            }
 
-        // Add headers
-        let client_headers = self.stream.client.get_headers();
-        for (key, value) in client_headers.iter() {
-            req = req.header(key.clone(), value.clone());
-        }
-        for (key, value) in &self.headers {
-            req = req.header(key.as_str(), value.as_str());
-        }
+            req
+        };
 
-        let resp = req.send().await?;
+        let client = &self.stream.client;
+        let resp = send_with_rate_limit_retry(client, Operation::Read, &self.stream.url, build).await?;
+        self.request_id = request_id::from_headers(resp.headers());
         let status = resp.status().as_u16();
 
         match status {
@@ -390,11 +1579,10 @@ impl ChunkIterator {
                     .and_then(|v| v.to_str().ok())
                     .map(|s| s.to_string());
 
-                self.sse_state = Some(SseState {
-                    response: resp,
-                    buffer: String::new(),
-                    pending_data: Vec::new(),
-                    current_event_type: None,
+                let reader = SseReader::new(resp).last_event_id_from(self.last_event_id.clone());
+                self.sse_state = Some(match self.stall_timeout {
+                    Some(timeout) => reader.stall_timeout(timeout),
+                    None => reader,
                 });
 
                 self.next_sse_chunk().await
@@ -406,186 +1594,193 @@ impl ChunkIterator {
             }
             404 => Err(StreamError::NotFound {
                 url: self.stream.url.clone(),
+                details: ErrorDetails::read(resp).await,
             }),
-            _ => Err(StreamError::from_status(status, &self.stream.url)),
+            _ => Err(StreamError::from_error_response(resp, &self.stream.url).await),
         }
     }
 
     async fn next_sse_chunk(&mut self) -> Result<Option<Chunk>, StreamError> {
-        // Get SSE state, or establish connection if needed
-        let state = match &mut self.sse_state {
-            Some(s) => s,
-            None => {
-                // Connection was closed, need to re-establish
-                // But to avoid recursion, just fall back to HTTP for this call
-                return self.next_http(Some("long-poll")).await;
-            }
-        };
-
-        // Read from SSE stream
         loop {
-            // First, try to process any complete lines in the buffer
-            while let Some(newline_pos) = state.buffer.find('\n') {
-                let line = state.buffer[..newline_pos].trim_end_matches('\r').to_string();
-                state.buffer = state.buffer[newline_pos + 1..].to_string();
-
-                // Empty line = event dispatch
-                if line.is_empty() {
-                    if !state.pending_data.is_empty() {
-                        let data = state.pending_data.join("\n");
-                        let event_type = state.current_event_type.take();
-                        state.pending_data.clear();
-
-                        match event_type.as_deref() {
-                            Some("control") => {
-                                // Validate control event data
-                                if data.trim().is_empty() {
-                                    return Err(StreamError::ParseError(
-                                        "Empty control event data".to_string(),
-                                    ));
-                                }
-
-                                // Parse control event JSON
-                                match serde_json::from_str::<serde_json::Value>(&data) {
-                                    Ok(json) => {
-                                        // Must be a JSON object
-                                        if !json.is_object() {
-                                            return Err(StreamError::ParseError(
-                                                "Control event data is not a JSON object".to_string(),
-                                            ));
-                                        }
-
-                                        let stream_next_offset = json
-                                            .get("streamNextOffset")
-                                            .and_then(|v| v.as_str())
-                                            .unwrap_or("")
-                                            .to_string();
-
-                                        let stream_cursor = json
-                                            .get("streamCursor")
-                                            .and_then(|v| v.as_str())
-                                            .map(|s| s.to_string());
-
-                                        let up_to_date = json
-                                            .get("upToDate")
-                                            .and_then(|v| v.as_bool())
-                                            .unwrap_or(false);
-
-                                        // Update state
-                                        self.offset = Offset::parse(&stream_next_offset);
-                                        if let Some(cursor) = stream_cursor {
-                                            self.cursor = Some(cursor);
-                                        }
-                                        self.up_to_date = up_to_date;
-
-                                        // Control-only event (no data)
-                                        if up_to_date {
-                                            return Ok(Some(Chunk {
-                                                data: Bytes::new(),
-                                                next_offset: self.offset.clone(),
-                                                up_to_date: true,
-                                                cursor: self.cursor.clone(),
-                                                status_code: Some(200),
-                                            }));
-                                        }
-                                    }
-                                    Err(e) => {
-                                        return Err(StreamError::ParseError(format!(
-                                            "Malformed control event JSON: {}",
-                                            e
-                                        )));
-                                    }
-                                }
-                            }
-                            Some("data") | Some("message") | None => {
-                                // Data event - decode base64 if encoding is set
-                                let chunk_data = if self.encoding.as_deref() == Some("base64") {
-                                    // Per protocol: remove \n and \r before decoding
-                                    let cleaned: String = data.chars()
-                                        .filter(|c| *c != '\n' && *c != '\r')
-                                        .collect();
-
-                                    // Empty string is valid
-                                    if cleaned.is_empty() {
-                                        Bytes::new()
-                                    } else {
-                                        // Validate length is multiple of 4
-                                        if cleaned.len() % 4 != 0 {
-                                            return Err(StreamError::ParseError(format!(
-                                                "Invalid base64 data: length {} is not a multiple of 4",
-                                                cleaned.len()
-                                            )));
-                                        }
-
-                                        match base64::engine::general_purpose::STANDARD.decode(&cleaned) {
-                                            Ok(decoded) => Bytes::from(decoded),
-                                            Err(e) => {
-                                                return Err(StreamError::ParseError(format!(
-                                                    "Failed to decode base64 data: {}",
-                                                    e
-                                                )));
-                                            }
-                                        }
-                                    }
-                                } else {
-                                    Bytes::from(data)
-                                };
-
-                                return Ok(Some(Chunk {
-                                    data: chunk_data,
-                                    next_offset: self.offset.clone(),
-                                    up_to_date: self.up_to_date,
-                                    cursor: self.cursor.clone(),
-                                    status_code: Some(200),
-                                }));
-                            }
-                            Some(_) => {
-                                // Unknown event type - ignore per SSE spec (forward compatibility)
-                            }
-                        }
-                    }
-                    continue;
-                }
-
-                // Parse SSE field
-                if let Some(rest) = line.strip_prefix("event:") {
-                    state.current_event_type = Some(rest.trim_start().to_string());
-                } else if let Some(rest) = line.strip_prefix("data:") {
-                    // Per SSE spec, strip exactly ONE leading space if present
-                    let value = rest.strip_prefix(' ').unwrap_or(rest);
-                    state.pending_data.push(value.to_string());
+            let read = match &mut self.sse_state {
+                Some(reader) => reader.next_event().await,
+                None => {
+                    // Connection was closed, need to re-establish
+                    // But to avoid recursion, just fall back to HTTP for this call
+                    return self.next_http(Some("long-poll")).await;
                 }
-                // Ignore other fields (id:, retry:, comments starting with :)
-            }
-
-            // Need more data from network
-            let chunk = match state.response.chunk().await {
-                Ok(Some(c)) => c,
+            };
+            let event = match read {
+                Ok(Some(event)) => event,
                 Ok(None) => {
                     // Connection closed
-                    self.sse_state = None;
+                    self.drop_sse();
                     if self.live.is_live() {
                         // Return with indication to reconnect on next call
+                        return Ok(Some(self.sse_closed_chunk()));
+                    }
+                    self.done = true;
+                    return Ok(None);
+                }
+                Err(StreamError::Timeout) => {
+                    // Stalled: drop the connection and reconnect on the next call
+                    self.drop_sse();
+                    return Ok(Some(self.sse_closed_chunk()));
+                }
+                Err(e) => {
+                    // Drop the broken connection so the next call reconnects
+                    self.drop_sse();
+                    return Err(e);
+                }
+            };
+
+            match event.event.as_deref() {
+                Some("control") => {
+                    // Validate control event data
+                    if event.data.iter().all(u8::is_ascii_whitespace) {
+                        return Err(StreamError::ParseError(
+                            "Empty control event data".to_string(),
+                        ));
+                    }
+
+                    // Parse control event JSON
+                    let json = match serde_json::from_slice::<serde_json::Value>(&event.data) {
+                        Ok(json) => json,
+                        Err(e) => {
+                            return Err(StreamError::ParseError(format!(
+                                "Malformed control event JSON: {}",
+                                e
+                            )));
+                        }
+                    };
+
+                    // Must be a JSON object
+                    if !json.is_object() {
+                        return Err(StreamError::ParseError(
+                            "Control event data is not a JSON object".to_string(),
+                        ));
+                    }
+
+                    let stream_next_offset = json
+                        .get("streamNextOffset")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("");
+
+                    let stream_cursor = json
+                        .get("streamCursor")
+                        .and_then(|v| v.as_str())
+                        .map(|s| s.to_string());
+
+                    let up_to_date = json
+                        .get("upToDate")
+                        .and_then(|v| v.as_bool())
+                        .unwrap_or(false);
+
+                    // Update state
+                    self.offset = Offset::parse(stream_next_offset);
+                    if let Some(cursor) = stream_cursor {
+                        self.cursor = Some(cursor);
+                    }
+                    self.up_to_date = up_to_date;
+
+                    // Control-only event (no data)
+                    if up_to_date {
                         return Ok(Some(Chunk {
                             data: Bytes::new(),
                             next_offset: self.offset.clone(),
-                            up_to_date: self.up_to_date,
+                            up_to_date: true,
                             cursor: self.cursor.clone(),
-                            status_code: None, // SSE closed, reconnect on next iteration
+                            status_code: Some(200),
+                            lag: None,
+                            request_id: self.request_id.clone(),
+                            event_id: event.id,
+                            reset: false,
                         }));
                     }
-                    self.done = true;
-                    return Ok(None);
                 }
-                Err(e) => return Err(e.into()),
-            };
+                Some("data") | Some("message") | None => {
+                    // Data event - decode base64 if encoding is set
+                    let chunk_data = if self.encoding.as_deref() == Some("base64") {
+                        decode_base64_data(&event.data)?
+                    } else {
+                        event.data
+                    };
+
+                    return Ok(Some(Chunk {
+                        data: chunk_data,
+                        next_offset: self.offset.clone(),
+                        up_to_date: self.up_to_date,
+                        cursor: self.cursor.clone(),
+                        status_code: Some(200),
+                        lag: None,
+                        request_id: self.request_id.clone(),
+                        event_id: event.id,
+                        reset: false,
+                    }));
+                }
+                Some(_) => {
+                    // Unknown event type - ignore per SSE spec (forward compatibility)
+                }
+            }
+        }
+    }
+
+    /// Chunk reporting a dropped SSE connection; the next call reconnects.
+    fn sse_closed_chunk(&self) -> Chunk {
+        Chunk {
+            data: Bytes::new(),
+            next_offset: self.offset.clone(),
+            up_to_date: self.up_to_date,
+            cursor: self.cursor.clone(),
+            status_code: None, // SSE closed, reconnect on next iteration
+            lag: None,
+            request_id: self.request_id.clone(),
+            event_id: self.last_event_id.clone(),
+            reset: false,
+        }
+    }
 
-            // Append to buffer
-            let text = String::from_utf8_lossy(&chunk);
-            state.buffer.push_str(&text);
+    /// Drop the SSE connection, keeping its last event ID and retry delay
+    /// for the next one.
+    fn drop_sse(&mut self) {
+        if let Some(reader) = self.sse_state.take() {
+            self.last_event_id = reader.last_event_id().map(str::to_string);
+            self.sse_retry = reader.retry().or(self.sse_retry);
+            self.sse_reconnect = true;
         }
     }
 }
 
+/// Decode the payload of a base64-encoded SSE data event.
+fn decode_base64_data(data: &[u8]) -> Result<Bytes, StreamError> {
+    // Per protocol: remove \n and \r before decoding
+    let cleaned: Vec<u8> = data
+        .iter()
+        .copied()
+        .filter(|b| *b != b'\n' && *b != b'\r')
+        .collect();
+
+    // Empty string is valid
+    if cleaned.is_empty() {
+        return Ok(Bytes::new());
+    }
+
+    // Validate length is multiple of 4
+    if !cleaned.len().is_multiple_of(4) {
+        return Err(StreamError::ParseError(format!(
+            "Invalid base64 data: length {} is not a multiple of 4",
+            cleaned.len()
+        )));
+    }
+
+    match base64::engine::general_purpose::STANDARD.decode(&cleaned) {
+        Ok(decoded) => Ok(Bytes::from(decoded)),
+        Err(e) => Err(StreamError::ParseError(format!(
+            "Failed to decode base64 data: {}",
+            e
+        ))),
+    }
+}
+
 // Note: We don't implement futures::Stream here because the async recursion
 // makes it complex. Users should use next_chunk() directly in a loop.
@@ -0,0 +1,61 @@
+//! How far a reader is behind the stream tail.
+
+use crate::types::Offset;
+
+/// A reader's distance from the stream tail, as of one measurement.
+///
+/// Offsets are opaque, so the byte count is only an estimate: it is
+/// derived from offsets of the form `<seq>_<byte position>` (the
+/// reference server's format) and is `None` for any other format, unless
+/// the reader is caught up.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct Lag {
+    /// The reader's position.
+    pub offset: Offset,
+    /// The stream tail when measured.
+    pub head: Offset,
+    /// Estimated bytes between `offset` and `head`.
+    pub bytes: Option<u64>,
+    /// Whether the reader had reached `head`.
+    pub caught_up: bool,
+}
+
+impl Lag {
+    /// Measure the distance from `offset` to `head`.
+    pub(crate) fn between(offset: Offset, head: Offset) -> Self {
+        let (bytes, caught_up) = match (byte_position(&offset), byte_position(&head)) {
+            (Some(from), Some(to)) => (Some(to.saturating_sub(from)), from >= to),
+            _ => {
+                let caught_up = offset.partial_cmp(&head).is_some_and(|o| o.is_ge());
+                (caught_up.then_some(0), caught_up)
+            }
+        };
+        Self {
+            offset,
+            head,
+            bytes,
+            caught_up,
+        }
+    }
+
+    /// A reader that has reached the tail.
+    pub(crate) fn caught_up(offset: Offset) -> Self {
+        Self {
+            head: offset.clone(),
+            offset,
+            bytes: Some(0),
+            caught_up: true,
+        }
+    }
+}
+
+/// Byte position encoded in an offset's trailing numeric field.
+fn byte_position(offset: &Offset) -> Option<u64> {
+    match offset {
+        Offset::Beginning => Some(0),
+        Offset::Now => None,
+        Offset::At(s) => s.rsplit_once('_')?.1.parse().ok(),
+    }
+}
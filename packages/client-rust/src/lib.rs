@@ -17,7 +17,7 @@
 //!     stream.create().await?;
 //!
 //!     // Append data
-//!     stream.append(b"hello world").await?;
+//!     stream.append("hello world").await?;
 //!
 //!     // Read data
 //!     let mut reader = stream.read().offset(Offset::Beginning).build()?;
@@ -29,19 +29,162 @@
 //! }
 //! ```
 
+#[cfg(feature = "json")]
+mod aggregate;
+mod auth;
+mod broadcast;
+#[cfg(feature = "checksum")]
+mod checksum;
 mod client;
+mod cursor;
+#[cfg(any(feature = "msgpack", feature = "cbor"))]
+mod codec;
+#[cfg(feature = "compression")]
+mod compression;
+#[cfg(feature = "json")]
+mod dead_letter;
+#[cfg(feature = "json")]
+mod document;
+#[cfg(feature = "encryption")]
+mod encryption;
 mod error;
+mod expiry;
+#[cfg(all(feature = "json", not(target_arch = "wasm32")))]
+mod export;
+mod failover;
+mod framing;
+#[cfg(feature = "json")]
+mod group;
+mod handler;
+#[cfg(all(feature = "http2", not(target_arch = "wasm32")))]
+mod http2;
 mod iterator;
+mod lag;
+#[cfg(feature = "json")]
+mod lease;
+mod long_poll;
+#[cfg(feature = "json")]
+mod materializer;
+mod metrics;
+mod middleware;
+#[cfg(not(target_arch = "wasm32"))]
+mod net;
+mod ops;
+mod partitioned;
 mod producer;
+mod rate_limit;
+#[cfg(feature = "json")]
+mod record;
+mod redact;
+mod redirect;
+mod replicator;
+mod request_id;
+#[cfg(all(feature = "recipes", not(target_arch = "wasm32")))]
+pub mod recipes;
+mod retry;
+mod rt;
+mod schema;
+#[cfg(all(feature = "tower", not(target_arch = "wasm32")))]
+mod service;
+#[cfg(feature = "signing")]
+mod signing;
+mod sink;
+#[cfg(feature = "json")]
+mod snapshot;
+mod spawned;
+mod sse;
+mod store;
 mod stream;
+mod subscription;
+#[cfg(all(feature = "testing", not(target_arch = "wasm32")))]
+pub mod testing;
+#[cfg(all(any(feature = "rustls", feature = "native-tls"), not(target_arch = "wasm32")))]
+mod tls;
+mod transport;
+#[cfg(feature = "json")]
+mod typed;
+#[cfg(feature = "json")]
+mod typed_stream;
 mod types;
+#[cfg(all(feature = "web", not(target_arch = "wasm32")))]
+mod web;
+mod writer;
 
+#[cfg(feature = "json")]
+pub use aggregate::{Aggregate, Repository, Versioned};
+pub use auth::AuthProvider;
+#[cfg(feature = "auth-oauth2")]
+pub use auth::OAuth2Provider;
+pub use broadcast::ChunkBroadcast;
 pub use client::{Client, ClientBuilder};
-pub use error::{InvalidHeaderError, ProducerError, StreamError};
-pub use iterator::{Chunk, ChunkIterator, ReadBuilder};
-pub use producer::{Producer, ProducerBuilder};
-pub use stream::{AppendOptions, AppendResponse, CloseOptions, CloseResponse, CreateOptions, DurableStream, HeadResponse};
-pub use types::{LiveMode, Offset};
+pub use cursor::CursorCoordinator;
+#[cfg(feature = "cbor")]
+pub use codec::CONTENT_TYPE_CBOR;
+#[cfg(feature = "msgpack")]
+pub use codec::CONTENT_TYPE_MSGPACK;
+#[cfg(feature = "json")]
+pub use dead_letter::DeadLetter;
+#[cfg(feature = "json")]
+pub use document::{DocumentFollower, PatchFormat, PatchReducer};
+#[cfg(feature = "encryption")]
+pub use encryption::{DataKey, EncryptedIterator, EncryptedProducer, EncryptedReadBuilder, EncryptedStream, Encryptor, Envelope, EnvelopeKeys, KeyProvider, KeyWrapper, StaticKeys};
+pub use error::{BatchFailure, ErrorDetails, EncryptionError, InvalidHeaderError, LimitKind, PatchError, ProducerConfigError, ProducerError, SignatureError, StoreError, StreamError, ValidationError};
+#[cfg(all(feature = "json", not(target_arch = "wasm32")))]
+pub use export::ExportSummary;
+pub use framing::Framing;
+#[cfg(feature = "json")]
+pub use group::{ConsumerGroup, ConsumerGroupBuilder};
+pub use handler::{ErrorAction, HandlerError, StreamHandler};
+pub use iterator::{Chunk, ChunkIterator, ChunkMeta, ReadBuilder};
+pub use lag::Lag;
+#[cfg(feature = "json")]
+pub use lease::Lease;
+#[cfg(feature = "json")]
+pub use materializer::{Materialized, Materializer, Reducer};
+pub use metrics::{HistogramSnapshot, Metrics, MetricsSnapshot, OperationStats};
+pub use middleware::{Middleware, Operation, RequestParts};
+#[cfg(not(target_arch = "wasm32"))]
+pub use net::IpPreference;
+pub use ops::{ChunkSource, RecordSink, StreamOps};
+pub use partitioned::{HashPartitioner, PartitionedChunk, PartitionedProducer, PartitionedReadBuilder, PartitionedReader, PartitionedStream, Partitioner};
+pub use producer::{AppendReceipt, CloseReport, Producer, ProducerBuilder, ProducerStats, RecordDraft, Txn};
+#[cfg(feature = "json")]
+pub use record::Record;
+pub use redirect::{RedirectPolicy, StripAuth};
+pub use replicator::Replicator;
+pub use retry::{JitterMode, RetryCause, RetryConfig, RetryEvent};
+pub use schema::SchemaValidator;
+#[cfg(all(feature = "tower", not(target_arch = "wasm32")))]
+pub use service::StreamRequest;
+#[cfg(feature = "signing")]
+pub use signing::{SignedIterator, SignedProducer, SignedReadBuilder, SignedRecord, SignedStream, Signer, Verifier, HEADER_STREAM_SIGNATURE};
+pub use sink::{Sink, SinkBatch, SinkRunner};
+#[cfg(feature = "json")]
+pub use snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
+#[cfg(not(target_arch = "wasm32"))]
+pub use sink::{FileSink, SegmentEntry};
+#[cfg(all(feature = "sink-postgres", not(target_arch = "wasm32")))]
+pub use sink::{PgSink, PgSinkMode};
+#[cfg(all(feature = "sink-s3", not(target_arch = "wasm32")))]
+pub use sink::{ArchivedObject, S3ArchiveReader, S3Bucket, S3Sink};
+#[cfg(all(feature = "sink-webhook", not(target_arch = "wasm32")))]
+pub use sink::WebhookPusher;
+pub use spawned::ReaderHandle;
+pub use sse::{SseEvent, SseParser, SseReader};
+#[cfg(not(target_arch = "wasm32"))]
+pub use store::FileOffsetStore;
+pub use store::{InMemoryOffsetStore, OffsetStore, ProducerCheckpoint, ProducerStateStore};
+#[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+pub use store::{SqliteStateStore, SqliteStateStoreBuilder};
+pub use stream::{AppendOptions, AppendResponse, CloseOptions, CloseResponse, CreateOptions, DurableStream, HeadOptions, HeadResponse, ReadOptions, StreamBuilder, StreamMetadata};
+pub use subscription::{Subscription, SubscriptionManager, SubscriptionManagerBuilder};
+#[cfg(feature = "json")]
+pub use typed::{DecodeErrorPolicy, DecodeLimits, TypedChunk, TypedIterator, TypedReadBuilder};
+#[cfg(feature = "json")]
+pub use typed_stream::{TypedProducer, TypedStream};
+pub use transport::HttpTransport;
+pub use types::{LiveMode, Offset, Restart};
+pub use writer::StreamWriter;
 
 /// Prelude module for convenient imports.
 ///
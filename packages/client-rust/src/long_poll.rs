@@ -0,0 +1,64 @@
+//! Sharing a host's connections between long-polling readers.
+
+use crate::rt;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Caps concurrent long-polls per host for all clones of a client.
+///
+/// Readers over the cap wait in arrival order, so none is starved while
+/// others re-poll. Each poll also starts after a random delay of up to
+/// `stagger`, so readers woken by the same append do not all reconnect at
+/// once.
+#[derive(Debug)]
+pub(crate) struct LongPollScheduler {
+    per_host: usize,
+    stagger: Duration,
+    hosts: Mutex<HashMap<String, Arc<Semaphore>>>,
+}
+
+impl LongPollScheduler {
+    pub(crate) fn new(per_host: Option<usize>, stagger: Duration) -> Self {
+        Self {
+            per_host: per_host.unwrap_or(Semaphore::MAX_PERMITS).max(1),
+            stagger,
+            hosts: Mutex::default(),
+        }
+    }
+
+    /// Wait for a slot to long-poll `url`'s host. The slot is given back
+    /// when the permit is dropped.
+    pub(crate) async fn acquire(&self, url: &str) -> OwnedSemaphorePermit {
+        let semaphore = self
+            .hosts
+            .lock()
+            .entry(host_key(url))
+            .or_insert_with(|| Arc::new(Semaphore::new(self.per_host)))
+            .clone();
+        let permit = semaphore
+            .acquire_owned()
+            .await
+            .expect("long-poll semaphores are never closed");
+        if !self.stagger.is_zero() {
+            rt::sleep(self.stagger.mul_f64(fastrand::f64())).await;
+        }
+        permit
+    }
+}
+
+/// `host:port` of `url`, which is what connections are pooled by.
+fn host_key(url: &str) -> String {
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|url| {
+            Some(format!(
+                "{}:{}",
+                url.host_str()?,
+                url.port_or_known_default()?
+            ))
+        })
+        .unwrap_or_default()
+}
@@ -0,0 +1,271 @@
+//! Fold a stream into in-memory state.
+
+use crate::error::StreamError;
+use crate::handler::{ErrorAction, HandlerError, StreamHandler};
+use crate::iterator::Chunk;
+use crate::snapshot::Snapshot;
+use crate::stream::DurableStream;
+use crate::typed::{decode_json, DecodeLimits};
+use crate::types::{LiveMode, Offset};
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use tokio::sync::watch;
+
+/// Folds records of type `T` into a state.
+///
+/// # Example
+///
+/// ```ignore
+/// struct Balances;
+///
+/// impl Reducer<Transfer> for Balances {
+///     type State = HashMap<String, i64>;
+///
+///     fn initial(&self) -> Self::State {
+///         HashMap::new()
+///     }
+///
+///     fn reduce(&self, state: &mut Self::State, t: Transfer) {
+///         *state.entry(t.from).or_default() -= t.amount;
+///         *state.entry(t.to).or_default() += t.amount;
+///     }
+/// }
+/// ```
+pub trait Reducer<T>: Send + Sync + 'static {
+    /// The folded state. Cloned only when a record arrives while a reader
+    /// still holds the previous version.
+    type State: Clone + Send + Sync + 'static;
+
+    /// State before the first record.
+    fn initial(&self) -> Self::State;
+
+    /// Apply one record.
+    fn reduce(&self, state: &mut Self::State, record: T);
+}
+
+/// A version of a [`Materializer`]'s state.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct Materialized<S> {
+    /// State after every record before `offset`.
+    pub state: Arc<S>,
+    /// Offset the state has been folded up to.
+    pub offset: Offset,
+    /// Whether the state had caught up with the stream tail when this
+    /// version was published.
+    pub up_to_date: bool,
+}
+
+impl<S> Clone for Materialized<S> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+            offset: self.offset.clone(),
+            up_to_date: self.up_to_date,
+        }
+    }
+}
+
+/// Keeps the fold of a JSON stream's records up to date in memory.
+///
+/// [`run`](Self::run) replays the stream from the beginning through a
+/// [`Reducer`], then follows it live. Meanwhile any clone of the
+/// materializer can read the [`current`](Self::current) state or
+/// [`subscribe`](Self::subscribe) to changes. If the stream's retention
+/// drops data the replay has not reached (410 Gone), the state is reset and
+/// the replay starts over.
+///
+/// # Example
+///
+/// ```ignore
+/// let balances = Materializer::new(stream, Balances);
+/// tokio::spawn({
+///     let balances = balances.clone();
+///     async move { balances.run(std::future::pending()).await }
+/// });
+///
+/// balances.wait_up_to_date().await;
+/// println!("alice has {}", balances.current()["alice"]);
+/// ```
+pub struct Materializer<T, R: Reducer<T>> {
+    stream: DurableStream,
+    reducer: Arc<R>,
+    live: LiveMode,
+    /// State and offset each run starts from.
+    start: Materialized<R::State>,
+    tx: Arc<watch::Sender<Materialized<R::State>>>,
+    records: PhantomData<fn() -> T>,
+}
+
+impl<T, R: Reducer<T>> Clone for Materializer<T, R> {
+    fn clone(&self) -> Self {
+        Self {
+            stream: self.stream.clone(),
+            reducer: self.reducer.clone(),
+            live: self.live.clone(),
+            start: self.start.clone(),
+            tx: self.tx.clone(),
+            records: PhantomData,
+        }
+    }
+}
+
+impl<T, R> Materializer<T, R>
+where
+    T: DeserializeOwned + Send + 'static,
+    R: Reducer<T>,
+{
+    /// Fold the records of `stream` with `reducer`.
+    pub fn new(stream: DurableStream, reducer: R) -> Self {
+        let initial = Materialized {
+            state: Arc::new(reducer.initial()),
+            offset: Offset::Beginning,
+            up_to_date: false,
+        };
+        Self {
+            stream,
+            reducer: Arc::new(reducer),
+            live: LiveMode::Sse,
+            start: initial.clone(),
+            tx: Arc::new(watch::Sender::new(initial)),
+            records: PhantomData,
+        }
+    }
+
+    /// Start from `snapshot` and read only the records after its offset,
+    /// e.g. one loaded by [`SnapshotReader`](crate::SnapshotReader).
+    #[must_use]
+    pub fn resume_from(mut self, snapshot: Snapshot<R::State>) -> Self {
+        self.start = Materialized {
+            state: Arc::new(snapshot.state),
+            offset: snapshot.offset,
+            up_to_date: false,
+        };
+        self.tx.send_replace(self.start.clone());
+        self
+    }
+
+    /// How to follow the stream once caught up (default
+    /// [`LiveMode::Sse`]). With [`LiveMode::Off`], [`run`](Self::run)
+    /// returns once the state is up to date.
+    #[must_use]
+    pub fn live(mut self, mode: LiveMode) -> Self {
+        self.live = mode;
+        self
+    }
+
+    /// The latest state.
+    pub fn current(&self) -> Arc<R::State> {
+        self.tx.borrow().state.clone()
+    }
+
+    /// The latest state with the offset it was folded up to.
+    pub fn snapshot(&self) -> Materialized<R::State> {
+        self.tx.borrow().clone()
+    }
+
+    /// Offset each run starts from.
+    pub(crate) fn start_offset(&self) -> &Offset {
+        &self.start.offset
+    }
+
+    /// Receive every new version of the state. Versions published while
+    /// the receiver is not looking are skipped; only the latest is kept.
+    pub fn subscribe(&self) -> watch::Receiver<Materialized<R::State>> {
+        self.tx.subscribe()
+    }
+
+    /// Wait until the state has caught up with the stream tail.
+    ///
+    /// Only completes while [`run`](Self::run) is running.
+    pub async fn wait_up_to_date(&self) {
+        let _ = self.subscribe().wait_for(|m| m.up_to_date).await;
+    }
+
+    /// Replay the stream and follow it until `shutdown` resolves, or until
+    /// caught up without live mode. Returns the offset the state was
+    /// folded up to.
+    ///
+    /// Each run starts again from the initial state, or the snapshot given
+    /// to [`resume_from`](Self::resume_from).
+    pub async fn run<S>(&self, shutdown: S) -> Result<Offset, StreamError>
+    where
+        S: Future<Output = ()>,
+    {
+        let mut folder = Folder {
+            reducer: self.reducer.clone(),
+            tx: self.tx.clone(),
+            limits: DecodeLimits::default(),
+            records: PhantomData,
+        };
+        self.tx.send_replace(self.start.clone());
+        self.stream
+            .read()
+            .offset(self.start.offset.clone())
+            .live(self.live.clone())
+            .run_handler(&mut folder, shutdown)
+            .await
+    }
+}
+
+/// Handler applying each chunk's records to the published state.
+struct Folder<T, R: Reducer<T>> {
+    reducer: Arc<R>,
+    tx: Arc<watch::Sender<Materialized<R::State>>>,
+    limits: DecodeLimits,
+    records: PhantomData<fn() -> T>,
+}
+
+impl<T, R: Reducer<T>> Folder<T, R> {
+    fn reset(&mut self, offset: &Offset) {
+        self.tx.send_replace(Materialized {
+            state: Arc::new(self.reducer.initial()),
+            offset: offset.clone(),
+            up_to_date: false,
+        });
+    }
+}
+
+#[async_trait]
+impl<T, R> StreamHandler for Folder<T, R>
+where
+    T: DeserializeOwned + Send + 'static,
+    R: Reducer<T>,
+{
+    async fn on_item(&mut self, chunk: &Chunk) -> Result<(), HandlerError> {
+        let records: Vec<T> = decode_json(&chunk.data, &self.limits)?;
+        let reducer = &self.reducer;
+        // Reduce in place unless a reader still holds the current version
+        self.tx.send_modify(|m| {
+            let state = Arc::make_mut(&mut m.state);
+            for record in records {
+                reducer.reduce(state, record);
+            }
+            m.offset = chunk.next_offset.clone();
+            m.up_to_date = chunk.up_to_date;
+        });
+        Ok(())
+    }
+
+    async fn on_catch_up_complete(&mut self, offset: &Offset) {
+        self.tx.send_modify(|m| {
+            m.offset = offset.clone();
+            m.up_to_date = true;
+        });
+    }
+
+    async fn on_error(&mut self, err: &StreamError) -> ErrorAction {
+        if err.is_retryable() {
+            ErrorAction::Continue
+        } else {
+            ErrorAction::Stop
+        }
+    }
+
+    async fn on_reset(&mut self, offset: &Offset) {
+        self.reset(offset);
+    }
+}
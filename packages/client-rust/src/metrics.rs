@@ -0,0 +1,359 @@
+//! Client-side metrics.
+
+use crate::middleware::Operation;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Upper bounds (seconds) of the request latency histogram buckets.
+const LATENCY_BUCKETS: [f64; 12] = [
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 10.0, 60.0,
+];
+
+/// Upper bounds (bytes) of the producer batch size histogram buckets.
+const BATCH_BYTES_BUCKETS: [f64; 10] = [
+    256.0, 1024.0, 4096.0, 16384.0, 65536.0, 262144.0, 1048576.0, 4194304.0, 16777216.0, 67108864.0,
+];
+
+/// Metrics recorded by a [`Client`](crate::Client) and everything created
+/// from it.
+///
+/// Recording is a handful of relaxed atomic operations per request, so the
+/// registry is always on. Read it with [`snapshot`](Self::snapshot), or with
+/// the `metrics` feature, render it for Prometheus with
+/// [`encode_prometheus`](Self::encode_prometheus).
+#[derive(Debug)]
+pub struct Metrics {
+    requests: [RequestMetrics; Operation::COUNT],
+    retries: AtomicU64,
+    requests_in_flight: AtomicI64,
+    producer_batches: Histogram<{ BATCH_BYTES_BUCKETS.len() }>,
+    producer_records: AtomicU64,
+    producer_in_flight: AtomicI64,
+    readers_lagging: AtomicI64,
+    bytes_in: AtomicU64,
+    bytes_out: AtomicU64,
+}
+
+#[derive(Debug, Default)]
+struct RequestMetrics {
+    errors: AtomicU64,
+    latency: Histogram<{ LATENCY_BUCKETS.len() }>,
+}
+
+#[derive(Debug)]
+struct Histogram<const N: usize> {
+    bounds: &'static [f64; N],
+    buckets: [AtomicU64; N],
+    count: AtomicU64,
+    // f64 bits, updated with a CAS loop
+    sum: AtomicU64,
+}
+
+impl Default for Histogram<{ LATENCY_BUCKETS.len() }> {
+    fn default() -> Self {
+        Histogram::new(&LATENCY_BUCKETS)
+    }
+}
+
+impl<const N: usize> Histogram<N> {
+    fn new(bounds: &'static [f64; N]) -> Self {
+        Self {
+            bounds,
+            buckets: std::array::from_fn(|_| AtomicU64::new(0)),
+            count: AtomicU64::new(0),
+            sum: AtomicU64::new(0f64.to_bits()),
+        }
+    }
+
+    fn observe(&self, value: f64) {
+        if let Some(i) = self.bounds.iter().position(|&b| value <= b) {
+            self.buckets[i].fetch_add(1, Ordering::Relaxed);
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+        let _ = self
+            .sum
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+                Some((f64::from_bits(bits) + value).to_bits())
+            });
+    }
+
+    fn snapshot(&self) -> HistogramSnapshot {
+        let mut cumulative = 0;
+        let buckets = self
+            .bounds
+            .iter()
+            .zip(&self.buckets)
+            .map(|(&bound, n)| {
+                cumulative += n.load(Ordering::Relaxed);
+                (bound, cumulative)
+            })
+            .collect();
+        HistogramSnapshot {
+            buckets,
+            count: self.count.load(Ordering::Relaxed),
+            sum: f64::from_bits(self.sum.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// Point-in-time copy of all metrics.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct MetricsSnapshot {
+    /// Per-operation request statistics.
+    pub requests: Vec<OperationStats>,
+    /// Retries performed (all causes).
+    pub retries: u64,
+    /// Requests currently awaiting a response.
+    pub requests_in_flight: i64,
+    /// Sizes (bytes) of producer batches sent.
+    pub producer_batch_bytes: HistogramSnapshot,
+    /// Records sent in producer batches.
+    pub producer_records: u64,
+    /// Producer batches currently in flight.
+    pub producer_in_flight: i64,
+    /// Readers whose last chunk was not up to date with the stream tail.
+    pub readers_lagging: i64,
+    /// Payload bytes received by readers.
+    pub bytes_in: u64,
+    /// Request body bytes sent.
+    pub bytes_out: u64,
+}
+
+/// Request statistics for one [`Operation`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct OperationStats {
+    /// The operation.
+    pub operation: Operation,
+    /// Requests that failed (transport error or status >= 400).
+    pub errors: u64,
+    /// Request latency in seconds; `count` is the number of requests.
+    pub latency: HistogramSnapshot,
+}
+
+/// Point-in-time copy of a histogram.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct HistogramSnapshot {
+    /// `(upper bound, cumulative count)` per bucket.
+    pub buckets: Vec<(f64, u64)>,
+    /// Number of observations.
+    pub count: u64,
+    /// Sum of observed values.
+    pub sum: f64,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self {
+            requests: Default::default(),
+            retries: AtomicU64::new(0),
+            requests_in_flight: AtomicI64::new(0),
+            producer_batches: Histogram::new(&BATCH_BYTES_BUCKETS),
+            producer_records: AtomicU64::new(0),
+            producer_in_flight: AtomicI64::new(0),
+            readers_lagging: AtomicI64::new(0),
+            bytes_in: AtomicU64::new(0),
+            bytes_out: AtomicU64::new(0),
+        }
+    }
+}
+
+impl Metrics {
+    /// Copy the current values.
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            requests: Operation::ALL
+                .iter()
+                .zip(&self.requests)
+                .map(|(&operation, m)| OperationStats {
+                    operation,
+                    errors: m.errors.load(Ordering::Relaxed),
+                    latency: m.latency.snapshot(),
+                })
+                .collect(),
+            retries: self.retries.load(Ordering::Relaxed),
+            requests_in_flight: self.requests_in_flight.load(Ordering::Relaxed),
+            producer_batch_bytes: self.producer_batches.snapshot(),
+            producer_records: self.producer_records.load(Ordering::Relaxed),
+            producer_in_flight: self.producer_in_flight.load(Ordering::Relaxed),
+            readers_lagging: self.readers_lagging.load(Ordering::Relaxed),
+            bytes_in: self.bytes_in.load(Ordering::Relaxed),
+            bytes_out: self.bytes_out.load(Ordering::Relaxed),
+        }
+    }
+
+    pub(crate) fn request_started(&self, body_bytes: usize) {
+        self.requests_in_flight.fetch_add(1, Ordering::Relaxed);
+        self.bytes_out
+            .fetch_add(body_bytes as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn request_finished(&self, operation: Operation, elapsed: Duration, failed: bool) {
+        self.requests_in_flight.fetch_sub(1, Ordering::Relaxed);
+        let m = &self.requests[operation.index()];
+        m.latency.observe(elapsed.as_secs_f64());
+        if failed {
+            m.errors.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn retry(&self) {
+        self.retries.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn producer_batch_started(&self, bytes: usize, records: usize) {
+        self.producer_batches.observe(bytes as f64);
+        self.producer_records
+            .fetch_add(records as u64, Ordering::Relaxed);
+        self.producer_in_flight.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn producer_batch_finished(&self) {
+        self.producer_in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn reader_lagging(&self, lagging: bool) {
+        let delta = if lagging { 1 } else { -1 };
+        self.readers_lagging.fetch_add(delta, Ordering::Relaxed);
+    }
+
+    pub(crate) fn bytes_received(&self, bytes: usize) {
+        self.bytes_in.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Render all metrics in the Prometheus text exposition format.
+    ///
+    /// Serve the result from your `/metrics` endpoint. Metric names are
+    /// prefixed with `durable_streams_`.
+    #[cfg(feature = "metrics")]
+    pub fn encode_prometheus(&self) -> String {
+        use std::fmt::Write;
+
+        let s = self.snapshot();
+        let mut out = String::new();
+
+        let _ = writeln!(
+            out,
+            "# HELP durable_streams_request_duration_seconds Request latency by operation."
+        );
+        let _ = writeln!(
+            out,
+            "# TYPE durable_streams_request_duration_seconds histogram"
+        );
+        for op in &s.requests {
+            let label = format!("operation=\"{}\"", op.operation.as_str());
+            write_histogram(
+                &mut out,
+                "durable_streams_request_duration_seconds",
+                &label,
+                &op.latency,
+            );
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP durable_streams_request_errors_total Failed requests by operation."
+        );
+        let _ = writeln!(out, "# TYPE durable_streams_request_errors_total counter");
+        for op in &s.requests {
+            let _ = writeln!(
+                out,
+                "durable_streams_request_errors_total{{operation=\"{}\"}} {}",
+                op.operation.as_str(),
+                op.errors
+            );
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP durable_streams_producer_batch_bytes Producer batch sizes."
+        );
+        let _ = writeln!(out, "# TYPE durable_streams_producer_batch_bytes histogram");
+        write_histogram(
+            &mut out,
+            "durable_streams_producer_batch_bytes",
+            "",
+            &s.producer_batch_bytes,
+        );
+
+        let scalars: [(&str, &str, &str, f64); 7] = [
+            (
+                "retries_total",
+                "counter",
+                "Retries performed.",
+                s.retries as f64,
+            ),
+            (
+                "requests_in_flight",
+                "gauge",
+                "Requests awaiting a response.",
+                s.requests_in_flight as f64,
+            ),
+            (
+                "producer_records_total",
+                "counter",
+                "Records sent by producers.",
+                s.producer_records as f64,
+            ),
+            (
+                "producer_batches_in_flight",
+                "gauge",
+                "Producer batches in flight.",
+                s.producer_in_flight as f64,
+            ),
+            (
+                "readers_lagging",
+                "gauge",
+                "Readers behind the stream tail.",
+                s.readers_lagging as f64,
+            ),
+            (
+                "received_bytes_total",
+                "counter",
+                "Payload bytes received by readers.",
+                s.bytes_in as f64,
+            ),
+            (
+                "sent_bytes_total",
+                "counter",
+                "Request body bytes sent.",
+                s.bytes_out as f64,
+            ),
+        ];
+        for (name, kind, help, value) in scalars {
+            let _ = writeln!(out, "# HELP durable_streams_{} {}", name, help);
+            let _ = writeln!(out, "# TYPE durable_streams_{} {}", name, kind);
+            let _ = writeln!(out, "durable_streams_{} {}", name, value);
+        }
+
+        out
+    }
+}
+
+#[cfg(feature = "metrics")]
+fn write_histogram(out: &mut String, name: &str, labels: &str, h: &HistogramSnapshot) {
+    use std::fmt::Write;
+
+    let sep = if labels.is_empty() { "" } else { "," };
+    for (bound, count) in &h.buckets {
+        let _ = writeln!(
+            out,
+            "{}_bucket{{{}{}le=\"{}\"}} {}",
+            name, labels, sep, bound, count
+        );
+    }
+    let _ = writeln!(
+        out,
+        "{}_bucket{{{}{}le=\"+Inf\"}} {}",
+        name, labels, sep, h.count
+    );
+    let braces = if labels.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", labels)
+    };
+    let _ = writeln!(out, "{}_sum{} {}", name, braces, h.sum);
+    let _ = writeln!(out, "{}_count{} {}", name, braces, h.count);
+}
@@ -0,0 +1,144 @@
+//! Request middleware invoked on every outgoing request.
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use reqwest::header::HeaderMap;
+use reqwest::{Method, Url};
+use std::future::Future;
+
+/// The client operation a request belongs to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Operation {
+    /// `PUT` creating a stream.
+    Create,
+    /// `POST` appending to a stream.
+    Append,
+    /// `GET` reading a stream (catch-up, long-poll, or SSE).
+    Read,
+    /// `HEAD` fetching stream metadata.
+    Head,
+    /// `DELETE` removing a stream.
+    Delete,
+    /// `POST` closing a stream.
+    Close,
+    /// `POST` of a producer batch.
+    ProducerAppend,
+    /// `POST` closing a stream through a producer.
+    ProducerClose,
+}
+
+impl Operation {
+    pub(crate) const COUNT: usize = 8;
+
+    pub(crate) const ALL: [Operation; Self::COUNT] = [
+        Operation::Create,
+        Operation::Append,
+        Operation::Read,
+        Operation::Head,
+        Operation::Delete,
+        Operation::Close,
+        Operation::ProducerAppend,
+        Operation::ProducerClose,
+    ];
+
+    pub(crate) fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Lowercase name, suitable as a metric label.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Operation::Create => "create",
+            Operation::Append => "append",
+            Operation::Read => "read",
+            Operation::Head => "head",
+            Operation::Delete => "delete",
+            Operation::Close => "close",
+            Operation::ProducerAppend => "producer_append",
+            Operation::ProducerClose => "producer_close",
+        }
+    }
+}
+
+/// Editable view of an outgoing request.
+///
+/// Middleware may change anything here; the request is rebuilt from these
+/// parts before it is sent.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct RequestParts {
+    /// Operation that issued the request.
+    pub operation: Operation,
+    /// HTTP method.
+    pub method: Method,
+    /// Full request URL, including query parameters.
+    pub url: Url,
+    /// Request headers, including client defaults.
+    pub headers: HeaderMap,
+    /// Request body, if any. Streaming bodies (see
+    /// [`DurableStream::append_stream`](crate::DurableStream::append_stream))
+    /// show up as `None` and are sent unchanged.
+    pub body: Option<Bytes>,
+}
+
+/// Hook run on every outgoing request, in the order registered with
+/// [`ClientBuilder::middleware`](crate::ClientBuilder::middleware).
+///
+/// Retries go through middleware again, so per-attempt values such as
+/// request IDs or signature timestamps stay fresh. Async closures taking and
+/// returning [`RequestParts`] implement this trait.
+///
+/// # Example
+///
+/// ```ignore
+/// let client = Client::builder()
+///     .middleware(|mut parts: RequestParts| async move {
+///         parts.headers.insert("x-tenant", "acme".parse().unwrap());
+///         parts
+///     })
+///     .build()?;
+/// ```
+#[async_trait]
+pub trait Middleware: Send + Sync {
+    /// Inspect or rewrite a request before it is sent.
+    async fn handle(&self, parts: RequestParts) -> RequestParts;
+}
+
+#[async_trait]
+impl<F, Fut> Middleware for F
+where
+    F: Fn(RequestParts) -> Fut + Send + Sync,
+    Fut: Future<Output = RequestParts> + Send,
+{
+    async fn handle(&self, parts: RequestParts) -> RequestParts {
+        self(parts).await
+    }
+}
+
+impl RequestParts {
+    pub(crate) fn from_request(operation: Operation, request: &reqwest::Request) -> Self {
+        Self {
+            operation,
+            method: request.method().clone(),
+            url: request.url().clone(),
+            headers: request.headers().clone(),
+            body: request
+                .body()
+                .and_then(|b| b.as_bytes())
+                .map(Bytes::copy_from_slice),
+        }
+    }
+
+    pub(crate) fn apply(self, request: &mut reqwest::Request) {
+        *request.method_mut() = self.method;
+        *request.url_mut() = self.url;
+        *request.headers_mut() = self.headers;
+        let streaming = request.body().is_some_and(|b| b.as_bytes().is_none());
+        match self.body {
+            Some(body) => *request.body_mut() = Some(body.into()),
+            None if streaming => {}
+            None => *request.body_mut() = None,
+        }
+    }
+}
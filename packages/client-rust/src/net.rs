@@ -0,0 +1,105 @@
+//! Address selection settings collected by `ClientBuilder`: IP family
+//! preference, local bind address and static DNS entries.
+
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use std::sync::Arc;
+
+/// Which IP family to connect over when a host has addresses in both.
+///
+/// Connections try the preferred family first and fall back to the other
+/// after a short delay if it does not connect (Happy Eyeballs), unless
+/// the preference excludes it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum IpPreference {
+    /// Use addresses in the order the system resolver returns them.
+    #[default]
+    System,
+    PreferIpv4,
+    PreferIpv6,
+    /// Never connect over IPv6.
+    Ipv4Only,
+    /// Never connect over IPv4.
+    Ipv6Only,
+}
+
+impl IpPreference {
+    /// Filter and order `addrs` by this preference.
+    fn apply(self, mut addrs: Vec<SocketAddr>) -> Vec<SocketAddr> {
+        match self {
+            Self::System => {}
+            // Stable sorts keep the resolver's order within each family
+            Self::PreferIpv4 => addrs.sort_by_key(|addr| addr.is_ipv6()),
+            Self::PreferIpv6 => addrs.sort_by_key(|addr| addr.is_ipv4()),
+            Self::Ipv4Only => addrs.retain(SocketAddr::is_ipv4),
+            Self::Ipv6Only => addrs.retain(SocketAddr::is_ipv6),
+        }
+        addrs
+    }
+}
+
+impl fmt::Display for IpPreference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::System | Self::PreferIpv4 | Self::PreferIpv6 => "IP",
+            Self::Ipv4Only => "IPv4",
+            Self::Ipv6Only => "IPv6",
+        })
+    }
+}
+
+/// Address selection settings for a client.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct NetConfig {
+    pub(crate) ip_preference: IpPreference,
+    pub(crate) local_address: Option<IpAddr>,
+    /// Static DNS entries, keyed by lowercase host name.
+    pub(crate) hosts: BTreeMap<String, Vec<IpAddr>>,
+}
+
+impl NetConfig {
+    /// Apply these settings to `builder`.
+    pub(crate) fn apply(self, mut builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+        if let Some(addr) = self.local_address {
+            builder = builder.local_address(addr);
+        }
+        if self.ip_preference != IpPreference::System {
+            builder = builder.dns_resolver(Arc::new(PreferenceResolver(self.ip_preference)));
+        }
+        for (host, ips) in &self.hosts {
+            // Port 0 leaves the port to the URL
+            let addrs: Vec<SocketAddr> = self
+                .ip_preference
+                .apply(ips.iter().map(|ip| SocketAddr::new(*ip, 0)).collect());
+            builder = builder.resolve_to_addrs(host, &addrs);
+        }
+        builder
+    }
+}
+
+/// The system resolver, with its answers filtered and ordered by an
+/// [`IpPreference`].
+struct PreferenceResolver(IpPreference);
+
+impl Resolve for PreferenceResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let preference = self.0;
+        Box::pin(async move {
+            let host = name.as_str().to_string();
+            let addrs = tokio::task::spawn_blocking(move || {
+                (host.as_str(), 0).to_socket_addrs().map(Iterator::collect)
+            })
+            .await??;
+            let addrs = preference.apply(addrs);
+            if addrs.is_empty() {
+                let message = format!("no {preference} addresses for {}", name.as_str());
+                return Err(io::Error::new(io::ErrorKind::NotFound, message).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
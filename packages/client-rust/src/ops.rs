@@ -0,0 +1,177 @@
+//! Object-safe traits over streams, readers and producers.
+//!
+//! Application code can take `&dyn StreamOps`, `Box<dyn ChunkSource>` or
+//! `Arc<dyn RecordSink>` instead of the concrete types, and unit tests can
+//! pass hand-written or `mockall` fakes that need no HTTP at all. The
+//! concrete types implement the traits by calling their own methods.
+
+use crate::error::{ProducerError, StreamError};
+use crate::iterator::{Chunk, ChunkIterator};
+use crate::producer::Producer;
+use crate::stream::{
+    AppendOptions, AppendResponse, CloseResponse, CreateOptions, DurableStream, HeadResponse,
+};
+use crate::types::{LiveMode, Offset};
+use async_trait::async_trait;
+use bytes::Bytes;
+
+/// Operations on one stream; implemented by [`DurableStream`].
+///
+/// # Example
+/// ```ignore
+/// async fn record_order(stream: &dyn StreamOps, order: &Order) -> Result<(), StreamError> {
+///     stream.append(serde_json::to_vec(order)?.into()).await?;
+///     Ok(())
+/// }
+///
+/// // In production
+/// record_order(&client.stream("/orders"), &order).await?;
+///
+/// // In a unit test
+/// let fake = FakeStream::default();
+/// record_order(&fake, &order).await?;
+/// assert_eq!(fake.appended(), 1);
+/// ```
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+pub trait StreamOps: Send + Sync {
+    /// The stream's URL.
+    fn url(&self) -> &str;
+
+    async fn create_with(&self, options: CreateOptions) -> Result<(), StreamError>;
+
+    async fn append_with(
+        &self,
+        data: Bytes,
+        options: AppendOptions,
+    ) -> Result<AppendResponse, StreamError>;
+
+    async fn head(&self) -> Result<HeadResponse, StreamError>;
+
+    async fn delete(&self) -> Result<(), StreamError>;
+
+    async fn close(&self) -> Result<CloseResponse, StreamError>;
+
+    /// Start reading at `offset`, in `live` mode.
+    fn open_reader(
+        &self,
+        offset: Offset,
+        live: LiveMode,
+    ) -> Result<Box<dyn ChunkSource>, StreamError>;
+
+    async fn create(&self) -> Result<(), StreamError> {
+        self.create_with(CreateOptions::default()).await
+    }
+
+    async fn append(&self, data: Bytes) -> Result<AppendResponse, StreamError> {
+        self.append_with(data, AppendOptions::default()).await
+    }
+}
+
+/// A source of chunks; implemented by [`ChunkIterator`].
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+pub trait ChunkSource: Send {
+    /// The next chunk, or `None` when there are no more.
+    async fn next_chunk(&mut self) -> Result<Option<Chunk>, StreamError>;
+
+    /// Offset the next chunk starts at.
+    fn offset(&self) -> &Offset;
+
+    /// Whether the source has caught up with the end of the stream.
+    fn is_up_to_date(&self) -> bool;
+
+    /// Stop reading and release resources.
+    async fn close(&mut self);
+}
+
+/// A batching writer of records; implemented by [`Producer`].
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+pub trait RecordSink: Send + Sync {
+    /// Queue a record. Failures surface through `flush` and `close`.
+    fn append(&self, data: Bytes);
+
+    /// Wait for every queued record to be written.
+    async fn flush(&self) -> Result<(), ProducerError>;
+
+    /// Flush, then stop accepting records.
+    async fn close(&self) -> Result<(), ProducerError>;
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl StreamOps for DurableStream {
+    fn url(&self) -> &str {
+        DurableStream::url(self)
+    }
+
+    async fn create_with(&self, options: CreateOptions) -> Result<(), StreamError> {
+        DurableStream::create_with(self, options).await
+    }
+
+    async fn append_with(
+        &self,
+        data: Bytes,
+        options: AppendOptions,
+    ) -> Result<AppendResponse, StreamError> {
+        DurableStream::append_with(self, data, options).await
+    }
+
+    async fn head(&self) -> Result<HeadResponse, StreamError> {
+        DurableStream::head(self).await
+    }
+
+    async fn delete(&self) -> Result<(), StreamError> {
+        DurableStream::delete(self).await
+    }
+
+    async fn close(&self) -> Result<CloseResponse, StreamError> {
+        DurableStream::close(self).await
+    }
+
+    fn open_reader(
+        &self,
+        offset: Offset,
+        live: LiveMode,
+    ) -> Result<Box<dyn ChunkSource>, StreamError> {
+        let reader = self.read().offset(offset).live(live).build()?;
+        Ok(Box::new(reader))
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl ChunkSource for ChunkIterator {
+    async fn next_chunk(&mut self) -> Result<Option<Chunk>, StreamError> {
+        ChunkIterator::next_chunk(self).await
+    }
+
+    fn offset(&self) -> &Offset {
+        ChunkIterator::offset(self)
+    }
+
+    fn is_up_to_date(&self) -> bool {
+        ChunkIterator::is_up_to_date(self)
+    }
+
+    async fn close(&mut self) {
+        ChunkIterator::close(self).await
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl RecordSink for Producer {
+    fn append(&self, data: Bytes) {
+        Producer::append(self, data)
+    }
+
+    async fn flush(&self) -> Result<(), ProducerError> {
+        Producer::flush(self).await
+    }
+
+    async fn close(&self) -> Result<(), ProducerError> {
+        Producer::close(self).await
+    }
+}
@@ -0,0 +1,544 @@
+//! Topics spread over several streams.
+
+use crate::client::Client;
+use crate::error::{ProducerError, StreamError};
+use crate::iterator::{Chunk, ChunkIterator, ReadBuilder};
+use crate::producer::Producer;
+use crate::store::OffsetStore;
+use crate::stream::{CreateOptions, DurableStream};
+use crate::types::{LiveMode, Offset};
+use bytes::Bytes;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::Poll;
+use std::time::Duration;
+
+impl Client {
+    /// A topic made of `partitions` streams named `{url}/part-0` to
+    /// `{url}/part-{partitions - 1}`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `partitions` is zero.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let orders = client.partitioned("/v1/stream/orders", 8).content_type("application/json");
+    /// orders.create().await?;
+    ///
+    /// let producer = orders.producer("checkout-1");
+    /// producer.append_json(&order.customer_id, &order);
+    /// ```
+    pub fn partitioned(&self, url: &str, partitions: usize) -> PartitionedStream {
+        assert!(
+            partitions > 0,
+            "a partitioned stream needs at least one partition"
+        );
+        let base = url.trim_end_matches('/');
+        PartitionedStream::from_streams(
+            (0..partitions)
+                .map(|i| self.stream(&format!("{base}/part-{i}")))
+                .collect(),
+        )
+    }
+}
+
+/// One logical topic stored as several streams, to write and read past the
+/// throughput of a single stream.
+///
+/// Writes go through a [`PartitionedProducer`], which sends all records
+/// with the same key to the same partition, so they stay in order. Reads
+/// merge the partitions through a [`PartitionedReader`], which tracks an
+/// offset per partition.
+#[derive(Clone, Debug)]
+pub struct PartitionedStream {
+    partitions: Vec<DurableStream>,
+}
+
+impl PartitionedStream {
+    /// Treat `partitions` as one topic, in this order. Partition numbers
+    /// are indexes into it, so every writer and reader must list the same
+    /// streams in the same order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `partitions` is empty.
+    pub fn from_streams(partitions: Vec<DurableStream>) -> Self {
+        assert!(
+            !partitions.is_empty(),
+            "a partitioned stream needs at least one partition"
+        );
+        Self { partitions }
+    }
+
+    /// Set the content type on every partition's handle; see
+    /// [`StreamBuilder::content_type`](crate::StreamBuilder::content_type).
+    #[must_use]
+    pub fn content_type(mut self, ct: impl Into<String>) -> Self {
+        let ct = ct.into();
+        for stream in &mut self.partitions {
+            stream.content_type = Some(ct.clone());
+        }
+        self
+    }
+
+    /// The partitions, by partition number.
+    pub fn partitions(&self) -> &[DurableStream] {
+        &self.partitions
+    }
+
+    /// Partition number `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of range.
+    pub fn partition(&self, index: usize) -> &DurableStream {
+        &self.partitions[index]
+    }
+
+    /// Number of partitions.
+    pub fn len(&self) -> usize {
+        self.partitions.len()
+    }
+
+    /// Always `false`: a partitioned stream has at least one partition.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Create every partition.
+    ///
+    /// Idempotent, like [`DurableStream::create`].
+    pub async fn create(&self) -> Result<(), StreamError> {
+        self.create_with(CreateOptions::default()).await
+    }
+
+    /// Create every partition with `options`, stopping at the first
+    /// failure.
+    pub async fn create_with(&self, options: CreateOptions) -> Result<(), StreamError> {
+        for stream in &self.partitions {
+            stream.create_with(options.clone()).await?;
+        }
+        Ok(())
+    }
+
+    /// Build a producer with default settings and a [`HashPartitioner`].
+    ///
+    /// Every partition gets its own idempotent producer named
+    /// `producer_id`. For custom batching, use
+    /// [`PartitionedProducer::new`].
+    pub fn producer(&self, producer_id: impl Into<String>) -> PartitionedProducer {
+        let producer_id = producer_id.into();
+        PartitionedProducer::new(
+            self.partitions
+                .iter()
+                .map(|stream| stream.producer(producer_id.clone()).build())
+                .collect(),
+        )
+    }
+
+    /// Start reading every partition (defaults to the beginning of each).
+    pub fn read(&self) -> PartitionedReadBuilder {
+        PartitionedReadBuilder {
+            partitions: self.partitions.clone(),
+            offsets: Vec::new(),
+            live: LiveMode::Off,
+            checkpoint: None,
+            ordered: false,
+        }
+    }
+}
+
+/// Chooses the partition for a record key.
+pub trait Partitioner: Send + Sync {
+    /// Partition number for `key`, below `partitions`.
+    fn partition(&self, key: &[u8], partitions: usize) -> usize;
+}
+
+/// The default [`Partitioner`]: 64-bit FNV-1a of the key, modulo the
+/// number of partitions.
+///
+/// The hash is fixed, so writers in any process or language that use the
+/// same function agree on where a key goes. Changing the number of
+/// partitions moves most keys.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HashPartitioner;
+
+impl Partitioner for HashPartitioner {
+    fn partition(&self, key: &[u8], partitions: usize) -> usize {
+        let hash = key.iter().fold(0xcbf2_9ce4_8422_2325_u64, |hash, &b| {
+            (hash ^ u64::from(b)).wrapping_mul(0x0000_0100_0000_01b3)
+        });
+        (hash % partitions as u64) as usize
+    }
+}
+
+impl<F> Partitioner for F
+where
+    F: Fn(&[u8], usize) -> usize + Send + Sync,
+{
+    fn partition(&self, key: &[u8], partitions: usize) -> usize {
+        self(key, partitions)
+    }
+}
+
+/// Appends records to a [`PartitionedStream`], one [`Producer`] per
+/// partition.
+///
+/// Records with a key go to the partition the [`Partitioner`] picks, so
+/// records sharing a key keep their order. Records without one are spread
+/// round-robin.
+#[derive(Clone)]
+pub struct PartitionedProducer {
+    producers: Vec<Producer>,
+    partitioner: Arc<dyn Partitioner>,
+    next: Arc<AtomicUsize>,
+}
+
+impl PartitionedProducer {
+    /// Wrap one producer per partition, in partition order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `producers` is empty.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let producer = PartitionedProducer::new(
+    ///     orders
+    ///         .partitions()
+    ///         .iter()
+    ///         .map(|p| p.producer("checkout-1").linger(Duration::from_millis(20)).build())
+    ///         .collect(),
+    /// );
+    /// ```
+    pub fn new(producers: Vec<Producer>) -> Self {
+        assert!(
+            !producers.is_empty(),
+            "a partitioned producer needs at least one partition"
+        );
+        Self {
+            producers,
+            partitioner: Arc::new(HashPartitioner),
+            next: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Choose partitions with `partitioner` instead of [`HashPartitioner`].
+    #[must_use]
+    pub fn with_partitioner(mut self, partitioner: impl Partitioner + 'static) -> Self {
+        self.partitioner = Arc::new(partitioner);
+        self
+    }
+
+    /// The producer of each partition, by partition number.
+    pub fn producers(&self) -> &[Producer] {
+        &self.producers
+    }
+
+    /// Partition number for `key`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the partitioner returns a number out of range.
+    pub fn partition_for(&self, key: impl AsRef<[u8]>) -> usize {
+        let partitions = self.producers.len();
+        let partition = self.partitioner.partition(key.as_ref(), partitions);
+        assert!(
+            partition < partitions,
+            "partitioner chose partition {partition} of {partitions}"
+        );
+        partition
+    }
+
+    /// Append `data` to the partition for `key` (fire-and-forget); see
+    /// [`Producer::append`]. Returns the partition number.
+    pub fn append(&self, key: impl AsRef<[u8]>, data: impl Into<Bytes>) -> usize {
+        let partition = self.partition_for(key);
+        self.producers[partition].append(data);
+        partition
+    }
+
+    /// Append a JSON value to the partition for `key`; see
+    /// [`Producer::append_json`]. Returns the partition number.
+    #[cfg(feature = "json")]
+    pub fn append_json<T: serde::Serialize>(&self, key: impl AsRef<[u8]>, data: &T) -> usize {
+        let partition = self.partition_for(key);
+        self.producers[partition].append_json(data);
+        partition
+    }
+
+    /// Append `data` to the next partition in turn. Returns the partition
+    /// number.
+    pub fn append_unkeyed(&self, data: impl Into<Bytes>) -> usize {
+        let partition = self.next.fetch_add(1, Ordering::Relaxed) % self.producers.len();
+        self.producers[partition].append(data);
+        partition
+    }
+
+    /// Wait for the records queued on every partition; see
+    /// [`Producer::flush`]. Returns the first error, after flushing all.
+    pub async fn flush(&self) -> Result<(), ProducerError> {
+        let mut result = Ok(());
+        for producer in &self.producers {
+            let flushed = producer.flush().await;
+            if result.is_ok() {
+                result = flushed;
+            }
+        }
+        result
+    }
+
+    /// Flush and close every partition's producer; see
+    /// [`Producer::close`]. Returns the first error, after closing all.
+    pub async fn close(&self) -> Result<(), ProducerError> {
+        let mut result = Ok(());
+        for producer in &self.producers {
+            let closed = producer.close().await;
+            if result.is_ok() {
+                result = closed;
+            }
+        }
+        result
+    }
+}
+
+impl std::fmt::Debug for PartitionedProducer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PartitionedProducer")
+            .field("partitions", &self.producers.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Builder for a [`PartitionedReader`].
+#[must_use = "builders do nothing unless you call .build()"]
+pub struct PartitionedReadBuilder {
+    partitions: Vec<DurableStream>,
+    offsets: Vec<Offset>,
+    live: LiveMode,
+    checkpoint: Option<(Arc<dyn OffsetStore>, Duration)>,
+    ordered: bool,
+}
+
+impl PartitionedReadBuilder {
+    /// Start each partition at its offset in `offsets`, by partition
+    /// number, e.g. from [`PartitionedReader::offsets`]. Partitions
+    /// without one start at the beginning.
+    pub fn offsets(mut self, offsets: impl IntoIterator<Item = Offset>) -> Self {
+        self.offsets = offsets.into_iter().collect();
+        self
+    }
+
+    /// Live mode for every partition; see [`ReadBuilder::live`].
+    pub fn live(mut self, mode: LiveMode) -> Self {
+        self.live = mode;
+        self
+    }
+
+    /// Checkpoint every partition in `store`, each under its own URL; see
+    /// [`ReadBuilder::checkpoint`]. A stored offset takes precedence over
+    /// [`offsets`](Self::offsets).
+    pub fn checkpoint(mut self, store: Arc<dyn OffsetStore>, interval: Duration) -> Self {
+        self.checkpoint = Some((store, interval));
+        self
+    }
+
+    /// Take one chunk from each partition in turn, instead of whichever
+    /// arrives first.
+    ///
+    /// The merged order is then the same on every run over the same data,
+    /// which suits replays and tests. In live mode a quiet partition holds
+    /// back the others until its long-poll or SSE read returns, so this
+    /// fits catch-up reads and evenly loaded partitions best.
+    pub fn ordered(mut self) -> Self {
+        self.ordered = true;
+        self
+    }
+
+    /// Build the reader.
+    pub fn build(self) -> Result<PartitionedReader, StreamError> {
+        let mut offsets = self.offsets.into_iter();
+        let mut parts = Vec::with_capacity(self.partitions.len());
+        for stream in &self.partitions {
+            let mut read: ReadBuilder = stream
+                .read()
+                .offset(offsets.next().unwrap_or(Offset::Beginning))
+                .live(self.live.clone());
+            if let Some((store, interval)) = &self.checkpoint {
+                read = read.checkpoint(store.clone(), *interval);
+            }
+            parts.push(Partition {
+                iter: read.build()?,
+                done: false,
+            });
+        }
+        Ok(PartitionedReader {
+            parts,
+            ordered: self.ordered,
+            next: 0,
+        })
+    }
+}
+
+impl std::fmt::Debug for PartitionedReadBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PartitionedReadBuilder")
+            .field("partitions", &self.partitions.len())
+            .field("offsets", &self.offsets)
+            .field("live", &self.live)
+            .field("ordered", &self.ordered)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A chunk read from one partition of a [`PartitionedStream`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct PartitionedChunk {
+    /// Partition number the chunk came from.
+    pub partition: usize,
+    pub chunk: Chunk,
+}
+
+/// Reads the partitions of a [`PartitionedStream`] as one sequence.
+///
+/// Chunks from one partition arrive in order; chunks from different
+/// partitions are interleaved, by arrival or, with
+/// [`ordered`](PartitionedReadBuilder::ordered), in turn. Without live mode
+/// the reader ends when every partition has been read to its tail.
+pub struct PartitionedReader {
+    parts: Vec<Partition>,
+    ordered: bool,
+    /// Partition to try first next time.
+    next: usize,
+}
+
+struct Partition {
+    iter: ChunkIterator,
+    done: bool,
+}
+
+impl PartitionedReader {
+    /// Get the next chunk from any partition.
+    ///
+    /// Cancellation safe: a chunk being fetched when the future is dropped
+    /// is returned by the next call.
+    pub async fn next_chunk(&mut self) -> Result<Option<PartitionedChunk>, StreamError> {
+        if self.ordered {
+            self.next_in_turn().await
+        } else {
+            self.next_arrived().await
+        }
+    }
+
+    async fn next_in_turn(&mut self) -> Result<Option<PartitionedChunk>, StreamError> {
+        let count = self.parts.len();
+        for _ in 0..count {
+            let partition = self.next;
+            let part = &mut self.parts[partition];
+            if part.done {
+                self.next = (partition + 1) % count;
+                continue;
+            }
+            match part.iter.next_chunk().await? {
+                Some(chunk) => {
+                    self.next = (partition + 1) % count;
+                    return Ok(Some(PartitionedChunk { partition, chunk }));
+                }
+                None => {
+                    part.done = true;
+                    self.next = (partition + 1) % count;
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    async fn next_arrived(&mut self) -> Result<Option<PartitionedChunk>, StreamError> {
+        let count = self.parts.len();
+        loop {
+            let readers = self
+                .parts
+                .iter_mut()
+                .enumerate()
+                .filter(|(_, part)| !part.done)
+                .map(|(i, part)| (i, &mut part.iter));
+            let Some((partition, result)) = next_of(readers, self.next).await else {
+                return Ok(None);
+            };
+            self.next = (partition + 1) % count;
+            match result? {
+                Some(chunk) => return Ok(Some(PartitionedChunk { partition, chunk })),
+                None => self.parts[partition].done = true,
+            }
+        }
+    }
+
+    /// Offset after the last chunk returned from each partition, by
+    /// partition number. Pass them to
+    /// [`PartitionedReadBuilder::offsets`] to resume.
+    pub fn offsets(&self) -> Vec<Offset> {
+        self.parts
+            .iter()
+            .map(|part| part.iter.offset().clone())
+            .collect()
+    }
+
+    /// Reader of partition number `index`, e.g. to
+    /// [`commit`](ChunkIterator::commit) its checkpoint.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of range.
+    pub fn partition_mut(&mut self, index: usize) -> &mut ChunkIterator {
+        &mut self.parts[index].iter
+    }
+
+    /// Close every partition's reader; see [`ChunkIterator::close`].
+    pub async fn close(&mut self) {
+        for part in &mut self.parts {
+            part.iter.close().await;
+        }
+    }
+}
+
+impl std::fmt::Debug for PartitionedReader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PartitionedReader")
+            .field("partitions", &self.parts.len())
+            .field("ordered", &self.ordered)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Wait for the first chunk from any of `readers`, polling them in turn
+/// from partition `first` on, so a busy partition cannot starve the rest.
+/// Returns `None` if there are no readers.
+///
+/// Cancellation safe, like [`ChunkIterator::next_chunk`].
+pub(crate) async fn next_of<'a>(
+    readers: impl Iterator<Item = (usize, &'a mut ChunkIterator)>,
+    first: usize,
+) -> Option<(usize, Result<Option<Chunk>, StreamError>)> {
+    let mut pending: Vec<_> = readers
+        .map(|(i, iter)| (i, Box::pin(iter.next_chunk())))
+        .collect();
+    if pending.is_empty() {
+        return None;
+    }
+    let start = pending.iter().position(|(i, _)| *i >= first).unwrap_or(0);
+    let ready = std::future::poll_fn(|cx| {
+        let len = pending.len();
+        for k in 0..len {
+            let (i, fut) = &mut pending[(start + k) % len];
+            if let Poll::Ready(result) = fut.as_mut().poll(cx) {
+                return Poll::Ready((*i, result));
+            }
+        }
+        Poll::Pending
+    })
+    .await;
+    Some(ready)
+}
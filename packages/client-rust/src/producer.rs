@@ -1,22 +1,44 @@
 //! Idempotent producer with exactly-once semantics.
 
-use crate::error::{ProducerError, StreamError};
+#[cfg(feature = "json")]
+use crate::dead_letter::{DeadLetter, DeadLetterQueue};
+use crate::error::{BatchFailure, ProducerConfigError, ProducerError, StoreError, StreamError};
+use crate::schema::SchemaValidator;
+use crate::middleware::Operation;
+use crate::retry::{notify, sleep_before_retry};
+use crate::store::{ProducerCheckpoint, ProducerStateStore};
 use crate::stream::{
     DurableStream, HEADER_CONTENT_TYPE, HEADER_PRODUCER_EPOCH, HEADER_PRODUCER_EXPECTED_SEQ,
     HEADER_PRODUCER_ID, HEADER_PRODUCER_SEQ, HEADER_STREAM_CLOSED, HEADER_STREAM_OFFSET,
 };
 use crate::types::Offset;
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
 use parking_lot::Mutex;
+use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::time::{Duration, Instant};
-use tokio::sync::oneshot;
-use tokio::time::sleep;
+use std::task::{Context, Poll, Waker};
+use crate::rt::{self, sleep, Instant};
+use std::time::Duration;
+use tokio::sync::{oneshot, watch, Notify};
+
+mod interceptor;
+#[cfg(feature = "futures")]
+mod sink;
+mod stats;
+mod throttle;
+mod txn;
+
+use interceptor::Interceptor;
+use throttle::Throttle;
+pub use interceptor::RecordDraft;
+pub use stats::ProducerStats;
+pub use txn::Txn;
 
 /// Receipt from an acknowledged append operation.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
 pub struct AppendReceipt {
     /// The offset after this message was appended.
     pub next_offset: Offset,
@@ -24,9 +46,34 @@ pub struct AppendReceipt {
     pub duplicate: bool,
 }
 
+/// Outcome of [`Producer::close_with_deadline`].
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct CloseReport {
+    /// Buffered records discarded without being sent.
+    pub dropped_records: usize,
+    /// Batches still being sent (and retried) in the background when the
+    /// deadline passed; their outcome is unknown.
+    pub in_flight_batches: usize,
+}
+
+impl CloseReport {
+    /// Whether every record was flushed before the deadline.
+    pub fn is_complete(&self) -> bool {
+        self.dropped_records == 0 && self.in_flight_batches == 0
+    }
+}
+
 /// Type alias for error callback function.
 pub type OnErrorCallback = Arc<dyn Fn(ProducerError) + Send + Sync>;
 
+/// Failed batches kept for the next `flush()`; older ones are dropped so a
+/// producer that is never flushed does not grow without bound.
+const MAX_TRACKED_FAILURES: usize = 1024;
+
+/// Called once with the outcome of the batch a record was sent in.
+type BatchCallback = Box<dyn FnOnce(Result<AppendReceipt, ProducerError>) + Send>;
+
 /// Builder for configuring an idempotent producer.
 #[must_use = "builders do nothing unless you call .build()"]
 pub struct ProducerBuilder {
@@ -37,8 +84,15 @@ pub struct ProducerBuilder {
     max_batch_bytes: usize,
     linger: Duration,
     max_in_flight: usize,
+    adaptive_in_flight: bool,
     content_type: Option<String>,
     on_error: Option<OnErrorCallback>,
+    state_store: Option<Arc<dyn ProducerStateStore>>,
+    interceptors: Vec<Interceptor>,
+    validator: Option<Arc<dyn SchemaValidator>>,
+    request_timeout: Option<Duration>,
+    #[cfg(feature = "json")]
+    dead_letter: Option<DeadLetterQueue>,
 }
 
 impl ProducerBuilder {
@@ -51,8 +105,15 @@ impl ProducerBuilder {
             max_batch_bytes: 1024 * 1024,
             linger: Duration::from_millis(5),
             max_in_flight: 5,
+            adaptive_in_flight: false,
             content_type: None,
             on_error: None,
+            state_store: None,
+            interceptors: Vec::new(),
+            validator: None,
+            request_timeout: None,
+            #[cfg(feature = "json")]
+            dead_letter: None,
         }
     }
 
@@ -81,11 +142,39 @@ impl ProducerBuilder {
     }
 
     /// Set maximum in-flight batches.
+    ///
+    /// The producer drops below this while the server is throttling it and
+    /// climbs back as batches are acknowledged; see
+    /// [`ProducerStats::in_flight_limit`].
     pub fn max_in_flight(mut self, count: usize) -> Self {
         self.max_in_flight = count;
         self
     }
 
+    /// Size the pipeline from observed latency and errors, with
+    /// [`max_in_flight`](Self::max_in_flight) as the ceiling.
+    ///
+    /// The producer starts with one batch in flight and doubles the window
+    /// each round trip until latency climbs well above the lowest seen, a
+    /// batch fails, or the server throttles it; from then on it grows by one
+    /// batch per round trip and shrinks again on the same signals. This
+    /// finds a deep pipeline on fast links and a shallow one on congested
+    /// links without tuning. Off by default; the window then stays at
+    /// `max_in_flight` unless the server throttles the producer.
+    pub fn adaptive_in_flight(mut self, enabled: bool) -> Self {
+        self.adaptive_in_flight = enabled;
+        self
+    }
+
+    /// Time limit for each batch request, overriding the client's
+    /// [`request_timeout`](crate::ClientBuilder::request_timeout).
+    ///
+    /// A batch that times out is reported like any other failed batch.
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
     /// Set content type for appends.
     pub fn content_type(mut self, ct: impl Into<String>) -> Self {
         self.content_type = Some(ct.into());
@@ -115,7 +204,144 @@ impl ProducerBuilder {
         self
     }
 
+    /// Add a hook that sees every record before it is queued.
+    ///
+    /// Interceptors can rewrite the payload and add headers to the record's
+    /// batch (tenant IDs, schema versions, envelope metadata) in one place
+    /// instead of at every call site. They run in the order added, on the
+    /// appending task, before the record is buffered.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let producer = stream.producer("ingest-1")
+    ///     .interceptor(|record: &mut RecordDraft| {
+    ///         record.headers_mut().insert("x-tenant", HeaderValue::from_static("acme"));
+    ///     })
+    ///     .build();
+    /// ```
+    pub fn interceptor<F>(mut self, interceptor: F) -> Self
+    where
+        F: Fn(&mut RecordDraft) + Send + Sync + 'static,
+    {
+        self.interceptors.push(Arc::new(interceptor));
+        self
+    }
+
+    /// Validate every record before it is queued.
+    ///
+    /// Runs after any [`interceptor`](Self::interceptor)s, on the final
+    /// payload. Rejected records are never sent: delivery callbacks get
+    /// [`ProducerError::Validation`], fire-and-forget appends report it to
+    /// the [`on_error`](Self::on_error) callback, and a [`Txn`] containing
+    /// one fails to commit.
+    pub fn validator(mut self, validator: Arc<dyn SchemaValidator>) -> Self {
+        self.validator = Some(validator);
+        self
+    }
+
+    /// Write records that cannot be delivered to the JSON stream `stream`
+    /// as [`DeadLetter`](crate::DeadLetter)s instead of dropping them.
+    ///
+    /// That covers every record of a batch that still fails after the
+    /// producer's own retries, and records rejected by the
+    /// [`validator`](Self::validator). Errors are still reported as usual;
+    /// if the dead-letter append fails too, that is reported to the
+    /// [`on_error`](Self::on_error) callback.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let producer = stream.producer("ingest-1")
+    ///     .validator(schema)
+    ///     .dead_letter(client.stream("/v1/stream/orders-dlq"))
+    ///     .build();
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn dead_letter(mut self, stream: DurableStream) -> Self {
+        self.dead_letter = Some(DeadLetterQueue::new(stream));
+        self
+    }
+
+    /// Persist epoch/sequence progress to a state store.
+    ///
+    /// The producer records its state after every acknowledged batch. Use
+    /// [`restore`](Self::restore) instead of [`build`](Self::build) to resume
+    /// from the persisted state on startup.
+    pub fn state_store(mut self, store: Arc<dyn ProducerStateStore>) -> Self {
+        self.state_store = Some(store);
+        self
+    }
+
+    /// Restore state from the configured state store, then build the producer.
+    ///
+    /// A restarted producer resumes with the persisted epoch + 1 (and sequence
+    /// 0), fencing off any previous instance still writing with the old epoch.
+    /// The new epoch is persisted before this returns. Without a state store
+    /// this is equivalent to [`build`](Self::build).
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let producer = stream.producer("ingest-1")
+    ///     .state_store(store)
+    ///     .restore()
+    ///     .await?;
+    /// ```
+    pub async fn restore(mut self) -> Result<Producer, StoreError> {
+        if let Some(store) = &self.state_store {
+            if let Some(saved) = store.load_producer(&self.producer_id).await? {
+                self.epoch = self.epoch.max(saved.epoch + 1);
+            }
+            store
+                .store_producer(
+                    &self.producer_id,
+                    &ProducerCheckpoint {
+                        epoch: self.epoch,
+                        next_seq: 0,
+                    },
+                )
+                .await?;
+        }
+        Ok(self.build())
+    }
+
+    /// Build the producer, validating the configuration first.
+    ///
+    /// Rejects settings that would make every batch fail or stall:
+    ///
+    /// - an empty producer ID, or one that is not a valid HTTP header value
+    /// - a content type that is not a valid HTTP header value
+    /// - `max_batch_bytes` of zero
+    /// - `max_in_flight` of zero
+    ///
+    /// These mirror the checks the conformance suite's `validate` command
+    /// applies to producer options (epochs are unsigned, so always valid).
+    pub fn try_build(self) -> Result<Producer, ProducerConfigError> {
+        if self.producer_id.is_empty() {
+            return Err(ProducerConfigError::EmptyProducerId);
+        }
+        if HeaderValue::from_str(&self.producer_id).is_err() {
+            return Err(ProducerConfigError::InvalidProducerId(self.producer_id));
+        }
+        if let Some(ct) = &self.content_type {
+            if HeaderValue::from_str(ct).is_err() {
+                return Err(ProducerConfigError::InvalidContentType(ct.clone()));
+            }
+        }
+        if self.max_batch_bytes == 0 {
+            return Err(ProducerConfigError::ZeroMaxBatchBytes);
+        }
+        if self.max_in_flight == 0 {
+            return Err(ProducerConfigError::ZeroMaxInFlight);
+        }
+        Ok(self.build())
+    }
+
     /// Build the producer.
+    ///
+    /// The configuration is not checked; use [`try_build`](Self::try_build)
+    /// when it comes from user input.
     pub fn build(self) -> Producer {
         let content_type = self.content_type.unwrap_or_else(|| {
             self.stream
@@ -124,9 +350,7 @@ impl ProducerBuilder {
                 .unwrap_or_else(|| "application/octet-stream".to_string())
         });
 
-        let linger = self.linger;
-
-        let producer = Producer {
+        Producer {
             stream: self.stream,
             producer_id: self.producer_id,
             state: Arc::new(Mutex::new(ProducerState {
@@ -137,29 +361,33 @@ impl ProducerBuilder {
                 closed: false,
                 epoch_claimed: !self.auto_claim,
                 stream_closed: false,
-                batch_started_at: None,
+                batch_generation: 0,
+                send_due: false,
+                batch_callbacks: Vec::new(),
+                batch_headers: HeaderMap::new(),
+                failures: Vec::new(),
+                counters: stats::Counters::default(),
+                throttle: Throttle::new(self.max_in_flight, self.adaptive_in_flight),
+                poll_wakers: Vec::new(),
             })),
             config: Arc::new(ProducerConfig {
                 auto_claim: self.auto_claim,
                 max_batch_bytes: self.max_batch_bytes,
-                linger,
-                max_in_flight: self.max_in_flight,
+                linger: self.linger,
                 content_type,
                 on_error: self.on_error,
+                state_store: self.state_store,
+                interceptors: self.interceptors,
+                validator: self.validator,
+                request_timeout: self.request_timeout,
+                #[cfg(feature = "json")]
+                dead_letter: self.dead_letter,
             }),
             in_flight: Arc::new(AtomicUsize::new(0)),
+            batch_done: Arc::new(Notify::new()),
             seq_state: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
-        };
-
-        // Spawn linger task if linger > 0
-        if linger > Duration::ZERO {
-            let producer_clone = producer.clone();
-            tokio::spawn(async move {
-                producer_clone.linger_task().await;
-            });
+            stats_tx: Arc::new(watch::Sender::new(ProducerStats::default())),
         }
-
-        producer
     }
 }
 
@@ -167,9 +395,14 @@ struct ProducerConfig {
     auto_claim: bool,
     max_batch_bytes: usize,
     linger: Duration,
-    max_in_flight: usize,
     content_type: String,
     on_error: Option<OnErrorCallback>,
+    state_store: Option<Arc<dyn ProducerStateStore>>,
+    interceptors: Vec<Interceptor>,
+    validator: Option<Arc<dyn SchemaValidator>>,
+    request_timeout: Option<Duration>,
+    #[cfg(feature = "json")]
+    dead_letter: Option<DeadLetterQueue>,
 }
 
 struct ProducerState {
@@ -180,14 +413,32 @@ struct ProducerState {
     closed: bool,
     epoch_claimed: bool,
     stream_closed: bool,
-    /// When the first item was added to the current pending batch
-    batch_started_at: Option<Instant>,
+    /// Bumped whenever the pending batch is sent or discarded, so a linger
+    /// timer can tell whether its batch is still pending
+    batch_generation: u64,
+    /// Whether the pending batch is due but was held back by the in-flight
+    /// limit, so the next batch to finish sends it
+    send_due: bool,
+    /// Notified with the outcome of the current pending batch
+    batch_callbacks: Vec<BatchCallback>,
+    /// Interceptor-added headers for the current pending batch
+    batch_headers: HeaderMap,
+    /// Batches that failed since the last flush
+    failures: Vec<BatchFailure>,
+    /// Lifetime counters reported by `stats()`
+    counters: stats::Counters,
+    /// In-flight window, narrowed while the server throttles
+    throttle: Throttle,
+    /// `Sink` and writer polls waiting for a batch to finish
+    poll_wakers: Vec<Waker>,
 }
 
 struct PendingEntry {
     data: Bytes,
     #[cfg(feature = "json")]
     json_data: Option<serde_json::Value>,
+    /// Headers added by interceptors, boxed to keep entries small
+    headers: Option<Box<HeaderMap>>,
 }
 
 /// Idempotent producer with exactly-once semantics.
@@ -202,7 +453,10 @@ pub struct Producer {
     state: Arc<Mutex<ProducerState>>,
     config: Arc<ProducerConfig>,
     in_flight: Arc<AtomicUsize>,
+    /// Signalled whenever an in-flight batch finishes, to wake `flush`.
+    batch_done: Arc<Notify>,
     seq_state: Arc<tokio::sync::Mutex<HashMap<u64, SeqState>>>,
+    stats_tx: Arc<watch::Sender<ProducerStats>>,
 }
 
 #[derive(Default)]
@@ -225,29 +479,228 @@ impl Producer {
     /// per-append; use `flush()` to ensure data is durably written.
     #[inline]
     pub fn append(&self, data: impl Into<Bytes>) {
-        let data = data.into();
-        let data_len = data.len();
+        self.enqueue(Self::raw_entry(data.into()), false);
+    }
 
-        let mut state = self.state.lock();
-        if state.closed {
-            return; // Silently ignore if closed
+    /// Append data and send the current batch immediately.
+    ///
+    /// Use this for latency-sensitive records (control messages, user-facing
+    /// events) on a producer otherwise tuned for throughput: the record and
+    /// everything queued before it skip the linger wait. If
+    /// `max_in_flight` batches are already outstanding, the batch goes out as
+    /// soon as one of them finishes.
+    ///
+    /// Same failure semantics as [`append`](Self::append).
+    pub fn append_urgent(&self, data: impl Into<Bytes>) {
+        self.enqueue(Self::raw_entry(data.into()), true);
+    }
+
+    /// Append a large payload as a sequence of batches of at most
+    /// `max_chunk_bytes` each, waiting for every piece to be acknowledged.
+    ///
+    /// Each piece goes out as its own idempotent batch, so retries never
+    /// duplicate data. Records appended concurrently from other tasks may
+    /// share a batch with a piece. Only meaningful for byte streams; JSON
+    /// streams need whole messages per append.
+    ///
+    /// Returns [`ProducerError::Stream`] without queueing anything if
+    /// `max_chunk_bytes` is zero.
+    pub async fn append_chunked(
+        &self,
+        data: impl Into<Bytes>,
+        max_chunk_bytes: usize,
+    ) -> Result<(), ProducerError> {
+        if max_chunk_bytes == 0 {
+            return Err(ProducerError::Stream {
+                message: StreamError::BadRequest {
+                    message: "max_chunk_bytes must be non-zero".to_string(),
+                    details: None,
+                }
+                .to_string(),
+            });
         }
+        let data = data.into();
 
-        // Track when batch started (for linger timer)
-        if state.pending_batch.is_empty() {
-            state.batch_started_at = Some(Instant::now());
+        // Drain anything already queued so the first piece starts its own batch
+        self.flush().await?;
+        for start in (0..data.len()).step_by(max_chunk_bytes) {
+            let end = data.len().min(start + max_chunk_bytes);
+            self.enqueue(Self::raw_entry(data.slice(start..end)), true);
+            self.flush().await?;
         }
+        Ok(())
+    }
 
-        state.pending_batch.push(PendingEntry {
+    fn raw_entry(data: Bytes) -> PendingEntry {
+        PendingEntry {
             data,
             #[cfg(feature = "json")]
             json_data: None,
+            headers: None,
+        }
+    }
+
+    /// Run the configured interceptors and validator over `entry`.
+    fn prepare(&self, entry: PendingEntry) -> Result<PendingEntry, ProducerError> {
+        let entry = if self.config.interceptors.is_empty() {
+            entry
+        } else {
+            interceptor::apply(&self.config.interceptors, entry)
+        };
+        if let Some(validator) = &self.config.validator {
+            if let Err(e) = validator.validate(&entry.data, &self.config.content_type) {
+                #[cfg(feature = "json")]
+                self.dead_letter(vec![entry.data], &e);
+                return Err(e.into());
+            }
+        }
+        Ok(entry)
+    }
+
+    /// Write `records` to the dead-letter stream, if there is one.
+    #[cfg(feature = "json")]
+    fn dead_letter(&self, records: Vec<Bytes>, error: &dyn std::fmt::Display) {
+        let Some(queue) = self.config.dead_letter.clone() else {
+            return;
+        };
+        let letters = dead_letters(&self.stream, &self.producer_id, &self.config, &records, error);
+        let config = self.config.clone();
+        let state = self.state.clone();
+        rt::spawn(async move {
+            send_dead_letters(&queue, &letters, &config, &state).await;
         });
-        state.batch_bytes += data_len;
+    }
 
-        if state.batch_bytes >= self.config.max_batch_bytes {
+    /// Queue an entry, sending the batch if it is full or `urgent` is set.
+    fn enqueue(&self, entry: PendingEntry, urgent: bool) {
+        match self.prepare(entry) {
+            // Silently ignore if closed
+            Ok(entry) => {
+                let _ = self.enqueue_all(std::iter::once(entry), urgent, None);
+            }
+            Err(e) => {
+                if let Some(callback) = &self.config.on_error {
+                    callback(e);
+                }
+            }
+        }
+    }
+
+    /// Queue `entries` into the pending batch, registering `callback` for
+    /// the outcome of that batch. Entries must already be prepared.
+    ///
+    /// The pending batch is only ever sent whole, so entries queued in one
+    /// call always land in the same batch. If the producer is closed,
+    /// `callback` is invoked with [`ProducerError::Closed`] right away.
+    fn enqueue_all(
+        &self,
+        entries: impl IntoIterator<Item = PendingEntry>,
+        urgent: bool,
+        callback: Option<BatchCallback>,
+    ) -> Result<(), ProducerError> {
+        let mut state = self.state.lock();
+        if state.closed {
+            drop(state);
+            if let Some(callback) = callback {
+                callback(Err(ProducerError::Closed));
+            }
+            return Err(ProducerError::Closed);
+        }
+
+        // A new batch starts its linger timer
+        if state.pending_batch.is_empty() && self.config.linger > Duration::ZERO {
+            self.start_linger_timer(state.batch_generation);
+        }
+
+        for mut entry in entries {
+            if let Some(headers) = entry.headers.take() {
+                // Later records win on conflicting names; `None` continues
+                // a multi-valued header from the same record
+                let mut current = None;
+                for (name, value) in headers.into_iter() {
+                    if let Some(name) = name {
+                        state.batch_headers.insert(name.clone(), value);
+                        current = Some(name);
+                    } else if let Some(name) = &current {
+                        state.batch_headers.append(name, value);
+                    }
+                }
+            }
+            state.batch_bytes += entry.data.len();
+            state.pending_batch.push(entry);
+        }
+        if let Some(callback) = callback {
+            state.batch_callbacks.push(callback);
+        }
+
+        if urgent || state.batch_bytes >= self.config.max_batch_bytes {
             self.send_batch_locked(&mut state);
         }
+        Ok(())
+    }
+
+    /// Append data and get called back once it is acknowledged or fails.
+    ///
+    /// Kafka-style delivery callback for fire-and-forget writers that still
+    /// need to log or count individual failures. The receipt describes the
+    /// batch the record was sent in, so `next_offset` is the offset after
+    /// that batch. The callback runs on the producer's background task and
+    /// should return quickly; it is not called if the producer is dropped
+    /// without [`flush`](Self::flush) or [`close`](Self::close).
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// producer.append_with_callback(payload, move |result| {
+    ///     if let Err(e) = result {
+    ///         eprintln!("record {} failed: {}", id, e);
+    ///     }
+    /// });
+    /// ```
+    pub fn append_with_callback<F>(&self, data: impl Into<Bytes>, callback: F)
+    where
+        F: FnOnce(Result<AppendReceipt, ProducerError>) + Send + 'static,
+    {
+        match self.prepare(Self::raw_entry(data.into())) {
+            Ok(entry) => {
+                let _ = self.enqueue_all(std::iter::once(entry), false, Some(Box::new(callback)));
+            }
+            Err(e) => callback(Err(e)),
+        }
+    }
+
+    /// Append JSON data with a delivery callback.
+    ///
+    /// See [`append_with_callback`](Self::append_with_callback).
+    /// Serialization errors are reported through the callback.
+    #[cfg(feature = "json")]
+    pub fn append_json_with_callback<T, F>(&self, data: &T, callback: F)
+    where
+        T: serde::Serialize,
+        F: FnOnce(Result<AppendReceipt, ProducerError>) + Send + 'static,
+    {
+        let entry = Self::json_entry(data).map_err(|e| StreamError::Json(e.to_string()).into());
+        match entry.and_then(|entry| self.prepare(entry)) {
+            Ok(entry) => {
+                let _ = self.enqueue_all(std::iter::once(entry), false, Some(Box::new(callback)));
+            }
+            Err(e) => callback(Err(e)),
+        }
+    }
+
+    /// Start a transaction: records appended to the returned [`Txn`] are
+    /// sent together in one batch when it is committed.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut txn = producer.transaction();
+    /// txn.append_json(&OrderPlaced { id })?;
+    /// txn.append_json(&StockReserved { id })?;
+    /// txn.commit().await?;
+    /// ```
+    pub fn transaction(&self) -> Txn {
+        Txn::new(self.clone())
     }
 
     /// Append JSON data (fire-and-forget).
@@ -263,50 +716,85 @@ impl Producer {
     #[cfg(feature = "json")]
     #[inline]
     pub fn append_json<T: serde::Serialize>(&self, data: &T) {
-        // Convert to Value first (avoids serialize -> parse -> unwrap)
-        let json_value = match serde_json::to_value(data) {
-            Ok(v) => v,
-            Err(_) => return, // Silently ignore serialization errors
-        };
+        // Silently ignore serialization errors
+        if let Ok(entry) = Self::json_entry(data) {
+            self.enqueue(entry, false);
+        }
+    }
 
-        // Serialize to bytes for size tracking
-        let json_bytes = match serde_json::to_vec(&json_value) {
-            Ok(b) => b,
-            Err(_) => return, // Shouldn't happen if to_value succeeded
-        };
+    /// Append JSON data and send the current batch immediately.
+    ///
+    /// See [`append_urgent`](Self::append_urgent).
+    #[cfg(feature = "json")]
+    pub fn append_json_urgent<T: serde::Serialize>(&self, data: &T) {
+        // Silently ignore serialization errors
+        if let Ok(entry) = Self::json_entry(data) {
+            self.enqueue(entry, true);
+        }
+    }
 
-        let mut state = self.state.lock();
-        if state.closed {
-            return;
+    /// Append a value encoded as MessagePack (fire-and-forget).
+    ///
+    /// Use on a stream created with [`CONTENT_TYPE_MSGPACK`](crate::CONTENT_TYPE_MSGPACK);
+    /// values are stored back to back and decoded by
+    /// [`ReadBuilder::typed_msgpack`](crate::ReadBuilder::typed_msgpack).
+    /// Encoding errors are silently ignored, like
+    /// [`append_json`](Self::append_json).
+    #[cfg(feature = "msgpack")]
+    pub fn append_msgpack<T: serde::Serialize>(&self, data: &T) {
+        if let Ok(bytes) = crate::codec::to_msgpack(data) {
+            self.enqueue(Self::raw_entry(bytes), false);
         }
+    }
 
-        // Track when batch started (for linger timer)
-        if state.pending_batch.is_empty() {
-            state.batch_started_at = Some(Instant::now());
+    /// Append a value encoded as CBOR (fire-and-forget).
+    ///
+    /// Use on a stream created with [`CONTENT_TYPE_CBOR`](crate::CONTENT_TYPE_CBOR);
+    /// values are stored back to back and decoded by
+    /// [`ReadBuilder::typed_cbor`](crate::ReadBuilder::typed_cbor).
+    /// Encoding errors are silently ignored, like
+    /// [`append_json`](Self::append_json).
+    #[cfg(feature = "cbor")]
+    pub fn append_cbor<T: serde::Serialize>(&self, data: &T) {
+        if let Ok(bytes) = crate::codec::to_cbor(data) {
+            self.enqueue(Self::raw_entry(bytes), false);
         }
+    }
+
+    #[cfg(feature = "json")]
+    fn json_entry<T: serde::Serialize>(data: &T) -> Result<PendingEntry, serde_json::Error> {
+        // Convert to Value first (avoids serialize -> parse -> unwrap)
+        let json_value = serde_json::to_value(data)?;
 
-        let len = json_bytes.len();
-        state.pending_batch.push(PendingEntry {
+        // Serialize to bytes for size tracking
+        let json_bytes = serde_json::to_vec(&json_value)?; // Shouldn't fail if to_value succeeded
+
+        Ok(PendingEntry {
             data: Bytes::from(json_bytes),
             json_data: Some(json_value),
-        });
-        state.batch_bytes += len;
-
-        if state.batch_bytes >= self.config.max_batch_bytes {
-            self.send_batch_locked(&mut state);
-        }
+            headers: None,
+        })
     }
 
     /// Flush all pending data and wait for all in-flight batches to complete.
     ///
     /// This method blocks until all buffered records have been sent and acknowledged.
-    /// Following Kafka semantics, errors are reported via the `on_error` callback
-    /// (if configured), not through the return value of this method.
+    /// If any batch failed since the previous flush, returns
+    /// [`ProducerError::Flush`] listing each failed batch's sequence number
+    /// and error (the most recent 1024 at most); each failure is reported
+    /// once. Failures are also still delivered to the `on_error` callback as
+    /// they happen.
     ///
     /// Call this before shutdown to ensure all messages have been sent.
     pub async fn flush(&self) -> Result<(), ProducerError> {
         // Keep sending batches until everything is flushed
         loop {
+            // Register before checking, so a batch finishing in between
+            // still wakes us
+            let batch_done = self.batch_done.notified();
+            tokio::pin!(batch_done);
+            batch_done.as_mut().enable();
+
             let has_pending = {
                 let mut state = self.state.lock();
                 if !state.pending_batch.is_empty() {
@@ -322,21 +810,141 @@ impl Producer {
                 break;
             }
 
-            // Yield to let in-flight requests complete
-            tokio::task::yield_now().await;
+            // Park until a batch completes rather than spinning, so an
+            // idle runtime can auto-advance a paused clock
+            batch_done.await;
         }
 
-        Ok(())
+        let failures = std::mem::take(&mut self.state.lock().failures);
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(ProducerError::Flush { failures })
+        }
+    }
+
+    /// The stream this producer appends to.
+    pub(crate) fn stream(&self) -> &DurableStream {
+        &self.stream
+    }
+
+    /// Ready once the pending batch has room, or can be sent to make room.
+    pub(crate) fn poll_room(&self, cx: &mut Context<'_>) -> Poll<Result<(), ProducerError>> {
+        let mut state = self.state.lock();
+        if state.closed {
+            return Poll::Ready(Err(ProducerError::Closed));
+        }
+        if state.batch_bytes >= self.config.max_batch_bytes {
+            self.send_batch_locked(&mut state);
+        }
+        if state.batch_bytes >= self.config.max_batch_bytes {
+            // Woken when an in-flight batch finishes
+            register_waker(&mut state, cx);
+            return Poll::Pending;
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    /// Queue raw bytes, reporting validation errors and a closed producer.
+    pub(crate) fn try_append(&self, data: Bytes) -> Result<(), ProducerError> {
+        let entry = self.prepare(Self::raw_entry(data))?;
+        self.enqueue_all(std::iter::once(entry), false, None)
+    }
+
+    /// Send the pending batch and wait for every batch to finish.
+    pub(crate) fn poll_flushed(&self, cx: &mut Context<'_>) -> Poll<Result<(), ProducerError>> {
+        let mut state = self.state.lock();
+        if !state.pending_batch.is_empty() {
+            self.send_batch_locked(&mut state);
+        }
+        // Checked under the lock, which a finishing batch takes after its
+        // decrement, so the wake-up cannot be missed
+        if !state.pending_batch.is_empty() || self.in_flight.load(Ordering::Acquire) > 0 {
+            register_waker(&mut state, cx);
+            return Poll::Pending;
+        }
+
+        let failures = std::mem::take(&mut state.failures);
+        if failures.is_empty() {
+            Poll::Ready(Ok(()))
+        } else {
+            Poll::Ready(Err(ProducerError::Flush { failures }))
+        }
     }
 
     /// Close the producer gracefully.
+    ///
+    /// The producer is closed even if the final flush reports failures;
+    /// those are returned as [`ProducerError::Flush`].
     pub async fn close(&self) -> Result<(), ProducerError> {
-        self.flush().await?;
+        let flushed = self.flush().await;
 
         let mut state = self.state.lock();
         state.closed = true;
+        stats::publish(&self.stats_tx, &state, self.in_flight.load(Ordering::Acquire));
 
-        Ok(())
+        flushed
+    }
+
+    /// Close the producer, flushing for at most `deadline`.
+    ///
+    /// Use this on shutdown paths that must not hang when the server is
+    /// down. If everything is flushed in time, this behaves like
+    /// [`close`](Self::close). Otherwise the producer is closed, records
+    /// still buffered are dropped (their delivery callbacks get
+    /// [`ProducerError::Closed`]), and the returned report says how much was
+    /// lost. Batch failures seen before the deadline stay available from
+    /// [`flush`](Self::flush).
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let report = producer.close_with_deadline(Duration::from_secs(5)).await?;
+    /// if !report.is_complete() {
+    ///     warn!("dropped {} records on shutdown", report.dropped_records);
+    /// }
+    /// ```
+    pub async fn close_with_deadline(&self, deadline: Duration) -> Result<CloseReport, ProducerError> {
+        tokio::select! {
+            flushed = self.close() => flushed.map(|_| CloseReport::default()),
+            _ = sleep(deadline) => {
+                let dropped_records = self.discard_pending();
+                Ok(CloseReport {
+                    dropped_records,
+                    in_flight_batches: self.in_flight.load(Ordering::Acquire),
+                })
+            }
+        }
+    }
+
+    /// Close the producer immediately, discarding buffered records.
+    ///
+    /// Returns the number of records dropped. Batches already in flight
+    /// are not cancelled and finish in the background.
+    pub fn abort(&self) -> usize {
+        self.discard_pending()
+    }
+
+    /// Close and drop the pending batch, failing its callbacks.
+    fn discard_pending(&self) -> usize {
+        let (dropped, callbacks) = {
+            let mut state = self.state.lock();
+            state.closed = true;
+            state.batch_bytes = 0;
+            state.batch_generation += 1;
+            state.send_due = false;
+            state.batch_headers.clear();
+            let dropped = state.pending_batch.len();
+            state.pending_batch.clear();
+            stats::publish(&self.stats_tx, &state, self.in_flight.load(Ordering::Acquire));
+            (dropped, std::mem::take(&mut state.batch_callbacks))
+        };
+
+        // Outside the lock, in case a callback touches the producer
+        for callback in callbacks {
+            callback(Err(ProducerError::Closed));
+        }
+        dropped
     }
 
     /// Close the stream using producer headers, optionally with a final message.
@@ -373,6 +981,7 @@ impl Producer {
             &self.stream,
             &self.producer_id,
             &content_type,
+            self.config.request_timeout,
             data,
             seq,
             epoch,
@@ -400,37 +1009,48 @@ impl Producer {
         self.state.lock().next_seq
     }
 
-    /// Background task that sends batches after linger duration.
-    async fn linger_task(&self) {
-        let linger = self.config.linger;
-
-        loop {
-            // Sleep for linger duration
-            sleep(linger).await;
-
-            // Check if we should stop
-            let should_send = {
-                let state = self.state.lock();
-                if state.closed {
-                    return; // Stop the task
-                }
+    /// Snapshot of buffered records, in-flight batches, and lifetime counters.
+    pub fn stats(&self) -> ProducerStats {
+        stats::snapshot(&self.state.lock(), self.in_flight.load(Ordering::Acquire))
+    }
 
-                // Check if there's a pending batch that's old enough
-                if let Some(started_at) = state.batch_started_at {
-                    started_at.elapsed() >= linger
-                } else {
-                    false
-                }
-            };
+    /// Subscribe to stats updates, e.g. for a dashboard or health check.
+    ///
+    /// A new snapshot is published whenever a batch is sent or completes,
+    /// and when the producer is closed. Records buffered between batches do
+    /// not trigger an update; use [`stats`](Self::stats) for an exact view.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut stats = producer.watch_stats();
+    /// tokio::spawn(async move {
+    ///     while stats.changed().await.is_ok() {
+    ///         let s = stats.borrow_and_update().clone();
+    ///         gauge.set(s.records_buffered as f64);
+    ///     }
+    /// });
+    /// ```
+    pub fn watch_stats(&self) -> watch::Receiver<ProducerStats> {
+        let rx = self.stats_tx.subscribe();
+        self.stats_tx.send_replace(self.stats());
+        rx
+    }
 
-            // Send the batch if needed (outside the lock)
-            if should_send {
-                let mut state = self.state.lock();
-                if !state.pending_batch.is_empty() {
-                    self.send_batch_locked(&mut state);
-                }
+    /// Send the pending batch once it has lingered, unless it was sent
+    /// or discarded first.
+    ///
+    /// The timer is a plain `sleep` in a task that ends with it, so it
+    /// follows `tokio::time::pause()` and keeps nothing alive afterwards.
+    fn start_linger_timer(&self, generation: u64) {
+        let producer = self.clone();
+        rt::spawn(async move {
+            sleep(producer.config.linger).await;
+            let mut state = producer.state.lock();
+            if state.batch_generation == generation && !state.pending_batch.is_empty() {
+                producer.send_batch_locked(&mut state);
             }
-        }
+        });
     }
 
     /// Send the current batch in a background task.
@@ -449,40 +1069,63 @@ impl Producer {
             return;
         }
 
-        // Check in-flight limit (atomic read - no lock needed)
+        // Check in-flight limit (atomic read - no lock needed), narrowed
+        // below max_in_flight while the server is throttling. A held-back
+        // batch is sent when an in-flight one finishes.
         let in_flight = self.in_flight.load(Ordering::Acquire);
-        if in_flight >= self.config.max_in_flight {
+        if in_flight >= state.throttle.limit() {
+            state.send_due = true;
             return;
         }
 
         // Check epoch claim
         if self.config.auto_claim && !state.epoch_claimed && in_flight > 0 {
+            state.send_due = true;
             return;
         }
 
         // Take the batch
         let batch: Vec<_> = state.pending_batch.drain(..).collect();
+        #[cfg(feature = "json")]
+        let records: Option<Vec<Bytes>> = self
+            .config
+            .dead_letter
+            .as_ref()
+            .map(|_| batch.iter().map(|e| e.data.clone()).collect());
+        self.stream
+            .client
+            .metrics
+            .producer_batch_started(state.batch_bytes, batch.len());
         let seq = state.next_seq;
         let epoch = state.epoch;
+        let batch_records = batch.len() as u64;
+        let batch_bytes = state.batch_bytes as u64;
 
         state.next_seq += 1;
         state.batch_bytes = 0;
-        state.batch_started_at = None;
+        state.batch_generation += 1;
+        state.send_due = false;
+        let callbacks = std::mem::take(&mut state.batch_callbacks);
+        let headers = std::mem::take(&mut state.batch_headers);
 
         // Increment in-flight (atomic - no lock needed)
-        self.in_flight.fetch_add(1, Ordering::AcqRel);
+        let in_flight = self.in_flight.fetch_add(1, Ordering::AcqRel) + 1;
+        stats::publish(&self.stats_tx, state, in_flight);
 
         // Send in background
         let stream = self.stream.clone();
         let producer_id = self.producer_id.clone();
         let config = self.config.clone();
         let in_flight_counter = self.in_flight.clone();
+        let batch_done = self.batch_done.clone();
         let state_arc = self.state.clone();
         let seq_state = self.seq_state.clone();
+        let stats_tx = self.stats_tx.clone();
+        let producer = self.clone();
 
-        tokio::spawn(async move {
+        rt::spawn(async move {
             let result =
-                do_send_batch(&stream, &producer_id, &config.content_type, batch, seq, epoch, config.auto_claim, &state_arc)
+                do_send_batch(&stream, &producer_id, &config.content_type, &headers, config.request_timeout, batch, seq, epoch, config.auto_claim, &state_arc)
                     .await;
 
             // Update epoch if claimed
@@ -493,6 +1136,64 @@ impl Producer {
                 }
             }
 
+            {
+                let mut state = state_arc.lock();
+                let counters = &mut state.counters;
+                match &result {
+                    Ok(receipt) => {
+                        counters.batches_sent += 1;
+                        counters.records_sent += batch_records;
+                        counters.bytes_sent += batch_bytes;
+                        if receipt.duplicate {
+                            counters.duplicates += 1;
+                        }
+                    }
+                    Err(e) => {
+                        counters.batches_failed += 1;
+                        counters.last_error = Some(e.clone());
+                    }
+                }
+            }
+
+            // Persist progress so a restart can resume with a fresh epoch
+            if let (Ok(_), Some(store)) = (&result, &config.state_store) {
+                let checkpoint = {
+                    let state = state_arc.lock();
+                    ProducerCheckpoint {
+                        epoch: state.epoch,
+                        next_seq: state.next_seq,
+                    }
+                };
+                if let Err(e) = store.store_producer(&producer_id, &checkpoint).await {
+                    let e = ProducerError::from(e);
+                    state_arc.lock().counters.last_error = Some(e.clone());
+                    if let Some(ref callback) = config.on_error {
+                        callback(e);
+                    }
+                }
+            }
+
+            #[cfg(feature = "json")]
+            if let (Err(e), Some(queue), Some(records)) = (&result, &config.dead_letter, records) {
+                let letters = dead_letters(&stream, &producer_id, &config, &records, e);
+                send_dead_letters(queue, &letters, &config, &state_arc).await;
+            }
+
+            for callback in callbacks {
+                callback(result.clone());
+            }
+
+            if let Err(ref e) = result {
+                let mut state = state_arc.lock();
+                if state.failures.len() >= MAX_TRACKED_FAILURES {
+                    state.failures.remove(0);
+                }
+                state.failures.push(BatchFailure {
+                    seq,
+                    error: e.clone(),
+                });
+            }
+
             // Call on_error callback if configured and error occurred
             if let Err(ref e) = result {
                 if let Some(ref callback) = config.on_error {
@@ -514,29 +1215,131 @@ impl Producer {
             }
 
             // Decrement in-flight (atomic - no lock needed)
-            in_flight_counter.fetch_sub(1, Ordering::AcqRel);
+            let in_flight = in_flight_counter.fetch_sub(1, Ordering::AcqRel) - 1;
+            stream.client.metrics.producer_batch_finished();
+            let wakers = {
+                let mut state = state_arc.lock();
+                stats::publish(&stats_tx, &state, in_flight);
+                if state.send_due {
+                    producer.send_batch_locked(&mut state);
+                }
+                std::mem::take(&mut state.poll_wakers)
+            };
+            wakers.into_iter().for_each(Waker::wake);
+            batch_done.notify_waiters();
         });
     }
 }
 
+/// Dead letters for `records`, which `producer_id` failed to write.
+#[cfg(feature = "json")]
+fn dead_letters(
+    stream: &DurableStream,
+    producer_id: &str,
+    config: &ProducerConfig,
+    records: &[Bytes],
+    error: &dyn std::fmt::Display,
+) -> Vec<DeadLetter> {
+    records
+        .iter()
+        .map(|data| {
+            let mut letter = DeadLetter::new(&stream.url, &config.content_type, data, error);
+            letter.producer_id = Some(producer_id.to_string());
+            letter
+        })
+        .collect()
+}
+
+/// Append `letters` to `queue`, counting them or reporting the failure.
+#[cfg(feature = "json")]
+async fn send_dead_letters(
+    queue: &DeadLetterQueue,
+    letters: &[DeadLetter],
+    config: &ProducerConfig,
+    state: &Mutex<ProducerState>,
+) {
+    match queue.send(letters).await {
+        Ok(()) => state.lock().counters.records_dead_lettered += letters.len() as u64,
+        Err(e) => {
+            let e = ProducerError::Stream {
+                message: format!("dead letter append failed: {e}"),
+            };
+            state.lock().counters.last_error = Some(e.clone());
+            if let Some(callback) = &config.on_error {
+                callback(e);
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn do_send_batch(
     stream: &DurableStream,
     producer_id: &str,
     content_type: &str,
+    headers: &HeaderMap,
+    timeout: Option<Duration>,
     batch: Vec<PendingEntry>,
     seq: u64,
     epoch: u64,
     auto_claim: bool,
     state: &Arc<Mutex<ProducerState>>,
 ) -> Result<AppendReceipt, ProducerError> {
-    do_send_batch_with_retry(stream, producer_id, content_type, batch, seq, epoch, auto_claim, state, 0).await
+    let body = batch_body(&batch, content_type)?;
+    // Release the records; the body shares their buffers where it can
+    drop(batch);
+    do_send_batch_with_retry(stream, producer_id, content_type, headers, timeout, body, seq, epoch, auto_claim, state, 0).await
+}
+
+/// Encode a batch as one request body, built once and shared by retries.
+///
+/// A single raw record is sent as is, without copying. Otherwise records
+/// are copied once into a buffer of the exact size; JSON records are
+/// already serialized, so a JSON batch just joins them into an array.
+#[cfg_attr(not(feature = "json"), allow(unused_variables))]
+fn batch_body(batch: &[PendingEntry], content_type: &str) -> Result<Bytes, ProducerError> {
+    #[cfg(feature = "json")]
+    if content_type.to_lowercase().contains("application/json") {
+        // Check for mixed append types (some with json_data, some without)
+        let json_count = batch.iter().filter(|e| e.json_data.is_some()).count();
+        if json_count > 0 && json_count < batch.len() {
+            // Mixed types in a JSON batch - this would silently drop entries
+            return Err(ProducerError::MixedAppendTypes);
+        }
+        if json_count > 0 {
+            // All entries have json_data - wrap in array for JSON batching
+            let len = batch.iter().map(|e| e.data.len() + 1).sum::<usize>() + 1;
+            let mut body = BytesMut::with_capacity(len);
+            body.extend_from_slice(b"[");
+            for (i, entry) in batch.iter().enumerate() {
+                if i > 0 {
+                    body.extend_from_slice(b",");
+                }
+                body.extend_from_slice(&entry.data);
+            }
+            body.extend_from_slice(b"]");
+            return Ok(body.freeze());
+        }
+    }
+    // Raw bytes - concatenate
+    if let [entry] = batch {
+        return Ok(entry.data.clone());
+    }
+    let mut body = BytesMut::with_capacity(batch.iter().map(|e| e.data.len()).sum());
+    for entry in batch {
+        body.extend_from_slice(&entry.data);
+    }
+    Ok(body.freeze())
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn do_send_batch_with_retry(
     stream: &DurableStream,
     producer_id: &str,
     content_type: &str,
-    batch: Vec<PendingEntry>,
+    headers: &HeaderMap,
+    timeout: Option<Duration>,
+    body: Bytes,
     seq: u64,
     epoch: u64,
     auto_claim: bool,
@@ -545,64 +1348,39 @@ async fn do_send_batch_with_retry(
 ) -> Result<AppendReceipt, ProducerError> {
     const MAX_409_RETRIES: u32 = 10;
 
-    let is_json = content_type.to_lowercase().contains("application/json");
-
-    // Build body
-    let body = if is_json {
-        #[cfg(feature = "json")]
-        {
-            // Check for mixed append types (some with json_data, some without)
-            let json_count = batch.iter().filter(|e| e.json_data.is_some()).count();
-            let raw_count = batch.len() - json_count;
-
-            if json_count > 0 && raw_count > 0 {
-                // Mixed types in a JSON batch - this would silently drop entries
-                return Err(ProducerError::MixedAppendTypes);
-            }
-
-            if json_count > 0 {
-                // All entries have json_data - wrap in array for JSON batching
-                let values: Vec<serde_json::Value> = batch
-                    .iter()
-                    .filter_map(|e| e.json_data.clone())
-                    .collect();
-                serde_json::to_vec(&values).unwrap_or_default()
-            } else {
-                // All raw bytes - concatenate
-                batch
-                    .iter()
-                    .flat_map(|e| e.data.iter().copied())
-                    .collect::<Vec<u8>>()
-            }
-        }
-        #[cfg(not(feature = "json"))]
-        {
-            batch
-                .iter()
-                .flat_map(|e| e.data.iter().copied())
-                .collect::<Vec<u8>>()
-        }
-    } else {
-        batch
-            .iter()
-            .flat_map(|e| e.data.iter().copied())
-            .collect::<Vec<u8>>()
-    };
-
-    let resp = stream
+    let mut req = stream
         .client
         .inner
         .post(&stream.url)
+        .headers(headers.clone())
         .header(HEADER_CONTENT_TYPE, content_type)
         .header(HEADER_PRODUCER_ID, producer_id)
         .header(HEADER_PRODUCER_EPOCH, epoch.to_string())
         .header(HEADER_PRODUCER_SEQ, seq.to_string())
-        .body(body)
-        .send()
-        .await?;
+        .body(body.clone());
+    if let Some(timeout) = timeout {
+        req = req.timeout(timeout);
+    }
+    let sent_at = Instant::now();
+    let resp = match stream.client.send(Operation::ProducerAppend, req).await {
+        Ok(resp) => resp,
+        Err(e) => {
+            state.lock().throttle.on_error(sent_at);
+            return Err(e.into());
+        }
+    };
 
     let status = resp.status().as_u16();
 
+    // A 429, or a Retry-After on any response, asks us to slow down
+    if status == 429 || resp.headers().contains_key(RETRY_AFTER) {
+        state.lock().throttle.on_throttled(sent_at);
+    } else if matches!(status, 200 | 204) {
+        state.lock().throttle.on_success(sent_at);
+    } else if status >= 500 {
+        state.lock().throttle.on_error(sent_at);
+    }
+
     match status {
         200 => {
             let offset = resp
@@ -641,13 +1419,16 @@ async fn do_send_batch_with_retry(
                     s.epoch = new_epoch;
                     s.next_seq = 1; // This batch uses seq 0
                     s.epoch_claimed = false; // Reset so pipelining waits for seq 0 to succeed
+                    s.counters.retries += 1;
                 }
                 // Retry with new epoch
                 return Box::pin(do_send_batch_with_retry(
                     stream,
                     producer_id,
                     content_type,
-                    batch,
+                    headers,
+                    timeout,
+                    body,
                     0,
                     new_epoch,
                     auto_claim,
@@ -662,19 +1443,47 @@ async fn do_send_batch_with_retry(
                 our_epoch: epoch,
             })
         }
+        429 if retry_count < stream.client.retry.max_retries => {
+            // Rate limited - the batch was not processed, so wait for the
+            // server's Retry-After (or back off) and resend the same seq
+            let err = StreamError::from_response(&resp, &stream.url);
+            sleep_before_retry(&stream.client, &stream.url, retry_count + 1, &err).await;
+            state.lock().counters.retries += 1;
+
+            return Box::pin(do_send_batch_with_retry(
+                stream,
+                producer_id,
+                content_type,
+                headers,
+                timeout,
+                body,
+                seq,
+                epoch,
+                auto_claim,
+                state,
+                retry_count + 1,
+            ))
+            .await;
+        }
         409 => {
             // Sequence gap - this can happen when requests arrive out of order
             // Retry with exponential backoff to let earlier sequences complete
             if retry_count < MAX_409_RETRIES {
                 // Wait before retrying - use exponential backoff
                 let delay_ms = 10 * (1 << retry_count.min(6)); // 10ms, 20ms, 40ms, ... up to 640ms
-                sleep(Duration::from_millis(delay_ms)).await;
+                let delay = Duration::from_millis(delay_ms);
+                let err = StreamError::Conflict { details: None };
+                notify(&stream.client, &stream.url, retry_count + 1, delay, delay, &err);
+                sleep(delay).await;
+                state.lock().counters.retries += 1;
 
                 return Box::pin(do_send_batch_with_retry(
                     stream,
                     producer_id,
                     content_type,
-                    batch,
+                    headers,
+                    timeout,
+                    body,
                     seq,
                     epoch,
                     auto_claim,
@@ -698,15 +1507,17 @@ async fn do_send_batch_with_retry(
             })
         }
         _ => Err(ProducerError::Stream {
-            message: StreamError::from_status(status, &stream.url).to_string(),
+            message: StreamError::from_error_response(resp, &stream.url).await.to_string(),
         }),
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn do_send_close_with_retry(
     stream: &DurableStream,
     producer_id: &str,
     content_type: &str,
+    timeout: Option<Duration>,
     data: Option<Bytes>,
     seq: u64,
     epoch: u64,
@@ -716,7 +1527,7 @@ async fn do_send_close_with_retry(
 ) -> Result<AppendReceipt, ProducerError> {
     const MAX_409_RETRIES: u32 = 10;
 
-    let data = data.unwrap_or_else(Bytes::new);
+    let data = data.unwrap_or_default();
     let has_data = !data.is_empty();
     let body = if has_data {
         if content_type.to_lowercase().contains("application/json") {
@@ -742,11 +1553,14 @@ async fn do_send_close_with_retry(
         .header(HEADER_PRODUCER_SEQ, seq.to_string())
         .header(HEADER_STREAM_CLOSED, "true");
 
+    if let Some(timeout) = timeout {
+        req = req.timeout(timeout);
+    }
     if !body.is_empty() {
         req = req.body(body);
     }
 
-    let resp = req.send().await?;
+    let resp = stream.client.send(Operation::ProducerClose, req).await?;
     let status = resp.status().as_u16();
 
     match status {
@@ -787,6 +1601,7 @@ async fn do_send_close_with_retry(
                     stream,
                     producer_id,
                     content_type,
+                    timeout,
                     if has_data { Some(data.clone()) } else { None },
                     0,
                     new_epoch,
@@ -802,6 +1617,23 @@ async fn do_send_close_with_retry(
                 our_epoch: epoch,
             })
         }
+        429 if retry_count < stream.client.retry.max_retries => {
+            let err = StreamError::from_response(&resp, &stream.url);
+            sleep_before_retry(&stream.client, &stream.url, retry_count + 1, &err).await;
+            return Box::pin(do_send_close_with_retry(
+                stream,
+                producer_id,
+                content_type,
+                timeout,
+                if has_data { Some(data.clone()) } else { None },
+                seq,
+                epoch,
+                auto_claim,
+                state,
+                retry_count + 1,
+            ))
+            .await;
+        }
         409 => {
             let stream_closed = resp
                 .headers()
@@ -815,11 +1647,15 @@ async fn do_send_close_with_retry(
 
             if retry_count < MAX_409_RETRIES {
                 let delay_ms = 10 * (1 << retry_count.min(6));
-                sleep(Duration::from_millis(delay_ms)).await;
+                let delay = Duration::from_millis(delay_ms);
+                let err = StreamError::Conflict { details: None };
+                notify(&stream.client, &stream.url, retry_count + 1, delay, delay, &err);
+                sleep(delay).await;
                 return Box::pin(do_send_close_with_retry(
                     stream,
                     producer_id,
                     content_type,
+                    timeout,
                     if has_data { Some(data.clone()) } else { None },
                     seq,
                     epoch,
@@ -843,7 +1679,13 @@ async fn do_send_close_with_retry(
             })
         }
         _ => Err(ProducerError::Stream {
-            message: StreamError::from_status(status, &stream.url).to_string(),
+            message: StreamError::from_error_response(resp, &stream.url).await.to_string(),
         }),
     }
 }
+
+fn register_waker(state: &mut ProducerState, cx: &mut Context<'_>) {
+    if !state.poll_wakers.iter().any(|w| w.will_wake(cx.waker())) {
+        state.poll_wakers.push(cx.waker().clone());
+    }
+}
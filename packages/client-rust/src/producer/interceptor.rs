@@ -0,0 +1,105 @@
+//! Record interceptors.
+
+use super::PendingEntry;
+use crate::stream::{
+    HEADER_CONTENT_TYPE, HEADER_PRODUCER_EPOCH, HEADER_PRODUCER_ID, HEADER_PRODUCER_SEQ,
+    HEADER_STREAM_CLOSED,
+};
+use bytes::Bytes;
+use reqwest::header::HeaderMap;
+use std::sync::Arc;
+
+/// Hook run on every record before it is queued.
+pub(super) type Interceptor = Arc<dyn Fn(&mut RecordDraft) + Send + Sync>;
+
+/// Headers the producer sets itself; interceptors cannot override them.
+const RESERVED_HEADERS: [&str; 5] = [
+    HEADER_CONTENT_TYPE,
+    HEADER_PRODUCER_ID,
+    HEADER_PRODUCER_EPOCH,
+    HEADER_PRODUCER_SEQ,
+    HEADER_STREAM_CLOSED,
+];
+
+/// A record about to be queued, as seen by a
+/// [`ProducerBuilder::interceptor`](super::ProducerBuilder::interceptor).
+///
+/// Headers added here are sent with the batch the record lands in. A batch
+/// carries the union of its records' headers; if two records set the same
+/// header, the later one wins. Content type and producer headers are
+/// reserved and silently dropped.
+pub struct RecordDraft {
+    data: Bytes,
+    #[cfg(feature = "json")]
+    json: Option<serde_json::Value>,
+    #[cfg(feature = "json")]
+    json_modified: bool,
+    headers: HeaderMap,
+}
+
+impl RecordDraft {
+    /// Encoded payload. For JSON records this is the serialized value as it
+    /// was before the interceptor ran.
+    pub fn payload(&self) -> &Bytes {
+        &self.data
+    }
+
+    /// Replace the payload with raw bytes.
+    ///
+    /// On a JSON record this discards the JSON value, turning it into a raw
+    /// append; JSON batches cannot mix the two, so prefer
+    /// [`json_mut`](Self::json_mut) there.
+    pub fn set_payload(&mut self, data: impl Into<Bytes>) {
+        self.data = data.into();
+        #[cfg(feature = "json")]
+        {
+            self.json = None;
+            self.json_modified = false;
+        }
+    }
+
+    /// JSON value of a record queued with `append_json`, for in-place edits.
+    #[cfg(feature = "json")]
+    pub fn json_mut(&mut self) -> Option<&mut serde_json::Value> {
+        self.json_modified = true;
+        self.json.as_mut()
+    }
+
+    /// Headers to send with this record's batch.
+    pub fn headers_mut(&mut self) -> &mut HeaderMap {
+        &mut self.headers
+    }
+}
+
+/// Run `interceptors` in order over `entry`.
+pub(super) fn apply(interceptors: &[Interceptor], entry: PendingEntry) -> PendingEntry {
+    let mut draft = RecordDraft {
+        data: entry.data,
+        #[cfg(feature = "json")]
+        json: entry.json_data,
+        #[cfg(feature = "json")]
+        json_modified: false,
+        headers: entry.headers.map(|h| *h).unwrap_or_default(),
+    };
+    for interceptor in interceptors {
+        interceptor(&mut draft);
+    }
+
+    #[cfg(feature = "json")]
+    if draft.json_modified {
+        if let Some(json) = &draft.json {
+            draft.data = serde_json::to_vec(json).unwrap_or_default().into();
+        }
+    }
+
+    for name in RESERVED_HEADERS {
+        draft.headers.remove(name);
+    }
+
+    PendingEntry {
+        data: draft.data,
+        #[cfg(feature = "json")]
+        json_data: draft.json,
+        headers: (!draft.headers.is_empty()).then(|| Box::new(draft.headers)),
+    }
+}
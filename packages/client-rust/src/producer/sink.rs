@@ -0,0 +1,77 @@
+//! `futures::Sink` adapters for producers.
+
+use super::{stats, Producer};
+use crate::error::ProducerError;
+#[cfg(feature = "json")]
+use crate::error::StreamError;
+use bytes::Bytes;
+use futures_sink::Sink;
+use std::pin::Pin;
+use std::sync::atomic::Ordering;
+use std::task::{ready, Context, Poll};
+
+/// Records appended through the sink are batched like
+/// [`append`](Producer::append). Backpressure comes from the batch buffer:
+/// `poll_ready` waits while a full batch is buffered and `max_in_flight`
+/// batches are already outstanding, so a fast upstream cannot grow the
+/// buffer without bound.
+///
+/// `poll_flush` waits like [`flush`](Producer::flush) and reports batch
+/// failures the same way; `poll_close` then closes the producer.
+///
+/// # Example
+///
+/// ```ignore
+/// use futures::{SinkExt, StreamExt};
+///
+/// let mut producer = stream.producer("ingest-1").build();
+/// let mut lines = lines.map(|line| Ok(Bytes::from(line)));
+/// producer.send_all(&mut lines).await?;
+/// producer.close().await?;
+/// ```
+impl Sink<Bytes> for Producer {
+    type Error = ProducerError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), ProducerError>> {
+        self.poll_room(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Bytes) -> Result<(), ProducerError> {
+        self.try_append(item)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), ProducerError>> {
+        self.poll_flushed(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), ProducerError>> {
+        self.poll_closed(cx)
+    }
+}
+
+impl Producer {
+    /// Queue one JSON item, reporting serialization and validation errors.
+    #[cfg(feature = "json")]
+    pub(crate) fn start_send_json<T: serde::Serialize>(
+        &self,
+        item: &T,
+    ) -> Result<(), ProducerError> {
+        let entry = Self::json_entry(item)
+            .map_err(|e| ProducerError::from(StreamError::Json(e.to_string())))?;
+        let entry = self.prepare(entry)?;
+        self.enqueue_all(std::iter::once(entry), false, None)
+    }
+
+    /// Flush, then close the producer even if the flush reports failures.
+    pub(crate) fn poll_closed(&self, cx: &mut Context<'_>) -> Poll<Result<(), ProducerError>> {
+        let flushed = ready!(self.poll_flushed(cx));
+        let mut state = self.state.lock();
+        state.closed = true;
+        stats::publish(
+            &self.stats_tx,
+            &state,
+            self.in_flight.load(Ordering::Acquire),
+        );
+        Poll::Ready(flushed)
+    }
+}
@@ -0,0 +1,102 @@
+//! Producer statistics.
+
+use super::ProducerState;
+use crate::error::ProducerError;
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Point-in-time view of a producer's progress.
+///
+/// Returned by [`Producer::stats`](super::Producer::stats) and published to
+/// [`Producer::watch_stats`](super::Producer::watch_stats) subscribers.
+/// Counters cover the producer's lifetime, across epochs.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct ProducerStats {
+    /// Records waiting in the pending batch.
+    pub records_buffered: usize,
+    /// Bytes waiting in the pending batch.
+    pub bytes_buffered: usize,
+    /// Batches sent and not yet acknowledged or failed.
+    pub batches_in_flight: usize,
+    /// Batches acknowledged by the server, including duplicates.
+    pub batches_sent: u64,
+    /// Records in acknowledged batches.
+    pub records_sent: u64,
+    /// Bytes in acknowledged batches.
+    pub bytes_sent: u64,
+    /// Batches the server reported as already written (`204`).
+    pub duplicates: u64,
+    /// Batch resends after rate limiting, sequence conflicts, or an epoch
+    /// auto-claim.
+    pub retries: u64,
+    /// Batches that failed for good.
+    pub batches_failed: u64,
+    /// Records written to the [dead-letter stream](super::ProducerBuilder::dead_letter).
+    pub records_dead_lettered: u64,
+    /// Batches currently allowed in flight: `max_in_flight`, or less while
+    /// the server is throttling the producer or the
+    /// [adaptive window](super::ProducerBuilder::adaptive_in_flight) is
+    /// smaller.
+    pub in_flight_limit: usize,
+    /// Whether `in_flight_limit` is still below where it was when the server
+    /// began throttling the producer.
+    pub throttled: bool,
+    /// Responses that asked the producer to slow down (`429`, or a
+    /// `Retry-After` header).
+    pub throttle_events: u64,
+    /// Smoothed batch round-trip time, tracked in adaptive mode only.
+    pub batch_latency: Option<Duration>,
+    /// Current producer epoch.
+    pub epoch: u64,
+    /// Sequence number of the next batch.
+    pub next_seq: u64,
+    /// Most recent batch or state store error.
+    pub last_error: Option<ProducerError>,
+    /// Whether the producer has been closed.
+    pub closed: bool,
+}
+
+/// Lifetime counters kept in the producer state.
+#[derive(Default)]
+pub(super) struct Counters {
+    pub(super) batches_sent: u64,
+    pub(super) records_sent: u64,
+    pub(super) bytes_sent: u64,
+    pub(super) duplicates: u64,
+    pub(super) retries: u64,
+    pub(super) batches_failed: u64,
+    pub(super) records_dead_lettered: u64,
+    pub(super) last_error: Option<ProducerError>,
+}
+
+pub(super) fn snapshot(state: &ProducerState, in_flight: usize) -> ProducerStats {
+    let counters = &state.counters;
+    ProducerStats {
+        records_buffered: state.pending_batch.len(),
+        bytes_buffered: state.batch_bytes,
+        batches_in_flight: in_flight,
+        batches_sent: counters.batches_sent,
+        records_sent: counters.records_sent,
+        bytes_sent: counters.bytes_sent,
+        duplicates: counters.duplicates,
+        retries: counters.retries,
+        batches_failed: counters.batches_failed,
+        records_dead_lettered: counters.records_dead_lettered,
+        in_flight_limit: state.throttle.limit(),
+        throttled: state.throttle.is_throttled(),
+        throttle_events: state.throttle.events,
+        batch_latency: state.throttle.latency(),
+        epoch: state.epoch,
+        next_seq: state.next_seq,
+        last_error: counters.last_error.clone(),
+        closed: state.closed,
+    }
+}
+
+/// Push a fresh snapshot to `watch_stats` subscribers, if there are any.
+pub(super) fn publish(tx: &watch::Sender<ProducerStats>, state: &ProducerState, in_flight: usize) {
+    if tx.receiver_count() > 0 {
+        tx.send_replace(snapshot(state, in_flight));
+    }
+}
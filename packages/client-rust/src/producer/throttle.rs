@@ -0,0 +1,136 @@
+//! Sizing the producer's pipeline: backing off when the server throttles
+//! it and, in adaptive mode, probing for the depth the network supports.
+
+use crate::rt::Instant;
+use std::time::Duration;
+
+/// Smoothed batch latency above this multiple of the lowest seen, plus
+/// [`LATENCY_SLACK`], means requests are queueing, so the window shrinks.
+const QUEUEING_FACTOR: u32 = 2;
+
+/// Absolute allowance so jitter on sub-millisecond links is not read as
+/// queueing.
+const LATENCY_SLACK: Duration = Duration::from_millis(5);
+
+/// Congestion window over in-flight batches (AIMD).
+///
+/// Each throttle signal halves the window, at most once per round: a
+/// signal for a batch sent before the last decrease reflects load that
+/// decrease already answered. Each acknowledged batch grows the window by
+/// `1 / window`, so it regains one batch per window's worth of
+/// acknowledgements, up to `max_in_flight`.
+///
+/// In adaptive mode the window starts at one batch and doubles each round
+/// (slow start) until the first decrease. Failed batches then count as
+/// throttle signals too, and latency growth shrinks the window by a
+/// quarter.
+pub(super) struct Throttle {
+    max: usize,
+    window: f64,
+    /// When the window was last decreased.
+    decreased_at: Option<Instant>,
+    /// Window before the current throttling began; cleared once regained.
+    recover_to: Option<f64>,
+    adaptive: Option<Latency>,
+    /// Throttle signals received.
+    pub(super) events: u64,
+}
+
+/// Latency tracking for adaptive mode.
+#[derive(Default)]
+struct Latency {
+    min: Option<Duration>,
+    smoothed: Option<Duration>,
+    slow_start: bool,
+}
+
+impl Throttle {
+    pub(super) fn new(max: usize, adaptive: bool) -> Self {
+        // `build()` does not validate, and a zero window would never send
+        let max = max.max(1);
+        Self {
+            max,
+            window: if adaptive { 1.0 } else { max as f64 },
+            decreased_at: None,
+            recover_to: None,
+            adaptive: adaptive.then(|| Latency {
+                slow_start: true,
+                ..Latency::default()
+            }),
+            events: 0,
+        }
+    }
+
+    /// Batches that may be in flight now.
+    pub(super) fn limit(&self) -> usize {
+        (self.window as usize).clamp(1, self.max)
+    }
+
+    /// Whether the window is still below where it was when the server
+    /// began throttling.
+    pub(super) fn is_throttled(&self) -> bool {
+        self.recover_to.is_some()
+    }
+
+    /// Smoothed latency of acknowledged batches, in adaptive mode.
+    pub(super) fn latency(&self) -> Option<Duration> {
+        self.adaptive.as_ref()?.smoothed
+    }
+
+    /// The server throttled a request sent at `sent_at`.
+    pub(super) fn on_throttled(&mut self, sent_at: Instant) {
+        self.events += 1;
+        let before = self.window;
+        if self.decrease(sent_at, 0.5) {
+            self.recover_to.get_or_insert(before);
+        }
+    }
+
+    /// A request sent at `sent_at` failed. Only adaptive mode reacts.
+    pub(super) fn on_error(&mut self, sent_at: Instant) {
+        if self.adaptive.is_some() {
+            self.decrease(sent_at, 0.5);
+        }
+    }
+
+    /// A batch sent at `sent_at` was acknowledged.
+    pub(super) fn on_success(&mut self, sent_at: Instant) {
+        let increase = match &mut self.adaptive {
+            None => 1.0 / self.window,
+            Some(latency) => {
+                let rtt = sent_at.elapsed();
+                let min = latency.min.map_or(rtt, |min| min.min(rtt));
+                latency.min = Some(min);
+                let smoothed = latency.smoothed.map_or(rtt, |s| (s * 7 + rtt) / 8);
+                latency.smoothed = Some(smoothed);
+
+                if smoothed > min * QUEUEING_FACTOR + LATENCY_SLACK {
+                    self.decrease(sent_at, 0.75);
+                    return;
+                }
+                if latency.slow_start {
+                    1.0
+                } else {
+                    1.0 / self.window
+                }
+            }
+        };
+        self.window = (self.window + increase).min(self.max as f64);
+        if self.recover_to.is_some_and(|to| self.window >= to) {
+            self.recover_to = None;
+        }
+    }
+
+    /// Scale the window down unless it already was for this round.
+    fn decrease(&mut self, sent_at: Instant, factor: f64) -> bool {
+        if self.decreased_at.is_some_and(|at| sent_at < at) {
+            return false;
+        }
+        self.window = (self.window * factor).max(1.0);
+        self.decreased_at = Some(Instant::now());
+        if let Some(latency) = &mut self.adaptive {
+            latency.slow_start = false;
+        }
+        true
+    }
+}
@@ -0,0 +1,97 @@
+//! Atomic multi-record appends.
+
+use super::{AppendReceipt, PendingEntry, Producer};
+use crate::error::ProducerError;
+use crate::types::Offset;
+use bytes::Bytes;
+use tokio::sync::oneshot;
+
+/// A group of records that land in the stream together or not at all.
+///
+/// Created by [`Producer::transaction`]. Records are buffered locally until
+/// [`commit`](Self::commit), which sends them as a single batch with one
+/// producer sequence number; the server applies a batch atomically, and
+/// retries are deduplicated like any other batch. Records appended directly
+/// to the producer just before the commit may share the batch.
+///
+/// Dropping a `Txn` without committing discards its records, same as
+/// [`abort`](Self::abort).
+#[must_use = "transactions do nothing unless you call .commit()"]
+pub struct Txn {
+    producer: Producer,
+    entries: Vec<PendingEntry>,
+    /// First record rejected by the producer's validator.
+    rejected: Option<ProducerError>,
+}
+
+impl Txn {
+    pub(super) fn new(producer: Producer) -> Self {
+        Self {
+            producer,
+            entries: Vec::new(),
+            rejected: None,
+        }
+    }
+
+    /// Buffer raw bytes.
+    ///
+    /// If the producer's validator rejects the record, the whole
+    /// transaction fails at [`commit`](Self::commit).
+    pub fn append(&mut self, data: impl Into<Bytes>) {
+        match self.producer.prepare(Producer::raw_entry(data.into())) {
+            Ok(entry) => self.entries.push(entry),
+            Err(e) => {
+                self.rejected.get_or_insert(e);
+            }
+        }
+    }
+
+    /// Buffer a JSON value.
+    ///
+    /// Unlike [`Producer::append_json`], serialization and validation errors
+    /// are returned, since silently dropping a record would break atomicity.
+    #[cfg(feature = "json")]
+    pub fn append_json<T: serde::Serialize>(&mut self, data: &T) -> Result<(), ProducerError> {
+        let entry = Producer::json_entry(data)
+            .map_err(|e| crate::error::StreamError::Json(e.to_string()))?;
+        self.entries.push(self.producer.prepare(entry)?);
+        Ok(())
+    }
+
+    /// Number of buffered records.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether no records have been buffered.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Send all buffered records in one batch and wait for the server to
+    /// acknowledge it.
+    ///
+    /// Committing an empty transaction is a no-op that returns a receipt
+    /// with `next_offset` of `Offset::Beginning`.
+    pub async fn commit(self) -> Result<AppendReceipt, ProducerError> {
+        if let Some(e) = self.rejected {
+            return Err(e);
+        }
+        if self.entries.is_empty() {
+            return Ok(AppendReceipt {
+                next_offset: Offset::Beginning,
+                duplicate: false,
+            });
+        }
+
+        let (tx, rx) = oneshot::channel();
+        let callback = Box::new(move |result| {
+            let _ = tx.send(result);
+        });
+        self.producer.enqueue_all(self.entries, true, Some(callback))?;
+        rx.await.unwrap_or(Err(ProducerError::Closed))
+    }
+
+    /// Discard all buffered records.
+    pub fn abort(self) {}
+}
@@ -0,0 +1,85 @@
+//! Client-side request rate limiting.
+
+use crate::middleware::Operation;
+use crate::rt::{self, Instant};
+use parking_lot::Mutex;
+use std::time::Duration;
+
+/// Token buckets for reads and writes; a missing bucket is unlimited.
+#[derive(Debug, Default)]
+pub(crate) struct RateLimiter {
+    pub(crate) read: Option<TokenBucket>,
+    pub(crate) write: Option<TokenBucket>,
+}
+
+impl RateLimiter {
+    pub(crate) fn is_unlimited(&self) -> bool {
+        self.read.is_none() && self.write.is_none()
+    }
+
+    /// Wait until a request for `operation` may be sent.
+    pub(crate) async fn acquire(&self, operation: Operation) {
+        let bucket = match operation {
+            Operation::Read | Operation::Head => &self.read,
+            _ => &self.write,
+        };
+        if let Some(bucket) = bucket {
+            let wait = bucket.reserve();
+            if !wait.is_zero() {
+                rt::sleep(wait).await;
+            }
+        }
+    }
+}
+
+/// A token bucket that refills at `rate` per second up to `burst`.
+#[derive(Debug)]
+pub(crate) struct TokenBucket {
+    rate: f64,
+    burst: f64,
+    state: Mutex<BucketState>,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    /// Available tokens; negative while requests are queued for future ones.
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    pub(crate) fn new(rate: f64, burst: u32) -> Self {
+        let burst = f64::from(burst.max(1));
+        Self {
+            rate,
+            burst,
+            state: Mutex::new(BucketState {
+                tokens: burst,
+                refilled_at: Instant::now(),
+            }),
+        }
+    }
+
+    /// Take a token, returning how long to wait before it is valid.
+    ///
+    /// Tokens are reserved immediately, so concurrent callers queue in the
+    /// order they arrived.
+    fn reserve(&self) -> Duration {
+        self.reserve_n(1.0)
+    }
+
+    /// Take `n` tokens at once, e.g. one per byte; see [`reserve`](Self::reserve).
+    pub(crate) fn reserve_n(&self, n: f64) -> Duration {
+        let mut state = self.state.lock();
+        let now = Instant::now();
+        let elapsed = now.duration_since(state.refilled_at).as_secs_f64();
+        state.tokens = (state.tokens + elapsed * self.rate).min(self.burst);
+        state.refilled_at = now;
+        state.tokens -= n;
+        if state.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-state.tokens / self.rate)
+        }
+    }
+}
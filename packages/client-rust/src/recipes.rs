@@ -0,0 +1,24 @@
+//! Ready-made compositions of the public API for common jobs.
+//!
+//! Each recipe is built only from items exported at the crate root, so it
+//! doubles as a check that the public surface covers real-world usage. Copy
+//! one as a starting point when your needs diverge.
+//!
+//! - [`tail_to_file`] - mirror a stream into a local file, resuming after
+//!   restarts
+//! - [`PgMaterializer`] - maintain a Postgres table from a JSON stream with
+//!   exactly-once updates
+//! - [`fan_in`] - merge several JSON streams into one
+//! - [`FileSource`] - ship lines appended to a local file into a stream
+//!
+//! Enable with the `recipes` feature.
+
+mod aggregator;
+mod materializer;
+mod source;
+mod tailer;
+
+pub use aggregator::fan_in;
+pub use materializer::PgMaterializer;
+pub use source::FileSource;
+pub use tailer::tail_to_file;
@@ -0,0 +1,89 @@
+//! Fan-in aggregator.
+
+use crate::{DurableStream, LiveMode, OffsetStore, Producer, StreamError};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinSet;
+
+/// Merge the items of several JSON `sources` into `target` until `shutdown`
+/// resolves or a source fails.
+///
+/// Items are written through one idempotent producer named `producer_id`.
+/// Each source's progress is checkpointed in `store` (keyed by source URL)
+/// only after its items have been flushed to the target, so delivery is
+/// at-least-once across restarts. Items from one source keep their order;
+/// there is no ordering between sources.
+///
+/// # Example
+///
+/// ```ignore
+/// let store = Arc::new(FileOffsetStore::new("fan-in.json"));
+/// fan_in(&[orders, refunds], &ledger, "ledger-fan-in", store, shutdown).await?;
+/// ```
+pub async fn fan_in<S>(
+    sources: &[DurableStream],
+    target: &DurableStream,
+    producer_id: &str,
+    store: Arc<dyn OffsetStore>,
+    shutdown: S,
+) -> Result<(), StreamError>
+where
+    S: Future<Output = ()>,
+{
+    let producer = target
+        .producer(producer_id)
+        .auto_claim(true)
+        .content_type("application/json")
+        .build();
+
+    let mut tasks = JoinSet::new();
+    for source in sources {
+        tasks.spawn(forward(source.clone(), producer.clone(), store.clone()));
+    }
+
+    let result = tokio::select! {
+        _ = shutdown => Ok(()),
+        Some(joined) = tasks.join_next() => match joined {
+            Ok(result) => result,
+            Err(e) => Err(StreamError::ParseError(format!("fan-in task failed: {}", e))),
+        },
+    };
+
+    tasks.shutdown().await;
+    producer
+        .close()
+        .await
+        .map_err(|e| StreamError::ParseError(e.to_string()))?;
+    result
+}
+
+/// Copy one source into the shared producer.
+async fn forward(
+    source: DurableStream,
+    producer: Producer,
+    store: Arc<dyn OffsetStore>,
+) -> Result<(), StreamError> {
+    let mut reader = source
+        .read()
+        .live(LiveMode::LongPoll)
+        .checkpoint(store, Duration::ZERO)
+        .typed::<serde_json::Value>()
+        .build()?;
+
+    // The reader commits each chunk's offset when the next one is requested,
+    // so flushing first guarantees checkpoints never run ahead of the target
+    while let Some(chunk) = reader.next_chunk().await? {
+        if chunk.items.is_empty() {
+            continue;
+        }
+        for item in &chunk.items {
+            producer.append_json(item);
+        }
+        producer
+            .flush()
+            .await
+            .map_err(|e| StreamError::ParseError(e.to_string()))?;
+    }
+    Ok(())
+}
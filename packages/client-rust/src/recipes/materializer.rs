@@ -0,0 +1,184 @@
+//! Postgres materializer.
+
+use crate::{DurableStream, LiveMode, Offset, StoreError, StreamError};
+use std::future::Future;
+use std::time::Duration;
+use tokio_postgres::Client;
+
+/// Maintains a Postgres table mirroring the latest value per key of a JSON
+/// stream.
+///
+/// Each stream item is a JSON object; the string or number at `key_field`
+/// selects the row, and the whole object becomes its `value`. An item with
+/// `"deleted": true` removes the row instead. Rows and the
+/// stream offset are written in one transaction per chunk, so the table is
+/// updated exactly once per item even across crashes.
+///
+/// Tables used (created on first run):
+///
+/// ```text
+/// <table>(key TEXT PRIMARY KEY, value JSONB NOT NULL)
+/// ds_materializer_offsets(name TEXT PRIMARY KEY, next_offset TEXT NOT NULL)
+/// ```
+///
+/// # Example
+///
+/// ```ignore
+/// let (pg, conn) = tokio_postgres::connect("host=localhost user=app", NoTls).await?;
+/// tokio::spawn(conn);
+///
+/// PgMaterializer::new(pg, "users", "id")
+///     .run(&stream, tokio::signal::ctrl_c().map(|_| ()))
+///     .await?;
+/// ```
+pub struct PgMaterializer {
+    client: Client,
+    table: String,
+    key_field: String,
+}
+
+impl PgMaterializer {
+    /// Materialize into `table`, keyed by the `key_field` of each item.
+    pub fn new(client: Client, table: impl Into<String>, key_field: impl Into<String>) -> Self {
+        Self {
+            client,
+            table: table.into(),
+            key_field: key_field.into(),
+        }
+    }
+
+    /// Follow `stream` until `shutdown` resolves, returning the last
+    /// committed offset.
+    ///
+    /// If the stored offset has expired from the stream (410 Gone), the table
+    /// is truncated and rebuilt from the beginning.
+    pub async fn run<S>(
+        mut self,
+        stream: &DurableStream,
+        shutdown: S,
+    ) -> Result<Offset, StreamError>
+    where
+        S: Future<Output = ()>,
+    {
+        self.ensure_schema().await?;
+        let mut offset = self.load_offset().await?.unwrap_or(Offset::Beginning);
+        tokio::pin!(shutdown);
+
+        loop {
+            let mut reader = stream
+                .read()
+                .offset(offset.clone())
+                .live(LiveMode::LongPoll)
+                .typed::<serde_json::Value>()
+                .build()?;
+
+            loop {
+                let next = tokio::select! {
+                    _ = &mut shutdown => return Ok(offset),
+                    next = reader.next_chunk() => next,
+                };
+                match next {
+                    Ok(Some(chunk)) => {
+                        if chunk.next_offset != offset {
+                            self.apply(&chunk.items, &chunk.next_offset).await?;
+                            offset = chunk.next_offset;
+                        }
+                    }
+                    Ok(None) => return Ok(offset),
+                    Err(StreamError::OffsetGone { .. }) => {
+                        self.truncate().await?;
+                        offset = Offset::Beginning;
+                        break;
+                    }
+                    Err(e) if e.is_retryable() => {
+                        tokio::time::sleep(Duration::from_secs(1)).await;
+                        break;
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+    }
+
+    async fn ensure_schema(&self) -> Result<(), StreamError> {
+        let sql = format!(
+            "CREATE TABLE IF NOT EXISTS {} (key TEXT PRIMARY KEY, value JSONB NOT NULL);
+             CREATE TABLE IF NOT EXISTS ds_materializer_offsets (
+                 name TEXT PRIMARY KEY,
+                 next_offset TEXT NOT NULL
+             );",
+            quote_ident(&self.table)
+        );
+        self.client.batch_execute(&sql).await.map_err(pg_error)
+    }
+
+    async fn load_offset(&self) -> Result<Option<Offset>, StreamError> {
+        let row = self
+            .client
+            .query_opt(
+                "SELECT next_offset FROM ds_materializer_offsets WHERE name = $1",
+                &[&self.table],
+            )
+            .await
+            .map_err(pg_error)?;
+        Ok(row.map(|r| Offset::parse(r.get::<_, &str>(0))))
+    }
+
+    async fn apply(
+        &mut self,
+        items: &[serde_json::Value],
+        next: &Offset,
+    ) -> Result<(), StreamError> {
+        let table = quote_ident(&self.table);
+        let upsert = format!(
+            "INSERT INTO {table} (key, value) VALUES ($1, $2)
+             ON CONFLICT (key) DO UPDATE SET value = EXCLUDED.value"
+        );
+        let delete = format!("DELETE FROM {table} WHERE key = $1");
+
+        let tx = self.client.transaction().await.map_err(pg_error)?;
+        for item in items {
+            let key = match item.get(&self.key_field) {
+                Some(serde_json::Value::String(s)) => s.clone(),
+                Some(serde_json::Value::Number(n)) => n.to_string(),
+                _ => {
+                    return Err(StreamError::ParseError(format!(
+                        "item has no string or number field {:?}",
+                        self.key_field
+                    )))
+                }
+            };
+            let deleted = item.get("deleted") == Some(&serde_json::Value::Bool(true));
+            if deleted {
+                tx.execute(&delete, &[&key]).await.map_err(pg_error)?;
+            } else {
+                tx.execute(&upsert, &[&key, item]).await.map_err(pg_error)?;
+            }
+        }
+        tx.execute(
+            "INSERT INTO ds_materializer_offsets (name, next_offset) VALUES ($1, $2)
+             ON CONFLICT (name) DO UPDATE SET next_offset = EXCLUDED.next_offset",
+            &[&self.table, &next.to_query_value()],
+        )
+        .await
+        .map_err(pg_error)?;
+        tx.commit().await.map_err(pg_error)
+    }
+
+    async fn truncate(&mut self) -> Result<(), StreamError> {
+        let sql = format!(
+            "TRUNCATE {}; DELETE FROM ds_materializer_offsets WHERE name = '{}'",
+            quote_ident(&self.table),
+            self.table.replace('\'', "''")
+        );
+        self.client.batch_execute(&sql).await.map_err(pg_error)
+    }
+}
+
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn pg_error(e: tokio_postgres::Error) -> StreamError {
+    StoreError::Backend(e.to_string()).into()
+}
@@ -0,0 +1,256 @@
+//! Local file shipper.
+
+use crate::{HandlerError, Producer};
+use std::future::Future;
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+/// Bytes read from the file per read call.
+const READ_SIZE: usize = 64 * 1024;
+
+/// Ships the lines appended to a local file (a log, CSV drops) into a
+/// stream through an idempotent [`Producer`].
+///
+/// Each complete line, including its `\n`, is appended as one record, so
+/// the stream's bytes mirror the file's; a trailing line without a newline
+/// waits until it is finished. The position shipped up to is persisted to
+/// `<path>.position` after each flushed burst, so a restarted source picks
+/// up where the previous one stopped. Delivery is at-least-once: a crash
+/// between a flush and persisting the position repeats those lines.
+///
+/// Rotation is followed: when the file is truncated, or replaced by a new
+/// file (checked by inode on Unix), the rest of the old file is shipped,
+/// even an unfinished last line, and reading restarts at the beginning of
+/// the new one.
+///
+/// # Example
+///
+/// ```ignore
+/// let producer = stream.producer("web-1-access-log").content_type("text/plain").build();
+/// FileSource::new("/var/log/nginx/access.log", producer)
+///     .poll_interval(Duration::from_millis(250))
+///     .run(tokio::signal::ctrl_c().map(|_| ()))
+///     .await?;
+/// ```
+#[must_use = "a FileSource does nothing unless you call .run()"]
+pub struct FileSource {
+    path: PathBuf,
+    position_path: PathBuf,
+    producer: Producer,
+    poll_interval: Duration,
+}
+
+/// The file being read and how far it has been shipped.
+struct Cursor {
+    file: tokio::fs::File,
+    id: u64,
+    /// Position after the last complete line read.
+    position: u64,
+    /// Bytes of an unfinished line.
+    partial: Vec<u8>,
+}
+
+impl FileSource {
+    /// Ship lines appended to the file at `path` through `producer`.
+    pub fn new(path: impl AsRef<Path>, producer: Producer) -> Self {
+        let path = path.as_ref().to_path_buf();
+        let mut position_path = path.as_os_str().to_owned();
+        position_path.push(".position");
+        Self {
+            path,
+            position_path: PathBuf::from(position_path),
+            producer,
+            poll_interval: Duration::from_millis(500),
+        }
+    }
+
+    /// File to persist the shipped position in (default `<path>.position`).
+    pub fn position_file(mut self, path: impl AsRef<Path>) -> Self {
+        self.position_path = path.as_ref().to_path_buf();
+        self
+    }
+
+    /// How often to check the file for new data once it has been read to
+    /// the end (default 500 ms).
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Ship lines until `shutdown` resolves or an error stops the source.
+    /// Returns the position shipped up to in the current file.
+    ///
+    /// Lines appended before shutdown are flushed before returning. The
+    /// producer is not closed, so it can be reused.
+    pub async fn run<S>(self, shutdown: S) -> Result<u64, HandlerError>
+    where
+        S: Future<Output = ()>,
+    {
+        tokio::pin!(shutdown);
+        let mut cursor = loop {
+            match self.open().await? {
+                Some(cursor) => break cursor,
+                // The file does not exist yet
+                None => tokio::select! {
+                    _ = &mut shutdown => return Ok(0),
+                    _ = tokio::time::sleep(self.poll_interval) => {}
+                },
+            }
+        };
+
+        loop {
+            let shipped = self.ship_available(&mut cursor).await?;
+            if shipped {
+                self.producer.flush().await?;
+                self.save_position(&cursor).await?;
+                continue;
+            }
+
+            // At the end of the file: follow rotation, or wait for more
+            if self.rotated(&cursor).await? {
+                if let Some(next) = self.open_from_start().await? {
+                    // The old file will not grow any more: ship its last line
+                    if !cursor.partial.is_empty() {
+                        self.producer.append(std::mem::take(&mut cursor.partial));
+                        self.producer.flush().await?;
+                    }
+                    cursor = next;
+                    self.save_position(&cursor).await?;
+                    continue;
+                }
+            }
+            tokio::select! {
+                _ = &mut shutdown => break,
+                _ = tokio::time::sleep(self.poll_interval) => {}
+            }
+        }
+
+        self.producer.flush().await?;
+        Ok(cursor.position)
+    }
+
+    /// Open the file at the persisted position, or `None` if it does not
+    /// exist.
+    async fn open(&self) -> Result<Option<Cursor>, HandlerError> {
+        let Some(mut cursor) = self.open_from_start().await? else {
+            return Ok(None);
+        };
+        let len = cursor.file.metadata().await?.len();
+        if let Some((id, position)) = self.load_position().await? {
+            // A different or shorter file was rotated in since
+            if id == cursor.id && position <= len {
+                cursor.file.seek(SeekFrom::Start(position)).await?;
+                cursor.position = position;
+            }
+        }
+        Ok(Some(cursor))
+    }
+
+    async fn open_from_start(&self) -> Result<Option<Cursor>, HandlerError> {
+        let file = match tokio::fs::File::open(&self.path).await {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let id = file_id(&file.metadata().await?);
+        Ok(Some(Cursor {
+            file,
+            id,
+            position: 0,
+            partial: Vec::new(),
+        }))
+    }
+
+    /// Read until at least one complete line is appended, or to the end of
+    /// the file. Returns whether any line was appended.
+    async fn ship_available(&self, cursor: &mut Cursor) -> Result<bool, HandlerError> {
+        let mut shipped = false;
+        let mut buf = vec![0; READ_SIZE];
+        loop {
+            let n = cursor.file.read(&mut buf).await?;
+            if n == 0 {
+                return Ok(shipped);
+            }
+            let mut data = &buf[..n];
+            while let Some(end) = data.iter().position(|&b| b == b'\n') {
+                let (line, rest) = data.split_at(end + 1);
+                let record = if cursor.partial.is_empty() {
+                    line.to_vec()
+                } else {
+                    let mut record = std::mem::take(&mut cursor.partial);
+                    record.extend_from_slice(line);
+                    record
+                };
+                cursor.position += record.len() as u64;
+                self.producer.append(record);
+                shipped = true;
+                data = rest;
+            }
+            cursor.partial.extend_from_slice(data);
+            if shipped {
+                return Ok(true);
+            }
+        }
+    }
+
+    /// Whether the file at the path is no longer the one being read, or has
+    /// been truncated below the read position.
+    async fn rotated(&self, cursor: &Cursor) -> Result<bool, HandlerError> {
+        match tokio::fs::metadata(&self.path).await {
+            Ok(meta) => Ok(file_id(&meta) != cursor.id
+                || meta.len() < cursor.position + cursor.partial.len() as u64),
+            // Moved away and not recreated yet
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn load_position(&self) -> Result<Option<(u64, u64)>, HandlerError> {
+        let text = match tokio::fs::read_to_string(&self.position_path).await {
+            Ok(text) => text,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let mut fields = text.split_whitespace().map(str::parse::<u64>);
+        match (fields.next(), fields.next()) {
+            (Some(Ok(id)), Some(Ok(position))) => Ok(Some((id, position))),
+            _ => Err(format!("malformed position file {}", self.position_path.display()).into()),
+        }
+    }
+
+    /// Persist the position atomically, by writing a temporary file and
+    /// renaming it over the old one.
+    async fn save_position(&self, cursor: &Cursor) -> Result<(), HandlerError> {
+        let mut tmp = self.position_path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        tokio::fs::write(&tmp, format!("{} {}\n", cursor.id, cursor.position)).await?;
+        tokio::fs::rename(&tmp, &self.position_path).await?;
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for FileSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FileSource")
+            .field("path", &self.path)
+            .field("position_path", &self.position_path)
+            .field("poll_interval", &self.poll_interval)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Identity of a file that survives renames: its inode on Unix. Elsewhere
+/// rotation is only detected by truncation.
+fn file_id(meta: &std::fs::Metadata) -> u64 {
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::MetadataExt::ino(meta)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = meta;
+        0
+    }
+}
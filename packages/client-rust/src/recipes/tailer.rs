@@ -0,0 +1,85 @@
+//! Resumable file tailer.
+
+use crate::{
+    Chunk, DurableStream, ErrorAction, FileOffsetStore, HandlerError, LiveMode, Offset,
+    StreamError, StreamHandler,
+};
+use async_trait::async_trait;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+
+/// Append every byte of `stream` to the file at `path`, tailing live until
+/// `shutdown` resolves.
+///
+/// Progress is checkpointed to `<path>.offset`, so a restarted tailer picks
+/// up where the previous one stopped. Delivery is at-least-once: a crash
+/// between writing a chunk and checkpointing it repeats that chunk.
+///
+/// # Example
+///
+/// ```ignore
+/// let last = tail_to_file(&stream, "events.log", tokio::signal::ctrl_c().map(|_| ())).await?;
+/// ```
+pub async fn tail_to_file<S>(
+    stream: &DurableStream,
+    path: impl AsRef<Path>,
+    shutdown: S,
+) -> Result<Offset, StreamError>
+where
+    S: Future<Output = ()>,
+{
+    let path = path.as_ref().to_path_buf();
+    let mut offsets = path.as_os_str().to_owned();
+    offsets.push(".offset");
+    let store = Arc::new(FileOffsetStore::new(PathBuf::from(offsets)));
+
+    let mut handler = FileAppender { path, file: None };
+    stream
+        .read()
+        .live(LiveMode::Sse)
+        .checkpoint(store, Duration::ZERO)
+        .run_handler(&mut handler, shutdown)
+        .await
+}
+
+struct FileAppender {
+    path: PathBuf,
+    file: Option<tokio::fs::File>,
+}
+
+#[async_trait]
+impl StreamHandler for FileAppender {
+    async fn on_item(&mut self, chunk: &Chunk) -> Result<(), HandlerError> {
+        let file = match &mut self.file {
+            Some(file) => file,
+            None => self.file.insert(
+                tokio::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&self.path)
+                    .await?,
+            ),
+        };
+        file.write_all(&chunk.data).await?;
+        // Data must be durable before the checkpoint moves past it
+        file.sync_data().await?;
+        Ok(())
+    }
+
+    async fn on_error(&mut self, err: &StreamError) -> ErrorAction {
+        if err.is_retryable() {
+            ErrorAction::Continue
+        } else {
+            ErrorAction::Stop
+        }
+    }
+
+    async fn on_reset(&mut self, _offset: &Offset) {
+        // The stream is replayed from the start - rebuild the file with it
+        self.file = None;
+        let _ = tokio::fs::remove_file(&self.path).await;
+    }
+}
@@ -0,0 +1,156 @@
+//! Records with a key, headers and a timestamp around the payload.
+
+use crate::error::ProducerError;
+use crate::iterator::ReadBuilder;
+use crate::producer::{AppendReceipt, Producer};
+use crate::stream::DurableStream;
+use crate::typed::TypedReadBuilder;
+use crate::typed_stream::TypedStream;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A payload with record metadata, stored as one JSON value:
+///
+/// ```json
+/// {"key": "order-17", "headers": {"traceparent": "00-..."}, "timestamp": 1718000000000, "payload": {...}}
+/// ```
+///
+/// `timestamp` is in milliseconds since the Unix epoch. `key`, `headers`
+/// and `timestamp` are left out when unset, and may be missing when read,
+/// so records written by other clients decode as long as they carry a
+/// `payload`.
+///
+/// # Example
+/// ```ignore
+/// let orders = client.stream("/v1/stream/orders").records::<Order>();
+/// orders
+///     .append(&Record::new(order).key(order.id.to_string()).header("traceparent", &trace))
+///     .await?;
+///
+/// let mut reader = orders.read().build()?;
+/// while let Some(chunk) = reader.next_chunk().await? {
+///     for record in chunk.items {
+///         handle(record.key.as_deref(), record.payload);
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Record<T> {
+    /// Identifies the entity the record is about, e.g. for compaction or
+    /// partitioning.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    /// Application metadata such as trace context or a schema version.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    /// When the record was created.
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "millis")]
+    pub timestamp: Option<SystemTime>,
+    /// The record's value.
+    pub payload: T,
+}
+
+impl<T> Record<T> {
+    /// A record holding `payload`, timestamped now.
+    pub fn new(payload: T) -> Self {
+        Self {
+            key: None,
+            headers: BTreeMap::new(),
+            timestamp: Some(SystemTime::now()),
+            payload,
+        }
+    }
+
+    /// Set the key.
+    #[must_use]
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    /// Add a header, replacing any with the same name.
+    #[must_use]
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.insert(name.into(), value.into());
+        self
+    }
+
+    /// Set the timestamp; `None` leaves it out.
+    #[must_use]
+    pub fn timestamp(mut self, timestamp: impl Into<Option<SystemTime>>) -> Self {
+        self.timestamp = timestamp.into();
+        self
+    }
+
+    /// Replace the payload, keeping the metadata.
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Record<U> {
+        Record {
+            key: self.key,
+            headers: self.headers,
+            timestamp: self.timestamp,
+            payload: f(self.payload),
+        }
+    }
+}
+
+/// `Option<SystemTime>` as milliseconds since the Unix epoch.
+pub(crate) mod millis {
+    use super::*;
+    use serde::{Deserializer, Serializer};
+
+    pub(crate) fn serialize<S: Serializer>(
+        timestamp: &Option<SystemTime>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let millis = timestamp
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_millis() as u64);
+        serializer.serialize_u64(millis)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<SystemTime>, D::Error> {
+        let millis = Option::<u64>::deserialize(deserializer)?;
+        Ok(millis.map(|ms| UNIX_EPOCH + Duration::from_millis(ms)))
+    }
+}
+
+impl DurableStream {
+    /// View this stream as a JSON stream of [`Record`]s wrapping `T`.
+    ///
+    /// Shorthand for `typed::<Record<T>>()`.
+    pub fn records<T: Serialize + DeserializeOwned>(&self) -> TypedStream<Record<T>> {
+        self.typed()
+    }
+}
+
+impl ReadBuilder {
+    /// Decode chunks of a JSON stream into [`Record`]s wrapping `T`.
+    ///
+    /// Shorthand for `typed::<Record<T>>()`.
+    pub fn records<T: DeserializeOwned>(self) -> TypedReadBuilder<Record<T>> {
+        self.typed()
+    }
+}
+
+impl Producer {
+    /// Append a [`Record`] (fire-and-forget); see
+    /// [`append_json`](Self::append_json).
+    pub fn append_record<T: Serialize>(&self, record: &Record<T>) {
+        self.append_json(record);
+    }
+
+    /// Append a [`Record`] with a delivery callback; see
+    /// [`append_json_with_callback`](Self::append_json_with_callback).
+    pub fn append_record_with_callback<T, F>(&self, record: &Record<T>, callback: F)
+    where
+        T: Serialize,
+        F: FnOnce(Result<AppendReceipt, ProducerError>) + Send + 'static,
+    {
+        self.append_json_with_callback(record, callback);
+    }
+}
@@ -0,0 +1,39 @@
+//! Keeping credentials out of debug output.
+//!
+//! Values of redacted headers are flagged sensitive, which makes
+//! `HeaderMap`'s `Debug` print them as `Sensitive` and keeps them out of
+//! HTTP/2 header compression tables.
+
+use reqwest::header::{HeaderMap, HeaderName};
+
+/// Headers redacted unless configured otherwise.
+pub(crate) const DEFAULT_REDACTED: [&str; 5] = [
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+    "x-api-key",
+];
+
+pub(crate) fn default_names() -> Vec<HeaderName> {
+    DEFAULT_REDACTED
+        .iter()
+        .map(|name| HeaderName::from_static(name))
+        .collect()
+}
+
+/// Flag the values of `names` in `headers` as sensitive.
+pub(crate) fn mark(headers: &mut HeaderMap, names: &[HeaderName]) {
+    for (name, value) in headers.iter_mut() {
+        if names.contains(name) {
+            value.set_sensitive(true);
+        }
+    }
+}
+
+/// Flag every value in `headers` as sensitive.
+pub(crate) fn mark_all(headers: &mut HeaderMap) {
+    for value in headers.values_mut() {
+        value.set_sensitive(true);
+    }
+}
@@ -0,0 +1,162 @@
+//! Following redirects under a configurable policy.
+
+use crate::client::Client;
+use reqwest::header::{HeaderMap, AUTHORIZATION, COOKIE, LOCATION, PROXY_AUTHORIZATION};
+use reqwest::{Method, Request, Response, StatusCode, Url};
+
+/// How the client follows redirects; see
+/// [`ClientBuilder::redirect_policy`](crate::ClientBuilder::redirect_policy).
+///
+/// `307` and `308` are followed for every request. `301`, `302` and `303`
+/// are only followed for reads: following them would turn an append into
+/// a `GET`, so writes report them as [`StreamError::Redirect`](crate::StreamError::Redirect)
+/// instead. A redirect that is not followed is reported the same way.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct RedirectPolicy {
+    /// Redirects followed per request before giving up (default 10).
+    pub max_redirects: usize,
+    /// Whether to follow redirects to another scheme, host or port
+    /// (default `true`).
+    pub cross_origin: bool,
+    /// When to drop credentials from the redirected request.
+    pub strip_auth: StripAuth,
+}
+
+/// When a redirected request loses its credentials: `Authorization`,
+/// `Proxy-Authorization`, `Cookie`, headers from the client's
+/// [`AuthProvider`](crate::AuthProvider) and any other
+/// [redacted header](crate::ClientBuilder::redact_header).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum StripAuth {
+    /// Keep them on every redirect. Only for redirects within a trusted
+    /// deployment.
+    Never,
+    /// Drop them when the redirect leaves the origin, e.g. for a signed
+    /// URL on a region-local endpoint.
+    #[default]
+    CrossOrigin,
+    /// Drop them on every redirect.
+    Always,
+}
+
+impl Default for RedirectPolicy {
+    fn default() -> Self {
+        Self {
+            max_redirects: 10,
+            cross_origin: true,
+            strip_auth: StripAuth::CrossOrigin,
+        }
+    }
+}
+
+impl RedirectPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Follow no redirects.
+    pub fn none() -> Self {
+        Self::default().max_redirects(0)
+    }
+
+    pub fn max_redirects(mut self, max: usize) -> Self {
+        self.max_redirects = max;
+        self
+    }
+
+    /// Allow or refuse redirects to another origin.
+    pub fn cross_origin(mut self, allow: bool) -> Self {
+        self.cross_origin = allow;
+        self
+    }
+
+    pub fn strip_auth(mut self, when: StripAuth) -> Self {
+        self.strip_auth = when;
+        self
+    }
+
+    /// The request to send after `status`, or `None` if the response
+    /// should be returned as is. `next` is a copy of the request that got it.
+    fn redirect(
+        &self,
+        status: StatusCode,
+        headers: &HeaderMap,
+        mut next: Request,
+        hops: usize,
+    ) -> Option<Request> {
+        let follow = match status.as_u16() {
+            307 | 308 => true,
+            // These turn the request into a GET, which only reads survive
+            301..=303 => matches!(*next.method(), Method::GET | Method::HEAD),
+            _ => false,
+        };
+        if !follow || hops >= self.max_redirects {
+            return None;
+        }
+        let location = headers.get(LOCATION)?.to_str().ok()?;
+        let target = next.url().join(location).ok()?;
+
+        let same_origin = origin(next.url()) == origin(&target);
+        if !same_origin && !self.cross_origin {
+            return None;
+        }
+        let strip = match self.strip_auth {
+            StripAuth::Never => false,
+            StripAuth::CrossOrigin => !same_origin,
+            StripAuth::Always => true,
+        };
+        if strip {
+            let headers = next.headers_mut();
+            for name in [AUTHORIZATION, PROXY_AUTHORIZATION, COOKIE] {
+                headers.remove(name);
+            }
+            // Auth provider and redacted headers are marked sensitive
+            let sensitive: Vec<_> = headers
+                .iter()
+                .filter(|(_, value)| value.is_sensitive())
+                .map(|(name, _)| name.clone())
+                .collect();
+            for name in sensitive {
+                headers.remove(name);
+            }
+        }
+        *next.url_mut() = target;
+        Some(next)
+    }
+}
+
+fn origin(url: &Url) -> (&str, Option<&str>, Option<u16>) {
+    (url.scheme(), url.host_str(), url.port_or_known_default())
+}
+
+impl Client {
+    /// Send `request` through the transport, following redirects as the
+    /// client's policy allows.
+    pub(crate) async fn send_following(
+        &self,
+        request: Request,
+    ) -> Result<Response, reqwest::Error> {
+        let Some(policy) = &self.redirects else {
+            return self.transport.execute(request).await;
+        };
+
+        let mut request = request;
+        let mut hops = 0;
+        loop {
+            let next = request.try_clone();
+            let resp = self.transport.execute(request).await?;
+            let Some(next) = next else {
+                return Ok(resp);
+            };
+            match policy.redirect(resp.status(), resp.headers(), next, hops) {
+                Some(next) => {
+                    request = next;
+                    hops += 1;
+                }
+                None => return Ok(resp),
+            }
+        }
+    }
+}
@@ -0,0 +1,191 @@
+//! Stream-to-stream replication.
+
+use crate::error::StreamError;
+use crate::handler::HandlerError;
+use crate::iterator::Chunk;
+use crate::rate_limit::TokenBucket;
+use crate::rt;
+use crate::sink::{Sink, SinkBatch, SinkRunner};
+use crate::store::OffsetStore;
+use crate::stream::{AppendOptions, CreateOptions, DurableStream};
+use crate::types::{LiveMode, Offset};
+use async_trait::async_trait;
+use bytes::Bytes;
+use std::future::Future;
+use std::sync::Arc;
+
+/// Rewrites or drops a chunk before it is written to the target.
+type Transform = Arc<dyn Fn(&Chunk) -> Result<Option<Bytes>, HandlerError> + Send + Sync>;
+
+/// Mirrors one stream into another, e.g. into a different region or
+/// account for disaster recovery.
+///
+/// The target is created with the source's content type if it does not
+/// exist. Progress is checkpointed to an [`OffsetStore`] under
+/// [`checkpoint_key`](Self::checkpoint_key) after every batch.
+///
+/// Each source chunk is appended with the source offset it ends at as
+/// `Stream-Seq`, so the target must only be written by this replicator.
+/// After a crash between an append and its checkpoint, the target rejects
+/// a re-read chunk it already holds in full, so nothing is written twice
+/// when the source returns the same chunk boundaries (the usual case).
+/// If boundaries shift, the overlap is written again: replication is
+/// at-least-once and never skips data.
+///
+/// # Example
+///
+/// ```ignore
+/// let source = us_client.stream("https://us.example.com/streams/orders");
+/// let target = eu_client.stream("https://eu.example.com/streams/orders");
+///
+/// Replicator::new(source, target, Arc::new(FileOffsetStore::new("replica.json")))
+///     .max_bytes_per_sec(10 * 1024 * 1024)
+///     .transform(|chunk| Ok(Some(redact(&chunk.data))))
+///     .run(tokio::signal::ctrl_c().map(|_| ()))
+///     .await?;
+/// ```
+#[must_use = "a Replicator does nothing unless you call .run()"]
+pub struct Replicator {
+    source: DurableStream,
+    target: DurableStream,
+    store: Arc<dyn OffsetStore>,
+    checkpoint_key: String,
+    live: LiveMode,
+    transform: Option<Transform>,
+    bandwidth: Option<TokenBucket>,
+}
+
+impl Replicator {
+    /// Replicate `source` into `target`, checkpointing to `store`.
+    pub fn new(source: DurableStream, target: DurableStream, store: Arc<dyn OffsetStore>) -> Self {
+        let checkpoint_key = format!("replicate:{} -> {}", source.url(), target.url());
+        Self {
+            source,
+            target,
+            store,
+            checkpoint_key,
+            live: LiveMode::Sse,
+            transform: None,
+            bandwidth: None,
+        }
+    }
+
+    /// Key to store the checkpoint under (default
+    /// `replicate:<source url> -> <target url>`).
+    pub fn checkpoint_key(mut self, key: impl Into<String>) -> Self {
+        self.checkpoint_key = key.into();
+        self
+    }
+
+    /// How to tail the source once caught up (default [`LiveMode::Sse`]).
+    /// With [`LiveMode::Off`], [`run`](Self::run) returns once the target
+    /// has caught up.
+    pub fn live(mut self, mode: LiveMode) -> Self {
+        self.live = mode;
+        self
+    }
+
+    /// Transform each chunk before it is written; return `Ok(None)` to skip
+    /// it. For JSON streams the returned bytes must be a JSON value or
+    /// array. An error stops the replicator.
+    pub fn transform<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Chunk) -> Result<Option<Bytes>, HandlerError> + Send + Sync + 'static,
+    {
+        self.transform = Some(Arc::new(f));
+        self
+    }
+
+    /// Write at most this many bytes per second to the target, averaged
+    /// over one second.
+    pub fn max_bytes_per_sec(mut self, bytes: u64) -> Self {
+        let burst = u32::try_from(bytes).unwrap_or(u32::MAX);
+        self.bandwidth = Some(TokenBucket::new(bytes as f64, burst));
+        self
+    }
+
+    /// Replicate until `shutdown` resolves (or the source is caught up,
+    /// without live mode). Returns the source offset replicated up to.
+    pub async fn run<S>(self, shutdown: S) -> Result<Offset, StreamError>
+    where
+        S: Future<Output = ()>,
+    {
+        let read = self.source.read().live(self.live);
+        let sink = ReplicaSink {
+            source: self.source,
+            target: self.target,
+            store: self.store,
+            checkpoint_key: self.checkpoint_key,
+            transform: self.transform,
+            bandwidth: self.bandwidth,
+        };
+        SinkRunner::new(read, sink).run(shutdown).await
+    }
+}
+
+struct ReplicaSink {
+    source: DurableStream,
+    target: DurableStream,
+    store: Arc<dyn OffsetStore>,
+    checkpoint_key: String,
+    transform: Option<Transform>,
+    bandwidth: Option<TokenBucket>,
+}
+
+#[async_trait]
+impl Sink for ReplicaSink {
+    async fn load_checkpoint(&mut self) -> Result<Option<Offset>, HandlerError> {
+        let content_type = self
+            .source
+            .head()
+            .await?
+            .content_type
+            .unwrap_or_else(|| "application/octet-stream".to_string());
+        match self.target.head().await {
+            Ok(_) => {}
+            Err(StreamError::NotFound { .. }) => {
+                self.target
+                    .create_with(CreateOptions::new().content_type(content_type.clone()))
+                    .await?;
+            }
+            Err(e) => return Err(e.into()),
+        }
+        self.target.content_type = Some(content_type);
+
+        Ok(self.store.load(&self.checkpoint_key).await?)
+    }
+
+    async fn apply(&mut self, batch: &SinkBatch) -> Result<(), HandlerError> {
+        for chunk in &batch.chunks {
+            let data = match &self.transform {
+                Some(transform) => match transform(chunk)? {
+                    Some(data) => data,
+                    None => continue,
+                },
+                None => chunk.data.clone(),
+            };
+            if data.is_empty() {
+                continue;
+            }
+            if let Some(bucket) = &self.bandwidth {
+                let wait = bucket.reserve_n(data.len() as f64);
+                if !wait.is_zero() {
+                    rt::sleep(wait).await;
+                }
+            }
+
+            let options = AppendOptions::new().seq(chunk.next_offset.as_str());
+            match self.target.append_with(data, options).await {
+                // The target already has everything up to this chunk's end
+                // from an earlier run
+                Ok(_) | Err(StreamError::SeqConflict) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(())
+    }
+
+    async fn store_checkpoint(&mut self, offset: &Offset) -> Result<(), HandlerError> {
+        Ok(self.store.commit(&self.checkpoint_key, offset).await?)
+    }
+}
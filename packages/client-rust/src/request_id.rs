@@ -0,0 +1,27 @@
+//! Request IDs for correlating client and server logs.
+//!
+//! Each request is sent with an `x-request-id` header. Servers that log or
+//! echo the header let a failed call be traced from a client-side error to
+//! the server's logs.
+
+use reqwest::header::{HeaderMap, HeaderValue};
+
+pub(crate) const HEADER_REQUEST_ID: &str = "x-request-id";
+
+/// Stamp `request` with a fresh ID unless it already has one.
+pub(crate) fn stamp(request: &mut reqwest::Request, prefix: &str) {
+    if request.headers().contains_key(HEADER_REQUEST_ID) {
+        return;
+    }
+    if let Ok(id) = HeaderValue::from_str(&format!("{}{:016x}", prefix, fastrand::u64(..))) {
+        request.headers_mut().insert(HEADER_REQUEST_ID, id);
+    }
+}
+
+/// Request ID carried by response `headers`.
+pub(crate) fn from_headers(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(HEADER_REQUEST_ID)
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string())
+}
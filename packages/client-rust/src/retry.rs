@@ -0,0 +1,266 @@
+//! Retry scheduling shared by all request paths.
+
+use crate::client::Client;
+use crate::error::StreamError;
+use crate::middleware::Operation;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use std::time::{Duration, UNIX_EPOCH};
+
+/// Retry/backoff configuration.
+///
+/// Applies to reads, metadata operations, producer batches, and the
+/// transient-error retries of [`DurableStream::append`](crate::DurableStream::append).
+/// A server-provided `Retry-After` always takes precedence over the computed
+/// backoff.
+#[derive(Clone, Debug)]
+pub struct RetryConfig {
+    /// Delay before the first retry.
+    pub initial_backoff: Duration,
+    /// Upper bound on any computed backoff.
+    pub max_backoff: Duration,
+    /// Growth factor between consecutive retries.
+    pub multiplier: f64,
+    /// Maximum number of retries after the initial attempt.
+    pub max_retries: u32,
+    /// Randomization applied to each computed backoff.
+    pub jitter: JitterMode,
+}
+
+/// Jitter mode for retry backoff.
+///
+/// Randomizing delays keeps many clients that failed together from retrying
+/// in lockstep.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum JitterMode {
+    /// Use the exact backoff delay.
+    None,
+    /// Random delay between zero and the backoff.
+    #[default]
+    Full,
+    /// Half the backoff plus a random share of the other half.
+    Equal,
+    /// Random delay between a third of the backoff and three times it,
+    /// capped at `max_backoff`.
+    Decorrelated,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(60),
+            multiplier: 2.0,
+            max_retries: 3,
+            jitter: JitterMode::Full,
+        }
+    }
+}
+
+impl RetryConfig {
+    /// Create a configuration with default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Jitter-free backoff before retry number `attempt` (1-based).
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exp = self.multiplier.powi(attempt.saturating_sub(1).min(64) as i32);
+        let secs = self.initial_backoff.as_secs_f64() * exp;
+        if !secs.is_finite() || secs >= self.max_backoff.as_secs_f64() {
+            return self.max_backoff;
+        }
+        Duration::from_secs_f64(secs)
+    }
+
+    /// The deterministic (jitter-free) delays before each of the first `n`
+    /// retries.
+    ///
+    /// Stops early at [`max_retries`](Self::max_retries), since no further
+    /// retries would be attempted.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use durable_streams::RetryConfig;
+    /// use std::time::Duration;
+    ///
+    /// let schedule = RetryConfig::default().preview(5);
+    /// assert_eq!(
+    ///     schedule,
+    ///     [100, 200, 400].map(Duration::from_millis).to_vec()
+    /// );
+    /// ```
+    pub fn preview(&self, n: u32) -> Vec<Duration> {
+        (1..=n.min(self.max_retries))
+            .map(|attempt| self.backoff(attempt))
+            .collect()
+    }
+
+    /// Apply this configuration's jitter to `delay`.
+    pub(crate) fn apply_jitter(&self, delay: Duration) -> Duration {
+        let secs = delay.as_secs_f64();
+        let jittered = match self.jitter {
+            JitterMode::None => return delay,
+            JitterMode::Full => fastrand::f64() * secs,
+            JitterMode::Equal => secs / 2.0 + fastrand::f64() * secs / 2.0,
+            JitterMode::Decorrelated => {
+                let low = secs / 3.0;
+                let high = secs * 3.0;
+                (low + fastrand::f64() * (high - low)).min(self.max_backoff.as_secs_f64())
+            }
+        };
+        Duration::from_secs_f64(jittered)
+    }
+}
+
+/// Why a request is being retried.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RetryCause {
+    /// The server answered 429, optionally with a `Retry-After` delay.
+    RateLimited {
+        /// Delay requested by the server.
+        retry_after: Option<Duration>,
+    },
+    /// The server answered with a retryable status code.
+    Status(u16),
+    /// The request timed out.
+    Timeout,
+    /// The connection failed.
+    Network(String),
+    /// Any other error.
+    Other(String),
+}
+
+impl RetryCause {
+    fn from_error(err: &StreamError) -> Self {
+        match err {
+            StreamError::RateLimited { retry_after, .. } => RetryCause::RateLimited {
+                retry_after: *retry_after,
+            },
+            StreamError::Timeout => RetryCause::Timeout,
+            StreamError::Network(e) => RetryCause::Network(e.to_string()),
+            other => match other.status_code() {
+                Some(status) => RetryCause::Status(status),
+                None => RetryCause::Other(other.to_string()),
+            },
+        }
+    }
+}
+
+/// A retry about to happen, passed to the
+/// [`on_retry`](crate::ClientBuilder::on_retry) hook.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct RetryEvent {
+    /// URL of the request being retried.
+    pub url: String,
+    /// Retry number (1 for the first retry).
+    pub attempt: u32,
+    /// Scheduled delay before jitter (the server's `Retry-After`, when sent).
+    pub delay: Duration,
+    /// Delay actually slept.
+    pub jittered_delay: Duration,
+    /// What triggered the retry.
+    pub cause: RetryCause,
+}
+
+/// Upper bound on any single server-requested delay.
+///
+/// Protects against misconfigured servers asking us to sleep for hours.
+pub(crate) const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Parse a `Retry-After` header value.
+///
+/// Supports both forms from RFC 9110: delay-seconds (`"120"`) and an
+/// HTTP-date (`"Wed, 21 Oct 2015 07:28:00 GMT"`). Dates in the past yield a
+/// zero delay.
+pub(crate) fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = httpdate::parse_http_date(value).ok()?;
+    // Compare via the Unix epoch: `SystemTime::now()` is unavailable on wasm
+    let at = at.duration_since(UNIX_EPOCH).ok()?;
+    Some(at.saturating_sub(crate::rt::unix_now()))
+}
+
+/// Extract the `Retry-After` delay from response headers, if present.
+pub(crate) fn retry_after_from_headers(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(parse_retry_after)
+}
+
+/// Callback invoked before every retry.
+pub(crate) type RetryHook = std::sync::Arc<dyn Fn(&RetryEvent) + Send + Sync>;
+
+/// Sleep before retry number `attempt` (1-based) of a request to `url`.
+///
+/// A server-provided `Retry-After` on `cause` takes precedence over the
+/// client's backoff schedule and is not jittered. The client's retry hook is
+/// invoked before sleeping.
+pub(crate) async fn sleep_before_retry(client: &Client, url: &str, attempt: u32, cause: &StreamError) {
+    let (delay, jittered_delay) = match cause.retry_after() {
+        Some(after) => {
+            let after = after.min(MAX_RETRY_AFTER);
+            (after, after)
+        }
+        None => {
+            let delay = client.retry.backoff(attempt);
+            (delay, client.retry.apply_jitter(delay))
+        }
+    };
+    notify(client, url, attempt, delay, jittered_delay, cause);
+    crate::rt::sleep(jittered_delay).await;
+}
+
+/// Report a retry with a fixed delay to the client's retry hook.
+pub(crate) fn notify(
+    client: &Client,
+    url: &str,
+    attempt: u32,
+    delay: Duration,
+    jittered_delay: Duration,
+    cause: &StreamError,
+) {
+    client.metrics.retry();
+    if let Some(hook) = &client.on_retry {
+        hook(&RetryEvent {
+            url: url.to_string(),
+            attempt,
+            delay,
+            jittered_delay,
+            cause: RetryCause::from_error(cause),
+        });
+    }
+}
+
+/// Send a request, transparently retrying on 429 Too Many Requests.
+///
+/// `build` is called once per attempt to produce a fresh request. Between
+/// attempts we sleep for the server's `Retry-After` (or the client's backoff
+/// when absent). After [`RetryConfig::max_retries`] the final 429 response is
+/// returned to the caller, which maps it to [`StreamError::RateLimited`].
+pub(crate) async fn send_with_rate_limit_retry<F>(
+    client: &Client,
+    operation: Operation,
+    url: &str,
+    build: F,
+) -> Result<reqwest::Response, reqwest::Error>
+where
+    F: Fn() -> reqwest::RequestBuilder,
+{
+    let mut attempt = 0;
+    loop {
+        let resp = client.send(operation, build()).await?;
+        if resp.status().as_u16() != 429 || attempt >= client.retry.max_retries {
+            return Ok(resp);
+        }
+        attempt += 1;
+        let err = StreamError::from_response(&resp, url);
+        sleep_before_retry(client, url, attempt, &err).await;
+    }
+}
@@ -0,0 +1,99 @@
+//! Runtime shims so the crate builds for native targets and
+//! `wasm32-unknown-unknown`.
+//!
+//! Native builds run on tokio. On wasm there is no tokio runtime: timers go
+//! through the host's `setTimeout`, tasks run on the JS event loop, and
+//! response bodies are read through the fetch streaming API.
+
+use bytes::Bytes;
+use std::future::Future;
+use std::time::Duration;
+
+/// Tokio's clock, so elapsed-time checks follow `tokio::time::pause()` in
+/// tests just as `sleep` does.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use tokio::time::Instant;
+#[cfg(target_arch = "wasm32")]
+pub(crate) use web_time::Instant;
+
+/// Sleep for `duration`.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await;
+}
+
+/// Sleep for `duration`.
+#[cfg(target_arch = "wasm32")]
+pub(crate) async fn sleep(duration: Duration) {
+    use wasm_bindgen::{JsCast, JsValue};
+
+    let ms = duration.as_millis().min(i32::MAX as u128) as i32;
+    let promise = js_sys::Promise::new(&mut |resolve, _reject| {
+        // `setTimeout` exists on both `window` and worker globals
+        let set_timeout = js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("setTimeout"))
+            .expect("setTimeout is not available")
+            .unchecked_into::<js_sys::Function>();
+        let _ = set_timeout.call2(&JsValue::NULL, &resolve, &JsValue::from(ms));
+    });
+    let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+}
+
+/// Run `future` in the background.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn spawn<F>(future: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    tokio::spawn(future);
+}
+
+/// Run `future` in the background.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn spawn<F>(future: F)
+where
+    F: Future<Output = ()> + 'static,
+{
+    wasm_bindgen_futures::spawn_local(future);
+}
+
+/// Current time as a duration since the Unix epoch.
+pub(crate) fn unix_now() -> Duration {
+    #[cfg(not(target_arch = "wasm32"))]
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH);
+    #[cfg(target_arch = "wasm32")]
+    let now = web_time::SystemTime::now().duration_since(web_time::UNIX_EPOCH);
+    now.unwrap_or(Duration::ZERO)
+}
+
+/// Incremental reader over a response body.
+pub(crate) struct BodyReader {
+    #[cfg(not(target_arch = "wasm32"))]
+    response: reqwest::Response,
+    #[cfg(target_arch = "wasm32")]
+    stream: std::pin::Pin<Box<dyn futures_core::Stream<Item = reqwest::Result<Bytes>>>>,
+}
+
+impl BodyReader {
+    pub(crate) fn new(response: reqwest::Response) -> Self {
+        Self {
+            #[cfg(not(target_arch = "wasm32"))]
+            response,
+            #[cfg(target_arch = "wasm32")]
+            stream: Box::pin(response.bytes_stream()),
+        }
+    }
+
+    /// Read the next piece of the body, or `None` at the end.
+    pub(crate) async fn chunk(&mut self) -> reqwest::Result<Option<Bytes>> {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.response.chunk().await
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            std::future::poll_fn(|cx| self.stream.as_mut().poll_next(cx))
+                .await
+                .transpose()
+        }
+    }
+}
@@ -0,0 +1,39 @@
+//! Client-side payload validation.
+
+use crate::error::ValidationError;
+
+/// Checks records against a schema before they are sent.
+///
+/// Plug a validator into
+/// [`ProducerBuilder::validator`](crate::ProducerBuilder::validator) or
+/// [`AppendOptions::validator`](crate::AppendOptions::validator) to reject
+/// malformed records locally, instead of writing them to the stream where
+/// every consumer has to deal with them. Closures taking the payload and
+/// content type implement this trait.
+///
+/// # Example
+///
+/// ```ignore
+/// let schema = jsonschema::validator_for(&schema_json)?;
+/// let validator: Arc<dyn SchemaValidator> = Arc::new(move |payload: &[u8], _ct: &str| {
+///     let value: serde_json::Value =
+///         serde_json::from_slice(payload).map_err(|e| ValidationError::new(e.to_string()))?;
+///     schema
+///         .validate(&value)
+///         .map_err(|e| ValidationError::new(e.to_string()))
+/// });
+/// ```
+pub trait SchemaValidator: Send + Sync {
+    /// Validate one record. `payload` is the encoded record (a single JSON
+    /// value for JSON appends) and `content_type` the stream's content type.
+    fn validate(&self, payload: &[u8], content_type: &str) -> Result<(), ValidationError>;
+}
+
+impl<F> SchemaValidator for F
+where
+    F: Fn(&[u8], &str) -> Result<(), ValidationError> + Send + Sync,
+{
+    fn validate(&self, payload: &[u8], content_type: &str) -> Result<(), ValidationError> {
+        self(payload, content_type)
+    }
+}
@@ -0,0 +1,68 @@
+//! `tower::Service` adapter for the client.
+
+use crate::client::Client;
+use crate::error::StreamError;
+use crate::middleware::Operation;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tower_service::Service;
+
+/// A request for [`Client`]'s `tower::Service` implementation.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct StreamRequest {
+    /// Kind of operation, used for rate limiting, metrics and middleware.
+    pub operation: Operation,
+    /// The HTTP request to send.
+    pub request: reqwest::Request,
+}
+
+impl StreamRequest {
+    /// A request to send as `operation`.
+    pub fn new(operation: Operation, request: reqwest::Request) -> Self {
+        Self { operation, request }
+    }
+}
+
+/// Sends requests the way the client sends its own: with default and
+/// dynamic headers (unless the request sets them), auth headers, middleware, rate limits, endpoint failover and metrics. Retries on
+/// failure are left to the surrounding stack, so standard tower layers
+/// (timeouts, concurrency limits, load shedding, retries) compose with it.
+///
+/// Always ready; the client's rate limits wait inside the returned future.
+///
+/// # Example
+///
+/// ```ignore
+/// use tower::{Service, ServiceBuilder, ServiceExt};
+///
+/// let mut svc = ServiceBuilder::new()
+///     .concurrency_limit(32)
+///     .timeout(Duration::from_secs(5))
+///     .service(client.clone());
+///
+/// let url = "http://localhost:4437/v1/stream/events".parse()?;
+/// let request = reqwest::Request::new(reqwest::Method::HEAD, url);
+/// let resp = svc.ready().await?.call(StreamRequest::new(Operation::Head, request)).await?;
+/// ```
+impl Service<StreamRequest> for Client {
+    type Response = reqwest::Response;
+    type Error = StreamError;
+    type Future = Pin<Box<dyn Future<Output = Result<reqwest::Response, StreamError>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), StreamError>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: StreamRequest) -> Self::Future {
+        let client = self.clone();
+        let mut request = req.request;
+        for (key, value) in client.get_headers().iter() {
+            if !request.headers().contains_key(key) {
+                request.headers_mut().insert(key.clone(), value.clone());
+            }
+        }
+        Box::pin(async move { Ok(client.send_request(req.operation, request).await?) })
+    }
+}
@@ -0,0 +1,511 @@
+//! Signing appended payloads and verifying them on read.
+
+use crate::error::{ProducerError, SignatureError, StreamError};
+use crate::middleware::{Middleware, Operation, RequestParts};
+use crate::producer::Producer;
+use crate::stream::{AppendResponse, CreateOptions, DurableStream};
+use crate::typed::{TypedChunk, TypedIterator, TypedReadBuilder};
+use crate::types::{LiveMode, Offset};
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use reqwest::header::HeaderValue;
+use ring::hmac;
+use ring::signature::{Ed25519KeyPair, KeyPair, UnparsedPublicKey, ED25519};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+const JSON: &str = "application/json";
+
+/// Header carrying the signature of an append's body.
+pub const HEADER_STREAM_SIGNATURE: &str = "stream-signature";
+
+const HS256: &str = "HS256";
+const ED_DSA: &str = "EdDSA";
+
+/// Bytes a signature covers: algorithm and key ID are bound to the payload.
+fn signing_input(alg: &str, kid: &str, payload: &[u8]) -> Vec<u8> {
+    let mut input = Vec::with_capacity(alg.len() + kid.len() + payload.len() + 2);
+    input.extend_from_slice(alg.as_bytes());
+    input.push(b'.');
+    input.extend_from_slice(kid.as_bytes());
+    input.push(b'.');
+    input.extend_from_slice(payload);
+    input
+}
+
+enum SigningKey {
+    Hmac(hmac::Key),
+    Ed25519(Ed25519KeyPair),
+}
+
+/// Signs payloads with HMAC-SHA256 or Ed25519.
+///
+/// Use it as [`Middleware`] to send a `Stream-Signature` header with every
+/// append body, for a gateway or server to check, or through
+/// [`DurableStream::signed`] to store each item in a signed envelope that
+/// consumers check with a [`Verifier`].
+///
+/// # Example
+/// ```ignore
+/// let signer = Signer::ed25519_from_seed("ingest-2024", &seed)?;
+/// let orders = client.stream("/v1/stream/orders").signed::<Order>(&signer);
+/// orders.append(&order).await?;
+///
+/// // Consumers only need the public key
+/// let verifier = Verifier::new().ed25519("ingest-2024", &signer.public_key().unwrap());
+/// let mut reader = client.stream("/v1/stream/orders").verified::<Order>(&verifier).build()?;
+/// ```
+#[derive(Clone)]
+pub struct Signer {
+    kid: String,
+    key: Arc<SigningKey>,
+}
+
+impl Signer {
+    /// Sign with HMAC-SHA256 under shared secret `secret`, known as `kid`.
+    pub fn hmac_sha256(kid: impl Into<String>, secret: &[u8]) -> Self {
+        Self {
+            kid: kid.into(),
+            key: Arc::new(SigningKey::Hmac(hmac::Key::new(hmac::HMAC_SHA256, secret))),
+        }
+    }
+
+    /// Sign with the Ed25519 private key derived from `seed`, known as
+    /// `kid`.
+    pub fn ed25519_from_seed(
+        kid: impl Into<String>,
+        seed: &[u8; 32],
+    ) -> Result<Self, SignatureError> {
+        let pair = Ed25519KeyPair::from_seed_unchecked(seed)
+            .map_err(|_| SignatureError::new("invalid Ed25519 seed"))?;
+        Ok(Self {
+            kid: kid.into(),
+            key: Arc::new(SigningKey::Ed25519(pair)),
+        })
+    }
+
+    /// Sign with a PKCS#8 v2 (DER) Ed25519 private key, known as `kid`.
+    pub fn ed25519_from_pkcs8(kid: impl Into<String>, der: &[u8]) -> Result<Self, SignatureError> {
+        let pair = Ed25519KeyPair::from_pkcs8(der)
+            .map_err(|_| SignatureError::new("invalid Ed25519 PKCS#8 key"))?;
+        Ok(Self {
+            kid: kid.into(),
+            key: Arc::new(SigningKey::Ed25519(pair)),
+        })
+    }
+
+    /// The key's ID.
+    pub fn key_id(&self) -> &str {
+        &self.kid
+    }
+
+    /// The Ed25519 public key to hand to verifiers; `None` for HMAC.
+    pub fn public_key(&self) -> Option<Vec<u8>> {
+        match &*self.key {
+            SigningKey::Hmac(_) => None,
+            SigningKey::Ed25519(pair) => Some(pair.public_key().as_ref().to_vec()),
+        }
+    }
+
+    fn alg(&self) -> &'static str {
+        match &*self.key {
+            SigningKey::Hmac(_) => HS256,
+            SigningKey::Ed25519(_) => ED_DSA,
+        }
+    }
+
+    fn signature(&self, payload: &[u8]) -> String {
+        let input = signing_input(self.alg(), &self.kid, payload);
+        match &*self.key {
+            SigningKey::Hmac(key) => BASE64.encode(hmac::sign(key, &input)),
+            SigningKey::Ed25519(pair) => BASE64.encode(pair.sign(&input)),
+        }
+    }
+
+    /// Sign `payload`.
+    pub fn sign(&self, payload: impl Into<String>) -> SignedRecord {
+        let payload = payload.into();
+        SignedRecord {
+            alg: self.alg().to_string(),
+            kid: self.kid.clone(),
+            sig: self.signature(payload.as_bytes()),
+            payload,
+        }
+    }
+
+    /// Serialize `item` as JSON and sign it.
+    pub fn sign_json<T: Serialize>(&self, item: &T) -> Result<SignedRecord, StreamError> {
+        Ok(self.sign(serde_json::to_string(item)?))
+    }
+
+    /// `Stream-Signature` header value for request body `body`:
+    /// `alg=...; kid=...; sig=...`.
+    pub fn header_value(&self, body: &[u8]) -> String {
+        format!(
+            "alg={}; kid={}; sig={}",
+            self.alg(),
+            self.kid,
+            self.signature(body)
+        )
+    }
+}
+
+impl std::fmt::Debug for Signer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Signer")
+            .field("kid", &self.kid)
+            .field("alg", &self.alg())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Adds a `Stream-Signature` header to every append with an in-memory
+/// body. Streaming appends are sent unsigned.
+#[async_trait]
+impl Middleware for Signer {
+    async fn handle(&self, mut parts: RequestParts) -> RequestParts {
+        let append = matches!(
+            parts.operation,
+            Operation::Append
+                | Operation::ProducerAppend
+                | Operation::Close
+                | Operation::ProducerClose
+        );
+        if let (true, Some(body)) = (append, &parts.body) {
+            if let Ok(value) = HeaderValue::from_str(&self.header_value(body)) {
+                parts.headers.insert(HEADER_STREAM_SIGNATURE, value);
+            }
+        }
+        parts
+    }
+}
+
+#[derive(Clone)]
+enum VerifyingKey {
+    Hmac(hmac::Key),
+    Ed25519(Vec<u8>),
+}
+
+/// Checks signatures made by a [`Signer`], against keys looked up by ID.
+#[derive(Clone, Default)]
+pub struct Verifier {
+    keys: Arc<HashMap<String, VerifyingKey>>,
+}
+
+impl Verifier {
+    /// A verifier that knows no keys yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn with(mut self, kid: impl Into<String>, key: VerifyingKey) -> Self {
+        Arc::make_mut(&mut self.keys).insert(kid.into(), key);
+        self
+    }
+
+    /// Accept HMAC-SHA256 signatures under shared secret `secret`, known as
+    /// `kid`.
+    #[must_use]
+    pub fn hmac_sha256(self, kid: impl Into<String>, secret: &[u8]) -> Self {
+        self.with(
+            kid,
+            VerifyingKey::Hmac(hmac::Key::new(hmac::HMAC_SHA256, secret)),
+        )
+    }
+
+    /// Accept Ed25519 signatures by public key `public_key`, known as `kid`.
+    #[must_use]
+    pub fn ed25519(self, kid: impl Into<String>, public_key: &[u8]) -> Self {
+        self.with(kid, VerifyingKey::Ed25519(public_key.to_vec()))
+    }
+
+    fn check(&self, alg: &str, kid: &str, payload: &[u8], sig: &str) -> Result<(), SignatureError> {
+        let key = self
+            .keys
+            .get(kid)
+            .ok_or_else(|| SignatureError::new(format!("unknown key {kid}")))?;
+        let sig = BASE64
+            .decode(sig)
+            .map_err(|_| SignatureError::new("signature is not base64"))?;
+        let input = signing_input(alg, kid, payload);
+        let valid = match (key, alg) {
+            (VerifyingKey::Hmac(key), HS256) => hmac::verify(key, &input, &sig).is_ok(),
+            (VerifyingKey::Ed25519(public_key), ED_DSA) => {
+                UnparsedPublicKey::new(&ED25519, public_key)
+                    .verify(&input, &sig)
+                    .is_ok()
+            }
+            _ => {
+                return Err(SignatureError::new(format!(
+                    "key {kid} does not sign with {alg}"
+                )))
+            }
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(SignatureError::new(format!(
+                "bad signature under key {kid}"
+            )))
+        }
+    }
+
+    /// Check `record` and return its payload.
+    pub fn verify<'a>(&self, record: &'a SignedRecord) -> Result<&'a str, SignatureError> {
+        self.check(
+            &record.alg,
+            &record.kid,
+            record.payload.as_bytes(),
+            &record.sig,
+        )?;
+        Ok(&record.payload)
+    }
+
+    /// Check `record` and parse its payload as JSON.
+    pub fn verify_json<T: DeserializeOwned>(
+        &self,
+        record: &SignedRecord,
+    ) -> Result<T, StreamError> {
+        Ok(serde_json::from_str(self.verify(record)?)?)
+    }
+
+    /// Check a `Stream-Signature` header value against request body
+    /// `body`, e.g. in a gateway in front of the stream server.
+    pub fn verify_header(&self, header: &str, body: &[u8]) -> Result<(), SignatureError> {
+        let field = |name: &str| {
+            header
+                .split(';')
+                .filter_map(|part| part.trim().split_once('='))
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value)
+                .ok_or_else(|| SignatureError::new(format!("signature header has no {name}")))
+        };
+        self.check(field("alg")?, field("kid")?, body, field("sig")?)
+    }
+}
+
+impl std::fmt::Debug for Verifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut kids: Vec<&String> = self.keys.keys().collect();
+        kids.sort();
+        f.debug_struct("Verifier").field("keys", &kids).finish()
+    }
+}
+
+/// A signed record as stored in the stream.
+///
+/// Serialized as `{"alg": ..., "kid": ..., "sig": ..., "payload": ...}`
+/// where `payload` is the signed JSON text, kept as a string so its bytes
+/// survive storage exactly. Signing does not hide the payload.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignedRecord {
+    alg: String,
+    kid: String,
+    sig: String,
+    payload: String,
+}
+
+impl SignedRecord {
+    /// ID of the key the record was signed with.
+    pub fn key_id(&self) -> &str {
+        &self.kid
+    }
+
+    /// The payload, unverified.
+    pub fn payload(&self) -> &str {
+        &self.payload
+    }
+}
+
+impl DurableStream {
+    /// View this stream as a JSON stream of `T` signed by `signer`: every
+    /// item is stored as a [`SignedRecord`].
+    pub fn signed<T: Serialize + DeserializeOwned>(&self, signer: &Signer) -> SignedStream<T> {
+        let mut stream = self.clone();
+        stream.content_type = Some(JSON.to_string());
+        SignedStream {
+            stream,
+            signer: signer.clone(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Read this stream's [`SignedRecord`]s as `T`, checking each with
+    /// `verifier`.
+    pub fn verified<T: DeserializeOwned>(&self, verifier: &Verifier) -> SignedReadBuilder<T> {
+        SignedReadBuilder {
+            inner: self.read().typed::<SignedRecord>(),
+            verifier: verifier.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// A JSON stream whose items are `T`, signed on append.
+///
+/// Created by [`DurableStream::signed`].
+#[derive(Debug)]
+pub struct SignedStream<T> {
+    stream: DurableStream,
+    signer: Signer,
+    _marker: PhantomData<fn(T) -> T>,
+}
+
+impl<T> Clone for SignedStream<T> {
+    fn clone(&self) -> Self {
+        Self {
+            stream: self.stream.clone(),
+            signer: self.signer.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Serialize + DeserializeOwned> SignedStream<T> {
+    /// The underlying stream handle.
+    pub fn inner(&self) -> &DurableStream {
+        &self.stream
+    }
+
+    /// Create the stream as `application/json`.
+    pub async fn create(&self) -> Result<(), StreamError> {
+        self.stream
+            .create_with(CreateOptions::new().content_type(JSON))
+            .await
+    }
+
+    /// Sign and append one item.
+    pub async fn append(&self, item: &T) -> Result<AppendResponse, StreamError> {
+        let record = self.signer.sign_json(item)?;
+        self.stream.append(serde_json::to_vec(&record)?).await
+    }
+
+    /// Build a producer with default settings that signs `T`.
+    pub fn producer(&self, producer_id: impl Into<String>) -> SignedProducer<T> {
+        SignedProducer::new(
+            self.stream.producer(producer_id).content_type(JSON).build(),
+            &self.signer,
+        )
+    }
+}
+
+/// Builder for a [`SignedIterator`].
+#[must_use = "builders do nothing unless you call .build()"]
+pub struct SignedReadBuilder<T> {
+    inner: TypedReadBuilder<SignedRecord>,
+    verifier: Verifier,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> SignedReadBuilder<T> {
+    /// Offset to start reading from.
+    pub fn offset(mut self, offset: impl Into<Offset>) -> Self {
+        self.inner = self.inner.offset(offset);
+        self
+    }
+
+    /// Live tailing mode.
+    pub fn live(mut self, mode: LiveMode) -> Self {
+        self.inner = self.inner.live(mode);
+        self
+    }
+
+    /// Build the iterator.
+    pub fn build(self) -> Result<SignedIterator<T>, StreamError> {
+        Ok(SignedIterator {
+            inner: self.inner.build()?,
+            verifier: self.verifier,
+            _marker: PhantomData,
+        })
+    }
+}
+
+/// Reads a signed stream, returning only items whose signatures check out.
+pub struct SignedIterator<T> {
+    inner: TypedIterator<SignedRecord>,
+    verifier: Verifier,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> SignedIterator<T> {
+    /// Next chunk of verified items.
+    ///
+    /// Fails with [`StreamError::Signature`] if any item's signature does
+    /// not check out.
+    pub async fn next_chunk(&mut self) -> Result<Option<TypedChunk<T>>, StreamError> {
+        let Some(chunk) = self.inner.next_chunk().await? else {
+            return Ok(None);
+        };
+        let items = chunk
+            .items
+            .iter()
+            .map(|record| self.verifier.verify_json(record))
+            .collect::<Result<_, _>>()?;
+        Ok(Some(TypedChunk {
+            items,
+            next_offset: chunk.next_offset,
+            up_to_date: chunk.up_to_date,
+            cursor: chunk.cursor,
+        }))
+    }
+
+    /// Offset the next chunk will be read from.
+    pub fn offset(&self) -> &Offset {
+        self.inner.offset()
+    }
+}
+
+/// A [`Producer`] that signs `T` before appending it.
+pub struct SignedProducer<T> {
+    producer: Producer,
+    signer: Signer,
+    _marker: PhantomData<fn(T)>,
+}
+
+impl<T> Clone for SignedProducer<T> {
+    fn clone(&self) -> Self {
+        Self {
+            producer: self.producer.clone(),
+            signer: self.signer.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Serialize> SignedProducer<T> {
+    /// Wrap a producer; it should target a JSON stream.
+    pub fn new(producer: Producer, signer: &Signer) -> Self {
+        Self {
+            producer,
+            signer: signer.clone(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// The underlying producer.
+    pub fn inner(&self) -> &Producer {
+        &self.producer
+    }
+
+    /// Sign and queue one item (fire-and-forget); see
+    /// [`Producer::append_json`]. Fails only if `item` does not serialize.
+    pub fn append(&self, item: &T) -> Result<(), StreamError> {
+        let record = self.signer.sign_json(item)?;
+        self.producer.append_json(&record);
+        Ok(())
+    }
+
+    /// Wait for all queued items; see [`Producer::flush`].
+    pub async fn flush(&self) -> Result<(), ProducerError> {
+        self.producer.flush().await
+    }
+
+    /// Flush and close; see [`Producer::close`].
+    pub async fn close(&self) -> Result<(), ProducerError> {
+        self.producer.close().await
+    }
+}
@@ -0,0 +1,446 @@
+//! Exactly-once delivery of a stream into external storage.
+//!
+//! A [`Sink`] owns both the side effect (rows, files, objects) and the offset
+//! it has applied up to, so the two can be committed together. A
+//! [`SinkRunner`] tails the stream from the sink's checkpoint and feeds it
+//! batches.
+
+#[cfg(feature = "json")]
+use crate::dead_letter::{DeadLetter, DeadLetterQueue};
+use crate::error::StreamError;
+use crate::handler::HandlerError;
+use crate::iterator::{Chunk, ReadBuilder};
+use crate::retry::sleep_before_retry;
+use crate::rt::sleep;
+use crate::stream::DurableStream;
+use crate::types::Offset;
+use async_trait::async_trait;
+use std::future::Future;
+use std::time::Duration;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod file;
+#[cfg(all(feature = "sink-postgres", not(target_arch = "wasm32")))]
+mod postgres;
+#[cfg(all(feature = "sink-s3", not(target_arch = "wasm32")))]
+mod s3;
+#[cfg(all(feature = "sink-webhook", not(target_arch = "wasm32")))]
+mod webhook;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use file::{FileSink, SegmentEntry};
+#[cfg(all(feature = "sink-postgres", not(target_arch = "wasm32")))]
+pub use postgres::{PgSink, PgSinkMode};
+#[cfg(all(feature = "sink-s3", not(target_arch = "wasm32")))]
+pub use s3::{ArchivedObject, S3ArchiveReader, S3Bucket, S3Sink};
+#[cfg(all(feature = "sink-webhook", not(target_arch = "wasm32")))]
+pub use webhook::WebhookPusher;
+
+/// Default upper bound on the payload bytes gathered into one batch.
+const DEFAULT_MAX_BATCH_BYTES: usize = 1024 * 1024;
+
+/// Consecutive chunks handed to [`Sink::apply`] in one call.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct SinkBatch {
+    /// Checkpoint the batch follows.
+    pub start_offset: Offset,
+    /// Offset to resume from once the batch is applied.
+    pub next_offset: Offset,
+    /// Chunks in stream order. Never empty, and no chunk has empty data.
+    pub chunks: Vec<Chunk>,
+}
+
+impl SinkBatch {
+    /// Total payload size of the batch in bytes.
+    pub fn total_bytes(&self) -> usize {
+        self.chunks.iter().map(|c| c.data.len()).sum()
+    }
+}
+
+/// Destination that a [`SinkRunner`] writes a stream into.
+///
+/// For every batch the runner calls [`apply`](Self::apply) and then
+/// [`store_checkpoint`](Self::store_checkpoint) with the batch's
+/// `next_offset`. A sink gets exactly-once results by making the pair
+/// atomic: stage the side effects in `apply` (e.g. inside an open database
+/// transaction) and make them visible together with the offset in
+/// `store_checkpoint`. If the runner stops between the two calls, staged
+/// work must not become visible; the batch is delivered again from
+/// [`load_checkpoint`](Self::load_checkpoint) on the next run.
+///
+/// Sinks whose side effects are idempotent can apply directly and store the
+/// checkpoint afterwards.
+///
+/// # Example
+///
+/// ```ignore
+/// struct Rows { tx: Option<Transaction>, db: Db }
+///
+/// #[async_trait::async_trait]
+/// impl Sink for Rows {
+///     async fn load_checkpoint(&mut self) -> Result<Option<Offset>, HandlerError> {
+///         Ok(self.db.get_offset("orders").await?)
+///     }
+///
+///     async fn apply(&mut self, batch: &SinkBatch) -> Result<(), HandlerError> {
+///         let tx = self.tx.insert(self.db.begin().await?);
+///         for chunk in &batch.chunks {
+///             tx.insert_rows(&chunk.data).await?;
+///         }
+///         Ok(())
+///     }
+///
+///     async fn store_checkpoint(&mut self, offset: &Offset) -> Result<(), HandlerError> {
+///         let tx = self.tx.take().expect("apply opened a transaction");
+///         tx.set_offset("orders", offset).await?;
+///         tx.commit().await?;
+///         Ok(())
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait Sink: Send {
+    /// Offset after the last batch this sink committed, or `None` to start
+    /// from the beginning of the stream.
+    async fn load_checkpoint(&mut self) -> Result<Option<Offset>, HandlerError>;
+
+    /// Apply one batch of records.
+    async fn apply(&mut self, batch: &SinkBatch) -> Result<(), HandlerError>;
+
+    /// Commit everything applied so far, recording `offset` as the point to
+    /// resume from.
+    async fn store_checkpoint(&mut self, offset: &Offset) -> Result<(), HandlerError>;
+
+    /// Discard all state, because the checkpoint expired from the stream's
+    /// retention (410 Gone) and the stream is replayed from the beginning.
+    ///
+    /// The default refuses, which stops the runner.
+    async fn reset(&mut self) -> Result<(), HandlerError> {
+        Err("checkpoint expired from the stream and the sink does not support reset".into())
+    }
+
+    /// How much longer work accepted by `store_checkpoint` may stay
+    /// buffered before [`flush`](Self::flush) must write it, or `None` if
+    /// nothing is buffered. Asked whenever the runner starts waiting for
+    /// new data.
+    ///
+    /// Only sinks that buffer across batches (e.g. into larger files or
+    /// objects) need this; they report their durable position from
+    /// `load_checkpoint`, so buffered work lost in a crash is read again.
+    fn flush_after(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Write out buffered work. Called when `flush_after` elapses before
+    /// the next batch arrives, and before the runner returns successfully.
+    async fn flush(&mut self) -> Result<(), HandlerError> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<K: Sink + ?Sized> Sink for &mut K {
+    async fn load_checkpoint(&mut self) -> Result<Option<Offset>, HandlerError> {
+        (**self).load_checkpoint().await
+    }
+
+    async fn apply(&mut self, batch: &SinkBatch) -> Result<(), HandlerError> {
+        (**self).apply(batch).await
+    }
+
+    async fn store_checkpoint(&mut self, offset: &Offset) -> Result<(), HandlerError> {
+        (**self).store_checkpoint(offset).await
+    }
+
+    async fn reset(&mut self) -> Result<(), HandlerError> {
+        (**self).reset().await
+    }
+
+    fn flush_after(&self) -> Option<Duration> {
+        (**self).flush_after()
+    }
+
+    async fn flush(&mut self) -> Result<(), HandlerError> {
+        (**self).flush().await
+    }
+}
+
+/// Tails a stream into a [`Sink`].
+///
+/// The read starts at the sink's checkpoint, whatever offset the
+/// [`ReadBuilder`] was given; its other settings (live mode, headers,
+/// framing, ...) are used as-is. Chunks the server returns back to back
+/// during catch-up are gathered into one batch, up to
+/// [`max_batch_bytes`](Self::max_batch_bytes); once the reader is live,
+/// each chunk is applied as soon as it arrives.
+///
+/// Retryable stream errors are retried with the client's backoff. A batch
+/// the sink fails to apply is retried up to
+/// [`max_attempts`](Self::max_attempts) times; after that the runner stops
+/// without committing it, unless a [`dead_letter`](Self::dead_letter) stream
+/// is set.
+///
+/// # Example
+///
+/// ```ignore
+/// let offset = SinkRunner::new(stream.read().live(LiveMode::Sse), &mut sink)
+///     .run(tokio::signal::ctrl_c().map(|_| ()))
+///     .await?;
+/// ```
+#[must_use = "a SinkRunner does nothing unless you call .run()"]
+pub struct SinkRunner<K> {
+    read: ReadBuilder,
+    sink: K,
+    max_batch_bytes: usize,
+    delivery: Delivery,
+}
+
+/// How a [`SinkRunner`] handles batches the sink fails to apply.
+struct Delivery {
+    max_attempts: u32,
+    #[cfg(feature = "json")]
+    dead_letter: Option<DeadLetterQueue>,
+}
+
+impl<K: Sink> SinkRunner<K> {
+    /// Write the stream read by `read` into `sink`.
+    pub fn new(read: ReadBuilder, sink: K) -> Self {
+        Self {
+            read,
+            sink,
+            max_batch_bytes: DEFAULT_MAX_BATCH_BYTES,
+            delivery: Delivery {
+                max_attempts: 1,
+                #[cfg(feature = "json")]
+                dead_letter: None,
+            },
+        }
+    }
+
+    /// Stop gathering a batch once it holds this many payload bytes
+    /// (default 1 MiB). A single chunk larger than this is still applied
+    /// whole.
+    pub fn max_batch_bytes(mut self, bytes: usize) -> Self {
+        self.max_batch_bytes = bytes;
+        self
+    }
+
+    /// Times to try applying a batch before giving up on it (default 1, so
+    /// no retries). Attempts are spaced with the client's retry backoff.
+    pub fn max_attempts(mut self, attempts: u32) -> Self {
+        self.delivery.max_attempts = attempts.max(1);
+        self
+    }
+
+    /// Write chunks the sink keeps rejecting to the JSON stream `stream` as
+    /// [`DeadLetter`](crate::DeadLetter)s, and carry on past them.
+    ///
+    /// Once a batch has failed [`max_attempts`](Self::max_attempts) times,
+    /// each of its chunks is applied on its own, so only the chunks that
+    /// still fail are dead-lettered. The batch's checkpoint is then
+    /// committed as usual.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// SinkRunner::new(stream.read().live(LiveMode::LongPoll), &mut sink)
+    ///     .max_attempts(3)
+    ///     .dead_letter(client.stream("/v1/stream/orders-dlq"))
+    ///     .run(shutdown)
+    ///     .await?;
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn dead_letter(mut self, stream: DurableStream) -> Self {
+        self.delivery.dead_letter = Some(DeadLetterQueue::new(stream));
+        self
+    }
+
+    /// Run until the stream ends, an error stops the runner, or `shutdown`
+    /// resolves. Returns the last checkpoint passed to the sink.
+    ///
+    /// A batch still being gathered at shutdown is dropped uncommitted and
+    /// read again on the next run. Unless the runner fails, the sink is
+    /// flushed before returning.
+    pub async fn run<S>(mut self, shutdown: S) -> Result<Offset, StreamError>
+    where
+        S: Future<Output = ()>,
+    {
+        let mut position = self
+            .sink
+            .load_checkpoint()
+            .await
+            .map_err(sink_error)?
+            .unwrap_or(Offset::Beginning);
+        let mut iter = self.read.offset(position.clone()).build()?;
+        tokio::pin!(shutdown);
+        let mut failures = 0;
+
+        'run: loop {
+            let mut batch = SinkBatch {
+                start_offset: position.clone(),
+                next_offset: position.clone(),
+                chunks: Vec::new(),
+            };
+            let mut bytes = 0;
+
+            // A chunk that is not up to date means the server already has
+            // more, so keep reading without waiting
+            let outcome = loop {
+                let next = {
+                    // The read is paused, not dropped, while the sink
+                    // flushes
+                    let next = iter.next_chunk();
+                    tokio::pin!(next);
+                    loop {
+                        let flush_after = if batch.chunks.is_empty() {
+                            self.sink.flush_after()
+                        } else {
+                            None
+                        };
+                        tokio::select! {
+                            _ = &mut shutdown => break 'run,
+                            _ = sleep(flush_after.unwrap_or_default()), if flush_after.is_some() => {
+                                self.sink.flush().await.map_err(sink_error)?;
+                            }
+                            next = &mut next => break next,
+                        }
+                    }
+                };
+                match next {
+                    Ok(Some(chunk)) => {
+                        failures = 0;
+                        let more = !chunk.up_to_date;
+                        batch.next_offset = chunk.next_offset.clone();
+                        if !chunk.data.is_empty() {
+                            bytes += chunk.data.len();
+                            batch.chunks.push(chunk);
+                        }
+                        if !more || bytes >= self.max_batch_bytes {
+                            break Ok(true);
+                        }
+                    }
+                    Ok(None) => break Ok(false),
+                    Err(err) => break Err(err),
+                }
+            };
+
+            // Chunks already read are applied even if the read then failed,
+            // since the reader has moved past them
+            if !batch.chunks.is_empty() {
+                self.delivery
+                    .apply(&mut self.sink, &batch, iter.stream())
+                    .await?;
+                self.sink
+                    .store_checkpoint(&batch.next_offset)
+                    .await
+                    .map_err(sink_error)?;
+                position = batch.next_offset;
+            }
+
+            match outcome {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(StreamError::OffsetGone { .. }) => {
+                    self.sink.reset().await.map_err(sink_error)?;
+                    iter.reset_to(Offset::Beginning);
+                    position = Offset::Beginning;
+                }
+                Err(err) if err.is_retryable() => {
+                    failures += 1;
+                    let stream = iter.stream();
+                    tokio::select! {
+                        _ = &mut shutdown => break,
+                        _ = sleep_before_retry(&stream.client, &stream.url, failures, &err) => {}
+                    }
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        self.sink.flush().await.map_err(sink_error)?;
+        Ok(position)
+    }
+}
+
+impl Delivery {
+    /// Apply `batch`, dead-lettering the chunks that keep failing if there
+    /// is a dead-letter stream.
+    async fn apply<K: Sink>(
+        &self,
+        sink: &mut K,
+        batch: &SinkBatch,
+        stream: &DurableStream,
+    ) -> Result<(), StreamError> {
+        let err = match self.apply_with_retry(sink, batch, stream).await {
+            Ok(()) => return Ok(()),
+            Err(err) => err,
+        };
+        #[cfg(feature = "json")]
+        if let Some(queue) = &self.dead_letter {
+            let mut letters = Vec::new();
+            if let [chunk] = batch.chunks.as_slice() {
+                letters.push(self.letter(stream, &batch.start_offset, chunk, &err));
+            } else {
+                // Narrow the failure down to the chunks causing it
+                let mut start = batch.start_offset.clone();
+                for chunk in &batch.chunks {
+                    let single = SinkBatch {
+                        start_offset: start.clone(),
+                        next_offset: chunk.next_offset.clone(),
+                        chunks: vec![chunk.clone()],
+                    };
+                    if let Err(err) = self.apply_with_retry(sink, &single, stream).await {
+                        letters.push(self.letter(stream, &start, chunk, &err));
+                    }
+                    start = chunk.next_offset.clone();
+                }
+            }
+            return queue.send(&letters).await;
+        }
+        Err(err)
+    }
+
+    async fn apply_with_retry<K: Sink>(
+        &self,
+        sink: &mut K,
+        batch: &SinkBatch,
+        stream: &DurableStream,
+    ) -> Result<(), StreamError> {
+        let mut attempt = 1;
+        loop {
+            match sink.apply(batch).await.map_err(sink_error) {
+                Ok(()) => return Ok(()),
+                Err(err) if attempt < self.max_attempts => {
+                    sleep_before_retry(&stream.client, &stream.url, attempt, &err).await;
+                    attempt += 1;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    #[cfg(feature = "json")]
+    fn letter(
+        &self,
+        stream: &DurableStream,
+        offset: &Offset,
+        chunk: &Chunk,
+        err: &StreamError,
+    ) -> DeadLetter {
+        let content_type = stream.content_type.as_deref().unwrap_or("application/octet-stream");
+        let mut letter = DeadLetter::new(&stream.url, content_type, &chunk.data, err);
+        letter.offset = Some(offset.clone());
+        letter.attempts = Some(self.max_attempts);
+        letter
+    }
+}
+
+/// Surface a sink's [`StreamError`] as-is, e.g. when it writes to another
+/// stream, and wrap anything else as [`StreamError::Handler`].
+fn sink_error(err: HandlerError) -> StreamError {
+    match err.downcast::<StreamError>() {
+        Ok(err) => *err,
+        Err(err) => StreamError::Handler(err),
+    }
+}
@@ -0,0 +1,355 @@
+//! Local segment file sink.
+
+use super::{Sink, SinkBatch};
+use crate::error::StoreError;
+use crate::handler::HandlerError;
+use crate::types::Offset;
+use async_trait::async_trait;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+const LOG_EXTENSION: &str = "log";
+const INDEX_EXTENSION: &str = "idx";
+
+/// [`Sink`] that appends a stream to rotating segment files in a local
+/// directory.
+///
+/// Segment `N` is stored as `{N:020}.log`, holding the raw bytes of each
+/// chunk back to back, next to `{N:020}.idx`, with one line per chunk:
+///
+/// ```text
+/// <byte position>\t<length>\t<start offset>\t<next offset>
+/// ```
+///
+/// Data is written to the log in [`apply`](Sink::apply) and becomes part of
+/// the sink once its index lines are written in
+/// [`store_checkpoint`](Sink::store_checkpoint). A restarted sink drops log
+/// bytes and index lines that were not fully committed, so every chunk is
+/// stored exactly once. Use [`FileSink::index`] to locate data by offset,
+/// e.g. from a downstream batch job.
+///
+/// If the checkpoint expires from the stream (410 Gone), the runner stops
+/// rather than leave a gap in the segments.
+///
+/// # Example
+///
+/// ```ignore
+/// let sink = FileSink::new("/var/lib/orders").max_segment_bytes(64 * 1024 * 1024);
+/// SinkRunner::new(stream.read().live(LiveMode::Sse), sink)
+///     .run(tokio::signal::ctrl_c().map(|_| ()))
+///     .await?;
+/// ```
+#[derive(Debug)]
+pub struct FileSink {
+    dir: PathBuf,
+    max_segment_bytes: u64,
+    max_segments: Option<usize>,
+    sync: bool,
+    /// Segment being appended to.
+    segment: u64,
+    /// Committed length of the current segment's log.
+    segment_len: u64,
+    /// Chunks written to the log by `apply` but not yet indexed.
+    staged: Vec<SegmentEntry>,
+}
+
+/// Location of one chunk in a [`FileSink`] directory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SegmentEntry {
+    /// Log file holding the chunk.
+    pub segment: PathBuf,
+    /// Byte position of the chunk in the log file.
+    pub position: u64,
+    /// Length of the chunk in bytes.
+    pub len: u64,
+    /// Offset of the chunk's first byte.
+    pub start_offset: Offset,
+    /// Offset just after the chunk.
+    pub next_offset: Offset,
+}
+
+impl FileSink {
+    /// Write segments into `dir`, which is created if needed.
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            max_segment_bytes: 128 * 1024 * 1024,
+            max_segments: None,
+            sync: true,
+            segment: 0,
+            segment_len: 0,
+            staged: Vec::new(),
+        }
+    }
+
+    /// Start a new segment once the current one holds this many bytes
+    /// (default 128 MiB). A batch is never split across segments.
+    #[must_use]
+    pub fn max_segment_bytes(mut self, bytes: u64) -> Self {
+        self.max_segment_bytes = bytes;
+        self
+    }
+
+    /// Keep only the newest `count` segments, deleting older ones on
+    /// rotation (default: keep all).
+    #[must_use]
+    pub fn max_segments(mut self, count: usize) -> Self {
+        self.max_segments = Some(count.max(1));
+        self
+    }
+
+    /// Flush each batch to disk with `fsync` before it is committed
+    /// (default on). Turning this off trades crash safety for speed, e.g.
+    /// when tailing to disk for debugging.
+    #[must_use]
+    pub fn sync(mut self, enabled: bool) -> Self {
+        self.sync = enabled;
+        self
+    }
+
+    /// Get the segment directory.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Every committed chunk in the segments under `dir`, in stream order.
+    pub async fn index(dir: impl AsRef<Path>) -> Result<Vec<SegmentEntry>, StoreError> {
+        let dir = dir.as_ref().to_path_buf();
+        blocking(move || {
+            let mut entries = Vec::new();
+            for segment in segment_numbers(&dir)? {
+                entries.extend(read_index(&dir, segment)?.0);
+            }
+            Ok(entries)
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl Sink for FileSink {
+    async fn load_checkpoint(&mut self) -> Result<Option<Offset>, HandlerError> {
+        self.staged.clear();
+        let dir = self.dir.clone();
+        let (segment, segment_len, checkpoint) = blocking(move || {
+            fs::create_dir_all(&dir)?;
+            let segments = segment_numbers(&dir)?;
+
+            // The newest segment with a committed chunk holds the checkpoint
+            let mut current = None;
+            for &segment in segments.iter().rev() {
+                let (entries, valid_len) = read_index(&dir, segment)?;
+                truncate(&segment_path(&dir, segment, INDEX_EXTENSION), valid_len)?;
+                if let Some(last) = entries.last() {
+                    current = Some((segment, last.position + last.len, last.next_offset.clone()));
+                    break;
+                }
+            }
+            let (segment, len, checkpoint) = match current {
+                Some((segment, len, offset)) => (segment, len, Some(offset)),
+                None => (segments.first().copied().unwrap_or(0), 0, None),
+            };
+
+            // Drop data written after the last commit
+            truncate(&segment_path(&dir, segment, LOG_EXTENSION), len)?;
+            for &later in segments.iter().filter(|&&s| s > segment) {
+                remove_segment(&dir, later)?;
+            }
+            Ok((segment, len, checkpoint))
+        })
+        .await?;
+
+        self.segment = segment;
+        self.segment_len = segment_len;
+        Ok(checkpoint)
+    }
+
+    async fn apply(&mut self, batch: &SinkBatch) -> Result<(), HandlerError> {
+        self.staged.clear();
+        if self.segment_len >= self.max_segment_bytes {
+            self.segment += 1;
+            self.segment_len = 0;
+        }
+
+        let log = segment_path(&self.dir, self.segment, LOG_EXTENSION);
+        let mut position = self.segment_len;
+        let mut start_offset = batch.start_offset.clone();
+        let mut data = Vec::with_capacity(batch.total_bytes());
+        for chunk in &batch.chunks {
+            self.staged.push(SegmentEntry {
+                segment: log.clone(),
+                position,
+                len: chunk.data.len() as u64,
+                start_offset,
+                next_offset: chunk.next_offset.clone(),
+            });
+            position += chunk.data.len() as u64;
+            start_offset = chunk.next_offset.clone();
+            data.extend_from_slice(&chunk.data);
+        }
+
+        let (segment_len, sync) = (self.segment_len, self.sync);
+        blocking(move || {
+            let mut file = OpenOptions::new()
+                .create(true)
+                .write(true)
+                .truncate(false)
+                .open(&log)?;
+            // Overwrite anything left by a batch that was never committed
+            file.set_len(segment_len)?;
+            file.seek(SeekFrom::Start(segment_len))?;
+            file.write_all(&data)?;
+            if sync {
+                file.sync_data()?;
+            }
+            Ok(())
+        })
+        .await?;
+        Ok(())
+    }
+
+    async fn store_checkpoint(&mut self, _offset: &Offset) -> Result<(), HandlerError> {
+        let Some(last) = self.staged.last() else {
+            return Ok(());
+        };
+        let segment_len = last.position + last.len;
+        let staged = std::mem::take(&mut self.staged);
+        let lines: String = staged
+            .iter()
+            .map(|e| {
+                format!(
+                    "{}\t{}\t{}\t{}\n",
+                    e.position, e.len, e.start_offset, e.next_offset
+                )
+            })
+            .collect();
+
+        let (dir, segment, sync) = (self.dir.clone(), self.segment, self.sync);
+        let rotated = self.segment_len == 0;
+        let max_segments = self.max_segments;
+        blocking(move || {
+            let mut index = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(segment_path(&dir, segment, INDEX_EXTENSION))?;
+            index.write_all(lines.as_bytes())?;
+            if sync {
+                index.sync_data()?;
+            }
+
+            if let (true, Some(keep)) = (rotated, max_segments) {
+                let segments = segment_numbers(&dir)?;
+                let excess = segments.len().saturating_sub(keep);
+                for &old in &segments[..excess] {
+                    remove_segment(&dir, old)?;
+                }
+            }
+            Ok(())
+        })
+        .await?;
+
+        self.segment_len = segment_len;
+        Ok(())
+    }
+}
+
+/// Run blocking file I/O off the async runtime.
+async fn blocking<T, F>(f: F) -> Result<T, StoreError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, StoreError> + Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| StoreError::Backend(e.to_string()))?
+}
+
+fn segment_path(dir: &Path, segment: u64, extension: &str) -> PathBuf {
+    dir.join(format!("{segment:020}.{extension}"))
+}
+
+/// Numbers of the segments in `dir`, ascending.
+fn segment_numbers(dir: &Path) -> Result<Vec<u64>, StoreError> {
+    let mut segments = Vec::new();
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(segments),
+        Err(e) => return Err(e.into()),
+    };
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some(LOG_EXTENSION) {
+            continue;
+        }
+        if let Some(n) = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .and_then(|s| s.parse().ok())
+        {
+            segments.push(n);
+        }
+    }
+    segments.sort_unstable();
+    Ok(segments)
+}
+
+/// Committed entries of a segment's index, and the length of the index up
+/// to its last complete line.
+fn read_index(dir: &Path, segment: u64) -> Result<(Vec<SegmentEntry>, u64), StoreError> {
+    let path = segment_path(dir, segment, INDEX_EXTENSION);
+    let text = match fs::read(&path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok((Vec::new(), 0)),
+        Err(e) => return Err(e.into()),
+    };
+    let log = segment_path(dir, segment, LOG_EXTENSION);
+    let corrupt =
+        |line: &str| StoreError::Corrupt(format!("{}: bad index line {line:?}", path.display()));
+
+    let complete = text.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+    let text =
+        std::str::from_utf8(&text[..complete]).map_err(|e| StoreError::Corrupt(e.to_string()))?;
+    let mut entries = Vec::new();
+    for line in text.lines() {
+        let mut fields = line.split('\t');
+        let (Some(position), Some(len), Some(start), Some(next), None) = (
+            fields.next(),
+            fields.next(),
+            fields.next(),
+            fields.next(),
+            fields.next(),
+        ) else {
+            return Err(corrupt(line));
+        };
+        entries.push(SegmentEntry {
+            segment: log.clone(),
+            position: position.parse().map_err(|_| corrupt(line))?,
+            len: len.parse().map_err(|_| corrupt(line))?,
+            start_offset: Offset::parse(start),
+            next_offset: Offset::parse(next),
+        });
+    }
+    Ok((entries, complete as u64))
+}
+
+/// Cut the file at `path` to `len` bytes, if it exists.
+fn truncate(path: &Path, len: u64) -> Result<(), StoreError> {
+    match OpenOptions::new().write(true).open(path) {
+        Ok(file) if file.metadata()?.len() > len => Ok(file.set_len(len)?),
+        Ok(_) => Ok(()),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn remove_segment(dir: &Path, segment: u64) -> Result<(), StoreError> {
+    for extension in [LOG_EXTENSION, INDEX_EXTENSION] {
+        match fs::remove_file(segment_path(dir, segment, extension)) {
+            Err(e) if e.kind() != ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+    }
+    Ok(())
+}
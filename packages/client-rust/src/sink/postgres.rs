@@ -0,0 +1,218 @@
+//! Postgres sink.
+
+use super::{Sink, SinkBatch};
+use crate::handler::HandlerError;
+use crate::typed::{decode_json, DecodeLimits};
+use crate::types::Offset;
+use async_trait::async_trait;
+use serde_json::Value;
+use tokio_postgres::Client;
+
+/// How a [`PgSink`] turns records into rows.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PgSinkMode {
+    /// Insert every record as a new row:
+    /// `<table>(id BIGSERIAL PRIMARY KEY, stream_offset TEXT NOT NULL, value JSONB NOT NULL)`,
+    /// where `stream_offset` is the offset of the chunk the record came from.
+    Append,
+    /// Keep the latest record per key:
+    /// `<table>(key TEXT PRIMARY KEY, value JSONB NOT NULL)`.
+    ///
+    /// The string or number at `key_field` selects the row. A record with
+    /// `"deleted": true` removes the row instead.
+    Upsert {
+        /// Field of each record holding its key.
+        key_field: String,
+    },
+}
+
+/// [`Sink`] that writes a JSON stream into a Postgres table.
+///
+/// The rows of a batch and the stream offset are written in one
+/// transaction, so each record is applied exactly once even across
+/// crashes. Offsets live in `ds_sink_offsets(name TEXT PRIMARY KEY,
+/// next_offset TEXT NOT NULL)`, keyed by the sink's [`name`](Self::name).
+/// Both tables are created on first use.
+///
+/// If the stored offset expires from the stream (410 Gone), the table is
+/// truncated and rebuilt from the beginning.
+///
+/// # Example
+///
+/// ```ignore
+/// let (pg, conn) = tokio_postgres::connect("host=localhost user=app", NoTls).await?;
+/// tokio::spawn(conn);
+///
+/// let sink = PgSink::new(pg, "users", PgSinkMode::Upsert { key_field: "id".into() });
+/// SinkRunner::new(stream.read().live(LiveMode::Sse), sink)
+///     .run(tokio::signal::ctrl_c().map(|_| ()))
+///     .await?;
+/// ```
+pub struct PgSink {
+    client: Client,
+    table: String,
+    name: String,
+    mode: PgSinkMode,
+    schema_ready: bool,
+    /// Records of the batch passed to `apply`, with the offset of their
+    /// chunk, written by `store_checkpoint`.
+    staged: Vec<(Offset, Value)>,
+}
+
+impl PgSink {
+    /// Write records into `table` using `mode`.
+    pub fn new(client: Client, table: impl Into<String>, mode: PgSinkMode) -> Self {
+        let table = table.into();
+        Self {
+            client,
+            name: table.clone(),
+            table,
+            mode,
+            schema_ready: false,
+            staged: Vec::new(),
+        }
+    }
+
+    /// Key the stored offset by `name` instead of the table name, e.g. when
+    /// several streams are written into tables of the same name in
+    /// different schemas.
+    #[must_use]
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Give back the Postgres client.
+    pub fn into_client(self) -> Client {
+        self.client
+    }
+
+    async fn ensure_schema(&mut self) -> Result<(), HandlerError> {
+        if self.schema_ready {
+            return Ok(());
+        }
+        let table = quote_ident(&self.table);
+        let columns = match self.mode {
+            PgSinkMode::Append => {
+                "id BIGSERIAL PRIMARY KEY, stream_offset TEXT NOT NULL, value JSONB NOT NULL"
+            }
+            PgSinkMode::Upsert { .. } => "key TEXT PRIMARY KEY, value JSONB NOT NULL",
+        };
+        let sql = format!(
+            "CREATE TABLE IF NOT EXISTS {table} ({columns});
+             CREATE TABLE IF NOT EXISTS ds_sink_offsets (
+                 name TEXT PRIMARY KEY,
+                 next_offset TEXT NOT NULL
+             );"
+        );
+        self.client.batch_execute(&sql).await?;
+        self.schema_ready = true;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Sink for PgSink {
+    async fn load_checkpoint(&mut self) -> Result<Option<Offset>, HandlerError> {
+        self.ensure_schema().await?;
+        let row = self
+            .client
+            .query_opt(
+                "SELECT next_offset FROM ds_sink_offsets WHERE name = $1",
+                &[&self.name],
+            )
+            .await?;
+        Ok(row.map(|r| Offset::parse(r.get::<_, &str>(0))))
+    }
+
+    async fn apply(&mut self, batch: &SinkBatch) -> Result<(), HandlerError> {
+        // Rows are only written in `store_checkpoint`, in the same
+        // transaction as the offset
+        self.staged.clear();
+        let limits = DecodeLimits::default();
+        let mut offset = batch.start_offset.clone();
+        for chunk in &batch.chunks {
+            for item in decode_json::<Value>(&chunk.data, &limits)? {
+                if let PgSinkMode::Upsert { key_field } = &self.mode {
+                    record_key(&item, key_field)?;
+                }
+                self.staged.push((offset.clone(), item));
+            }
+            offset = chunk.next_offset.clone();
+        }
+        Ok(())
+    }
+
+    async fn store_checkpoint(&mut self, offset: &Offset) -> Result<(), HandlerError> {
+        self.ensure_schema().await?;
+        let staged = std::mem::take(&mut self.staged);
+        let table = quote_ident(&self.table);
+
+        let tx = self.client.transaction().await?;
+        match &self.mode {
+            PgSinkMode::Append => {
+                let insert = tx
+                    .prepare(&format!(
+                        "INSERT INTO {table} (stream_offset, value) VALUES ($1, $2)"
+                    ))
+                    .await?;
+                for (from, item) in &staged {
+                    tx.execute(&insert, &[&from.to_query_value(), item]).await?;
+                }
+            }
+            PgSinkMode::Upsert { key_field } => {
+                let upsert = tx
+                    .prepare(&format!(
+                        "INSERT INTO {table} (key, value) VALUES ($1, $2)
+                         ON CONFLICT (key) DO UPDATE SET value = EXCLUDED.value"
+                    ))
+                    .await?;
+                let delete = tx
+                    .prepare(&format!("DELETE FROM {table} WHERE key = $1"))
+                    .await?;
+                for (_, item) in &staged {
+                    let key = record_key(item, key_field)?;
+                    if item.get("deleted") == Some(&Value::Bool(true)) {
+                        tx.execute(&delete, &[&key]).await?;
+                    } else {
+                        tx.execute(&upsert, &[&key, item]).await?;
+                    }
+                }
+            }
+        }
+        tx.execute(
+            "INSERT INTO ds_sink_offsets (name, next_offset) VALUES ($1, $2)
+             ON CONFLICT (name) DO UPDATE SET next_offset = EXCLUDED.next_offset",
+            &[&self.name, &offset.to_query_value()],
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn reset(&mut self) -> Result<(), HandlerError> {
+        self.ensure_schema().await?;
+        self.staged.clear();
+        let tx = self.client.transaction().await?;
+        tx.batch_execute(&format!("TRUNCATE {}", quote_ident(&self.table)))
+            .await?;
+        tx.execute("DELETE FROM ds_sink_offsets WHERE name = $1", &[&self.name])
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+/// Row key of `item`: the string or number at `key_field`.
+fn record_key(item: &Value, key_field: &str) -> Result<String, HandlerError> {
+    match item.get(key_field) {
+        Some(Value::String(s)) => Ok(s.clone()),
+        Some(Value::Number(n)) => Ok(n.to_string()),
+        _ => Err(format!("record has no string or number field {key_field:?}").into()),
+    }
+}
+
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
@@ -0,0 +1,269 @@
+//! Object storage archive sink.
+
+use super::{Sink, SinkBatch};
+use crate::error::StoreError;
+use crate::handler::HandlerError;
+use crate::rt::Instant;
+use crate::types::Offset;
+use async_trait::async_trait;
+use bytes::Bytes;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::{Read, Write};
+use std::time::Duration;
+
+mod bucket;
+
+pub use bucket::S3Bucket;
+
+/// Separates the start and end offsets in an object key.
+const RANGE_SEPARATOR: &str = "--";
+const GZIP_SUFFIX: &str = ".gz";
+
+/// [`Sink`] that archives a stream into S3-compatible object storage.
+///
+/// Chunks are buffered and uploaded as one object once the buffer reaches
+/// [`max_object_bytes`](Self::max_object_bytes) or its oldest data is
+/// [`max_object_age`](Self::max_object_age) old. Each object holds the
+/// stream's bytes for one offset range, concatenated, under the key
+/// `{prefix}{start}--{end}` (plus `.gz` when [`gzip`](Self::gzip) is on).
+/// Objects of a JSON stream hold one array per chunk, back to back, which a
+/// streaming deserializer such as `serde_json::StreamDeserializer` reads
+/// directly.
+///
+/// The archive is its own checkpoint: a restarted sink resumes after the
+/// last uploaded object, so each range is archived exactly once and data
+/// still buffered at a crash is read again. If the resume point has expired
+/// from the stream (410 Gone), the runner stops rather than leave a gap in
+/// the archive. Use [`S3ArchiveReader`] to replay archived ranges.
+///
+/// # Example
+///
+/// ```ignore
+/// let sink = S3Sink::new(bucket, "orders/").gzip(true);
+/// SinkRunner::new(stream.read().live(LiveMode::Sse), sink)
+///     .run(tokio::signal::ctrl_c().map(|_| ()))
+///     .await?;
+/// ```
+#[derive(Debug)]
+pub struct S3Sink {
+    bucket: S3Bucket,
+    prefix: String,
+    max_object_bytes: usize,
+    max_object_age: Duration,
+    gzip: bool,
+    buffer: Vec<u8>,
+    /// Offset the buffered data starts at.
+    buffer_start: Option<Offset>,
+    /// Checkpoint the buffered data runs up to.
+    buffer_end: Option<Offset>,
+    /// When the oldest buffered data was applied.
+    opened_at: Option<Instant>,
+}
+
+impl S3Sink {
+    /// Archive into `bucket` under keys starting with `prefix`.
+    pub fn new(bucket: S3Bucket, prefix: impl Into<String>) -> Self {
+        Self {
+            bucket,
+            prefix: prefix.into(),
+            max_object_bytes: 64 * 1024 * 1024,
+            max_object_age: Duration::from_secs(300),
+            gzip: false,
+            buffer: Vec::new(),
+            buffer_start: None,
+            buffer_end: None,
+            opened_at: None,
+        }
+    }
+
+    /// Upload an object once it holds this many bytes, before compression
+    /// (default 64 MiB).
+    #[must_use]
+    pub fn max_object_bytes(mut self, bytes: usize) -> Self {
+        self.max_object_bytes = bytes;
+        self
+    }
+
+    /// Upload an object at the latest this long after its first data was
+    /// read (default 5 minutes), so a quiet stream is still archived.
+    #[must_use]
+    pub fn max_object_age(mut self, age: Duration) -> Self {
+        self.max_object_age = age;
+        self
+    }
+
+    /// Gzip objects before upload (default off).
+    #[must_use]
+    pub fn gzip(mut self, enabled: bool) -> Self {
+        self.gzip = enabled;
+        self
+    }
+
+    fn clear(&mut self) {
+        self.buffer.clear();
+        self.buffer_start = None;
+        self.buffer_end = None;
+        self.opened_at = None;
+    }
+
+    async fn upload(&mut self) -> Result<(), HandlerError> {
+        let (Some(start), Some(end)) = (&self.buffer_start, &self.buffer_end) else {
+            return Ok(());
+        };
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+
+        let mut key = format!("{}{}{RANGE_SEPARATOR}{}", self.prefix, start, end);
+        let (body, content_type) = if self.gzip {
+            key.push_str(GZIP_SUFFIX);
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(&self.buffer)?;
+            (encoder.finish()?, "application/gzip")
+        } else {
+            (std::mem::take(&mut self.buffer), "application/octet-stream")
+        };
+        self.bucket.put(&key, body, content_type).await?;
+        self.clear();
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Sink for S3Sink {
+    async fn load_checkpoint(&mut self) -> Result<Option<Offset>, HandlerError> {
+        // Anything still buffered was never uploaded, so it is read again
+        self.clear();
+        let objects = list_objects(&self.bucket, &self.prefix).await?;
+        Ok(objects.into_iter().last().map(|o| o.end_offset))
+    }
+
+    async fn apply(&mut self, batch: &SinkBatch) -> Result<(), HandlerError> {
+        if self.buffer_start.is_none() {
+            self.buffer_start = Some(batch.start_offset.clone());
+            self.opened_at = Some(Instant::now());
+        }
+        for chunk in &batch.chunks {
+            self.buffer.extend_from_slice(&chunk.data);
+        }
+        Ok(())
+    }
+
+    async fn store_checkpoint(&mut self, offset: &Offset) -> Result<(), HandlerError> {
+        self.buffer_end = Some(offset.clone());
+        let expired = self
+            .opened_at
+            .is_some_and(|at| at.elapsed() >= self.max_object_age);
+        if self.buffer.len() >= self.max_object_bytes || expired {
+            self.upload().await?;
+        }
+        Ok(())
+    }
+
+    fn flush_after(&self) -> Option<Duration> {
+        self.buffer_end.as_ref()?;
+        let opened_at = self.opened_at?;
+        Some(self.max_object_age.saturating_sub(opened_at.elapsed()))
+    }
+
+    async fn flush(&mut self) -> Result<(), HandlerError> {
+        self.upload().await
+    }
+}
+
+/// One object written by [`S3Sink`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ArchivedObject {
+    /// Object key.
+    pub key: String,
+    /// Offset of the first byte in the object.
+    pub start_offset: Offset,
+    /// Offset just after the last byte in the object.
+    pub end_offset: Offset,
+}
+
+/// Replays ranges of a stream archived by [`S3Sink`].
+///
+/// # Example
+///
+/// ```ignore
+/// let reader = S3ArchiveReader::new(bucket, "orders/");
+/// for object in reader.objects(&Offset::Beginning, Some(&checkpoint)).await? {
+///     let data = reader.fetch(&object).await?;
+///     restore(&data)?;
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct S3ArchiveReader {
+    bucket: S3Bucket,
+    prefix: String,
+}
+
+impl S3ArchiveReader {
+    /// Read the archive written to `bucket` under `prefix`.
+    pub fn new(bucket: S3Bucket, prefix: impl Into<String>) -> Self {
+        Self {
+            bucket,
+            prefix: prefix.into(),
+        }
+    }
+
+    /// Archived objects holding any data in `from..until` (to the end of
+    /// the archive if `until` is `None`), in stream order.
+    ///
+    /// Objects are whole: the first and last may extend past the range.
+    pub async fn objects(
+        &self,
+        from: &Offset,
+        until: Option<&Offset>,
+    ) -> Result<Vec<ArchivedObject>, StoreError> {
+        let objects = list_objects(&self.bucket, &self.prefix).await?;
+        Ok(objects
+            .into_iter()
+            .filter(|o| o.end_offset > *from)
+            .filter(|o| until.is_none_or(|until| o.start_offset < *until))
+            .collect())
+    }
+
+    /// Download an object's data, decompressed.
+    pub async fn fetch(&self, object: &ArchivedObject) -> Result<Bytes, StoreError> {
+        let data = self.bucket.get(&object.key).await?;
+        if !object.key.ends_with(GZIP_SUFFIX) {
+            return Ok(data);
+        }
+        let mut out = Vec::new();
+        GzDecoder::new(&data[..])
+            .read_to_end(&mut out)
+            .map_err(|e| StoreError::Corrupt(format!("{}: {e}", object.key)))?;
+        Ok(out.into())
+    }
+}
+
+/// Objects under `prefix` named by [`S3Sink`], in stream order.
+async fn list_objects(bucket: &S3Bucket, prefix: &str) -> Result<Vec<ArchivedObject>, StoreError> {
+    let mut objects: Vec<ArchivedObject> = bucket
+        .list(prefix)
+        .await?
+        .into_iter()
+        .filter_map(|key| {
+            let name = key.strip_prefix(prefix)?;
+            let name = name.strip_suffix(GZIP_SUFFIX).unwrap_or(name);
+            let (start, end) = name.split_once(RANGE_SEPARATOR)?;
+            let (start_offset, end_offset) = (Offset::parse(start), Offset::parse(end));
+            Some(ArchivedObject {
+                key,
+                start_offset,
+                end_offset,
+            })
+        })
+        .collect();
+    objects.sort_by(|a, b| {
+        a.end_offset
+            .partial_cmp(&b.end_offset)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    Ok(objects)
+}
@@ -0,0 +1,329 @@
+//! Minimal S3-compatible object store client with SigV4 signing.
+
+use crate::error::StoreError;
+use bytes::Bytes;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use std::fmt::Write as _;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// An S3-compatible bucket: AWS S3, MinIO, Cloudflare R2, Ceph and others.
+///
+/// Requests use path-style URLs (`{endpoint}/{bucket}/{key}`) and AWS
+/// Signature Version 4.
+///
+/// # Example
+///
+/// ```ignore
+/// let bucket = S3Bucket::new(
+///     "https://s3.eu-west-1.amazonaws.com",
+///     "stream-archive",
+///     "eu-west-1",
+///     std::env::var("AWS_ACCESS_KEY_ID")?,
+///     std::env::var("AWS_SECRET_ACCESS_KEY")?,
+/// );
+/// ```
+#[derive(Clone)]
+pub struct S3Bucket {
+    http: reqwest::Client,
+    endpoint: String,
+    bucket: String,
+    region: String,
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<String>,
+}
+
+impl std::fmt::Debug for S3Bucket {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("S3Bucket")
+            .field("endpoint", &self.endpoint)
+            .field("bucket", &self.bucket)
+            .field("region", &self.region)
+            .field("access_key_id", &self.access_key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl S3Bucket {
+    /// Address `bucket` at `endpoint` (scheme and host, e.g.
+    /// `https://s3.us-east-1.amazonaws.com` or `http://localhost:9000`).
+    pub fn new(
+        endpoint: impl Into<String>,
+        bucket: impl Into<String>,
+        region: impl Into<String>,
+        access_key_id: impl Into<String>,
+        secret_access_key: impl Into<String>,
+    ) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            endpoint: endpoint.into().trim_end_matches('/').to_string(),
+            bucket: bucket.into(),
+            region: region.into(),
+            access_key_id: access_key_id.into(),
+            secret_access_key: secret_access_key.into(),
+            session_token: None,
+        }
+    }
+
+    /// Sign requests with temporary credentials' session token.
+    #[must_use]
+    pub fn session_token(mut self, token: impl Into<String>) -> Self {
+        self.session_token = Some(token.into());
+        self
+    }
+
+    /// Use `client` for requests, e.g. to share a connection pool or set
+    /// timeouts.
+    #[must_use]
+    pub fn http_client(mut self, client: reqwest::Client) -> Self {
+        self.http = client;
+        self
+    }
+
+    /// Upload `body` as `key`, replacing any existing object.
+    pub(crate) async fn put(
+        &self,
+        key: &str,
+        body: Vec<u8>,
+        content_type: &str,
+    ) -> Result<(), StoreError> {
+        let request = self
+            .signed(reqwest::Method::PUT, key, &[], &body)?
+            .header(reqwest::header::CONTENT_TYPE, content_type)
+            .body(body);
+        check(request.send().await.map_err(backend)?).await?;
+        Ok(())
+    }
+
+    /// Download the object at `key`.
+    pub(crate) async fn get(&self, key: &str) -> Result<Bytes, StoreError> {
+        let request = self.signed(reqwest::Method::GET, key, &[], &[])?;
+        let response = check(request.send().await.map_err(backend)?).await?;
+        response.bytes().await.map_err(backend)
+    }
+
+    /// Keys of all objects starting with `prefix`, in lexicographic order.
+    pub(crate) async fn list(&self, prefix: &str) -> Result<Vec<String>, StoreError> {
+        let mut keys = Vec::new();
+        let mut token: Option<String> = None;
+        loop {
+            let mut query = vec![
+                ("list-type", "2".to_string()),
+                ("prefix", prefix.to_string()),
+            ];
+            if let Some(token) = &token {
+                query.push(("continuation-token", token.clone()));
+            }
+            let request = self.signed(reqwest::Method::GET, "", &query, &[])?;
+            let response = check(request.send().await.map_err(backend)?).await?;
+            let body = response.text().await.map_err(backend)?;
+
+            keys.extend(xml_values(&body, "Key"));
+            let truncated = xml_values(&body, "IsTruncated").next().as_deref() == Some("true");
+            token = xml_values(&body, "NextContinuationToken").next();
+            if !truncated || token.is_none() {
+                break;
+            }
+        }
+        keys.sort();
+        Ok(keys)
+    }
+
+    /// Build a request for `key` (or the bucket itself if empty), signed
+    /// with SigV4.
+    fn signed(
+        &self,
+        method: reqwest::Method,
+        key: &str,
+        query: &[(&str, String)],
+        body: &[u8],
+    ) -> Result<reqwest::RequestBuilder, StoreError> {
+        let mut path = format!("/{}", uri_encode(&self.bucket, false));
+        if !key.is_empty() {
+            path.push('/');
+            path.push_str(&uri_encode(key, false));
+        }
+        let mut pairs: Vec<(String, String)> = query
+            .iter()
+            .map(|(k, v)| (uri_encode(k, true), uri_encode(v, true)))
+            .collect();
+        pairs.sort();
+        let canonical_query = pairs
+            .iter()
+            .map(|(k, v)| format!("{k}={v}"))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let mut url = format!("{}{}", self.endpoint, path);
+        if !canonical_query.is_empty() {
+            url.push('?');
+            url.push_str(&canonical_query);
+        }
+        let parsed = reqwest::Url::parse(&url)
+            .map_err(|e| StoreError::Backend(format!("invalid S3 URL {url}: {e}")))?;
+        let host = match (parsed.host_str(), parsed.port()) {
+            (Some(host), Some(port)) => format!("{host}:{port}"),
+            (Some(host), None) => host.to_string(),
+            (None, _) => {
+                return Err(StoreError::Backend(format!(
+                    "S3 endpoint has no host: {}",
+                    self.endpoint
+                )))
+            }
+        };
+
+        let amz_date = amz_date(SystemTime::now());
+        let date = &amz_date[..8];
+        let payload_hash = hex(&Sha256::digest(body));
+
+        let mut headers = vec![
+            ("host", host),
+            ("x-amz-content-sha256", payload_hash.clone()),
+            ("x-amz-date", amz_date.clone()),
+        ];
+        if let Some(token) = &self.session_token {
+            headers.push(("x-amz-security-token", token.clone()));
+        }
+        let signed_headers = headers
+            .iter()
+            .map(|(k, _)| *k)
+            .collect::<Vec<_>>()
+            .join(";");
+        let canonical_headers: String = headers
+            .iter()
+            .map(|(k, v)| format!("{k}:{}\n", v.trim()))
+            .collect();
+
+        let canonical_request = format!(
+            "{method}\n{path}\n{canonical_query}\n{canonical_headers}\n{signed_headers}\n{payload_hash}"
+        );
+        let scope = format!("{date}/{}/s3/aws4_request", self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+            hex(&Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let mut signing_key = hmac(
+            format!("AWS4{}", self.secret_access_key).as_bytes(),
+            date.as_bytes(),
+        );
+        for part in [self.region.as_str(), "s3", "aws4_request"] {
+            signing_key = hmac(&signing_key, part.as_bytes());
+        }
+        let signature = hex(&hmac(&signing_key, string_to_sign.as_bytes()));
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+            self.access_key_id
+        );
+
+        let mut request = self
+            .http
+            .request(method, parsed)
+            .header(reqwest::header::AUTHORIZATION, authorization);
+        // `host` is set by the HTTP client from the URL
+        for (name, value) in headers.into_iter().skip(1) {
+            request = request.header(name, value);
+        }
+        Ok(request)
+    }
+}
+
+/// Turn a non-2xx response into an error carrying the S3 error code.
+async fn check(response: reqwest::Response) -> Result<reqwest::Response, StoreError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    let code = xml_values(&body, "Code").next().unwrap_or_default();
+    Err(StoreError::Backend(format!(
+        "S3 request failed with {status} {code}"
+    )))
+}
+
+fn backend(err: reqwest::Error) -> StoreError {
+    StoreError::Backend(err.to_string())
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .fold(String::with_capacity(bytes.len() * 2), |mut s, b| {
+            let _ = write!(s, "{b:02x}");
+            s
+        })
+}
+
+/// Percent-encode as SigV4 requires: everything but unreserved characters,
+/// and `/` too unless encoding a path.
+fn uri_encode(s: &str, encode_slash: bool) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(b as char)
+            }
+            b'/' if !encode_slash => out.push('/'),
+            _ => {
+                let _ = write!(out, "%{b:02X}");
+            }
+        }
+    }
+    out
+}
+
+/// `time` as a SigV4 timestamp, e.g. `20240102T030405Z`.
+fn amz_date(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}{month:02}{day:02}T{:02}{:02}{:02}Z",
+        rem / 3_600,
+        rem % 3_600 / 60,
+        rem % 60
+    )
+}
+
+/// Text of every `<tag>` element in an S3 XML response, unescaped.
+fn xml_values<'a>(xml: &'a str, tag: &'a str) -> impl Iterator<Item = String> + 'a {
+    let open = format!("<{tag}>");
+    let close = format!("</{tag}>");
+    let mut rest = xml;
+    std::iter::from_fn(move || {
+        let start = rest.find(&open)? + open.len();
+        let len = rest[start..].find(&close)?;
+        let value = &rest[start..start + len];
+        rest = &rest[start + len + close.len()..];
+        Some(
+            value
+                .replace("&lt;", "<")
+                .replace("&gt;", ">")
+                .replace("&quot;", "\"")
+                .replace("&apos;", "'")
+                .replace("&amp;", "&"),
+        )
+    })
+}
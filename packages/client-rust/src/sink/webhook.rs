@@ -0,0 +1,240 @@
+//! Webhook sink: POST records to an HTTP endpoint.
+
+use super::{Sink, SinkBatch};
+use crate::handler::HandlerError;
+use crate::retry::{retry_after_from_headers, RetryConfig, MAX_RETRY_AFTER};
+use crate::rt::{sleep, unix_now};
+use crate::store::{InMemoryOffsetStore, OffsetStore};
+use crate::types::Offset;
+use async_trait::async_trait;
+use bytes::{Bytes, BytesMut};
+use hmac::{Hmac, Mac};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE};
+use sha2::Sha256;
+use std::fmt::{self, Write};
+use std::sync::Arc;
+
+/// [`Sink`] that pushes a stream to an HTTP endpoint, e.g. to notify a
+/// system that cannot consume streams itself.
+///
+/// Each chunk is POSTed as one request, or with [`batched`](Self::batched)
+/// each batch the runner gathers, with the chunks' bytes back to back.
+/// Read with [`Framing::NdJson`](crate::Framing::NdJson) to push one
+/// request per line. Every request carries `stream-start-offset` and
+/// `stream-next-offset` headers for the range it holds.
+///
+/// With [`signing_secret`](Self::signing_secret), requests also carry
+/// `webhook-timestamp` (Unix seconds) and `webhook-signature:
+/// sha256=<hex>`, an HMAC-SHA256 of `"{timestamp}.{body}"`, so the
+/// receiver can authenticate them.
+///
+/// Connection failures, 408, 429 and 5xx responses are retried with
+/// backoff, honoring `Retry-After`; other responses outside 2xx stop the
+/// runner. Delivery is at least once: the offset is committed to the
+/// checkpoint store after each successful request, and a request that
+/// succeeded just before a crash is sent again. Receivers deduplicate on
+/// `stream-next-offset`; framed records from one response share it, as
+/// they resume from that response's start.
+///
+/// # Example
+///
+/// ```ignore
+/// let pusher = WebhookPusher::new("https://legacy.internal/hooks/orders")
+///     .signing_secret(std::env::var("WEBHOOK_SECRET")?)
+///     .checkpoint(store, "orders-webhook");
+/// SinkRunner::new(stream.read().live(LiveMode::Sse), pusher)
+///     .run(tokio::signal::ctrl_c().map(|_| ()))
+///     .await?;
+/// ```
+pub struct WebhookPusher {
+    url: String,
+    http: reqwest::Client,
+    headers: HeaderMap,
+    content_type: HeaderValue,
+    secret: Option<Vec<u8>>,
+    batched: bool,
+    retry: RetryConfig,
+    store: Arc<dyn OffsetStore>,
+    key: String,
+}
+
+impl WebhookPusher {
+    /// Push records to `url`.
+    ///
+    /// The checkpoint is kept in memory until set with
+    /// [`checkpoint`](Self::checkpoint).
+    pub fn new(url: impl Into<String>) -> Self {
+        let url = url.into();
+        Self {
+            key: url.clone(),
+            url,
+            http: reqwest::Client::new(),
+            headers: HeaderMap::new(),
+            content_type: HeaderValue::from_static("application/octet-stream"),
+            secret: None,
+            batched: false,
+            retry: RetryConfig::default(),
+            store: Arc::new(InMemoryOffsetStore::new()),
+        }
+    }
+
+    /// Send requests with this HTTP client, e.g. one with custom TLS.
+    pub fn http_client(mut self, client: reqwest::Client) -> Self {
+        self.http = client;
+        self
+    }
+
+    /// Add a header to every request.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `key` or `value` is not a valid header.
+    pub fn header(mut self, key: &str, value: &str) -> Self {
+        let key = HeaderName::try_from(key).expect("invalid webhook header name");
+        let value = HeaderValue::try_from(value).expect("invalid webhook header value");
+        self.headers.insert(key, value);
+        self
+    }
+
+    /// `Content-Type` of the requests (default `application/octet-stream`).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `content_type` is not a valid header value.
+    pub fn content_type(mut self, content_type: &str) -> Self {
+        self.content_type =
+            HeaderValue::try_from(content_type).expect("invalid webhook content type");
+        self
+    }
+
+    /// Sign requests with an HMAC-SHA256 of the timestamp and body.
+    pub fn signing_secret(mut self, secret: impl AsRef<[u8]>) -> Self {
+        self.secret = Some(secret.as_ref().to_vec());
+        self
+    }
+
+    /// Send one request per batch instead of one per chunk.
+    pub fn batched(mut self) -> Self {
+        self.batched = true;
+        self
+    }
+
+    /// Backoff and retry limit for failed requests.
+    pub fn retry(mut self, retry: RetryConfig) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Persist the offset pushed up to in `store` under `key`, so a
+    /// restarted pusher resumes where it left off.
+    pub fn checkpoint(mut self, store: Arc<dyn OffsetStore>, key: impl Into<String>) -> Self {
+        self.store = store;
+        self.key = key.into();
+        self
+    }
+
+    /// POST `body`, retrying transient failures.
+    async fn push(&self, body: Bytes, start: &Offset, next: &Offset) -> Result<(), HandlerError> {
+        let mut attempt = 0;
+        loop {
+            let mut req = self
+                .http
+                .post(&self.url)
+                .headers(self.headers.clone())
+                .header(CONTENT_TYPE, self.content_type.clone())
+                .header("stream-start-offset", start.to_string())
+                .header("stream-next-offset", next.to_string());
+            if let Some(secret) = &self.secret {
+                let timestamp = unix_now().as_secs().to_string();
+                req = req
+                    .header("webhook-timestamp", &timestamp)
+                    .header("webhook-signature", signature(secret, &timestamp, &body));
+            }
+
+            let retry_after = match req.body(body.clone()).send().await {
+                Ok(resp) if resp.status().is_success() => return Ok(()),
+                Ok(resp) => {
+                    let status = resp.status().as_u16();
+                    if !matches!(status, 408 | 429 | 500..=599) || attempt >= self.retry.max_retries
+                    {
+                        return Err(format!("webhook {} answered {status}", self.url).into());
+                    }
+                    retry_after_from_headers(resp.headers())
+                }
+                Err(err) if attempt < self.retry.max_retries && !err.is_builder() => None,
+                Err(err) => return Err(Box::new(err)),
+            };
+            attempt += 1;
+            let delay = match retry_after {
+                Some(after) => after.min(MAX_RETRY_AFTER),
+                None => self.retry.apply_jitter(self.retry.backoff(attempt)),
+            };
+            sleep(delay).await;
+        }
+    }
+}
+
+#[async_trait]
+impl Sink for WebhookPusher {
+    async fn load_checkpoint(&mut self) -> Result<Option<Offset>, HandlerError> {
+        Ok(self.store.load(&self.key).await?)
+    }
+
+    async fn apply(&mut self, batch: &SinkBatch) -> Result<(), HandlerError> {
+        if self.batched {
+            let mut body = BytesMut::with_capacity(batch.total_bytes());
+            for chunk in &batch.chunks {
+                body.extend_from_slice(&chunk.data);
+            }
+            return self
+                .push(body.freeze(), &batch.start_offset, &batch.next_offset)
+                .await;
+        }
+
+        let mut start = batch.start_offset.clone();
+        for chunk in &batch.chunks {
+            self.push(chunk.data.clone(), &start, &chunk.next_offset)
+                .await?;
+            // Commit per request, so a failure later in the batch does not
+            // resend what was already delivered
+            self.store.commit(&self.key, &chunk.next_offset).await?;
+            start = chunk.next_offset.clone();
+        }
+        Ok(())
+    }
+
+    async fn store_checkpoint(&mut self, offset: &Offset) -> Result<(), HandlerError> {
+        Ok(self.store.commit(&self.key, offset).await?)
+    }
+
+    async fn reset(&mut self) -> Result<(), HandlerError> {
+        // Everything still retained comes after the expired checkpoint, so
+        // replaying from the beginning sends nothing twice
+        Ok(())
+    }
+}
+
+impl fmt::Debug for WebhookPusher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebhookPusher")
+            .field("url", &self.url)
+            .field("signed", &self.secret.is_some())
+            .field("batched", &self.batched)
+            .field("retry", &self.retry)
+            .field("key", &self.key)
+            .finish_non_exhaustive()
+    }
+}
+
+/// `sha256=<hex>` HMAC of `"{timestamp}.{body}"`.
+fn signature(secret: &[u8], timestamp: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    let digest = mac.finalize().into_bytes();
+    digest.iter().fold(String::from("sha256="), |mut s, b| {
+        let _ = write!(s, "{b:02x}");
+        s
+    })
+}
@@ -0,0 +1,223 @@
+//! Snapshots of materialized state in a companion stream.
+
+use crate::error::StreamError;
+use crate::materializer::{Materializer, Reducer};
+use crate::rt;
+use crate::stream::{AppendResponse, CreateOptions, DurableStream};
+use crate::typed::{decode_json, DecodeLimits};
+use crate::types::{LiveMode, Offset};
+use bytes::Bytes;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::time::Duration;
+
+const JSON: &str = "application/json";
+
+/// State folded from a changelog up to an offset, as stored in a snapshot
+/// stream.
+///
+/// Each snapshot is one JSON value in the snapshot stream:
+/// `{"offset": "<changelog offset>", "state": <state>}`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Snapshot<S> {
+    /// Changelog offset the state includes every record before.
+    pub offset: Offset,
+    /// The folded state.
+    pub state: S,
+}
+
+impl<S> Snapshot<S> {
+    /// Snapshot of `state` folded up to `offset`.
+    pub fn new(offset: Offset, state: S) -> Self {
+        Self { offset, state }
+    }
+}
+
+/// Periodically appends a [`Materializer`]'s state to a snapshot stream.
+///
+/// The snapshot stream is created as `application/json` if needed and
+/// should only be written by one writer. Each snapshot is a complete copy
+/// of the state, so give the snapshot stream a TTL or retention policy that
+/// keeps the last few.
+///
+/// # Example
+///
+/// ```ignore
+/// let balances = Materializer::new(changelog.clone(), Balances);
+/// tokio::spawn({
+///     let balances = balances.clone();
+///     async move { balances.run(std::future::pending()).await }
+/// });
+///
+/// SnapshotWriter::new(client.stream("/balances.snapshots"))
+///     .interval(Duration::from_secs(60))
+///     .run(&balances, shutdown_signal())
+///     .await?;
+/// ```
+#[derive(Clone, Debug)]
+pub struct SnapshotWriter {
+    snapshots: DurableStream,
+    interval: Duration,
+}
+
+impl SnapshotWriter {
+    /// Write snapshots to `snapshots`.
+    pub fn new(mut snapshots: DurableStream) -> Self {
+        snapshots.content_type = Some(JSON.to_string());
+        Self {
+            snapshots,
+            interval: Duration::from_secs(300),
+        }
+    }
+
+    /// How often to write a snapshot while the state changes (default 5
+    /// minutes).
+    #[must_use]
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Append one snapshot, creating the snapshot stream if needed.
+    pub async fn write<S: Serialize>(
+        &self,
+        snapshot: &Snapshot<S>,
+    ) -> Result<AppendResponse, StreamError> {
+        let data = Bytes::from(serde_json::to_vec(snapshot)?);
+        match self.snapshots.append(data.clone()).await {
+            Err(StreamError::NotFound { .. }) => {
+                self.snapshots
+                    .create_with(CreateOptions::new().content_type(JSON))
+                    .await?;
+                self.snapshots.append(data).await
+            }
+            result => result,
+        }
+    }
+
+    /// Snapshot `materializer` every [`interval`](Self::interval) until
+    /// `shutdown` resolves, then once more. A snapshot is only written when
+    /// the state has advanced past the previous one, or past the snapshot
+    /// the materializer resumed from.
+    pub async fn run<T, R, S>(
+        &self,
+        materializer: &Materializer<T, R>,
+        shutdown: S,
+    ) -> Result<(), StreamError>
+    where
+        T: DeserializeOwned + Send + 'static,
+        R: Reducer<T>,
+        R::State: Serialize,
+        S: Future<Output = ()>,
+    {
+        tokio::pin!(shutdown);
+        // The materializer's starting point is either the beginning or an
+        // existing snapshot
+        let mut written = materializer.start_offset().clone();
+        loop {
+            let stop = tokio::select! {
+                _ = &mut shutdown => true,
+                _ = rt::sleep(self.interval) => false,
+            };
+
+            let current = materializer.snapshot();
+            if current.offset != written {
+                self.write(&Snapshot::new(current.offset.clone(), &*current.state))
+                    .await?;
+                written = current.offset;
+            }
+            if stop {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Bootstraps state from the latest snapshot in a snapshot stream, then
+/// tails the changelog from the snapshot's offset.
+///
+/// Cold start reads the snapshot stream plus the changelog records written
+/// since the latest snapshot, instead of the whole changelog.
+///
+/// # Example
+///
+/// ```ignore
+/// let balances = SnapshotReader::new(changelog, client.stream("/balances.snapshots"))
+///     .materializer(Balances)
+///     .await?;
+/// tokio::spawn({
+///     let balances = balances.clone();
+///     async move { balances.run(shutdown_signal()).await }
+/// });
+/// ```
+#[derive(Clone, Debug)]
+pub struct SnapshotReader {
+    changelog: DurableStream,
+    snapshots: DurableStream,
+    limits: DecodeLimits,
+}
+
+impl SnapshotReader {
+    /// Read snapshots of `changelog` from `snapshots`.
+    pub fn new(changelog: DurableStream, snapshots: DurableStream) -> Self {
+        Self {
+            changelog,
+            snapshots,
+            limits: DecodeLimits::default(),
+        }
+    }
+
+    /// Limits applied when decoding a snapshot (default
+    /// [`DecodeLimits::default`]).
+    #[must_use]
+    pub fn decode_limits(mut self, limits: DecodeLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// The newest snapshot, or `None` if there is none yet.
+    ///
+    /// Reads the snapshot stream to its end but only decodes the last
+    /// snapshot.
+    pub async fn latest<S: DeserializeOwned>(&self) -> Result<Option<Snapshot<S>>, StreamError> {
+        let mut reader = self
+            .snapshots
+            .read()
+            .offset(Offset::Beginning)
+            .live(LiveMode::Off)
+            .build()?;
+        let mut last = None;
+        loop {
+            match reader.next_chunk().await {
+                Ok(Some(chunk)) if !chunk.data.iter().all(u8::is_ascii_whitespace) => {
+                    last = Some(chunk.data);
+                }
+                Ok(Some(_)) => {}
+                Ok(None) => break,
+                Err(StreamError::NotFound { .. }) => return Ok(None),
+                Err(e) => return Err(e),
+            }
+        }
+        match last {
+            Some(data) => Ok(decode_json::<Snapshot<S>>(&data, &self.limits)?.pop()),
+            None => Ok(None),
+        }
+    }
+
+    /// A [`Materializer`] of the changelog resuming from the latest
+    /// snapshot, or replaying from the beginning if there is none.
+    pub async fn materializer<T, R>(&self, reducer: R) -> Result<Materializer<T, R>, StreamError>
+    where
+        T: DeserializeOwned + Send + 'static,
+        R: Reducer<T>,
+        R::State: DeserializeOwned,
+    {
+        let materializer = Materializer::new(self.changelog.clone(), reducer);
+        Ok(match self.latest().await? {
+            Some(snapshot) => materializer.resume_from(snapshot),
+            None => materializer,
+        })
+    }
+}
@@ -0,0 +1,154 @@
+//! Readers running in a background task.
+
+use crate::error::StreamError;
+use crate::iterator::{Chunk, ChunkIterator, ReadBuilder};
+use crate::retry::sleep_before_retry;
+use crate::rt;
+use crate::types::Offset;
+use tokio::sync::{mpsc, oneshot, watch};
+
+impl ReadBuilder {
+    /// Build the reader and run it in a background task that sends each
+    /// chunk to the returned channel.
+    ///
+    /// The channel holds up to `capacity` chunks. When it is full the
+    /// reader waits, so a slow consumer slows the read down rather than
+    /// buffering without bound. Receiving from a channel is cancellation
+    /// safe, which makes it a natural `tokio::select!` branch.
+    ///
+    /// Retryable errors are retried with the client's backoff; any other
+    /// error is sent as the last item. The reader stops then, when the
+    /// stream ends, or when the [`ReaderHandle`] or the receiver is
+    /// dropped, and the channel closes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let (reader, mut chunks) = stream.read().live(LiveMode::Sse).spawn(64)?;
+    ///
+    /// loop {
+    ///     tokio::select! {
+    ///         Some(chunk) = chunks.recv() => process(chunk?),
+    ///         _ = shutdown.recv() => {
+    ///             let offset = reader.stop().await;
+    ///             save_checkpoint(&offset);
+    ///             break;
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn spawn(
+        self,
+        capacity: usize,
+    ) -> Result<(ReaderHandle, mpsc::Receiver<Result<Chunk, StreamError>>), StreamError> {
+        let iter = self.build()?;
+        let (tx, rx) = mpsc::channel(capacity);
+        let (paused_tx, paused_rx) = watch::channel(false);
+        let (stop_tx, stop_rx) = oneshot::channel();
+        let (offset_tx, offset_rx) = oneshot::channel();
+        let handle = ReaderHandle {
+            paused: paused_tx,
+            stop: Some(stop_tx),
+            offset: offset_rx,
+        };
+        rt::spawn(run(iter, tx, paused_rx, stop_rx, offset_tx));
+        Ok((handle, rx))
+    }
+}
+
+/// Controls a reader started with [`ReadBuilder::spawn`]. Dropping it
+/// stops the reader.
+#[derive(Debug)]
+pub struct ReaderHandle {
+    paused: watch::Sender<bool>,
+    /// Dropped to stop the reader.
+    stop: Option<oneshot::Sender<()>>,
+    /// Offset after the last chunk sent, reported when the reader ends.
+    offset: oneshot::Receiver<Offset>,
+}
+
+impl ReaderHandle {
+    /// Stop reading after the chunk being fetched, if any, until
+    /// [`resume`](Self::resume) is called. Open connections stay open,
+    /// so a paused live read may time out and reconnect on resume.
+    pub fn pause(&self) {
+        self.paused.send_replace(true);
+    }
+
+    /// Continue reading after [`pause`](Self::pause).
+    pub fn resume(&self) {
+        self.paused.send_replace(false);
+    }
+
+    /// Whether the reader is paused.
+    pub fn is_paused(&self) -> bool {
+        *self.paused.borrow()
+    }
+
+    /// Stop the reader, aborting any request in flight, and return the
+    /// offset after the last chunk sent to the channel. Chunks already in
+    /// the channel can still be received.
+    pub async fn stop(mut self) -> Offset {
+        self.stop = None;
+        (&mut self.offset).await.unwrap_or_default()
+    }
+}
+
+async fn run(
+    mut iter: ChunkIterator,
+    tx: mpsc::Sender<Result<Chunk, StreamError>>,
+    mut paused: watch::Receiver<bool>,
+    mut stop: oneshot::Receiver<()>,
+    offset: oneshot::Sender<Offset>,
+) {
+    let mut failures = 0;
+    loop {
+        if *paused.borrow_and_update() {
+            tokio::select! {
+                _ = &mut stop => break,
+                _ = tx.closed() => break,
+                resumed = paused.wait_for(|paused| !paused) => match resumed {
+                    Ok(_) => continue,
+                    Err(_) => break,
+                },
+            }
+        }
+        let next = tokio::select! {
+            _ = &mut stop => break,
+            _ = tx.closed() => break,
+            next = iter.next_chunk() => next,
+        };
+        let item = match next {
+            Ok(Some(chunk)) => {
+                failures = 0;
+                Ok(chunk)
+            }
+            Ok(None) => break,
+            Err(err) if err.is_retryable() => {
+                failures += 1;
+                let stream = iter.stream();
+                tokio::select! {
+                    _ = &mut stop => break,
+                    _ = sleep_before_retry(&stream.client, &stream.url, failures, &err) => {}
+                }
+                continue;
+            }
+            Err(err) => Err(err),
+        };
+        let failed = item.is_err();
+        // Waiting for room in the channel is the backpressure
+        tokio::select! {
+            _ = &mut stop => break,
+            sent = tx.send(item) => if sent.is_err() { break },
+        }
+        if failed {
+            break;
+        }
+    }
+    iter.close().await;
+    let _ = offset.send(iter.offset().clone());
+}
@@ -0,0 +1,284 @@
+//! Server-sent events parsing.
+
+use crate::error::StreamError;
+use crate::rt::{sleep, BodyReader};
+use bytes::{Buf, Bytes, BytesMut};
+use std::time::Duration;
+
+/// UTF-8 byte order mark, skipped at the start of a stream.
+const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// One event from a `text/event-stream`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SseEvent {
+    /// Value of the `event:` field; `None` for the default `message` type.
+    pub event: Option<String>,
+    /// The `data:` lines, joined with `\n`. Bytes are kept as sent, so
+    /// payloads that are not valid UTF-8 arrive intact.
+    pub data: Bytes,
+    /// Last event ID of the stream when this event was dispatched: the
+    /// most recent non-empty `id:` field, from this or an earlier event.
+    pub id: Option<String>,
+}
+
+/// Incremental `text/event-stream` parser.
+///
+/// Feed it bytes as they arrive with [`push`](Self::push), in pieces of any
+/// size, then drain complete events with [`next_event`](Self::next_event).
+/// Lines may end in `\n`, `\r\n` or `\r`, as the SSE specification allows.
+/// Comments and unknown fields are skipped, and events without data lines
+/// are not dispatched.
+///
+/// # Example
+///
+/// ```ignore
+/// let mut parser = SseParser::new();
+/// parser.push(b"event: greeting\ndata: hel");
+/// parser.push(b"lo\n\n");
+/// let event = parser.next_event().unwrap();
+/// assert_eq!(event.data, "hello");
+/// ```
+#[derive(Debug, Default)]
+pub struct SseParser {
+    /// Bytes not yet split into lines.
+    buffer: BytesMut,
+    /// Whether the last line ended in a `\r` at the end of the buffer, so
+    /// a `\n` arriving next completes that line ending.
+    after_cr: bool,
+    /// Whether a leading byte order mark has been checked for.
+    started: bool,
+    event: Option<String>,
+    /// Data lines of the event being built, joined on dispatch.
+    data: Vec<Bytes>,
+    last_event_id: Option<String>,
+    retry: Option<Duration>,
+}
+
+impl SseParser {
+    /// A parser at the start of a stream.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add bytes received from the stream.
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// The next complete event, or `None` until more bytes are pushed.
+    pub fn next_event(&mut self) -> Option<SseEvent> {
+        if !self.started {
+            if self.buffer.len() < BOM.len() && BOM.starts_with(&self.buffer) {
+                return None;
+            }
+            if self.buffer.starts_with(BOM) {
+                self.buffer.advance(BOM.len());
+            }
+            self.started = true;
+        }
+
+        while let Some(line) = self.next_line() {
+            if line.is_empty() {
+                if let Some(event) = self.dispatch() {
+                    return Some(event);
+                }
+            } else {
+                self.field(line);
+            }
+        }
+        None
+    }
+
+    /// The most recent non-empty `id:` field, to send as `Last-Event-ID`
+    /// when reconnecting.
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.last_event_id.as_deref()
+    }
+
+    /// The most recent `retry:` field: how long the server asks clients to
+    /// wait before reconnecting.
+    pub fn retry(&self) -> Option<Duration> {
+        self.retry
+    }
+
+    /// Split the next complete line off the buffer, without its ending.
+    fn next_line(&mut self) -> Option<Bytes> {
+        if self.after_cr && !self.buffer.is_empty() {
+            if self.buffer[0] == b'\n' {
+                self.buffer.advance(1);
+            }
+            self.after_cr = false;
+        }
+
+        let end = self.buffer.iter().position(|&b| b == b'\n' || b == b'\r')?;
+        let line = self.buffer.split_to(end).freeze();
+        if self.buffer[0] == b'\r' {
+            match self.buffer.get(1) {
+                Some(b'\n') => self.buffer.advance(2),
+                Some(_) => self.buffer.advance(1),
+                None => {
+                    self.buffer.advance(1);
+                    self.after_cr = true;
+                }
+            }
+        } else {
+            self.buffer.advance(1);
+        }
+        Some(line)
+    }
+
+    /// Apply one non-empty line.
+    fn field(&mut self, line: Bytes) {
+        if line[0] == b':' {
+            // Comment, e.g. a heartbeat
+            return;
+        }
+        let (name, value) = match line.iter().position(|&b| b == b':') {
+            Some(colon) => {
+                let mut value = line.slice(colon + 1..);
+                // Strip exactly one leading space
+                if value.first() == Some(&b' ') {
+                    value.advance(1);
+                }
+                (line.slice(..colon), value)
+            }
+            None => (line, Bytes::new()),
+        };
+
+        match &name[..] {
+            b"event" => {
+                self.event =
+                    (!value.is_empty()).then(|| String::from_utf8_lossy(&value).into_owned());
+            }
+            b"data" => self.data.push(value),
+            // IDs containing NUL are ignored; an empty ID resets it
+            b"id" if !value.contains(&0) => {
+                self.last_event_id =
+                    (!value.is_empty()).then(|| String::from_utf8_lossy(&value).into_owned());
+            }
+            b"retry" if !value.is_empty() && value.iter().all(u8::is_ascii_digit) => {
+                // Too large to parse as milliseconds: keep the previous value
+                if let Some(ms) = std::str::from_utf8(&value)
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                {
+                    self.retry = Some(Duration::from_millis(ms));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Finish the event being built at a blank line.
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        let event = self.event.take();
+        let data = match self.data.len() {
+            0 => return None,
+            1 => self.data.pop().unwrap_or_default(),
+            _ => {
+                let mut joined = BytesMut::new();
+                for (i, line) in self.data.drain(..).enumerate() {
+                    if i > 0 {
+                        joined.extend_from_slice(b"\n");
+                    }
+                    joined.extend_from_slice(&line);
+                }
+                joined.freeze()
+            }
+        };
+        Some(SseEvent {
+            event,
+            data,
+            id: self.last_event_id.clone(),
+        })
+    }
+}
+
+/// Reads [`SseEvent`]s from a `text/event-stream` response.
+///
+/// Works with any `reqwest` response, so it can parse event streams from
+/// other servers too.
+///
+/// # Example
+///
+/// ```ignore
+/// let resp = reqwest::get("https://example.com/events").await?;
+/// let mut events = SseReader::new(resp).stall_timeout(Duration::from_secs(45));
+/// while let Some(event) = events.next_event().await? {
+///     println!("{:?}: {} bytes", event.event, event.data.len());
+/// }
+/// ```
+pub struct SseReader {
+    body: BodyReader,
+    parser: SseParser,
+    stall_timeout: Option<Duration>,
+}
+
+impl SseReader {
+    /// Read events from the body of `response`.
+    pub fn new(response: reqwest::Response) -> Self {
+        Self {
+            body: BodyReader::new(response),
+            parser: SseParser::new(),
+            stall_timeout: None,
+        }
+    }
+
+    /// Fail with [`StreamError::Timeout`] if the connection sends nothing
+    /// for `timeout`. Any bytes count as activity, including comments.
+    #[must_use]
+    pub fn stall_timeout(mut self, timeout: Duration) -> Self {
+        self.stall_timeout = Some(timeout);
+        self
+    }
+
+    /// Start from the last event ID of an earlier connection, as sent in
+    /// its `Last-Event-ID` header, so it carries over until this
+    /// connection sends an `id:` of its own.
+    #[must_use]
+    pub fn last_event_id_from(mut self, id: Option<String>) -> Self {
+        self.parser.last_event_id = id;
+        self
+    }
+
+    /// See [`SseParser::last_event_id`].
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.parser.last_event_id()
+    }
+
+    /// See [`SseParser::retry`].
+    pub fn retry(&self) -> Option<Duration> {
+        self.parser.retry()
+    }
+
+    /// The next event, or `None` once the server closes the connection. An
+    /// event cut off by the close is discarded.
+    pub async fn next_event(&mut self) -> Result<Option<SseEvent>, StreamError> {
+        loop {
+            if let Some(event) = self.parser.next_event() {
+                return Ok(Some(event));
+            }
+            let read = match self.stall_timeout {
+                Some(timeout) => tokio::select! {
+                    read = self.body.chunk() => read,
+                    _ = sleep(timeout) => return Err(StreamError::Timeout),
+                },
+                None => self.body.chunk().await,
+            };
+            match read? {
+                Some(bytes) => self.parser.push(&bytes),
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for SseReader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SseReader")
+            .field("parser", &self.parser)
+            .field("stall_timeout", &self.stall_timeout)
+            .finish_non_exhaustive()
+    }
+}
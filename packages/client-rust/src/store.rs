@@ -0,0 +1,63 @@
+//! Persistence for consumer checkpoints and producer state.
+//!
+//! Resumable consumers need to remember the last offset they processed, and
+//! idempotent producers need to remember their epoch across restarts so a new
+//! incarnation fences off the old one. These traits let that state live in any
+//! backend. The crate ships [`FileOffsetStore`] and [`InMemoryOffsetStore`],
+//! plus an embedded SQLite implementation behind the `sqlite` feature.
+
+use crate::error::StoreError;
+use crate::types::Offset;
+use async_trait::async_trait;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod file;
+mod memory;
+#[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+mod sqlite;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use file::FileOffsetStore;
+pub use memory::InMemoryOffsetStore;
+#[cfg(all(feature = "sqlite", not(target_arch = "wasm32")))]
+pub use sqlite::{SqliteStateStore, SqliteStateStoreBuilder};
+
+/// Durable storage for consumer offsets, keyed by stream.
+///
+/// `stream_id` is an opaque key chosen by the caller - typically the stream
+/// URL, optionally combined with a consumer name when several consumers share
+/// a store.
+#[async_trait]
+pub trait OffsetStore: Send + Sync {
+    /// Load the last committed offset for a stream, if any.
+    async fn load(&self, stream_id: &str) -> Result<Option<Offset>, StoreError>;
+
+    /// Record that everything before `offset` has been processed.
+    async fn commit(&self, stream_id: &str, offset: &Offset) -> Result<(), StoreError>;
+}
+
+/// Persisted state of an idempotent producer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProducerCheckpoint {
+    /// Epoch the producer was last running with.
+    pub epoch: u64,
+    /// Next sequence number the producer would have used in that epoch.
+    pub next_seq: u64,
+}
+
+/// Durable storage for producer epoch/sequence state, keyed by producer ID.
+#[async_trait]
+pub trait ProducerStateStore: Send + Sync {
+    /// Load the last persisted state for a producer, if any.
+    async fn load_producer(
+        &self,
+        producer_id: &str,
+    ) -> Result<Option<ProducerCheckpoint>, StoreError>;
+
+    /// Persist the current state for a producer.
+    async fn store_producer(
+        &self,
+        producer_id: &str,
+        checkpoint: &ProducerCheckpoint,
+    ) -> Result<(), StoreError>;
+}
@@ -0,0 +1,82 @@
+//! JSON-file offset store.
+
+use super::OffsetStore;
+use crate::error::StoreError;
+use crate::types::Offset;
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// Offset store that persists checkpoints to a single JSON file.
+///
+/// The file maps stream IDs to offset strings. Every commit rewrites the file
+/// atomically (write to a temporary sibling, then rename), so a crash never
+/// leaves a half-written checkpoint behind.
+#[derive(Debug)]
+pub struct FileOffsetStore {
+    path: PathBuf,
+    // Serializes read-modify-write cycles within this process
+    lock: tokio::sync::Mutex<()>,
+}
+
+impl FileOffsetStore {
+    /// Use the JSON file at `path`, which is created on first commit.
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Get the path of the backing file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[async_trait]
+impl OffsetStore for FileOffsetStore {
+    async fn load(&self, stream_id: &str) -> Result<Option<Offset>, StoreError> {
+        let _guard = self.lock.lock().await;
+        let path = self.path.clone();
+        let offsets = tokio::task::spawn_blocking(move || read_offsets(&path))
+            .await
+            .map_err(|e| StoreError::Backend(e.to_string()))??;
+        Ok(offsets.get(stream_id).map(|s| Offset::parse(s)))
+    }
+
+    async fn commit(&self, stream_id: &str, offset: &Offset) -> Result<(), StoreError> {
+        let _guard = self.lock.lock().await;
+        let path = self.path.clone();
+        let stream_id = stream_id.to_string();
+        let offset = offset.to_query_value().to_string();
+        tokio::task::spawn_blocking(move || {
+            let mut offsets = read_offsets(&path)?;
+            offsets.insert(stream_id, offset);
+            write_offsets(&path, &offsets)
+        })
+        .await
+        .map_err(|e| StoreError::Backend(e.to_string()))?
+    }
+}
+
+fn read_offsets(path: &Path) -> Result<BTreeMap<String, String>, StoreError> {
+    match std::fs::read(path) {
+        Ok(bytes) => serde_json::from_slice(&bytes)
+            .map_err(|e| StoreError::Corrupt(format!("{}: {}", path.display(), e))),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn write_offsets(path: &Path, offsets: &BTreeMap<String, String>) -> Result<(), StoreError> {
+    let json =
+        serde_json::to_vec_pretty(offsets).map_err(|e| StoreError::Backend(e.to_string()))?;
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    std::fs::write(&tmp, json)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
@@ -0,0 +1,38 @@
+//! In-memory offset store.
+
+use super::OffsetStore;
+use crate::error::StoreError;
+use crate::types::Offset;
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+
+/// Offset store that keeps checkpoints in process memory.
+///
+/// Nothing survives a restart, so this is mainly useful for tests and for
+/// sharing progress between readers within one process.
+#[derive(Debug, Default)]
+pub struct InMemoryOffsetStore {
+    offsets: Mutex<HashMap<String, Offset>>,
+}
+
+impl InMemoryOffsetStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl OffsetStore for InMemoryOffsetStore {
+    async fn load(&self, stream_id: &str) -> Result<Option<Offset>, StoreError> {
+        Ok(self.offsets.lock().get(stream_id).cloned())
+    }
+
+    async fn commit(&self, stream_id: &str, offset: &Offset) -> Result<(), StoreError> {
+        self.offsets
+            .lock()
+            .insert(stream_id.to_string(), offset.clone());
+        Ok(())
+    }
+}
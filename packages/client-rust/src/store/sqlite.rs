@@ -0,0 +1,275 @@
+//! Embedded SQLite implementation of the state store traits.
+
+use super::{OffsetStore, ProducerCheckpoint, ProducerStateStore};
+use crate::error::StoreError;
+use crate::types::Offset;
+use async_trait::async_trait;
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Schema migrations, applied in order and tracked via `PRAGMA user_version`.
+///
+/// Never edit an entry once released - append a new one instead.
+const MIGRATIONS: &[&str] = &[
+    // v1: consumer offsets and producer epoch/seq
+    "CREATE TABLE consumer_offsets (
+        stream_id   TEXT PRIMARY KEY NOT NULL,
+        next_offset TEXT NOT NULL,
+        updated_at  INTEGER NOT NULL
+    );
+    CREATE TABLE producer_state (
+        producer_id TEXT PRIMARY KEY NOT NULL,
+        epoch       INTEGER NOT NULL,
+        next_seq    INTEGER NOT NULL,
+        updated_at  INTEGER NOT NULL
+    );",
+];
+
+/// Checkpoint and producer-state store backed by a single SQLite file.
+///
+/// Implements both [`OffsetStore`] and [`ProducerStateStore`], so one file
+/// holds all durable client state for a small service. Connections are pooled
+/// and all queries run on tokio's blocking thread pool.
+///
+/// # Example
+///
+/// ```ignore
+/// let store = Arc::new(SqliteStateStore::open("state.db")?);
+///
+/// let producer = stream.producer("ingest-1")
+///     .state_store(store.clone())
+///     .restore()
+///     .await?;
+/// ```
+#[derive(Clone)]
+pub struct SqliteStateStore {
+    pool: Pool<SqliteConnectionManager>,
+}
+
+impl std::fmt::Debug for SqliteStateStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SqliteStateStore")
+            .field("connections", &self.pool.state().connections)
+            .finish()
+    }
+}
+
+impl SqliteStateStore {
+    /// Open (or create) a store at `path` with default settings.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, StoreError> {
+        Self::builder(path).build()
+    }
+
+    /// Open a private in-memory store (useful for tests).
+    ///
+    /// The pool is limited to one connection because every SQLite in-memory
+    /// connection is a separate database.
+    pub fn in_memory() -> Result<Self, StoreError> {
+        let pool = Pool::builder()
+            .max_size(1)
+            .build(SqliteConnectionManager::memory())
+            .map_err(backend)?;
+        Self::from_pool(pool)
+    }
+
+    /// Create a builder for a store at `path`.
+    pub fn builder(path: impl AsRef<Path>) -> SqliteStateStoreBuilder {
+        SqliteStateStoreBuilder::new(path.as_ref().to_path_buf())
+    }
+
+    fn from_pool(pool: Pool<SqliteConnectionManager>) -> Result<Self, StoreError> {
+        let mut conn = pool.get().map_err(backend)?;
+        migrate(&mut conn)?;
+        Ok(Self { pool })
+    }
+
+    /// Run a blocking query on a pooled connection.
+    async fn with_conn<T, F>(&self, f: F) -> Result<T, StoreError>
+    where
+        F: FnOnce(&Connection) -> rusqlite::Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let pool = self.pool.clone();
+        tokio::task::spawn_blocking(move || {
+            let conn = pool.get().map_err(backend)?;
+            f(&conn).map_err(backend)
+        })
+        .await
+        .map_err(backend)?
+    }
+}
+
+/// Builder for [`SqliteStateStore`].
+#[must_use = "builders do nothing unless you call .build()"]
+pub struct SqliteStateStoreBuilder {
+    path: PathBuf,
+    max_connections: u32,
+    busy_timeout: Duration,
+}
+
+impl SqliteStateStoreBuilder {
+    fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            max_connections: 4,
+            busy_timeout: Duration::from_secs(5),
+        }
+    }
+
+    /// Set the maximum number of pooled connections.
+    pub fn max_connections(mut self, n: u32) -> Self {
+        self.max_connections = n;
+        self
+    }
+
+    /// Set how long a writer waits for a lock held by another connection.
+    pub fn busy_timeout(mut self, timeout: Duration) -> Self {
+        self.busy_timeout = timeout;
+        self
+    }
+
+    /// Open the database, creating it if needed, and apply pending migrations.
+    pub fn build(self) -> Result<SqliteStateStore, StoreError> {
+        let busy_timeout = self.busy_timeout;
+        let manager = SqliteConnectionManager::file(&self.path).with_init(move |conn| {
+            conn.busy_timeout(busy_timeout)?;
+            conn.pragma_update(None, "journal_mode", "WAL")?;
+            conn.pragma_update(None, "synchronous", "NORMAL")
+        });
+        let pool = Pool::builder()
+            .max_size(self.max_connections.max(1))
+            .build(manager)
+            .map_err(backend)?;
+        SqliteStateStore::from_pool(pool)
+    }
+}
+
+#[async_trait]
+impl OffsetStore for SqliteStateStore {
+    async fn load(&self, stream_id: &str) -> Result<Option<Offset>, StoreError> {
+        let stream_id = stream_id.to_string();
+        let offset: Option<String> = self
+            .with_conn(move |conn| {
+                conn.query_row(
+                    "SELECT next_offset FROM consumer_offsets WHERE stream_id = ?1",
+                    params![stream_id],
+                    |row| row.get(0),
+                )
+                .optional()
+            })
+            .await?;
+        Ok(offset.map(|s| Offset::parse(&s)))
+    }
+
+    async fn commit(&self, stream_id: &str, offset: &Offset) -> Result<(), StoreError> {
+        let stream_id = stream_id.to_string();
+        let offset = offset.to_query_value().to_string();
+        self.with_conn(move |conn| {
+            conn.execute(
+                "INSERT INTO consumer_offsets (stream_id, next_offset, updated_at)
+                 VALUES (?1, ?2, ?3)
+                 ON CONFLICT(stream_id) DO UPDATE SET
+                     next_offset = excluded.next_offset,
+                     updated_at = excluded.updated_at",
+                params![stream_id, offset, unix_now()],
+            )
+            .map(|_| ())
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl ProducerStateStore for SqliteStateStore {
+    async fn load_producer(
+        &self,
+        producer_id: &str,
+    ) -> Result<Option<ProducerCheckpoint>, StoreError> {
+        let producer_id = producer_id.to_string();
+        let row: Option<(i64, i64)> = self
+            .with_conn(move |conn| {
+                conn.query_row(
+                    "SELECT epoch, next_seq FROM producer_state WHERE producer_id = ?1",
+                    params![producer_id],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()
+            })
+            .await?;
+
+        row.map(|(epoch, next_seq)| {
+            Ok(ProducerCheckpoint {
+                epoch: u64::try_from(epoch)
+                    .map_err(|_| StoreError::Corrupt(format!("negative epoch {}", epoch)))?,
+                next_seq: u64::try_from(next_seq)
+                    .map_err(|_| StoreError::Corrupt(format!("negative seq {}", next_seq)))?,
+            })
+        })
+        .transpose()
+    }
+
+    async fn store_producer(
+        &self,
+        producer_id: &str,
+        checkpoint: &ProducerCheckpoint,
+    ) -> Result<(), StoreError> {
+        let producer_id = producer_id.to_string();
+        let epoch = i64::try_from(checkpoint.epoch)
+            .map_err(|_| StoreError::Corrupt(format!("epoch {} out of range", checkpoint.epoch)))?;
+        let next_seq = i64::try_from(checkpoint.next_seq)
+            .map_err(|_| StoreError::Corrupt(format!("seq {} out of range", checkpoint.next_seq)))?;
+        self.with_conn(move |conn| {
+            conn.execute(
+                "INSERT INTO producer_state (producer_id, epoch, next_seq, updated_at)
+                 VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT(producer_id) DO UPDATE SET
+                     epoch = excluded.epoch,
+                     next_seq = excluded.next_seq,
+                     updated_at = excluded.updated_at",
+                params![producer_id, epoch, next_seq, unix_now()],
+            )
+            .map(|_| ())
+        })
+        .await
+    }
+}
+
+/// Bring the schema up to date.
+fn migrate(conn: &mut Connection) -> Result<(), StoreError> {
+    let version: usize = conn
+        .pragma_query_value(None, "user_version", |row| row.get(0))
+        .map_err(backend)?;
+
+    if version > MIGRATIONS.len() {
+        return Err(StoreError::Corrupt(format!(
+            "database schema version {} is newer than supported version {}",
+            version,
+            MIGRATIONS.len()
+        )));
+    }
+    if version == MIGRATIONS.len() {
+        return Ok(());
+    }
+
+    let tx = conn.transaction().map_err(backend)?;
+    for sql in &MIGRATIONS[version..] {
+        tx.execute_batch(sql).map_err(backend)?;
+    }
+    tx.pragma_update(None, "user_version", MIGRATIONS.len())
+        .map_err(backend)?;
+    tx.commit().map_err(backend)
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+fn backend(err: impl std::fmt::Display) -> StoreError {
+    StoreError::Backend(err.to_string())
+}
@@ -1,11 +1,20 @@
 //! Stream handle and operations.
 
 use crate::client::Client;
-use crate::error::StreamError;
+use crate::error::{ErrorDetails, StreamError};
 use crate::iterator::ReadBuilder;
+use crate::middleware::Operation;
 use crate::producer::ProducerBuilder;
-use crate::types::Offset;
-use bytes::Bytes;
+use crate::request_id;
+use crate::redact;
+use crate::retry::{send_with_rate_limit_retry, sleep_before_retry, RetryConfig};
+use crate::rt;
+use crate::schema::SchemaValidator;
+use crate::types::{LiveMode, Offset};
+use bytes::{Bytes, BytesMut};
+use reqwest::header::HeaderMap;
+use std::collections::BTreeMap;
+use std::sync::Arc;
 use std::time::Duration;
 
 /// Protocol header names
@@ -18,6 +27,7 @@ pub(crate) const HEADER_STREAM_TTL: &str = "stream-ttl";
 pub(crate) const HEADER_STREAM_EXPIRES: &str = "stream-expires-at";
 pub(crate) const HEADER_ETAG: &str = "etag";
 pub(crate) const HEADER_IF_MATCH: &str = "if-match";
+pub(crate) const HEADER_IF_NONE_MATCH: &str = "if-none-match";
 
 /// Producer headers
 pub(crate) const HEADER_PRODUCER_ID: &str = "producer-id";
@@ -26,9 +36,6 @@ pub(crate) const HEADER_PRODUCER_SEQ: &str = "producer-seq";
 pub(crate) const HEADER_PRODUCER_EXPECTED_SEQ: &str = "producer-expected-seq";
 pub(crate) const HEADER_STREAM_CLOSED: &str = "stream-closed";
 
-/// Maximum retries for transient errors on append operations
-const MAX_APPEND_RETRIES: u32 = 3;
-
 /// A handle to a durable stream.
 ///
 /// This is a lightweight, cloneable object - not a persistent connection.
@@ -52,8 +59,8 @@ impl DurableStream {
     /// and by the Producer for JSON mode detection.
     ///
     /// Note: This is not automatically populated from the server.
-    /// Use [`set_content_type`](Self::set_content_type) to set it after
-    /// creating a stream, or set it explicitly on the Producer.
+    /// Set it with [`Client::stream_builder`], or explicitly on the
+    /// Producer.
     pub fn content_type(&self) -> Option<&str> {
         self.content_type.as_deref()
     }
@@ -67,6 +74,7 @@ impl DurableStream {
     /// let mut stream = client.stream("...");
     /// stream.set_content_type("application/json");
     /// ```
+    #[deprecated(note = "configure the content type with `Client::stream_builder` instead")]
     pub fn set_content_type(&mut self, ct: impl Into<String>) {
         self.content_type = Some(ct.into());
     }
@@ -79,56 +87,72 @@ impl DurableStream {
         self.create_with(CreateOptions::default()).await
     }
 
-    /// Create the stream with options.
+    /// Create the stream with options, on top of the client's
+    /// [`default_create_options`](crate::ClientBuilder::default_create_options).
+    ///
+    /// Without a content type in either, the handle's
+    /// [`content_type`](Self::content_type) is used.
     pub async fn create_with(&self, options: CreateOptions) -> Result<(), StreamError> {
+        let options = options.with_defaults(&self.client.defaults.create);
         let content_type = options
             .content_type
             .as_deref()
+            .or(self.content_type.as_deref())
             .unwrap_or("application/octet-stream");
 
-        let mut req = self
-            .client
-            .inner
-            .put(&self.url)
-            .header(HEADER_CONTENT_TYPE, content_type);
+        let build = || {
+            let mut req = self
+                .client
+                .inner
+                .put(&self.url)
+                .header(HEADER_CONTENT_TYPE, content_type);
 
-        // Add TTL header if specified
-        if let Some(ttl) = options.ttl {
-            req = req.header(HEADER_STREAM_TTL, ttl.as_secs().to_string());
-        }
+            // Add TTL header if specified
+            if let Some(ttl) = options.ttl {
+                req = req.header(HEADER_STREAM_TTL, ttl.as_secs().to_string());
+            }
 
-        // Add expires header if specified
-        if let Some(expires) = &options.expires_at {
-            req = req.header(HEADER_STREAM_EXPIRES, expires);
-        }
+            // Add expires header if specified
+            if let Some(expires) = &options.expires_at {
+                req = req.header(HEADER_STREAM_EXPIRES, expires);
+            }
 
-        // Add custom headers
-        let client_headers = self.client.get_headers();
-        for (key, value) in client_headers.iter() {
-            req = req.header(key.clone(), value.clone());
-        }
+            // Add custom headers
+            let client_headers = self.client.get_headers();
+            for (key, value) in client_headers.iter() {
+                req = req.header(key.clone(), value.clone());
+            }
 
-        for (key, value) in &options.headers {
-            req = req.header(key.as_str(), value.as_str());
-        }
+            for (key, value) in &options.headers {
+                req = req.header(key.as_str(), value.as_str());
+            }
 
-        // Add closed header if specified
-        if options.closed {
-            req = req.header(HEADER_STREAM_CLOSED, "true");
-        }
+            // Add closed header if specified
+            if options.closed {
+                req = req.header(HEADER_STREAM_CLOSED, "true");
+            }
 
-        // Add initial data if provided
-        if let Some(data) = options.initial_data {
-            req = req.body(data);
-        }
+            // Add initial data if provided
+            if let Some(data) = &options.initial_data {
+                req = req.body(data.clone());
+            }
+
+            if let Some(timeout) = options.timeout {
+                req = req.timeout(timeout);
+            }
+
+            req
+        };
 
-        let resp = req.send().await?;
+        let resp = send_with_rate_limit_retry(&self.client, Operation::Create, &self.url, build).await?;
         let status = resp.status().as_u16();
 
         match status {
             200 | 201 | 204 => Ok(()),
-            409 => Err(StreamError::Conflict),
-            _ => Err(StreamError::from_status(status, &self.url)),
+            409 => Err(StreamError::Conflict {
+                details: ErrorDetails::read(resp).await,
+            }),
+            _ => Err(StreamError::from_error_response(resp, &self.url).await),
         }
     }
 
@@ -137,59 +161,36 @@ impl DurableStream {
         self.append_with(data, AppendOptions::default()).await
     }
 
-    /// Append data with options.
+    /// Append data with options, on top of the client's
+    /// [`default_append_options`](crate::ClientBuilder::default_append_options).
     pub async fn append_with(
         &self,
         data: impl Into<Bytes>,
         options: AppendOptions,
     ) -> Result<AppendResponse, StreamError> {
+        let options = options.with_defaults(&self.client.defaults.append);
         let data = data.into();
         if data.is_empty() {
             return Err(StreamError::EmptyAppend);
         }
-
-        let content_type = self
-            .content_type
-            .as_deref()
-            .unwrap_or("application/octet-stream");
+        if let Some(validator) = &options.validator {
+            let content_type = self.content_type.as_deref().unwrap_or("application/octet-stream");
+            validator.validate(&data, content_type)?;
+        }
 
         // Retry logic for transient errors
         let mut last_error = None;
 
-        for attempt in 0..=MAX_APPEND_RETRIES {
-            if attempt > 0 {
-                // Exponential backoff: 100ms, 200ms, 400ms
-                tokio::time::sleep(std::time::Duration::from_millis(100 * (1 << (attempt - 1)))).await;
+        for attempt in 0..=self.client.retry.max_retries {
+            if let Some(err) = &last_error {
+                // Honor Retry-After if the server sent one, otherwise the
+                // client's backoff schedule
+                sleep_before_retry(&self.client, &self.url, attempt, err).await;
             }
 
-            let mut req = self
-                .client
-                .inner
-                .post(&self.url)
-                .header(HEADER_CONTENT_TYPE, content_type)
-                .body(data.clone());
+            let req = self.append_request(&options).body(data.clone());
 
-            // Add sequence header if specified
-            if let Some(seq) = &options.seq {
-                req = req.header(HEADER_STREAM_SEQ, seq.as_str());
-            }
-
-            // Add if-match header if specified
-            if let Some(etag) = &options.if_match {
-                req = req.header(HEADER_IF_MATCH, etag.as_str());
-            }
-
-            // Add custom headers
-            let client_headers = self.client.get_headers();
-            for (key, value) in client_headers.iter() {
-                req = req.header(key.clone(), value.clone());
-            }
-
-            for (key, value) in &options.headers {
-                req = req.header(key.as_str(), value.as_str());
-            }
-
-            let resp = match req.send().await {
+            let resp = match self.client.send(Operation::Append, req).await {
                 Ok(r) => r,
                 Err(e) => {
                     last_error = Some(StreamError::from(e));
@@ -197,46 +198,13 @@ impl DurableStream {
                 }
             };
 
-            let status = resp.status().as_u16();
-
-            match status {
-                200 | 204 => {
-                    let next_offset = resp
-                        .headers()
-                        .get(HEADER_STREAM_OFFSET)
-                        .and_then(|v| v.to_str().ok())
-                        .map(|s| Offset::parse(s))
-                        .unwrap_or(Offset::Beginning);
-
-                    let etag = resp
-                        .headers()
-                        .get(HEADER_ETAG)
-                        .and_then(|v| v.to_str().ok())
-                        .map(|s| s.to_string());
-
-                    return Ok(AppendResponse { next_offset, etag });
-                }
-                404 => return Err(StreamError::NotFound {
-                    url: self.url.clone(),
-                }),
-                409 => {
-                    let stream_closed = resp
-                        .headers()
-                        .get(HEADER_STREAM_CLOSED)
-                        .and_then(|v| v.to_str().ok())
-                        .map(|s| s.eq_ignore_ascii_case("true"))
-                        .unwrap_or(false);
-                    if stream_closed {
-                        return Err(StreamError::StreamClosed);
-                    }
-                    return Err(StreamError::SeqConflict);
-                }
+            match resp.status().as_u16() {
                 // Retry on transient server errors
                 500 | 502 | 503 | 504 | 429 => {
-                    last_error = Some(StreamError::from_status(status, &self.url));
+                    last_error = Some(StreamError::from_error_response(resp, &self.url).await);
                     continue;
                 }
-                _ => return Err(StreamError::from_status(status, &self.url)),
+                _ => return self.append_result(resp).await,
             }
         }
 
@@ -244,17 +212,107 @@ impl DurableStream {
         Err(last_error.unwrap_or_else(|| StreamError::ServerError {
             status: 500,
             message: "All retries failed".to_string(),
+            details: None,
         }))
     }
 
-    /// Get stream metadata via HEAD request.
-    pub async fn head(&self) -> Result<HeadResponse, StreamError> {
-        self.head_with(HeadOptions::default()).await
+    /// Append a payload larger than the server's body limit as sequential
+    /// appends of at most `max_chunk_bytes` each, returning the final
+    /// offset.
+    ///
+    /// Pieces are written in order, each with the usual retries. If one
+    /// fails, the pieces before it remain in the stream; use
+    /// [`Producer::append_chunked`](crate::Producer::append_chunked) when a
+    /// partial write must be safely resumable. Only meaningful for byte
+    /// streams; JSON streams need whole messages per append.
+    ///
+    /// Returns [`StreamError::BadRequest`] without writing anything if
+    /// `max_chunk_bytes` is zero.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let blob = tokio::fs::read("model.bin").await?;
+    /// let offset = stream.append_chunked(blob, 4 * 1024 * 1024).await?;
+    /// ```
+    pub async fn append_chunked(
+        &self,
+        data: impl Into<Bytes>,
+        max_chunk_bytes: usize,
+    ) -> Result<Offset, StreamError> {
+        if max_chunk_bytes == 0 {
+            return Err(StreamError::BadRequest {
+                message: "max_chunk_bytes must be non-zero".to_string(),
+                details: None,
+            });
+        }
+        let data = data.into();
+        if data.is_empty() {
+            return Err(StreamError::EmptyAppend);
+        }
+
+        let mut offset = Offset::Beginning;
+        for start in (0..data.len()).step_by(max_chunk_bytes) {
+            let end = data.len().min(start + max_chunk_bytes);
+            offset = self.append(data.slice(start..end)).await?.next_offset;
+        }
+        Ok(offset)
     }
 
-    /// Get stream metadata with options.
-    pub async fn head_with(&self, options: HeadOptions) -> Result<HeadResponse, StreamError> {
-        let mut req = self.client.inner.head(&self.url);
+    /// Append the contents of `reader`, streaming the request body instead
+    /// of buffering it in memory.
+    ///
+    /// Use this for payloads too large to hold in memory, such as log files
+    /// or snapshots. Since the body can only be read once, the request is
+    /// not retried; on a transient error, re-open the source and call again
+    /// (with [`AppendOptions::seq`] to make that safe). Middleware sees the
+    /// request with `body: None` and cannot rewrite the body, and
+    /// [`AppendOptions::validator`] is not applied.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let file = tokio::fs::File::open("snapshot.bin").await?;
+    /// stream.append_stream(file, AppendOptions::new()).await?;
+    /// ```
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn append_stream<R>(
+        &self,
+        reader: R,
+        options: AppendOptions,
+    ) -> Result<AppendResponse, StreamError>
+    where
+        R: tokio::io::AsyncRead + Send + 'static,
+    {
+        let options = options.with_defaults(&self.client.defaults.append);
+        let body = reqwest::Body::wrap_stream(tokio_util::io::ReaderStream::new(reader));
+        let req = self.append_request(&options).body(body);
+        let resp = self.client.send(Operation::Append, req).await?;
+        self.append_result(resp).await
+    }
+
+    /// Build an append request (without body) carrying `options`.
+    fn append_request(&self, options: &AppendOptions) -> reqwest::RequestBuilder {
+        let content_type = self
+            .content_type
+            .as_deref()
+            .unwrap_or("application/octet-stream");
+
+        let mut req = self
+            .client
+            .inner
+            .post(&self.url)
+            .header(HEADER_CONTENT_TYPE, content_type);
+
+        // Add sequence header if specified
+        if let Some(seq) = &options.seq {
+            req = req.header(HEADER_STREAM_SEQ, seq.as_str());
+        }
+
+        // Add if-match header if specified
+        if let Some(etag) = &options.if_match {
+            req = req.header(HEADER_IF_MATCH, etag.as_str());
+        }
 
         // Add custom headers
         let client_headers = self.client.get_headers();
@@ -266,63 +324,204 @@ impl DurableStream {
             req = req.header(key.as_str(), value.as_str());
         }
 
-        let resp = req.send().await?;
-        let status = resp.status().as_u16();
+        if let Some(timeout) = options.timeout {
+            req = req.timeout(timeout);
+        }
 
-        match status {
-            200 => {
+        req
+    }
+
+    /// Map an append response to its result.
+    async fn append_result(&self, resp: reqwest::Response) -> Result<AppendResponse, StreamError> {
+        match resp.status().as_u16() {
+            200 | 204 => {
                 let next_offset = resp
                     .headers()
                     .get(HEADER_STREAM_OFFSET)
                     .and_then(|v| v.to_str().ok())
-                    .map(|s| Offset::parse(s))
+                    .map(Offset::parse)
                     .unwrap_or(Offset::Beginning);
 
-                let content_type = resp
-                    .headers()
-                    .get(HEADER_CONTENT_TYPE)
-                    .and_then(|v| v.to_str().ok())
-                    .map(|s| s.to_string());
-
-                let ttl = resp
-                    .headers()
-                    .get(HEADER_STREAM_TTL)
-                    .and_then(|v| v.to_str().ok())
-                    .and_then(|s| s.parse::<u64>().ok())
-                    .map(Duration::from_secs);
-
-                let expires_at = resp
-                    .headers()
-                    .get(HEADER_STREAM_EXPIRES)
-                    .and_then(|v| v.to_str().ok())
-                    .map(|s| s.to_string());
-
                 let etag = resp
                     .headers()
                     .get(HEADER_ETAG)
                     .and_then(|v| v.to_str().ok())
                     .map(|s| s.to_string());
 
+                Ok(AppendResponse {
+                    next_offset,
+                    etag,
+                    request_id: request_id::from_headers(resp.headers()),
+                })
+            }
+            404 => Err(StreamError::NotFound {
+                url: self.url.clone(),
+                details: ErrorDetails::read(resp).await,
+            }),
+            409 => {
                 let stream_closed = resp
                     .headers()
                     .get(HEADER_STREAM_CLOSED)
                     .and_then(|v| v.to_str().ok())
                     .map(|s| s.eq_ignore_ascii_case("true"))
                     .unwrap_or(false);
+                if stream_closed {
+                    return Err(StreamError::StreamClosed);
+                }
+                Err(StreamError::SeqConflict)
+            }
+            _ => Err(StreamError::from_error_response(resp, &self.url).await),
+        }
+    }
 
-                Ok(HeadResponse {
-                    next_offset,
-                    content_type,
-                    ttl,
-                    expires_at,
-                    etag,
-                    stream_closed,
-                })
+    /// Wait until the stream's tail reaches or passes `offset`, returning
+    /// the tail offset observed.
+    ///
+    /// This is the read-your-writes primitive across processes: one service
+    /// appends and hands the returned `next_offset` to another, which calls
+    /// this before reading. The stream is checked with a HEAD request first,
+    /// then followed with long-polls from its tail until the offset shows
+    /// up. `Offset::Beginning` and `Offset::Now` are reached immediately.
+    ///
+    /// Fails with [`StreamError::Timeout`] if `timeout` elapses first, and
+    /// [`StreamError::StreamClosed`] if the stream is closed short of the
+    /// offset.
+    ///
+    /// # Example
+    /// ```ignore
+    /// // Service A
+    /// let written = stream.append(event).await?.next_offset;
+    /// // Service B, after receiving `written`
+    /// stream.wait_for_offset(written, Duration::from_secs(5)).await?;
+    /// ```
+    pub async fn wait_for_offset(
+        &self,
+        offset: impl Into<Offset>,
+        timeout: Duration,
+    ) -> Result<Offset, StreamError> {
+        let target = offset.into();
+        let reached = |tail: &Offset| match &target {
+            Offset::At(_) => tail >= &target,
+            Offset::Beginning | Offset::Now => true,
+        };
+
+        let wait = async {
+            let head = self.head().await?;
+            let tail = head.next_offset;
+            if reached(&tail) {
+                return Ok(tail);
             }
+            if head.stream_closed {
+                return Err(StreamError::StreamClosed);
+            }
+
+            let mut reader = self.read().offset(tail).live(LiveMode::LongPoll).build()?;
+            while let Some(chunk) = reader.next_chunk().await? {
+                if reached(&chunk.next_offset) {
+                    return Ok(chunk.next_offset);
+                }
+            }
+            Err(StreamError::StreamClosed)
+        };
+
+        tokio::select! {
+            result = wait => result,
+            _ = rt::sleep(timeout) => Err(StreamError::Timeout),
+        }
+    }
+
+    /// Get stream metadata via HEAD request.
+    pub async fn head(&self) -> Result<HeadResponse, StreamError> {
+        self.head_with(HeadOptions::default()).await
+    }
+
+    /// Get stream metadata with options.
+    pub async fn head_with(&self, options: HeadOptions) -> Result<HeadResponse, StreamError> {
+        let resp = self.send_head(&options).await?;
+        Ok(HeadResponse::from_headers(resp.headers()))
+    }
+
+    /// Check whether the stream exists.
+    ///
+    /// Returns `Ok(false)` only for 404; transport and server errors are
+    /// reported as errors rather than guessed at.
+    pub async fn exists(&self) -> Result<bool, StreamError> {
+        match self.head().await {
+            Ok(_) => Ok(true),
+            Err(StreamError::NotFound { .. }) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Get every attribute the server reports for the stream, including
+    /// `stream-*` headers this client does not interpret.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let meta = stream.metadata().await?;
+    /// println!("{} at {}", meta.content_type.unwrap_or_default(), meta.next_offset);
+    /// for (name, value) in &meta.other {
+    ///     println!("{}: {}", name, value);
+    /// }
+    /// ```
+    pub async fn metadata(&self) -> Result<StreamMetadata, StreamError> {
+        let resp = self.send_head(&HeadOptions::default()).await?;
+        let headers = resp.headers();
+        let head = HeadResponse::from_headers(headers);
+
+        let other = headers
+            .iter()
+            .filter(|(name, _)| {
+                let name = name.as_str();
+                name.starts_with("stream-") && !KNOWN_HEAD_HEADERS.contains(&name)
+            })
+            .filter_map(|(name, value)| {
+                Some((name.as_str().to_string(), value.to_str().ok()?.to_string()))
+            })
+            .collect();
+
+        Ok(StreamMetadata {
+            next_offset: head.next_offset,
+            content_type: head.content_type,
+            ttl: head.ttl,
+            expires_at: head.expires_at,
+            etag: head.etag,
+            stream_closed: head.stream_closed,
+            other,
+        })
+    }
+
+    /// Send a HEAD request, returning the response if the stream exists.
+    async fn send_head(&self, options: &HeadOptions) -> Result<reqwest::Response, StreamError> {
+        let build = || {
+            let mut req = self.client.inner.head(&self.url);
+
+            // Add custom headers
+            let client_headers = self.client.get_headers();
+            for (key, value) in client_headers.iter() {
+                req = req.header(key.clone(), value.clone());
+            }
+
+            for (key, value) in &options.headers {
+                req = req.header(key.as_str(), value.as_str());
+            }
+
+            if let Some(timeout) = options.timeout {
+                req = req.timeout(timeout);
+            }
+
+            req
+        };
+
+        let resp = send_with_rate_limit_retry(&self.client, Operation::Head, &self.url, build).await?;
+
+        match resp.status().as_u16() {
+            200 => Ok(resp),
             404 => Err(StreamError::NotFound {
                 url: self.url.clone(),
+                details: ErrorDetails::read(resp).await,
             }),
-            _ => Err(StreamError::from_status(status, &self.url)),
+            _ => Err(StreamError::from_error_response(resp, &self.url).await),
         }
     }
 
@@ -333,27 +532,32 @@ impl DurableStream {
 
     /// Delete the stream with options.
     pub async fn delete_with(&self, options: DeleteOptions) -> Result<(), StreamError> {
-        let mut req = self.client.inner.delete(&self.url);
+        let build = || {
+            let mut req = self.client.inner.delete(&self.url);
 
-        // Add custom headers
-        let client_headers = self.client.get_headers();
-        for (key, value) in client_headers.iter() {
-            req = req.header(key.clone(), value.clone());
-        }
+            // Add custom headers
+            let client_headers = self.client.get_headers();
+            for (key, value) in client_headers.iter() {
+                req = req.header(key.clone(), value.clone());
+            }
 
-        for (key, value) in &options.headers {
-            req = req.header(key.as_str(), value.as_str());
-        }
+            for (key, value) in &options.headers {
+                req = req.header(key.as_str(), value.as_str());
+            }
 
-        let resp = req.send().await?;
+            req
+        };
+
+        let resp = send_with_rate_limit_retry(&self.client, Operation::Delete, &self.url, build).await?;
         let status = resp.status().as_u16();
 
         match status {
             200 | 204 => Ok(()),
             404 => Err(StreamError::NotFound {
                 url: self.url.clone(),
+                details: ErrorDetails::read(resp).await,
             }),
-            _ => Err(StreamError::from_status(status, &self.url)),
+            _ => Err(StreamError::from_error_response(resp, &self.url).await),
         }
     }
 
@@ -370,25 +574,9 @@ impl DurableStream {
             .or(self.content_type.as_deref())
             .unwrap_or("application/octet-stream");
 
-        let mut req = self.client.inner.post(&self.url);
-
-        // Add custom headers
-        let client_headers = self.client.get_headers();
-        for (key, value) in client_headers.iter() {
-            req = req.header(key.clone(), value.clone());
-        }
-
-        for (key, value) in &options.headers {
-            req = req.header(key.as_str(), value.as_str());
-        }
-
-        req = req.header(HEADER_STREAM_CLOSED, "true");
-        req = req.header(HEADER_CONTENT_TYPE, content_type);
-
-        // Add data if provided
-        if let Some(data) = options.data {
-            // For JSON streams, wrap data in array
-            let body = if content_type.to_lowercase().contains("application/json") {
+        // For JSON streams, wrap data in array
+        let body = options.data.map(|data| {
+            if content_type.to_lowercase().contains("application/json") {
                 let mut wrapped = Vec::with_capacity(data.len() + 2);
                 wrapped.push(b'[');
                 wrapped.extend_from_slice(&data);
@@ -396,11 +584,34 @@ impl DurableStream {
                 Bytes::from(wrapped)
             } else {
                 data
-            };
-            req = req.body(body);
-        }
+            }
+        });
+
+        let build = || {
+            let mut req = self.client.inner.post(&self.url);
+
+            // Add custom headers
+            let client_headers = self.client.get_headers();
+            for (key, value) in client_headers.iter() {
+                req = req.header(key.clone(), value.clone());
+            }
 
-        let resp = req.send().await?;
+            for (key, value) in &options.headers {
+                req = req.header(key.as_str(), value.as_str());
+            }
+
+            req = req.header(HEADER_STREAM_CLOSED, "true");
+            req = req.header(HEADER_CONTENT_TYPE, content_type);
+
+            // Add data if provided
+            if let Some(body) = &body {
+                req = req.body(body.clone());
+            }
+
+            req
+        };
+
+        let resp = send_with_rate_limit_retry(&self.client, Operation::Close, &self.url, build).await?;
         let status = resp.status().as_u16();
 
         match status {
@@ -409,13 +620,14 @@ impl DurableStream {
                     .headers()
                     .get(HEADER_STREAM_OFFSET)
                     .and_then(|v| v.to_str().ok())
-                    .map(|s| Offset::parse(s))
+                    .map(Offset::parse)
                     .unwrap_or(Offset::Beginning);
 
                 Ok(CloseResponse { final_offset })
             }
             404 => Err(StreamError::NotFound {
                 url: self.url.clone(),
+                details: ErrorDetails::read(resp).await,
             }),
             409 => {
                 let stream_closed = resp
@@ -431,7 +643,7 @@ impl DurableStream {
                     Err(StreamError::SeqConflict)
                 }
             }
-            _ => Err(StreamError::from_status(status, &self.url)),
+            _ => Err(StreamError::from_error_response(resp, &self.url).await),
         }
     }
 
@@ -440,44 +652,118 @@ impl DurableStream {
         ReadBuilder::new(self.clone())
     }
 
+    /// Read everything from `offset` up to the current tail and return it
+    /// as one buffer.
+    ///
+    /// Catch-up reads are repeated until the server reports the stream is
+    /// up to date, so data appended while reading may or may not be
+    /// included. For JSON streams, see
+    /// [`read_json_to_end`](Self::read_json_to_end).
+    ///
+    /// # Example
+    /// ```ignore
+    /// let snapshot = stream.read_to_end(Offset::Beginning).await?;
+    /// ```
+    pub async fn read_to_end(&self, offset: impl Into<Offset>) -> Result<Bytes, StreamError> {
+        let mut reader = self.read().offset(offset).live(LiveMode::Off).build()?;
+
+        let mut buf = BytesMut::new();
+        while let Some(chunk) = reader.next_chunk().await? {
+            buf.extend_from_slice(&chunk.data);
+        }
+        Ok(buf.freeze())
+    }
+
     /// Create an idempotent producer builder.
     pub fn producer(&self, producer_id: impl Into<String>) -> ProducerBuilder {
         ProducerBuilder::new(self.clone(), producer_id.into())
     }
 
-    /// Build a read URL with query parameters.
+    /// Build a read URL with query parameters, percent-encoded.
+    ///
+    /// `extra` parameters follow the protocol's own. A URL that does not
+    /// parse is returned unchanged, so sending it reports the error.
     pub(crate) fn build_read_url(
         &self,
         offset: &Offset,
         live: Option<&str>,
         cursor: Option<&str>,
+        extra: &[(String, String)],
     ) -> String {
-        let mut url = self.url.clone();
-        let mut params = Vec::new();
+        let Ok(mut url) = reqwest::Url::parse(&self.url) else {
+            return self.url.clone();
+        };
+        {
+            let mut params = url.query_pairs_mut();
+
+            // Always include offset
+            params.append_pair("offset", offset.to_query_value());
+
+            // Add live mode if specified
+            if let Some(live) = live {
+                params.append_pair("live", live);
+            }
 
-        // Always include offset
-        params.push(format!("offset={}", offset.to_query_value()));
+            // Add cursor if specified
+            if let Some(cursor) = cursor {
+                params.append_pair("cursor", cursor);
+            }
 
-        // Add live mode if specified
-        if let Some(live) = live {
-            params.push(format!("live={}", live));
+            for (key, value) in extra {
+                params.append_pair(key, value);
+            }
         }
+        url.into()
+    }
+}
 
-        // Add cursor if specified
-        if let Some(cursor) = cursor {
-            params.push(format!("cursor={}", cursor));
-        }
+/// Builder for a [`DurableStream`] with its own configuration, from
+/// [`Client::stream_builder`].
+#[derive(Clone, Debug)]
+#[must_use = "builders do nothing unless you call .build()"]
+pub struct StreamBuilder {
+    stream: DurableStream,
+}
 
-        if !params.is_empty() {
-            if url.contains('?') {
-                url.push('&');
-            } else {
-                url.push('?');
-            }
-            url.push_str(&params.join("&"));
+impl StreamBuilder {
+    pub(crate) fn new(stream: DurableStream) -> Self {
+        Self { stream }
+    }
+
+    /// Content type of the stream, used when creating it, as the default
+    /// for appends, and by producers to detect JSON mode.
+    pub fn content_type(mut self, ct: impl Into<String>) -> Self {
+        self.stream.content_type = Some(ct.into());
+        self
+    }
+
+    /// Send a header with every request on this stream, replacing a
+    /// client default header of the same name.
+    ///
+    /// Invalid header names or values are silently ignored, as with
+    /// [`ClientBuilder::default_header`](crate::ClientBuilder::default_header).
+    pub fn header(mut self, key: &str, value: &str) -> Self {
+        if let (Ok(name), Ok(val)) = (
+            reqwest::header::HeaderName::from_bytes(key.as_bytes()),
+            reqwest::header::HeaderValue::from_str(value),
+        ) {
+            self.stream.client.default_headers.insert(name, val);
         }
+        self
+    }
 
-        url
+    /// Retry configuration for requests on this stream, replacing the
+    /// client's.
+    pub fn retry(mut self, config: RetryConfig) -> Self {
+        self.stream.client.retry = config;
+        self
+    }
+
+    /// Build the stream handle. No network request is made.
+    pub fn build(mut self) -> DurableStream {
+        let client = &mut self.stream.client;
+        redact::mark(&mut client.default_headers, &client.redacted_headers);
+        self.stream
     }
 }
 
@@ -491,6 +777,7 @@ pub struct CreateOptions {
     pub headers: Vec<(String, String)>,
     pub initial_data: Option<Bytes>,
     pub closed: bool,
+    pub timeout: Option<Duration>,
 }
 
 impl CreateOptions {
@@ -527,15 +814,49 @@ impl CreateOptions {
         self.closed = closed;
         self
     }
+
+    /// Time limit for this request, overriding the client's
+    /// [`request_timeout`](crate::ClientBuilder::request_timeout).
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Fill in what these options leave unset from `defaults`.
+    pub(crate) fn with_defaults(self, defaults: &Self) -> Self {
+        Self {
+            content_type: self.content_type.or_else(|| defaults.content_type.clone()),
+            ttl: self.ttl.or(defaults.ttl),
+            expires_at: self.expires_at.or_else(|| defaults.expires_at.clone()),
+            headers: merge_headers(&defaults.headers, self.headers),
+            initial_data: self.initial_data.or_else(|| defaults.initial_data.clone()),
+            closed: self.closed || defaults.closed,
+            timeout: self.timeout.or(defaults.timeout),
+        }
+    }
 }
 
 /// Options for appending to a stream.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Default)]
 #[non_exhaustive]
 pub struct AppendOptions {
     pub seq: Option<String>,
     pub if_match: Option<String>,
     pub headers: Vec<(String, String)>,
+    pub validator: Option<Arc<dyn SchemaValidator>>,
+    pub timeout: Option<Duration>,
+}
+
+impl std::fmt::Debug for AppendOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AppendOptions")
+            .field("seq", &self.seq)
+            .field("if_match", &self.if_match)
+            .field("headers", &self.headers)
+            .field("validator", &self.validator.is_some())
+            .field("timeout", &self.timeout)
+            .finish()
+    }
 }
 
 impl AppendOptions {
@@ -557,12 +878,97 @@ impl AppendOptions {
         self.headers.push((key.into(), value.into()));
         self
     }
+
+    /// Validate the payload before sending; a rejected payload fails with
+    /// [`StreamError::Validation`] and nothing is written.
+    pub fn validator(mut self, validator: Arc<dyn SchemaValidator>) -> Self {
+        self.validator = Some(validator);
+        self
+    }
+
+    /// Time limit for this request, overriding the client's
+    /// [`request_timeout`](crate::ClientBuilder::request_timeout).
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Fill in what these options leave unset from `defaults`.
+    pub(crate) fn with_defaults(self, defaults: &Self) -> Self {
+        Self {
+            seq: self.seq.or_else(|| defaults.seq.clone()),
+            if_match: self.if_match.or_else(|| defaults.if_match.clone()),
+            headers: merge_headers(&defaults.headers, self.headers),
+            validator: self.validator.or_else(|| defaults.validator.clone()),
+            timeout: self.timeout.or(defaults.timeout),
+        }
+    }
+}
+
+/// Defaults for reads started with [`DurableStream::read`], set with
+/// [`ClientBuilder::default_read_options`](crate::ClientBuilder::default_read_options).
+/// Calls on the [`ReadBuilder`] override them.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct ReadOptions {
+    pub live: Option<LiveMode>,
+    pub timeout: Option<Duration>,
+    pub request_timeout: Option<Duration>,
+    pub headers: Vec<(String, String)>,
+}
+
+impl ReadOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Live mode; see [`ReadBuilder::live`].
+    pub fn live(mut self, live: LiveMode) -> Self {
+        self.live = Some(live);
+        self
+    }
+
+    /// Long-poll timeout; see [`ReadBuilder::timeout`].
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Catch-up request timeout; see [`ReadBuilder::request_timeout`].
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
+    pub fn header(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((key.into(), value.into()));
+        self
+    }
+}
+
+/// The `defaults` not overridden by a header of the same name in
+/// `headers`, followed by `headers`.
+pub(crate) fn merge_headers(
+    defaults: &[(String, String)],
+    headers: Vec<(String, String)>,
+) -> Vec<(String, String)> {
+    if defaults.is_empty() {
+        return headers;
+    }
+    let mut merged: Vec<_> = defaults
+        .iter()
+        .filter(|(key, _)| !headers.iter().any(|(k, _)| k.eq_ignore_ascii_case(key)))
+        .cloned()
+        .collect();
+    merged.extend(headers);
+    merged
 }
 
 /// Options for HEAD request.
 #[derive(Clone, Debug, Default)]
 pub struct HeadOptions {
     pub headers: Vec<(String, String)>,
+    pub timeout: Option<Duration>,
 }
 
 impl HeadOptions {
@@ -574,6 +980,13 @@ impl HeadOptions {
         self.headers.push((key.into(), value.into()));
         self
     }
+
+    /// Time limit for this request, overriding the client's
+    /// [`request_timeout`](crate::ClientBuilder::request_timeout).
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
 }
 
 /// Options for DELETE request.
@@ -595,14 +1008,18 @@ impl DeleteOptions {
 
 /// Response from an append operation.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 pub struct AppendResponse {
     pub next_offset: Offset,
     pub etag: Option<String>,
+    /// Server's request ID, else the `x-request-id` the client sent.
+    pub request_id: Option<String>,
 }
 
 /// Response from a HEAD operation.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 pub struct HeadResponse {
     pub next_offset: Offset,
@@ -611,10 +1028,84 @@ pub struct HeadResponse {
     pub expires_at: Option<String>,
     pub etag: Option<String>,
     pub stream_closed: bool,
+    /// Server's request ID, else the `x-request-id` the client sent.
+    pub request_id: Option<String>,
+}
+
+impl HeadResponse {
+    fn from_headers(headers: &HeaderMap) -> Self {
+        let next_offset = headers
+            .get(HEADER_STREAM_OFFSET)
+            .and_then(|v| v.to_str().ok())
+            .map(Offset::parse)
+            .unwrap_or(Offset::Beginning);
+
+        let content_type = headers
+            .get(HEADER_CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.to_string());
+
+        let ttl = headers
+            .get(HEADER_STREAM_TTL)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|s| s.parse::<u64>().ok())
+            .map(Duration::from_secs);
+
+        let expires_at = headers
+            .get(HEADER_STREAM_EXPIRES)
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.to_string());
+
+        let etag = headers
+            .get(HEADER_ETAG)
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.to_string());
+
+        let stream_closed = headers
+            .get(HEADER_STREAM_CLOSED)
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.eq_ignore_ascii_case("true"))
+            .unwrap_or(false);
+
+        HeadResponse {
+            next_offset,
+            content_type,
+            ttl,
+            expires_at,
+            etag,
+            stream_closed,
+            request_id: request_id::from_headers(headers),
+        }
+    }
+}
+
+/// Protocol headers parsed into [`HeadResponse`] fields.
+const KNOWN_HEAD_HEADERS: [&str; 4] = [
+    HEADER_STREAM_OFFSET,
+    HEADER_STREAM_TTL,
+    HEADER_STREAM_EXPIRES,
+    HEADER_STREAM_CLOSED,
+];
+
+/// All stream attributes reported by the server, from
+/// [`DurableStream::metadata`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct StreamMetadata {
+    /// Offset of the stream tail.
+    pub next_offset: Offset,
+    pub content_type: Option<String>,
+    pub ttl: Option<Duration>,
+    pub expires_at: Option<String>,
+    pub etag: Option<String>,
+    pub stream_closed: bool,
+    /// Other `stream-*` headers, keyed by lowercase name.
+    pub other: BTreeMap<String, String>,
 }
 
 /// Response from a close operation.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 pub struct CloseResponse {
     pub final_offset: Offset,
@@ -0,0 +1,263 @@
+//! Many stream subscriptions sharing a bounded set of connections.
+
+use crate::client::Client;
+use crate::error::StreamError;
+use crate::iterator::{Chunk, ChunkIterator, ReadBuilder};
+use crate::rt::{self, Instant};
+use crate::types::LiveMode;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, OwnedSemaphorePermit, Semaphore};
+
+/// Chunks buffered per subscription before its reads pause.
+const CHANNEL_CAPACITY: usize = 16;
+
+impl Client {
+    /// Start configuring a [`SubscriptionManager`] for streams of this
+    /// client.
+    pub fn subscription_manager(&self) -> SubscriptionManagerBuilder {
+        SubscriptionManagerBuilder::new()
+    }
+}
+
+/// Builder for [`SubscriptionManager`], from
+/// [`Client::subscription_manager`].
+#[derive(Clone, Debug)]
+#[must_use = "builders do nothing unless you call .build()"]
+pub struct SubscriptionManagerBuilder {
+    max_connections: usize,
+    max_per_host: usize,
+    live: LiveMode,
+    long_poll_timeout: Duration,
+    sse_slice: Duration,
+}
+
+impl SubscriptionManagerBuilder {
+    fn new() -> Self {
+        Self {
+            max_connections: 64,
+            max_per_host: 32,
+            live: LiveMode::LongPoll,
+            long_poll_timeout: Duration::from_secs(30),
+            sse_slice: Duration::from_secs(60),
+        }
+    }
+
+    /// Requests open at once across all subscriptions (default 64).
+    pub fn max_connections(mut self, max: usize) -> Self {
+        self.max_connections = max.max(1);
+        self
+    }
+
+    /// Requests open at once to any one host (default 32).
+    pub fn max_per_host(mut self, max: usize) -> Self {
+        self.max_per_host = max.max(1);
+        self
+    }
+
+    /// How subscriptions wait for new data (default
+    /// [`LiveMode::LongPoll`]). With [`LiveMode::Off`], each subscription
+    /// ends once caught up.
+    pub fn live(mut self, mode: LiveMode) -> Self {
+        self.live = mode;
+        self
+    }
+
+    /// How long a long-poll waits for new data before giving up its
+    /// connection (default 30 seconds). Shorter timeouts cycle idle
+    /// streams faster when there are more subscriptions than connections.
+    pub fn long_poll_timeout(mut self, timeout: Duration) -> Self {
+        self.long_poll_timeout = timeout;
+        self
+    }
+
+    /// With [`LiveMode::Sse`], how long a subscription keeps its
+    /// connection before yielding it to others (default 60 seconds).
+    pub fn sse_slice(mut self, slice: Duration) -> Self {
+        self.sse_slice = slice;
+        self
+    }
+
+    /// Create the manager.
+    pub fn build(self) -> SubscriptionManager {
+        SubscriptionManager {
+            shared: Arc::new(Shared {
+                connections: Arc::new(Semaphore::new(self.max_connections)),
+                hosts: Mutex::new(HashMap::new()),
+                config: self,
+            }),
+        }
+    }
+}
+
+/// Tails many streams over a bounded number of concurrent connections.
+///
+/// Each subscription reads in turn: it waits for a free connection (and
+/// one for its host), makes one long-poll request, then hands the
+/// connection to the next waiting subscription. Waiters are served in
+/// arrival order, so every stream gets a fair share however many there
+/// are. Idle subscriptions cost a parked task, not a polling loop.
+///
+/// With [`LiveMode::Sse`], a subscription keeps its connection for up to
+/// [`sse_slice`](SubscriptionManagerBuilder::sse_slice), then gives it up
+/// at the next point it can resume from exactly (an up-to-date event) or
+/// when the connection stalls or closes.
+///
+/// # Example
+///
+/// ```ignore
+/// let manager = client
+///     .subscription_manager()
+///     .max_connections(100)
+///     .max_per_host(50)
+///     .build();
+///
+/// for user in users {
+///     let mut sub = manager.subscribe(client.stream(&format!("/inbox/{user}")).read())?;
+///     tokio::spawn(async move {
+///         while let Some(chunk) = sub.next_chunk().await? {
+///             deliver(&user, chunk.data);
+///         }
+///         Ok::<_, StreamError>(())
+///     });
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct SubscriptionManager {
+    shared: Arc<Shared>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    config: SubscriptionManagerBuilder,
+    connections: Arc<Semaphore>,
+    hosts: Mutex<HashMap<String, Arc<Semaphore>>>,
+}
+
+impl Shared {
+    fn host_limit(&self, url: &str) -> Arc<Semaphore> {
+        let host = reqwest::Url::parse(url)
+            .ok()
+            .and_then(|u| Some(format!("{}:{}", u.host_str()?, u.port_or_known_default()?)))
+            .unwrap_or_default();
+        self.hosts
+            .lock()
+            .entry(host)
+            .or_insert_with(|| Arc::new(Semaphore::new(self.config.max_per_host)))
+            .clone()
+    }
+}
+
+impl SubscriptionManager {
+    /// Subscribe to a stream, reading from the offset and with the options
+    /// of `read`. The manager sets the live mode and timeouts.
+    ///
+    /// Dropping the [`Subscription`] ends it.
+    pub fn subscribe(&self, read: ReadBuilder) -> Result<Subscription, StreamError> {
+        let config = &self.shared.config;
+        let iter = read
+            .live(config.live.clone())
+            .timeout(config.long_poll_timeout)
+            .stall_timeout(config.sse_slice)
+            .build()?;
+        let url = iter.stream().url().to_string();
+        let host = self.shared.host_limit(&url);
+
+        let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+        rt::spawn(drive(self.shared.clone(), host, iter, tx));
+        Ok(Subscription { url, rx })
+    }
+}
+
+/// One stream read by a [`SubscriptionManager`].
+#[derive(Debug)]
+pub struct Subscription {
+    url: String,
+    rx: mpsc::Receiver<Result<Option<Chunk>, StreamError>>,
+}
+
+impl Subscription {
+    /// URL of the stream.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Wait for the next chunk, like [`ChunkIterator::next_chunk`].
+    ///
+    /// After `Ok(None)` or an error the subscription has ended.
+    pub async fn next_chunk(&mut self) -> Result<Option<Chunk>, StreamError> {
+        self.rx.recv().await.unwrap_or(Ok(None))
+    }
+}
+
+/// Read `iter` whenever a connection is free, until the subscription is
+/// dropped or the read ends.
+async fn drive(
+    shared: Arc<Shared>,
+    host: Arc<Semaphore>,
+    mut iter: ChunkIterator,
+    tx: mpsc::Sender<Result<Option<Chunk>, StreamError>>,
+) {
+    let sse = shared.config.live == LiveMode::Sse;
+    loop {
+        let permits = tokio::select! {
+            permits = acquire(&host, &shared.connections) => permits,
+            _ = tx.closed() => return,
+        };
+        let connected_at = Instant::now();
+
+        loop {
+            let next = tokio::select! {
+                next = iter.next_chunk() => next,
+                _ = tx.closed() => return,
+            };
+            let release = match &next {
+                // An SSE data event arrives before the control event that
+                // moves the offset, so only yield between events
+                Ok(Some(chunk)) if sse => {
+                    chunk.status_code.is_none()
+                        || (chunk.data.is_empty()
+                            && connected_at.elapsed() >= shared.config.sse_slice)
+                }
+                _ => true,
+            };
+            let ended = !matches!(next, Ok(Some(_)));
+            if release && sse {
+                let offset = iter.offset().clone();
+                iter.reset_to(offset);
+            }
+            // Hand the connection on before waiting for the consumer
+            if release {
+                drop(permits);
+                if tx.send(next).await.is_err() || ended {
+                    return;
+                }
+                break;
+            }
+            if tx.send(next).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Take a connection slot for `host`, host first so a busy host does not
+/// hold global slots while waiting.
+async fn acquire(
+    host: &Arc<Semaphore>,
+    connections: &Arc<Semaphore>,
+) -> (OwnedSemaphorePermit, OwnedSemaphorePermit) {
+    let host = host
+        .clone()
+        .acquire_owned()
+        .await
+        .expect("semaphore is never closed");
+    let connection = connections
+        .clone()
+        .acquire_owned()
+        .await
+        .expect("semaphore is never closed");
+    (host, connection)
+}
@@ -0,0 +1,24 @@
+//! Test support for code built on this client.
+//!
+//! - [`MockServer`] - an in-memory Durable Streams server that a [`Client`]
+//!   talks to in-process, with no listener, Docker or network
+//! - [`Recorder`] and [`Replayer`] - record a client's real HTTP
+//!   interactions to a fixture file and replay them offline (`replay`
+//!   feature)
+//!
+//! Enable with the `testing` feature, usually as a dev-dependency:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! durable-streams = { version = "0.1", features = ["testing"] }
+//! ```
+//!
+//! [`Client`]: crate::Client
+
+mod mock;
+#[cfg(feature = "replay")]
+mod replay;
+
+pub use mock::MockServer;
+#[cfg(feature = "replay")]
+pub use replay::{Cassette, Data, Interaction, RecordedChunk, RecordedRequest, RecordedResponse, Recorder, Replayer};
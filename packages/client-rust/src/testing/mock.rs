@@ -0,0 +1,836 @@
+//! In-memory implementation of the Durable Streams protocol.
+
+use crate::client::{Client, ClientBuilder};
+use crate::rt::{self, Instant};
+use crate::transport::HttpTransport;
+use async_trait::async_trait;
+use base64::Engine;
+use bytes::{Bytes, BytesMut};
+use parking_lot::Mutex;
+use reqwest::{Method, Request, Response};
+use std::collections::{HashMap, VecDeque};
+use std::convert::Infallible;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
+
+const BASE_URL: &str = "http://durable-streams.mock";
+const DEFAULT_LONG_POLL_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// An in-memory Durable Streams server for tests.
+///
+/// It implements [`HttpTransport`], so requests from a client built with
+/// [`client`](Self::client) are answered in-process. It follows the
+/// protocol's semantics for create, append, close, delete and metadata;
+/// catch-up, long-poll and SSE reads; JSON mode; `Stream-Seq`; and
+/// idempotent producer headers. Errors carry a JSON body with a `code`.
+///
+/// Not modelled: TTL expiry, forks, retention (`410 Gone`) and
+/// subscriptions.
+///
+/// Clones share the same streams.
+///
+/// # Example
+/// ```ignore
+/// use durable_streams::testing::MockServer;
+///
+/// let server = MockServer::new();
+/// let client = server.client();
+/// let stream = client.stream("/orders");
+/// stream.create().await?;
+/// stream.append("hello").await?;
+/// assert_eq!(server.contents("/orders").unwrap(), "hello");
+/// ```
+#[derive(Clone)]
+pub struct MockServer {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    state: Mutex<State>,
+    /// Bumped on every change, waking long-polls and SSE connections.
+    changes: watch::Sender<u64>,
+}
+
+struct State {
+    streams: HashMap<String, MockStream>,
+    next_id: u64,
+    long_poll_timeout: Duration,
+    /// Statuses to answer the next requests with, in order.
+    failures: VecDeque<u16>,
+}
+
+struct MockStream {
+    id: u64,
+    content_type: String,
+    /// Appended data, as (end position, bytes). JSON streams store one
+    /// entry per message.
+    messages: Vec<(u64, Bytes)>,
+    tail: u64,
+    closed: bool,
+    ttl: Option<String>,
+    expires_at: Option<String>,
+    last_stream_seq: Option<String>,
+    producers: HashMap<String, ProducerState>,
+    /// Producer request that closed the stream, as (id, epoch, seq).
+    closed_by: Option<(String, u64, u64)>,
+}
+
+struct ProducerState {
+    epoch: u64,
+    last_seq: u64,
+}
+
+impl std::fmt::Debug for MockServer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.inner.state.lock();
+        f.debug_struct("MockServer")
+            .field("streams", &state.streams.len())
+            .field("long_poll_timeout", &state.long_poll_timeout)
+            .finish()
+    }
+}
+
+impl Default for MockServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockServer {
+    /// Create a server with no streams.
+    pub fn new() -> Self {
+        let (changes, _) = watch::channel(0);
+        Self {
+            inner: Arc::new(Inner {
+                state: Mutex::new(State {
+                    streams: HashMap::new(),
+                    next_id: 1,
+                    long_poll_timeout: DEFAULT_LONG_POLL_TIMEOUT,
+                    failures: VecDeque::new(),
+                }),
+                changes,
+            }),
+        }
+    }
+
+    /// Base URL that clients from [`client`](Self::client) resolve
+    /// relative stream paths against.
+    pub fn base_url(&self) -> &'static str {
+        BASE_URL
+    }
+
+    /// A client that sends every request to this server.
+    pub fn client(&self) -> Client {
+        self.client_builder()
+            .build()
+            .expect("mock client configuration is valid")
+    }
+
+    /// A client builder already pointed at this server, for tests that
+    /// need other client settings too.
+    pub fn client_builder(&self) -> ClientBuilder {
+        Client::builder().base_url(BASE_URL).transport(self.clone())
+    }
+
+    /// How long a long-poll waits for data before answering `204`
+    /// (default 20 seconds).
+    pub fn set_long_poll_timeout(&self, timeout: Duration) {
+        self.inner.state.lock().long_poll_timeout = timeout;
+    }
+
+    /// Answer the next request, whatever it is, with `status` and no
+    /// effect. Calls queue up, e.g. to test retries.
+    pub fn fail_next(&self, status: u16) {
+        self.inner.state.lock().failures.push_back(status);
+    }
+
+    /// Paths of the existing streams, sorted.
+    pub fn streams(&self) -> Vec<String> {
+        let mut paths: Vec<_> = self.inner.state.lock().streams.keys().cloned().collect();
+        paths.sort();
+        paths
+    }
+
+    /// Everything in the stream at `path`, as a catch-up read from the
+    /// beginning would return it.
+    pub fn contents(&self, path: &str) -> Option<Bytes> {
+        let state = self.inner.state.lock();
+        state.streams.get(path).map(|stream| stream.read_from(0))
+    }
+
+    /// Whether the stream at `path` is closed.
+    pub fn is_closed(&self, path: &str) -> Option<bool> {
+        self.inner
+            .state
+            .lock()
+            .streams
+            .get(path)
+            .map(|stream| stream.closed)
+    }
+
+    fn notify(&self) {
+        self.inner.changes.send_modify(|version| *version += 1);
+    }
+
+    async fn handle(&self, mut request: Request) -> Result<Response, reqwest::Error> {
+        if let Some(status) = self.inner.state.lock().failures.pop_front() {
+            return Ok(error(status, "INJECTED", "injected failure"));
+        }
+
+        let path = request.url().path().to_string();
+        let query: HashMap<String, String> = request.url().query_pairs().into_owned().collect();
+        let headers = request.headers().clone();
+        let body = match request.body_mut().take() {
+            Some(body) => collect(body).await?,
+            None => Bytes::new(),
+        };
+        let req = Incoming {
+            path,
+            query,
+            headers,
+            body,
+        };
+
+        Ok(match request.method().clone() {
+            Method::PUT => self.create(&req),
+            Method::POST => self.append(&req),
+            Method::GET => self.read(&req).await,
+            Method::HEAD => self.head(&req),
+            Method::DELETE => self.delete(&req),
+            _ => error(405, "METHOD_NOT_ALLOWED", "method not allowed"),
+        })
+    }
+
+    fn create(&self, req: &Incoming) -> Response {
+        if req.header("stream-forked-from").is_some() {
+            return error(
+                501,
+                "NOT_IMPLEMENTED",
+                "forks are not supported by MockServer",
+            );
+        }
+        let content_type = req
+            .header("content-type")
+            .map(media_type)
+            .unwrap_or_else(|| "application/octet-stream".to_string());
+        let ttl = req.header("stream-ttl").map(str::to_string);
+        let expires_at = req.header("stream-expires-at").map(str::to_string);
+        let closed = req.header_is_true("stream-closed");
+        if ttl.is_some() && expires_at.is_some() {
+            return error(
+                400,
+                "BAD_REQUEST",
+                "Stream-TTL and Stream-Expires-At are exclusive",
+            );
+        }
+        if let Some(ttl) = &ttl {
+            let canonical = ttl.parse::<u64>().is_ok_and(|n| n.to_string() == *ttl);
+            if !canonical {
+                return error(400, "BAD_REQUEST", "invalid Stream-TTL");
+            }
+        }
+
+        let mut state = self.inner.state.lock();
+        if let Some(existing) = state.streams.get(&req.path) {
+            let matches = existing.content_type == content_type
+                && existing.ttl == ttl
+                && existing.expires_at == expires_at
+                && existing.closed == closed;
+            if !matches {
+                return error(
+                    409,
+                    "CONFLICT",
+                    "stream exists with different configuration",
+                );
+            }
+            return existing.reply(200).body(Bytes::new()).into_response();
+        }
+
+        let messages = if req.body.is_empty() {
+            Vec::new()
+        } else if is_json(&content_type) {
+            match json_messages(&req.body, true) {
+                Ok(messages) => messages,
+                Err(message) => return error(400, "BAD_REQUEST", message),
+            }
+        } else {
+            vec![req.body.clone()]
+        };
+
+        let id = state.next_id;
+        state.next_id += 1;
+        let mut stream = MockStream {
+            id,
+            content_type,
+            messages: Vec::new(),
+            tail: 0,
+            closed,
+            ttl,
+            expires_at,
+            last_stream_seq: None,
+            producers: HashMap::new(),
+            closed_by: None,
+        };
+        stream.push(messages);
+        let resp = stream
+            .reply(201)
+            .header("location", format!("{}{}", BASE_URL, req.path))
+            .body(Bytes::new())
+            .into_response();
+        state.streams.insert(req.path.clone(), stream);
+        drop(state);
+        self.notify();
+        resp
+    }
+
+    fn append(&self, req: &Incoming) -> Response {
+        let producer = match req.producer() {
+            Ok(producer) => producer,
+            Err(message) => return error(400, "BAD_REQUEST", message),
+        };
+        let close = req.header_is_true("stream-closed");
+
+        let mut state = self.inner.state.lock();
+        let Some(stream) = state.streams.get_mut(&req.path) else {
+            return error(404, "STREAM_NOT_FOUND", "stream not found");
+        };
+
+        if stream.closed {
+            let closing_request = producer
+                .as_ref()
+                .is_some_and(|p| stream.closed_by.as_ref() == Some(p));
+            if (req.body.is_empty() && close) || closing_request {
+                return stream.reply(204).body(Bytes::new()).into_response();
+            }
+            return stream
+                .reply(409)
+                .header("content-type", "application/json")
+                .body(error_body("STREAM_CLOSED", "stream is closed"))
+                .into_response();
+        }
+        if req.body.is_empty() && !close {
+            return error(400, "BAD_REQUEST", "empty append body");
+        }
+        if !req.body.is_empty() {
+            match req.header("content-type").map(media_type) {
+                None => return error(400, "BAD_REQUEST", "missing Content-Type"),
+                Some(content_type) if content_type != stream.content_type => {
+                    return error(
+                        409,
+                        "CONTENT_TYPE_MISMATCH",
+                        "content type does not match the stream",
+                    );
+                }
+                Some(_) => {}
+            }
+        }
+        if let Some(seq) = req.header("stream-seq") {
+            if stream
+                .last_stream_seq
+                .as_deref()
+                .is_some_and(|last| seq <= last)
+            {
+                return error(409, "SEQUENCE_CONFLICT", "Stream-Seq must increase");
+            }
+        }
+
+        let mut producer_reply = None;
+        if let Some((id, epoch, seq)) = &producer {
+            match stream.producers.get(id) {
+                Some(current) if *epoch < current.epoch => {
+                    return reply(403)
+                        .header("producer-epoch", current.epoch.to_string())
+                        .header("content-type", "application/json")
+                        .body(error_body("STALE_EPOCH", "producer epoch is stale"))
+                        .into_response();
+                }
+                Some(current) if *epoch > current.epoch && *seq != 0 => {
+                    return error(400, "BAD_REQUEST", "a new epoch must start at seq 0");
+                }
+                Some(current) if *epoch > current.epoch => {}
+                Some(current) if *seq <= current.last_seq => {
+                    return stream
+                        .reply(204)
+                        .header("producer-epoch", epoch.to_string())
+                        .header("producer-seq", current.last_seq.to_string())
+                        .body(Bytes::new())
+                        .into_response();
+                }
+                Some(current) if *seq > current.last_seq + 1 => {
+                    return sequence_gap(current.last_seq + 1, *seq);
+                }
+                None if *seq != 0 => return sequence_gap(0, *seq),
+                _ => {}
+            }
+            producer_reply = Some((*epoch, *seq));
+        }
+
+        let messages = if req.body.is_empty() {
+            Vec::new()
+        } else if is_json(&stream.content_type) {
+            match json_messages(&req.body, false) {
+                Ok(messages) => messages,
+                Err(message) => return error(400, "BAD_REQUEST", message),
+            }
+        } else {
+            vec![req.body.clone()]
+        };
+
+        stream.push(messages);
+        if let Some(seq) = req.header("stream-seq") {
+            stream.last_stream_seq = Some(seq.to_string());
+        }
+        if let Some((id, epoch, seq)) = &producer {
+            stream.producers.insert(
+                id.clone(),
+                ProducerState {
+                    epoch: *epoch,
+                    last_seq: *seq,
+                },
+            );
+        }
+        if close {
+            stream.closed = true;
+            stream.closed_by = producer;
+        }
+
+        let resp = match producer_reply {
+            Some((epoch, seq)) => stream
+                .reply(200)
+                .header("producer-epoch", epoch.to_string())
+                .header("producer-seq", seq.to_string()),
+            None => stream.reply(204),
+        };
+        let resp = resp.body(Bytes::new()).into_response();
+        drop(state);
+        self.notify();
+        resp
+    }
+
+    async fn read(&self, req: &Incoming) -> Response {
+        let live = req.query.get("live").map(String::as_str);
+        let cursor = next_cursor(req.query.get("cursor"));
+        let mut changes = self.inner.changes.subscribe();
+
+        let (start, timeout) = {
+            let state = self.inner.state.lock();
+            let Some(stream) = state.streams.get(&req.path) else {
+                return error(404, "STREAM_NOT_FOUND", "stream not found");
+            };
+            let start = match stream.position(req.query.get("offset").map(String::as_str)) {
+                Some(start) => start,
+                None => return error(400, "BAD_REQUEST", "invalid offset"),
+            };
+            (start, state.long_poll_timeout)
+        };
+
+        match live {
+            None => {
+                let state = self.inner.state.lock();
+                let Some(stream) = state.streams.get(&req.path) else {
+                    return error(404, "STREAM_NOT_FOUND", "stream not found");
+                };
+                let etag = stream.etag(start);
+                if req.header("if-none-match") == Some(etag.as_str()) {
+                    return stream
+                        .read_reply(304, stream.tail, None)
+                        .body(Bytes::new())
+                        .into_response();
+                }
+                let now = req.query.get("offset").is_some_and(|o| o == "now");
+                let mut resp = stream.read_reply(200, stream.tail, None);
+                if !now {
+                    resp = resp.header("etag", etag);
+                }
+                resp.body(stream.read_from(start)).into_response()
+            }
+            Some("long-poll") => {
+                let deadline = Instant::now() + timeout;
+                loop {
+                    {
+                        let state = self.inner.state.lock();
+                        let Some(stream) = state.streams.get(&req.path) else {
+                            return error(404, "STREAM_NOT_FOUND", "stream not found");
+                        };
+                        if start < stream.tail {
+                            return stream
+                                .read_reply(200, stream.tail, Some(&cursor))
+                                .header("etag", stream.etag(start))
+                                .body(stream.read_from(start))
+                                .into_response();
+                        }
+                        if stream.closed {
+                            return stream
+                                .read_reply(204, stream.tail, None)
+                                .body(Bytes::new())
+                                .into_response();
+                        }
+                    }
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    let changed = tokio::select! {
+                        changed = changes.changed() => changed.is_ok(),
+                        _ = rt::sleep(remaining) => false,
+                    };
+                    if !changed {
+                        let state = self.inner.state.lock();
+                        let Some(stream) = state.streams.get(&req.path) else {
+                            return error(404, "STREAM_NOT_FOUND", "stream not found");
+                        };
+                        return stream
+                            .read_reply(204, stream.tail, Some(&cursor))
+                            .body(Bytes::new())
+                            .into_response();
+                    }
+                }
+            }
+            Some("sse") => {
+                let encoding = {
+                    let state = self.inner.state.lock();
+                    let Some(stream) = state.streams.get(&req.path) else {
+                        return error(404, "STREAM_NOT_FOUND", "stream not found");
+                    };
+                    (!is_text(&stream.content_type)).then_some("base64")
+                };
+                let (tx, rx) = mpsc::channel(16);
+                rt::spawn(sse(
+                    self.clone(),
+                    req.path.clone(),
+                    start,
+                    cursor,
+                    changes,
+                    tx,
+                ));
+                let mut resp = reply(200).header("content-type", "text/event-stream");
+                if let Some(encoding) = encoding {
+                    resp = resp.header("stream-sse-data-encoding", encoding);
+                }
+                resp.body(reqwest::Body::wrap_stream(Events(rx)))
+                    .into_response()
+            }
+            Some(_) => error(400, "BAD_REQUEST", "invalid live mode"),
+        }
+    }
+
+    fn head(&self, req: &Incoming) -> Response {
+        let state = self.inner.state.lock();
+        let Some(stream) = state.streams.get(&req.path) else {
+            return reply(404).body(Bytes::new()).into_response();
+        };
+        let mut resp = stream.reply(200).header("cache-control", "no-store");
+        if let Some(ttl) = &stream.ttl {
+            resp = resp.header("stream-ttl", ttl.as_str());
+        }
+        if let Some(expires_at) = &stream.expires_at {
+            resp = resp.header("stream-expires-at", expires_at.as_str());
+        }
+        resp.body(Bytes::new()).into_response()
+    }
+
+    fn delete(&self, req: &Incoming) -> Response {
+        if self.inner.state.lock().streams.remove(&req.path).is_none() {
+            return error(404, "STREAM_NOT_FOUND", "stream not found");
+        }
+        self.notify();
+        reply(204).body(Bytes::new()).into_response()
+    }
+}
+
+#[async_trait]
+impl HttpTransport for MockServer {
+    async fn execute(&self, request: Request) -> Result<Response, reqwest::Error> {
+        self.handle(request).await
+    }
+}
+
+/// Feed an SSE connection until the stream closes or the client goes away.
+async fn sse(
+    server: MockServer,
+    path: String,
+    mut position: u64,
+    cursor: String,
+    mut changes: watch::Receiver<u64>,
+    tx: mpsc::Sender<Bytes>,
+) {
+    let mut first = true;
+    loop {
+        let (events, done) = {
+            let state = server.inner.state.lock();
+            let Some(stream) = state.streams.get(&path) else {
+                return;
+            };
+            let mut events = String::new();
+            if position < stream.tail {
+                let data = stream.read_from(position);
+                events.push_str("event: data\n");
+                if is_text(&stream.content_type) {
+                    for line in String::from_utf8_lossy(&data).split('\n') {
+                        events.push_str(&format!("data: {}\n", line));
+                    }
+                } else {
+                    let encoded = base64::engine::general_purpose::STANDARD.encode(&data);
+                    events.push_str(&format!("data: {}\n", encoded));
+                }
+                events.push('\n');
+                position = stream.tail;
+            }
+            if !events.is_empty() || first || stream.closed {
+                let mut control = serde_json::json!({
+                    "streamNextOffset": format_offset(position),
+                    "upToDate": true,
+                });
+                if stream.closed {
+                    control["streamClosed"] = true.into();
+                } else {
+                    control["streamCursor"] = cursor.clone().into();
+                }
+                events.push_str(&format!("event: control\ndata: {}\n\n", control));
+            }
+            (events, stream.closed)
+        };
+        first = false;
+
+        if !events.is_empty() && tx.send(Bytes::from(events)).await.is_err() {
+            return;
+        }
+        if done {
+            return;
+        }
+        tokio::select! {
+            changed = changes.changed() => {
+                if changed.is_err() {
+                    return;
+                }
+            }
+            _ = tx.closed() => return,
+        }
+    }
+}
+
+/// SSE body fed by the connection task.
+struct Events(mpsc::Receiver<Bytes>);
+
+impl futures_core::Stream for Events {
+    type Item = Result<Bytes, Infallible>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.0.poll_recv(cx).map(|event| event.map(Ok))
+    }
+}
+
+/// The parts of a request the server looks at.
+struct Incoming {
+    path: String,
+    query: HashMap<String, String>,
+    headers: reqwest::header::HeaderMap,
+    body: Bytes,
+}
+
+impl Incoming {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).and_then(|v| v.to_str().ok())
+    }
+
+    fn header_is_true(&self, name: &str) -> bool {
+        self.header(name)
+            .is_some_and(|v| v.eq_ignore_ascii_case("true"))
+    }
+
+    /// Idempotent producer headers, as (id, epoch, seq).
+    fn producer(&self) -> Result<Option<(String, u64, u64)>, &'static str> {
+        let id = self.header("producer-id");
+        let epoch = self.header("producer-epoch");
+        let seq = self.header("producer-seq");
+        match (id, epoch, seq) {
+            (None, None, None) => Ok(None),
+            (Some(id), Some(epoch), Some(seq)) => {
+                if id.is_empty() {
+                    return Err("empty Producer-Id");
+                }
+                let epoch = epoch.parse().map_err(|_| "invalid Producer-Epoch")?;
+                let seq = seq.parse().map_err(|_| "invalid Producer-Seq")?;
+                Ok(Some((id.to_string(), epoch, seq)))
+            }
+            _ => Err("Producer-Id, Producer-Epoch and Producer-Seq must be sent together"),
+        }
+    }
+}
+
+impl MockStream {
+    fn push(&mut self, messages: Vec<Bytes>) {
+        for message in messages {
+            self.tail += message.len() as u64;
+            self.messages.push((self.tail, message));
+        }
+    }
+
+    /// Resolve an `offset` query parameter to a position.
+    fn position(&self, offset: Option<&str>) -> Option<u64> {
+        match offset {
+            None | Some("-1") => Some(0),
+            Some("now") => Some(self.tail),
+            Some(offset) => {
+                let (_, position) = offset.split_once('_')?;
+                position.parse().ok().filter(|p| *p <= self.tail)
+            }
+        }
+    }
+
+    /// Data from `position` to the tail, as a read returns it.
+    fn read_from(&self, position: u64) -> Bytes {
+        let first = self.messages.partition_point(|(end, _)| *end <= position);
+        let rest = &self.messages[first..];
+        if is_json(&self.content_type) {
+            let mut body = BytesMut::from(&b"["[..]);
+            for (i, (_, message)) in rest.iter().enumerate() {
+                if i > 0 {
+                    body.extend_from_slice(b",");
+                }
+                body.extend_from_slice(message);
+            }
+            body.extend_from_slice(b"]");
+            return body.freeze();
+        }
+        let mut body = BytesMut::new();
+        for (end, message) in rest {
+            let start = end - message.len() as u64;
+            let skip = position.saturating_sub(start) as usize;
+            body.extend_from_slice(&message[skip..]);
+        }
+        body.freeze()
+    }
+
+    /// A response carrying the stream's tail and closure status.
+    fn reply(&self, status: u16) -> http::response::Builder {
+        let mut resp = reply(status)
+            .header("content-type", self.content_type.as_str())
+            .header("stream-next-offset", format_offset(self.tail));
+        if self.closed {
+            resp = resp.header("stream-closed", "true");
+        }
+        resp
+    }
+
+    /// ETag for a read from `start`, marked `:c` once the stream is closed
+    /// as the protocol requires.
+    fn etag(&self, start: u64) -> String {
+        let closed = if self.closed { ":c" } else { "" };
+        format!("\"{}:{}:{}{closed}\"", self.id, start, self.tail)
+    }
+
+    /// A read response that ends at `end`.
+    fn read_reply(&self, status: u16, end: u64, cursor: Option<&str>) -> http::response::Builder {
+        let mut resp = reply(status)
+            .header("content-type", self.content_type.as_str())
+            .header("stream-next-offset", format_offset(end))
+            .header("stream-up-to-date", "true");
+        if self.closed {
+            resp = resp.header("stream-closed", "true");
+        } else if let Some(cursor) = cursor {
+            resp = resp.header("stream-cursor", cursor);
+        }
+        resp
+    }
+}
+
+trait IntoResponse {
+    fn into_response(self) -> Response;
+}
+
+impl IntoResponse for Result<http::Response<Bytes>, http::Error> {
+    fn into_response(self) -> Response {
+        self.expect("mock response is valid").into()
+    }
+}
+
+impl IntoResponse for Result<http::Response<reqwest::Body>, http::Error> {
+    fn into_response(self) -> Response {
+        self.expect("mock response is valid").into()
+    }
+}
+
+fn reply(status: u16) -> http::response::Builder {
+    http::Response::builder().status(status)
+}
+
+fn error(status: u16, code: &str, message: &str) -> Response {
+    reply(status)
+        .header("content-type", "application/json")
+        .body(error_body(code, message))
+        .into_response()
+}
+
+fn error_body(code: &str, message: &str) -> Bytes {
+    let body = serde_json::json!({ "error": { "code": code, "message": message } });
+    Bytes::from(body.to_string())
+}
+
+fn sequence_gap(expected: u64, received: u64) -> Response {
+    reply(409)
+        .header("producer-expected-seq", expected.to_string())
+        .header("producer-received-seq", received.to_string())
+        .header("content-type", "application/json")
+        .body(error_body("SEQUENCE_GAP", "producer sequence gap"))
+        .into_response()
+}
+
+/// Offsets in the reference server's `<read seq>_<byte position>` format.
+fn format_offset(position: u64) -> String {
+    format!("{:016}_{:016}", 0, position)
+}
+
+fn next_cursor(cursor: Option<&String>) -> String {
+    let current = cursor.and_then(|c| c.parse::<u64>().ok()).unwrap_or(0);
+    (current + 1).to_string()
+}
+
+/// Content type without parameters, lowercased.
+fn media_type(content_type: &str) -> String {
+    content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+}
+
+fn is_json(content_type: &str) -> bool {
+    content_type == "application/json"
+}
+
+/// Whether SSE carries this content type as text rather than base64.
+fn is_text(content_type: &str) -> bool {
+    content_type.starts_with("text/") || is_json(content_type)
+}
+
+/// Split a JSON body into messages, flattening one level of array.
+fn json_messages(body: &[u8], allow_empty: bool) -> Result<Vec<Bytes>, &'static str> {
+    let value: serde_json::Value = serde_json::from_slice(body).map_err(|_| "invalid JSON")?;
+    let values = match value {
+        serde_json::Value::Array(values) => values,
+        value => vec![value],
+    };
+    if values.is_empty() && !allow_empty {
+        return Err("empty JSON array");
+    }
+    Ok(values.iter().map(|v| Bytes::from(v.to_string())).collect())
+}
+
+/// Read a request body, streamed or not.
+async fn collect(body: reqwest::Body) -> Result<Bytes, reqwest::Error> {
+    if let Some(bytes) = body.as_bytes() {
+        return Ok(Bytes::copy_from_slice(bytes));
+    }
+    let mut body = std::pin::pin!(body);
+    let mut buf = BytesMut::new();
+    while let Some(frame) =
+        std::future::poll_fn(|cx| http_body::Body::poll_frame(body.as_mut(), cx)).await
+    {
+        if let Ok(data) = frame?.into_data() {
+            buf.extend_from_slice(&data);
+        }
+    }
+    Ok(buf.freeze())
+}
@@ -0,0 +1,408 @@
+//! Recording real HTTP interactions and replaying them in tests.
+
+use crate::client::{Client, ClientBuilder};
+use crate::rt::{self, Instant};
+use crate::transport::HttpTransport;
+use async_trait::async_trait;
+use base64::Engine;
+use bytes::Bytes;
+use parking_lot::Mutex;
+use reqwest::header::{HeaderMap, AUTHORIZATION, COOKIE, PROXY_AUTHORIZATION, SET_COOKIE};
+use reqwest::{Request, Response};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Recorded interactions, in the order their requests were sent.
+///
+/// Saved as JSON by [`Recorder::save`] and loaded by [`Replayer::load`].
+/// Fixtures are plain files, so they can be trimmed or edited by hand.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+/// One request and the response it got.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Interaction {
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+/// A request as sent, without credentials.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct RecordedRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Data,
+}
+
+/// A response, with its body as it arrived.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct RecordedResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    /// Milliseconds from sending the request to the response headers.
+    pub at_ms: u64,
+    /// Body pieces, e.g. one per SSE flush.
+    pub chunks: Vec<RecordedChunk>,
+}
+
+/// A piece of a response body.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct RecordedChunk {
+    /// Milliseconds from sending the request to this piece.
+    pub at_ms: u64,
+    pub data: Data,
+}
+
+/// Bytes stored as text when they are UTF-8, and as base64 otherwise.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Data(pub Bytes);
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum DataRepr {
+    Text(String),
+    Binary { base64: String },
+}
+
+impl Serialize for Data {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let repr = match std::str::from_utf8(&self.0) {
+            Ok(text) => DataRepr::Text(text.to_string()),
+            Err(_) => DataRepr::Binary {
+                base64: base64::engine::general_purpose::STANDARD.encode(&self.0),
+            },
+        };
+        repr.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Data {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match DataRepr::deserialize(deserializer)? {
+            DataRepr::Text(text) => Ok(Data(Bytes::from(text))),
+            DataRepr::Binary { base64 } => base64::engine::general_purpose::STANDARD
+                .decode(base64)
+                .map(|bytes| Data(Bytes::from(bytes)))
+                .map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// Records the interactions of a client for later replay.
+///
+/// It implements [`HttpTransport`]: requests go through to the real
+/// server (or `inner` transport) and each request, response and body piece
+/// is recorded as it passes, with its timing, including long-polls and SSE
+/// connections that stay open. Credentials (`Authorization`, cookies and
+/// headers marked sensitive) are left out of the recording.
+///
+/// Clones share the same recording.
+///
+/// # Example
+/// ```ignore
+/// use durable_streams::testing::Recorder;
+///
+/// let recorder = Recorder::new();
+/// let client = recorder.client_builder().base_url("https://streams.example.com").build()?;
+/// reproduce_bug(&client).await?;
+/// recorder.save("tests/fixtures/bug-1234.json")?;
+/// ```
+#[derive(Clone)]
+pub struct Recorder {
+    inner: Arc<dyn HttpTransport>,
+    cassette: Arc<Mutex<Cassette>>,
+}
+
+impl std::fmt::Debug for Recorder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Recorder")
+            .field("interactions", &self.cassette.lock().interactions.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for Recorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Recorder {
+    /// Record requests sent over HTTP with a default `reqwest::Client`.
+    pub fn new() -> Self {
+        Self::with_transport(reqwest::Client::new())
+    }
+
+    /// Record requests sent through `inner`.
+    pub fn with_transport(inner: impl HttpTransport + 'static) -> Self {
+        Self {
+            inner: Arc::new(inner),
+            cassette: Arc::default(),
+        }
+    }
+
+    /// A client builder whose requests are recorded.
+    pub fn client_builder(&self) -> ClientBuilder {
+        Client::builder().transport(self.clone())
+    }
+
+    /// The interactions recorded so far. Bodies still streaming are
+    /// included up to the last piece received.
+    pub fn cassette(&self) -> Cassette {
+        self.cassette.lock().clone()
+    }
+
+    /// Write the recording to `path` as JSON.
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let json = serde_json::to_vec_pretty(&self.cassette())?;
+        std::fs::write(path, json)
+    }
+}
+
+#[async_trait]
+impl HttpTransport for Recorder {
+    async fn execute(&self, request: Request) -> Result<Response, reqwest::Error> {
+        let recorded = RecordedRequest {
+            method: request.method().to_string(),
+            url: request.url().to_string(),
+            headers: header_pairs(request.headers()),
+            body: Data(
+                request
+                    .body()
+                    .and_then(|body| body.as_bytes())
+                    .map(Bytes::copy_from_slice)
+                    .unwrap_or_default(),
+            ),
+        };
+        let started = Instant::now();
+        let resp = self.inner.execute(request).await?;
+
+        let index = {
+            let mut cassette = self.cassette.lock();
+            cassette.interactions.push(Interaction {
+                request: recorded,
+                response: RecordedResponse {
+                    status: resp.status().as_u16(),
+                    headers: header_pairs(resp.headers()),
+                    at_ms: millis(started.elapsed()),
+                    chunks: Vec::new(),
+                },
+            });
+            cassette.interactions.len() - 1
+        };
+
+        let mut builder = http::Response::builder()
+            .status(resp.status())
+            .version(resp.version());
+        for (name, value) in resp.headers() {
+            builder = builder.header(name, value);
+        }
+        let tee = Tee {
+            body: Box::pin(resp.bytes_stream()),
+            cassette: self.cassette.clone(),
+            index,
+            started,
+        };
+        let resp = builder
+            .body(reqwest::Body::wrap_stream(tee))
+            .expect("headers copied from a valid response");
+        Ok(resp.into())
+    }
+}
+
+/// A response body that records each piece as the client reads it.
+struct Tee {
+    body: Pin<Box<dyn futures_core::Stream<Item = reqwest::Result<Bytes>> + Send>>,
+    cassette: Arc<Mutex<Cassette>>,
+    index: usize,
+    started: Instant,
+}
+
+impl futures_core::Stream for Tee {
+    type Item = reqwest::Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let polled = self.body.as_mut().poll_next(cx);
+        if let Poll::Ready(Some(Ok(data))) = &polled {
+            let chunk = RecordedChunk {
+                at_ms: millis(self.started.elapsed()),
+                data: Data(data.clone()),
+            };
+            self.cassette.lock().interactions[self.index]
+                .response
+                .chunks
+                .push(chunk);
+        }
+        polled
+    }
+}
+
+/// Answers requests from a recording, with the recorded timing.
+///
+/// Each request is matched to the first unplayed interaction with the
+/// same method and URL, so a client that behaves as it did when recorded
+/// gets the same responses in the same order. Response headers and body
+/// pieces arrive after the delays they had when recorded; the delays use
+/// tokio's clock, so under `tokio::time::pause()` replays run instantly
+/// and deterministically. A request with no match gets a `501` with a
+/// JSON error body.
+///
+/// Clones share the same playback position.
+///
+/// # Example
+/// ```ignore
+/// use durable_streams::testing::Replayer;
+///
+/// #[tokio::test(start_paused = true)]
+/// async fn bug_1234() {
+///     let replayer = Replayer::load("tests/fixtures/bug-1234.json").unwrap();
+///     let client = replayer.client_builder().base_url("https://streams.example.com").build().unwrap();
+///     reproduce_bug(&client).await.unwrap();
+///     assert_eq!(replayer.remaining(), 0);
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Replayer {
+    unplayed: Arc<Mutex<Vec<Interaction>>>,
+    timing: bool,
+}
+
+impl std::fmt::Debug for Replayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Replayer")
+            .field("remaining", &self.remaining())
+            .field("timing", &self.timing)
+            .finish()
+    }
+}
+
+impl Replayer {
+    pub fn new(cassette: Cassette) -> Self {
+        Self {
+            unplayed: Arc::new(Mutex::new(cassette.interactions)),
+            timing: true,
+        }
+    }
+
+    /// Load a recording saved by [`Recorder::save`].
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let cassette = serde_json::from_slice(&std::fs::read(path)?)?;
+        Ok(Self::new(cassette))
+    }
+
+    /// Answer immediately instead of with the recorded delays.
+    pub fn ignore_timing(mut self) -> Self {
+        self.timing = false;
+        self
+    }
+
+    /// A client builder whose requests are answered from the recording.
+    pub fn client_builder(&self) -> ClientBuilder {
+        Client::builder().transport(self.clone())
+    }
+
+    /// Interactions not played yet.
+    pub fn remaining(&self) -> usize {
+        self.unplayed.lock().len()
+    }
+}
+
+#[async_trait]
+impl HttpTransport for Replayer {
+    async fn execute(&self, request: Request) -> Result<Response, reqwest::Error> {
+        let method = request.method().as_str();
+        let url = request.url().as_str();
+        let interaction = {
+            let mut unplayed = self.unplayed.lock();
+            let found = unplayed
+                .iter()
+                .position(|i| i.request.method == method && i.request.url == url);
+            found.map(|index| unplayed.remove(index))
+        };
+        let Some(interaction) = interaction else {
+            let body = serde_json::json!({
+                "code": "REPLAY_MISMATCH",
+                "message": format!("no recorded interaction for {method} {url}"),
+            });
+            let resp = http::Response::builder()
+                .status(501)
+                .header("content-type", "application/json")
+                .body(body.to_string())
+                .expect("static response is valid");
+            return Ok(resp.into());
+        };
+
+        let response = interaction.response;
+        let mut elapsed = 0;
+        if self.timing {
+            rt::sleep(Duration::from_millis(response.at_ms)).await;
+            elapsed = response.at_ms;
+        }
+        let mut builder = http::Response::builder().status(response.status);
+        for (name, value) in &response.headers {
+            builder = builder.header(name, value);
+        }
+
+        // Feed the body from a task, so each piece keeps its delay
+        let (tx, rx) = mpsc::channel(1);
+        let timing = self.timing;
+        let chunks: VecDeque<RecordedChunk> = response.chunks.into();
+        rt::spawn(async move {
+            for chunk in chunks {
+                if timing && chunk.at_ms > elapsed {
+                    rt::sleep(Duration::from_millis(chunk.at_ms - elapsed)).await;
+                    elapsed = chunk.at_ms;
+                }
+                if tx.send(chunk.data.0).await.is_err() {
+                    return;
+                }
+            }
+        });
+        let resp = builder
+            .body(reqwest::Body::wrap_stream(Pieces(rx)))
+            .expect("recorded headers are valid");
+        Ok(resp.into())
+    }
+}
+
+/// Replayed response body.
+struct Pieces(mpsc::Receiver<Bytes>);
+
+impl futures_core::Stream for Pieces {
+    type Item = Result<Bytes, Infallible>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.0.poll_recv(cx).map(|piece| piece.map(Ok))
+    }
+}
+
+/// Headers worth keeping: everything but credentials and cookies.
+fn header_pairs(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .filter(|(name, value)| {
+            !value.is_sensitive()
+                && ![AUTHORIZATION, PROXY_AUTHORIZATION, COOKIE, SET_COOKIE].contains(name)
+        })
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+        .collect()
+}
+
+fn millis(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
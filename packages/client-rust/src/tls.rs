@@ -0,0 +1,67 @@
+//! TLS settings collected by `ClientBuilder`.
+//!
+//! Certificates are kept as PEM and parsed in `ClientBuilder::build`, so a
+//! malformed certificate is reported there like any other build error.
+//!
+//! The backend follows this crate's features: `native-tls` if enabled,
+//! otherwise rustls. It is selected explicitly because another crate in the
+//! dependency graph can enable reqwest's default TLS backend.
+
+/// Root certificates and client identity for a client.
+#[derive(Clone)]
+pub(crate) struct TlsConfig {
+    pub(crate) root_certificates: Vec<Vec<u8>>,
+    pub(crate) built_in_roots: bool,
+    /// Client certificate chain and private key, both PEM.
+    pub(crate) identity: Option<(Vec<u8>, Vec<u8>)>,
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
+        Self {
+            root_certificates: Vec::new(),
+            built_in_roots: true,
+            identity: None,
+        }
+    }
+}
+
+impl TlsConfig {
+    /// Apply these settings to `builder`.
+    pub(crate) fn apply(self, builder: reqwest::ClientBuilder) -> Result<reqwest::ClientBuilder, reqwest::Error> {
+        #[cfg(feature = "native-tls")]
+        let mut builder = builder.use_native_tls();
+        #[cfg(not(feature = "native-tls"))]
+        let mut builder = builder.use_rustls_tls();
+
+        for pem in &self.root_certificates {
+            for cert in reqwest::Certificate::from_pem_bundle(pem)? {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+        if !self.built_in_roots {
+            builder = builder.tls_built_in_root_certs(false);
+        }
+        if let Some((cert, key)) = &self.identity {
+            builder = builder.identity(identity(cert, key)?);
+        }
+        Ok(builder)
+    }
+}
+
+/// native-tls wants the key separately, in PKCS#8.
+#[cfg(feature = "native-tls")]
+fn identity(cert: &[u8], key: &[u8]) -> Result<reqwest::Identity, reqwest::Error> {
+    reqwest::Identity::from_pkcs8_pem(cert, key)
+}
+
+/// rustls reads the chain and key from a single PEM buffer.
+#[cfg(not(feature = "native-tls"))]
+fn identity(cert: &[u8], key: &[u8]) -> Result<reqwest::Identity, reqwest::Error> {
+    let mut pem = cert.to_vec();
+    if !pem.ends_with(b"\n") {
+        pem.push(b'\n');
+    }
+    pem.extend_from_slice(key);
+    reqwest::Identity::from_pem(&pem)
+}
@@ -0,0 +1,43 @@
+//! Pluggable HTTP transport.
+
+use async_trait::async_trait;
+
+/// Sends the HTTP requests of a [`Client`](crate::Client).
+///
+/// The default transport is the client's `reqwest::Client`. A custom one
+/// sees each request after auth headers and middleware are applied, so it
+/// can route requests elsewhere or answer them itself, e.g. a test fake
+/// that needs no network listener.
+///
+/// # Example
+/// ```ignore
+/// struct Fake;
+///
+/// #[async_trait::async_trait]
+/// impl HttpTransport for Fake {
+///     async fn execute(&self, request: reqwest::Request) -> Result<reqwest::Response, reqwest::Error> {
+///         let resp = http::Response::builder()
+///             .status(200)
+///             .header("stream-next-offset", "0_5")
+///             .body("hello")
+///             .unwrap();
+///         Ok(resp.into())
+///     }
+/// }
+///
+/// let client = Client::builder().transport(Fake).build()?;
+/// ```
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+pub trait HttpTransport: Send + Sync {
+    /// Send `request` and return the response.
+    async fn execute(&self, request: reqwest::Request) -> Result<reqwest::Response, reqwest::Error>;
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl HttpTransport for reqwest::Client {
+    async fn execute(&self, request: reqwest::Request) -> Result<reqwest::Response, reqwest::Error> {
+        reqwest::Client::execute(self, request).await
+    }
+}
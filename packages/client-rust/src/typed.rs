@@ -0,0 +1,500 @@
+//! Typed readers with bounded decoding.
+
+#[cfg(any(feature = "msgpack", feature = "cbor"))]
+use crate::codec;
+use crate::dead_letter::{DeadLetter, DeadLetterQueue};
+use crate::error::{LimitKind, StreamError};
+use crate::iterator::{ChunkIterator, ReadBuilder};
+use crate::stream::DurableStream;
+use crate::types::{LiveMode, Offset};
+use serde::de::DeserializeOwned;
+use std::marker::PhantomData;
+
+/// Limits enforced when a chunk is deserialized.
+///
+/// A chunk is scanned once without allocating; if any limit is exceeded the
+/// read fails with [`StreamError::PayloadTooLarge`] and nothing is decoded.
+/// This keeps a malicious or buggy writer from exhausting memory in every
+/// consumer of a stream. MessagePack and CBOR chunks are checked value by
+/// value as they are decoded instead.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct DecodeLimits {
+    /// Maximum encoded size of a single item, in bytes.
+    pub max_item_bytes: usize,
+    /// Maximum number of items in one chunk.
+    pub max_items_per_chunk: usize,
+    /// Maximum nesting depth of arrays and objects within an item.
+    pub max_depth: usize,
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        Self {
+            max_item_bytes: 8 * 1024 * 1024,
+            max_items_per_chunk: 100_000,
+            max_depth: 64,
+        }
+    }
+}
+
+impl DecodeLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn max_item_bytes(mut self, bytes: usize) -> Self {
+        self.max_item_bytes = bytes;
+        self
+    }
+
+    pub fn max_items_per_chunk(mut self, count: usize) -> Self {
+        self.max_items_per_chunk = count;
+        self
+    }
+
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+}
+
+/// What a typed reader does with records that fail to decode.
+///
+/// Set with [`TypedReadBuilder::on_decode_error`]. Only decode errors are
+/// covered; chunks exceeding the [`DecodeLimits`] still fail the read. In a
+/// JSON chunk that does not decode as a whole, each record is decoded on
+/// its own so the others are kept; MessagePack and CBOR chunks, and JSON
+/// chunks that are not valid JSON at all, are rejected whole.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub enum DecodeErrorPolicy {
+    /// Return the error from `next_chunk` (the default).
+    #[default]
+    Fail,
+    /// Drop the record and carry on, logging a warning when the `tracing`
+    /// feature is enabled.
+    SkipAndLog,
+    /// Append the record to this JSON stream as a
+    /// [`DeadLetter`](crate::DeadLetter) and carry on. If that append
+    /// fails, `next_chunk` returns its error. See [`route_to`](Self::route_to).
+    RouteTo(Box<DurableStream>),
+}
+
+impl DecodeErrorPolicy {
+    /// [`RouteTo`](Self::RouteTo) the given dead-letter stream.
+    pub fn route_to(stream: DurableStream) -> Self {
+        Self::RouteTo(Box::new(stream))
+    }
+}
+
+/// A chunk of decoded items.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct TypedChunk<T> {
+    /// Items decoded from this chunk, in stream order.
+    pub items: Vec<T>,
+    /// Next offset to read from (for resumption/checkpointing).
+    pub next_offset: Offset,
+    /// Whether this chunk represents the current tail of the stream.
+    pub up_to_date: bool,
+    /// Cursor for CDN request collapsing.
+    pub cursor: Option<String>,
+}
+
+impl ReadBuilder {
+    /// Decode chunks of a JSON stream into `T`.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let mut reader = stream.read()
+    ///     .live(LiveMode::LongPoll)
+    ///     .typed::<Event>()
+    ///     .limits(DecodeLimits::new().max_item_bytes(64 * 1024))
+    ///     .build()?;
+    ///
+    /// while let Some(chunk) = reader.next_chunk().await? {
+    ///     for event in chunk.items {
+    ///         handle(event);
+    ///     }
+    /// }
+    /// ```
+    pub fn typed<T: DeserializeOwned>(self) -> TypedReadBuilder<T> {
+        TypedReadBuilder::new(self, Format::Json)
+    }
+
+    /// Decode chunks of a MessagePack stream into `T`.
+    ///
+    /// Each append is one MessagePack value; see
+    /// [`Producer::append_msgpack`](crate::Producer::append_msgpack).
+    #[cfg(feature = "msgpack")]
+    pub fn typed_msgpack<T: DeserializeOwned>(self) -> TypedReadBuilder<T> {
+        TypedReadBuilder::new(self, Format::MsgPack)
+    }
+
+    /// Decode chunks of a CBOR stream into `T`.
+    ///
+    /// Each append is one CBOR value; see
+    /// [`Producer::append_cbor`](crate::Producer::append_cbor).
+    #[cfg(feature = "cbor")]
+    pub fn typed_cbor<T: DeserializeOwned>(self) -> TypedReadBuilder<T> {
+        TypedReadBuilder::new(self, Format::Cbor)
+    }
+}
+
+/// Encoding of a typed stream.
+#[derive(Clone, Copy, Debug)]
+enum Format {
+    Json,
+    #[cfg(feature = "msgpack")]
+    MsgPack,
+    #[cfg(feature = "cbor")]
+    Cbor,
+}
+
+impl Format {
+    fn content_type(self) -> &'static str {
+        match self {
+            Format::Json => "application/json",
+            #[cfg(feature = "msgpack")]
+            Format::MsgPack => codec::CONTENT_TYPE_MSGPACK,
+            #[cfg(feature = "cbor")]
+            Format::Cbor => codec::CONTENT_TYPE_CBOR,
+        }
+    }
+}
+
+impl DurableStream {
+    /// Read every item from `offset` up to the current tail of a JSON
+    /// stream.
+    ///
+    /// JSON counterpart of [`read_to_end`](Self::read_to_end), decoded with
+    /// the default [`DecodeLimits`].
+    ///
+    /// # Example
+    /// ```ignore
+    /// let users: Vec<User> = stream.read_json_to_end(Offset::Beginning).await?;
+    /// ```
+    pub async fn read_json_to_end<T: DeserializeOwned>(
+        &self,
+        offset: impl Into<Offset>,
+    ) -> Result<Vec<T>, StreamError> {
+        let mut reader = self
+            .read()
+            .offset(offset)
+            .live(LiveMode::Off)
+            .typed::<T>()
+            .build()?;
+
+        let mut items = Vec::new();
+        while let Some(chunk) = reader.next_chunk().await? {
+            items.extend(chunk.items);
+        }
+        Ok(items)
+    }
+}
+
+/// Builder for typed reads.
+#[derive(Debug)]
+#[must_use = "builders do nothing unless you call .build()"]
+pub struct TypedReadBuilder<T> {
+    inner: ReadBuilder,
+    format: Format,
+    limits: DecodeLimits,
+    on_decode_error: DecodeErrorPolicy,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> TypedReadBuilder<T> {
+    fn new(inner: ReadBuilder, format: Format) -> Self {
+        Self {
+            inner,
+            format,
+            limits: DecodeLimits::default(),
+            on_decode_error: DecodeErrorPolicy::Fail,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Set the starting offset; see [`ReadBuilder::offset`].
+    pub fn offset(mut self, offset: impl Into<Offset>) -> Self {
+        self.inner = self.inner.offset(offset);
+        self
+    }
+
+    /// Set the live mode; see [`ReadBuilder::live`].
+    pub fn live(mut self, mode: LiveMode) -> Self {
+        self.inner = self.inner.live(mode);
+        self
+    }
+
+    /// Set decoding limits (defaults to [`DecodeLimits::default`]).
+    pub fn limits(mut self, limits: DecodeLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Choose what happens to records that fail to decode (defaults to
+    /// [`DecodeErrorPolicy::Fail`]).
+    ///
+    /// # Example
+    /// ```ignore
+    /// let mut reader = stream.read()
+    ///     .typed::<Event>()
+    ///     .on_decode_error(DecodeErrorPolicy::route_to(client.stream("/v1/stream/events-dlq")))
+    ///     .build()?;
+    /// ```
+    pub fn on_decode_error(mut self, policy: DecodeErrorPolicy) -> Self {
+        self.on_decode_error = policy;
+        self
+    }
+
+    /// Build the TypedIterator.
+    ///
+    /// No network request is made until `next_chunk()` is called.
+    pub fn build(self) -> Result<TypedIterator<T>, StreamError> {
+        let on_decode_error = match self.on_decode_error {
+            DecodeErrorPolicy::Fail => OnDecodeError::Fail,
+            DecodeErrorPolicy::SkipAndLog => OnDecodeError::Skip,
+            DecodeErrorPolicy::RouteTo(stream) => {
+                OnDecodeError::RouteTo(Box::new(DeadLetterQueue::new(*stream)))
+            }
+        };
+        Ok(TypedIterator {
+            inner: self.inner.build()?,
+            format: self.format,
+            limits: self.limits,
+            on_decode_error,
+            skipped: 0,
+            _marker: PhantomData,
+        })
+    }
+}
+
+/// [`DecodeErrorPolicy`] as applied by an iterator.
+enum OnDecodeError {
+    Fail,
+    Skip,
+    RouteTo(Box<DeadLetterQueue>),
+}
+
+/// Iterator yielding decoded chunks from a JSON, MessagePack, or CBOR stream.
+pub struct TypedIterator<T> {
+    inner: ChunkIterator,
+    format: Format,
+    limits: DecodeLimits,
+    on_decode_error: OnDecodeError,
+    skipped: u64,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> TypedIterator<T> {
+    /// Get the current offset.
+    pub fn offset(&self) -> &Offset {
+        self.inner.offset()
+    }
+
+    /// Check if we've caught up to the stream tail.
+    pub fn is_up_to_date(&self) -> bool {
+        self.inner.is_up_to_date()
+    }
+
+    /// Commit the current offset to the checkpoint store immediately.
+    pub async fn commit(&mut self) -> Result<(), StreamError> {
+        self.inner.commit().await
+    }
+
+    /// Close the iterator and release resources.
+    pub async fn close(&mut self) {
+        self.inner.close().await;
+    }
+
+    /// Records skipped or dead-lettered under the
+    /// [`on_decode_error`](TypedReadBuilder::on_decode_error) policy.
+    pub fn skipped(&self) -> u64 {
+        self.skipped
+    }
+
+    /// Fetch and decode the next chunk.
+    ///
+    /// Chunks with no data (e.g. long-poll timeouts) yield an empty `items`.
+    pub async fn next_chunk(&mut self) -> Result<Option<TypedChunk<T>>, StreamError> {
+        let offset = self.inner.offset().clone();
+        let Some(chunk) = self.inner.next_chunk().await? else {
+            return Ok(None);
+        };
+
+        let decoded = match self.format {
+            Format::Json => decode_json(&chunk.data, &self.limits),
+            #[cfg(feature = "msgpack")]
+            Format::MsgPack => codec::decode_msgpack(&chunk.data, &self.limits),
+            #[cfg(feature = "cbor")]
+            Format::Cbor => codec::decode_cbor(&chunk.data, &self.limits),
+        };
+        let items = match decoded {
+            Err(err @ (StreamError::Json(_) | StreamError::ParseError(_)))
+                if !matches!(self.on_decode_error, OnDecodeError::Fail) =>
+            {
+                self.salvage(&chunk.data, &offset, err).await?
+            }
+            decoded => decoded?,
+        };
+
+        Ok(Some(TypedChunk {
+            items,
+            next_offset: chunk.next_offset,
+            up_to_date: chunk.up_to_date,
+            cursor: chunk.cursor,
+        }))
+    }
+}
+
+impl<T: DeserializeOwned> TypedIterator<T> {
+    /// Decode what can be decoded of a chunk that failed to decode, and skip
+    /// or dead-letter the rest.
+    async fn salvage(
+        &mut self,
+        data: &[u8],
+        offset: &Offset,
+        err: StreamError,
+    ) -> Result<Vec<T>, StreamError> {
+        let mut items = Vec::new();
+        let mut rejected = Vec::new();
+        let values = match self.format {
+            Format::Json => match serde_json::from_slice::<serde_json::Value>(data) {
+                Ok(serde_json::Value::Array(values)) => Some(values),
+                Ok(value) => Some(vec![value]),
+                Err(_) => None,
+            },
+            #[cfg(any(feature = "msgpack", feature = "cbor"))]
+            _ => None,
+        };
+        match values {
+            Some(values) => {
+                for value in values {
+                    match T::deserialize(&value) {
+                        Ok(item) => items.push(item),
+                        Err(e) => rejected.push((serde_json::to_vec(&value)?, e.to_string())),
+                    }
+                }
+            }
+            None => rejected.push((data.to_vec(), err.to_string())),
+        }
+
+        self.skipped += rejected.len() as u64;
+        match &self.on_decode_error {
+            OnDecodeError::Fail => {}
+            OnDecodeError::Skip => {
+                #[cfg(feature = "tracing")]
+                for (_, error) in &rejected {
+                    tracing::warn!(url = %self.inner.stream().url, ?offset, %error, "skipping record that failed to decode");
+                }
+            }
+            OnDecodeError::RouteTo(queue) => {
+                let source = &self.inner.stream().url;
+                let letters: Vec<_> = rejected
+                    .iter()
+                    .map(|(record, error)| {
+                        let mut letter = DeadLetter::new(source, self.format.content_type(), record, error);
+                        letter.offset = Some(offset.clone());
+                        letter
+                    })
+                    .collect();
+                queue.send(&letters).await?;
+            }
+        }
+        Ok(items)
+    }
+}
+
+/// Decode a JSON chunk: a batch array, a single value, or nothing.
+pub(crate) fn decode_json<T: DeserializeOwned>(data: &[u8], limits: &DecodeLimits) -> Result<Vec<T>, StreamError> {
+    if data.iter().all(u8::is_ascii_whitespace) {
+        Ok(Vec::new())
+    } else if check_limits(data, limits)? {
+        Ok(serde_json::from_slice(data)?)
+    } else {
+        Ok(vec![serde_json::from_slice(data)?])
+    }
+}
+
+/// Check `data` against `limits` without decoding it.
+///
+/// A top-level array is treated as a batch of items; any other value is a
+/// single item. Returns whether the payload is a batch.
+fn check_limits(data: &[u8], limits: &DecodeLimits) -> Result<bool, StreamError> {
+    let too_large = |kind, limit, actual| StreamError::PayloadTooLarge {
+        kind,
+        limit,
+        actual,
+    };
+
+    let is_batch = data.iter().find(|b| !b.is_ascii_whitespace()) == Some(&b'[');
+    // The batch wrapper itself does not count towards item depth
+    let base_depth = usize::from(is_batch);
+
+    if !is_batch && data.len() > limits.max_item_bytes {
+        return Err(too_large(LimitKind::ItemBytes, limits.max_item_bytes, data.len()));
+    }
+
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut items = 0usize;
+    let mut item_start = None;
+
+    for (i, &b) in data.iter().enumerate() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if b == b'\\' {
+                escaped = true;
+            } else if b == b'"' {
+                in_string = false;
+            }
+            continue;
+        }
+
+        // Track item boundaries directly inside the batch array
+        if is_batch && depth == 1 {
+            match b {
+                b',' | b']' => {
+                    if let Some(start) = item_start.take() {
+                        let len = i - start;
+                        if len > limits.max_item_bytes {
+                            return Err(too_large(LimitKind::ItemBytes, limits.max_item_bytes, len));
+                        }
+                    }
+                }
+                _ if b.is_ascii_whitespace() => {}
+                _ if item_start.is_none() => {
+                    item_start = Some(i);
+                    items += 1;
+                    if items > limits.max_items_per_chunk {
+                        return Err(too_large(
+                            LimitKind::ItemsPerChunk,
+                            limits.max_items_per_chunk,
+                            items,
+                        ));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        match b {
+            b'"' => in_string = true,
+            b'[' | b'{' => {
+                depth += 1;
+                let item_depth = depth - base_depth;
+                if item_depth > limits.max_depth {
+                    return Err(too_large(LimitKind::Depth, limits.max_depth, item_depth));
+                }
+            }
+            b']' | b'}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+
+    Ok(is_batch)
+}
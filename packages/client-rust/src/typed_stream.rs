@@ -0,0 +1,197 @@
+//! Streams of serde types.
+
+use crate::error::{ProducerError, StreamError};
+use crate::producer::{AppendReceipt, Producer};
+use crate::stream::{AppendResponse, CreateOptions, DurableStream};
+use crate::typed::TypedReadBuilder;
+use crate::types::Offset;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::marker::PhantomData;
+#[cfg(feature = "futures")]
+use futures_sink::Sink;
+#[cfg(feature = "futures")]
+use std::pin::Pin;
+#[cfg(feature = "futures")]
+use std::task::{Context, Poll};
+
+const JSON: &str = "application/json";
+
+impl DurableStream {
+    /// View this stream as a JSON stream of `T`.
+    ///
+    /// The handle's content type is forced to `application/json`.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let events = client.stream("events").typed::<Event>();
+    /// events.create().await?;
+    /// events.append(&Event::Started).await?;
+    ///
+    /// let mut reader = events.read().build()?;
+    /// while let Some(chunk) = reader.next_chunk().await? {
+    ///     for event in chunk.items { /* ... */ }
+    /// }
+    /// ```
+    pub fn typed<T: Serialize + DeserializeOwned>(&self) -> TypedStream<T> {
+        let mut stream = self.clone();
+        stream.content_type = Some(JSON.to_string());
+        TypedStream {
+            stream,
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// A JSON stream whose items are `T`.
+///
+/// Created by [`DurableStream::typed`]. Use [`inner`](Self::inner) for
+/// anything not covered here.
+#[derive(Debug)]
+pub struct TypedStream<T> {
+    stream: DurableStream,
+    _marker: PhantomData<fn(T) -> T>,
+}
+
+impl<T> Clone for TypedStream<T> {
+    fn clone(&self) -> Self {
+        Self {
+            stream: self.stream.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Serialize + DeserializeOwned> TypedStream<T> {
+    /// The underlying stream handle.
+    pub fn inner(&self) -> &DurableStream {
+        &self.stream
+    }
+
+    /// Create the stream as `application/json`.
+    ///
+    /// Idempotent, like [`DurableStream::create`].
+    pub async fn create(&self) -> Result<(), StreamError> {
+        self.stream
+            .create_with(CreateOptions::new().content_type(JSON))
+            .await
+    }
+
+    /// Append one item.
+    pub async fn append(&self, item: &T) -> Result<AppendResponse, StreamError> {
+        self.stream.append(serde_json::to_vec(item)?).await
+    }
+
+    /// Start a typed read (defaults to the beginning of the stream).
+    pub fn read(&self) -> TypedReadBuilder<T> {
+        self.stream.read().typed::<T>()
+    }
+
+    /// Read every item from `offset` up to the current tail.
+    pub async fn read_to_end(&self, offset: impl Into<Offset>) -> Result<Vec<T>, StreamError> {
+        self.stream.read_json_to_end(offset).await
+    }
+
+    /// Build a producer with default settings that appends `T`.
+    ///
+    /// For custom batching, wrap a configured producer with
+    /// [`TypedProducer::new`].
+    pub fn producer(&self, producer_id: impl Into<String>) -> TypedProducer<T> {
+        TypedProducer::new(self.stream.producer(producer_id).build())
+    }
+}
+
+/// A [`Producer`] that appends `T` as JSON.
+pub struct TypedProducer<T> {
+    producer: Producer,
+    _marker: PhantomData<fn(T)>,
+}
+
+impl<T> Clone for TypedProducer<T> {
+    fn clone(&self) -> Self {
+        Self {
+            producer: self.producer.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Serialize> TypedProducer<T> {
+    /// Wrap a producer; it should target a JSON stream.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let producer = TypedProducer::<Event>::new(
+    ///     events.inner().producer("ingest-1").linger(Duration::from_millis(20)).build(),
+    /// );
+    /// ```
+    pub fn new(producer: Producer) -> Self {
+        Self {
+            producer,
+            _marker: PhantomData,
+        }
+    }
+
+    /// The underlying producer.
+    pub fn inner(&self) -> &Producer {
+        &self.producer
+    }
+
+    /// Append one item (fire-and-forget); see [`Producer::append_json`].
+    pub fn append(&self, item: &T) {
+        self.producer.append_json(item);
+    }
+
+    /// Append one item with a delivery callback; see
+    /// [`Producer::append_json_with_callback`].
+    pub fn append_with_callback<F>(&self, item: &T, callback: F)
+    where
+        F: FnOnce(Result<AppendReceipt, ProducerError>) + Send + 'static,
+    {
+        self.producer.append_json_with_callback(item, callback);
+    }
+
+    /// Wait for all queued items; see [`Producer::flush`].
+    pub async fn flush(&self) -> Result<(), ProducerError> {
+        self.producer.flush().await
+    }
+
+    /// Flush and close; see [`Producer::close`].
+    pub async fn close(&self) -> Result<(), ProducerError> {
+        self.producer.close().await
+    }
+}
+
+/// Items are serialized and batched like [`append`](TypedProducer::append),
+/// with serialization and validation errors returned from `start_send`.
+/// Backpressure, flushing and closing work as for
+/// [`Producer`].
+#[cfg(feature = "futures")]
+impl<T: Serialize> Sink<T> for TypedProducer<T> {
+    type Error = ProducerError;
+
+    fn poll_ready(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), ProducerError>> {
+        self.producer.poll_room(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), ProducerError> {
+        self.producer.start_send_json(&item)
+    }
+
+    fn poll_flush(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), ProducerError>> {
+        self.producer.poll_flushed(cx)
+    }
+
+    fn poll_close(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), ProducerError>> {
+        self.producer.poll_closed(cx)
+    }
+}
@@ -10,9 +10,10 @@ use std::fmt;
 /// - Lexicographically sortable: Compare offsets to determine ordering
 /// - Persistent: Valid for the stream's lifetime
 /// - Unique: Each position has exactly one offset
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub enum Offset {
     /// Start from the beginning of the stream (sentinel "-1")
+    #[default]
     Beginning,
     /// Start from the current tail (only future data, sentinel "now")
     Now,
@@ -73,12 +74,6 @@ impl Offset {
     }
 }
 
-impl Default for Offset {
-    fn default() -> Self {
-        Offset::Beginning
-    }
-}
-
 impl fmt::Display for Offset {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.to_query_value())
@@ -97,6 +92,22 @@ impl From<&str> for Offset {
     }
 }
 
+/// Serialized as its protocol string: `"-1"`, `"now"` or the offset token.
+#[cfg(feature = "json")]
+impl serde::Serialize for Offset {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "json")]
+impl<'de> serde::Deserialize<'de> for Offset {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Ok(Offset::parse(&s))
+    }
+}
+
 impl PartialOrd for Offset {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         match (self, other) {
@@ -143,3 +154,29 @@ impl LiveMode {
         !matches!(self, LiveMode::Off)
     }
 }
+
+/// Where a reader goes when its offset has fallen out of retention (410
+/// Gone); see [`ReadBuilder::on_offset_gone`](crate::ReadBuilder::on_offset_gone).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Restart {
+    /// Return [`StreamError::OffsetGone`](crate::StreamError::OffsetGone)
+    /// (the default).
+    #[default]
+    Fail,
+    /// Read again from the oldest data the stream still holds.
+    FromEarliest,
+    /// Skip to the tail and read only new data.
+    FromNow,
+}
+
+impl Restart {
+    /// Offset to restart from, or `None` for [`Restart::Fail`].
+    pub(crate) fn offset(self) -> Option<Offset> {
+        match self {
+            Restart::Fail => None,
+            Restart::FromEarliest => Some(Offset::Beginning),
+            Restart::FromNow => Some(Offset::Now),
+        }
+    }
+}
@@ -0,0 +1,131 @@
+//! Serving a reader to browsers as server-sent events.
+
+use crate::error::StreamError;
+use crate::iterator::{Chunk, ChunkIterator};
+use axum::response::sse::{Event, Sse};
+use base64::Engine;
+use futures_util::stream::{self, Stream};
+use std::collections::VecDeque;
+
+impl ChunkIterator {
+    /// Serve this reader as an axum SSE response, in the protocol's own
+    /// event format.
+    ///
+    /// Each chunk with data becomes a `data` event, followed by a `control`
+    /// event carrying `streamNextOffset`, `streamCursor` and `upToDate`.
+    /// Control events also set the SSE `id` to the next offset, so a
+    /// browser `EventSource` that reconnects sends it back as
+    /// `Last-Event-ID`. Clients of this crate or the TypeScript client can
+    /// read the response like a Durable Streams server's.
+    ///
+    /// Data is sent as text, with invalid UTF-8 replaced and line endings
+    /// normalized to `\n` as SSE requires; use
+    /// [`into_sse_base64`](Self::into_sse_base64) for binary streams. An
+    /// error ends the response.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// async fn events(State(client): State<Client>, Path(name): Path<String>) -> impl IntoResponse {
+    ///     let reader = client
+    ///         .stream(&format!("http://origin:4437/v1/stream/{name}"))
+    ///         .read()
+    ///         .live(LiveMode::Sse)
+    ///         .build()?;
+    ///     Ok::<_, StreamError>(reader.into_sse().keep_alive(KeepAlive::default()))
+    /// }
+    /// ```
+    pub fn into_sse(self) -> Sse<impl Stream<Item = Result<Event, StreamError>>> {
+        Sse::new(events(self, Encoding::Text))
+    }
+
+    /// Like [`into_sse`](Self::into_sse), but with data events base64
+    /// encoded so any bytes arrive intact. Add a
+    /// `stream-sse-data-encoding: base64` header to the response so
+    /// clients decode them:
+    ///
+    /// ```ignore
+    /// ([("stream-sse-data-encoding", "base64")], reader.into_sse_base64())
+    /// ```
+    pub fn into_sse_base64(self) -> Sse<impl Stream<Item = Result<Event, StreamError>>> {
+        Sse::new(events(self, Encoding::Base64))
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Encoding {
+    Text,
+    Base64,
+}
+
+struct State {
+    iter: ChunkIterator,
+    encoding: Encoding,
+    /// Events of the last chunk not yet sent.
+    queued: VecDeque<Event>,
+    done: bool,
+}
+
+fn events(
+    iter: ChunkIterator,
+    encoding: Encoding,
+) -> impl Stream<Item = Result<Event, StreamError>> {
+    let state = State {
+        iter,
+        encoding,
+        queued: VecDeque::new(),
+        done: false,
+    };
+    stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(event) = state.queued.pop_front() {
+                return Some((Ok(event), state));
+            }
+            if state.done {
+                return None;
+            }
+            match state.iter.next_chunk().await {
+                Ok(Some(chunk)) => state.queue(chunk),
+                Ok(None) => state.done = true,
+                Err(err) => {
+                    state.done = true;
+                    return Some((Err(err), state));
+                }
+            }
+        }
+    })
+}
+
+impl State {
+    fn queue(&mut self, chunk: Chunk) {
+        // Marks a dropped upstream SSE connection; nothing to forward
+        if chunk.status_code.is_none() {
+            return;
+        }
+        if !chunk.data.is_empty() {
+            let data = match self.encoding {
+                Encoding::Text => String::from_utf8_lossy(&chunk.data)
+                    .replace("\r\n", "\n")
+                    .replace('\r', "\n"),
+                Encoding::Base64 => base64::engine::general_purpose::STANDARD.encode(&chunk.data),
+            };
+            self.queued
+                .push_back(Event::default().event("data").data(data));
+        }
+
+        let offset = chunk.next_offset.to_string();
+        let mut control = serde_json::json!({
+            "streamNextOffset": offset,
+            "upToDate": chunk.up_to_date,
+        });
+        if let Some(cursor) = &chunk.cursor {
+            control["streamCursor"] = cursor.as_str().into();
+        }
+        self.queued.push_back(
+            Event::default()
+                .event("control")
+                .id(offset)
+                .data(control.to_string()),
+        );
+    }
+}
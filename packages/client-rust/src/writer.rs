@@ -0,0 +1,181 @@
+//! `AsyncWrite` adapter for appending.
+
+use crate::error::StreamError;
+use crate::producer::Producer;
+use crate::rt;
+use crate::stream::DurableStream;
+use bytes::BytesMut;
+use std::fmt;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+use tokio::io::AsyncWrite;
+use tokio::sync::oneshot;
+
+/// Bytes buffered before they are appended, unless set with
+/// [`StreamWriter::buffer_size`].
+const DEFAULT_BUFFER_SIZE: usize = 64 * 1024;
+
+impl DurableStream {
+    /// An [`AsyncWrite`] that appends everything written to this stream.
+    ///
+    /// Writes are buffered and sent as one append whenever the buffer
+    /// fills or the writer is flushed. One append is in flight at a time,
+    /// so data lands in order. Meant for byte and text streams: an append can end
+    /// anywhere in the written data, so JSON streams need whole messages
+    /// per append instead.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut log = tokio::fs::File::open("app.log").await?;
+    /// let mut writer = stream.writer();
+    /// tokio::io::copy(&mut log, &mut writer).await?;
+    /// writer.shutdown().await?;
+    /// ```
+    pub fn writer(&self) -> StreamWriter {
+        StreamWriter::new(Target::Stream(self.clone()), self.url())
+    }
+}
+
+impl Producer {
+    /// An [`AsyncWrite`] that appends everything written through this
+    /// producer.
+    ///
+    /// Like [`DurableStream::writer`], but each filled buffer is queued on
+    /// the producer, so appends are pipelined and retried without
+    /// duplicates. Writes wait while the producer's batch buffer is full;
+    /// a flush waits for every batch to be acknowledged and reports batch
+    /// failures. Shutting the writer down flushes but does not close the
+    /// producer.
+    pub fn writer(&self) -> StreamWriter {
+        StreamWriter::new(Target::Producer(self.clone()), self.stream().url())
+    }
+}
+
+/// Appends written bytes to a stream, from [`DurableStream::writer`] or
+/// [`Producer::writer`].
+///
+/// Errors from appends surface as [`io::Error`]s wrapping the
+/// [`StreamError`] or [`ProducerError`](crate::ProducerError). Bytes still
+/// buffered when the writer is dropped are discarded; flush or shut it
+/// down first.
+#[must_use = "writers do nothing unless written to"]
+pub struct StreamWriter {
+    target: Target,
+    url: String,
+    buf: BytesMut,
+    buffer_size: usize,
+    /// Outcome of the append in flight, when writing to a stream directly.
+    in_flight: Option<oneshot::Receiver<Result<(), StreamError>>>,
+}
+
+enum Target {
+    Stream(DurableStream),
+    Producer(Producer),
+}
+
+impl StreamWriter {
+    fn new(target: Target, url: &str) -> Self {
+        Self {
+            target,
+            url: url.to_string(),
+            buf: BytesMut::new(),
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            in_flight: None,
+        }
+    }
+
+    /// Bytes to buffer before appending them (default 64 KiB).
+    pub fn buffer_size(mut self, bytes: usize) -> Self {
+        self.buffer_size = bytes.max(1);
+        self
+    }
+
+    /// Bytes written but not yet handed to an append.
+    pub fn buffered(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Hand the buffered bytes to an append, once the previous append
+    /// has finished.
+    fn poll_hand_off(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.poll_in_flight(cx))?;
+        if self.buf.is_empty() {
+            return Poll::Ready(Ok(()));
+        }
+        match &self.target {
+            Target::Stream(stream) => {
+                let (tx, rx) = oneshot::channel();
+                let stream = stream.clone();
+                let data = self.buf.split().freeze();
+                rt::spawn(async move {
+                    let _ = tx.send(stream.append(data).await.map(|_| ()));
+                });
+                self.in_flight = Some(rx);
+            }
+            Target::Producer(producer) => {
+                ready!(producer.poll_room(cx)).map_err(io::Error::other)?;
+                producer
+                    .try_append(self.buf.split().freeze())
+                    .map_err(io::Error::other)?;
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    /// Wait for the append in flight, if any.
+    fn poll_in_flight(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let Some(rx) = &mut self.in_flight else {
+            return Poll::Ready(Ok(()));
+        };
+        let result = ready!(Pin::new(rx).poll(cx));
+        self.in_flight = None;
+        match result {
+            Ok(appended) => Poll::Ready(appended.map_err(io::Error::other)),
+            // The append task was dropped with the runtime
+            Err(_) => Poll::Ready(Err(io::ErrorKind::BrokenPipe.into())),
+        }
+    }
+}
+
+impl AsyncWrite for StreamWriter {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        data: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if this.buf.len() >= this.buffer_size {
+            ready!(this.poll_hand_off(cx))?;
+        }
+        let n = data.len().min(this.buffer_size - this.buf.len());
+        this.buf.extend_from_slice(&data[..n]);
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_hand_off(cx))?;
+        ready!(this.poll_in_flight(cx))?;
+        match &this.target {
+            Target::Stream(_) => Poll::Ready(Ok(())),
+            Target::Producer(producer) => producer.poll_flushed(cx).map_err(io::Error::other),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.poll_flush(cx)
+    }
+}
+
+impl fmt::Debug for StreamWriter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamWriter")
+            .field("url", &self.url)
+            .field("buffered", &self.buf.len())
+            .field("buffer_size", &self.buffer_size)
+            .finish()
+    }
+}
@@ -0,0 +1,65 @@
+//! Producer behaviour against the in-memory mock server.
+
+use durable_streams::testing::MockServer;
+use durable_streams::{DurableStream, JitterMode, RetryConfig};
+use std::time::Duration;
+
+async fn created_stream(server: &MockServer, retry: RetryConfig) -> DurableStream {
+    let stream = server
+        .client_builder()
+        .retry_config(retry)
+        .build()
+        .unwrap()
+        .stream_builder("/producer")
+        .content_type("text/plain")
+        .build();
+    stream.create().await.unwrap();
+    stream
+}
+
+#[tokio::test(start_paused = true)]
+async fn batch_held_back_by_the_in_flight_limit_goes_out_when_one_finishes() {
+    let server = MockServer::new();
+    let retry = RetryConfig {
+        initial_backoff: Duration::from_millis(100),
+        jitter: JitterMode::None,
+        ..Default::default()
+    };
+    let stream = created_stream(&server, retry).await;
+    let producer = stream
+        .producer("held")
+        .max_in_flight(1)
+        .linger(Duration::from_millis(10))
+        .build();
+
+    // The first batch is throttled and stays in flight while it backs off
+    server.fail_next(429);
+    producer.append("a");
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    // The second lingers out while the first is still outstanding
+    producer.append("b");
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert_eq!(server.contents("/producer").unwrap(), "");
+
+    // No flush: finishing the first batch must send the second
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    assert_eq!(server.contents("/producer").unwrap(), "ab");
+    producer.close().await.unwrap();
+}
+
+#[tokio::test(start_paused = true)]
+async fn zero_max_in_flight_still_sends() {
+    let server = MockServer::new();
+    let stream = created_stream(&server, RetryConfig::default()).await;
+    let producer = stream.producer("zero").max_in_flight(0).build();
+
+    producer.append("a");
+    producer.append("b");
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    assert_eq!(server.contents("/producer").unwrap(), "ab");
+
+    producer.append("c");
+    producer.close().await.unwrap();
+    assert_eq!(server.contents("/producer").unwrap(), "abc");
+}
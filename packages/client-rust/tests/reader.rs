@@ -0,0 +1,81 @@
+//! Reader behaviour against the in-memory mock server.
+
+use durable_streams::testing::MockServer;
+use durable_streams::{LiveMode, Restart, StreamError};
+use std::time::Duration;
+
+#[tokio::test]
+async fn closed_stream_is_read_to_the_end_without_a_reset() {
+    let server = MockServer::new();
+    server.set_long_poll_timeout(Duration::from_millis(50));
+    let stream = server
+        .client()
+        .stream_builder("/closing")
+        .content_type("text/plain")
+        .build();
+    stream.create().await.unwrap();
+    stream.append("a").await.unwrap();
+
+    let mut reader = stream.read().live(LiveMode::LongPoll).build().unwrap();
+    let first = reader.next_chunk().await.unwrap().unwrap();
+    assert_eq!(first.data, "a");
+
+    // The next response's ETag ends in `:c`
+    stream.append("b").await.unwrap();
+    stream.close().await.unwrap();
+    let mut data = Vec::new();
+    loop {
+        let chunk = reader.next_chunk().await.unwrap().unwrap();
+        assert!(!chunk.reset);
+        data.extend_from_slice(&chunk.data);
+        if chunk.up_to_date {
+            break;
+        }
+    }
+    assert_eq!(data, b"b");
+}
+
+#[tokio::test]
+async fn not_found_on_a_stream_that_exists_again_is_a_reset() {
+    let server = MockServer::new();
+    let stream = server
+        .client()
+        .stream_builder("/recreated")
+        .content_type("text/plain")
+        .build();
+    stream.create().await.unwrap();
+    stream.append("old").await.unwrap();
+
+    let mut reader = stream.read().live(LiveMode::LongPoll).build().unwrap();
+    reader.next_chunk().await.unwrap().unwrap();
+    server.fail_next(404);
+    let err = reader.next_chunk().await.unwrap_err();
+    assert!(matches!(err, StreamError::StreamReset), "{err:?}");
+
+    let mut reader = stream
+        .read()
+        .live(LiveMode::LongPoll)
+        .on_stream_reset(Restart::FromEarliest)
+        .build()
+        .unwrap();
+    reader.next_chunk().await.unwrap().unwrap();
+    stream.append("new").await.unwrap();
+    server.fail_next(404);
+    let chunk = reader.next_chunk().await.unwrap().unwrap();
+    assert!(chunk.reset);
+    assert_eq!(chunk.data, "oldnew");
+}
+
+#[tokio::test]
+async fn deleted_stream_is_not_found() {
+    let server = MockServer::new();
+    let stream = server.client().stream("/deleted");
+    stream.create().await.unwrap();
+    stream.append("a").await.unwrap();
+
+    let mut reader = stream.read().live(LiveMode::LongPoll).build().unwrap();
+    reader.next_chunk().await.unwrap().unwrap();
+    stream.delete().await.unwrap();
+    let err = reader.next_chunk().await.unwrap_err();
+    assert!(matches!(err, StreamError::NotFound { .. }), "{err:?}");
+}